@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+
+/// This component is used to define a checkpoint. It should be on an entity with a sensor
+/// collider, so the checkpoint is reached when a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// walks into it.
+///
+/// When a checkpoint is reached, a snapshot of the level is taken with the players standing
+/// at its respawn transform. They're put back there when a [`PlayerDied`](crate::systems::PlayerDied)
+/// event is sent.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Checkpoint {
+    respawn: Transform,
+}
+
+impl Checkpoint {
+    pub fn new(respawn: Transform) -> Self {
+        Checkpoint { respawn }
+    }
+
+    /// Where players respawn after reaching this checkpoint
+    pub fn respawn(&self) -> Transform {
+        self.respawn
+    }
+}
+
+/// This component is on the full-screen overlay that fades to black while players respawn
+#[derive(Component)]
+pub struct RespawnOverlay;
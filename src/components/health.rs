@@ -0,0 +1,248 @@
+use crate::save::Saveable;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What hurt something. In a level file, it's written in snake case, ie `"fire"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DamageType {
+    Generic,
+    /// Landing too hard. It hurts even while invulnerable.
+    Fall,
+    Fire,
+    Electric,
+    /// Shot by a [`Weapon`](crate::weapons::Weapon)
+    Ballistic,
+}
+
+impl Default for DamageType {
+    fn default() -> Self {
+        DamageType::Generic
+    }
+}
+
+impl DamageType {
+    pub fn ignores_invulnerability(&self) -> bool {
+        matches!(self, DamageType::Fall)
+    }
+}
+
+/// This component lets an entity be hurt by [`DamageEvent`](crate::systems::DamageEvent)s. It
+/// dies when it runs out, and it's saved with the game.
+///
+/// After it's hurt, it can't be hurt again for a moment, and after a while it regenerates. How
+/// long, and how fast, is set in the [`PlayerConfig`](crate::resources::PlayerConfig).
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    current: f32,
+    max: f32,
+    #[serde(skip)]
+    invulnerable: Duration,
+    #[serde(skip)]
+    since_damage: Duration,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health {
+            current: max,
+            max,
+            invulnerable: Duration::ZERO,
+            since_damage: Duration::ZERO,
+        }
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0f32
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerable.is_zero()
+    }
+
+    /// Take `amount` of damage, then be invulnerable for `invulnerability`. Returns how much
+    /// health was lost, which is nothing if it's dead or invulnerable.
+    pub fn damage(
+        &mut self,
+        amount: f32,
+        damage_type: DamageType,
+        invulnerability: Duration,
+    ) -> f32 {
+        if self.is_dead()
+            || amount <= 0f32
+            || (self.is_invulnerable() && !damage_type.ignores_invulnerability())
+        {
+            return 0f32;
+        }
+        let lost = amount.min(self.current);
+        self.current -= lost;
+        self.invulnerable = invulnerability;
+        self.since_damage = Duration::ZERO;
+        lost
+    }
+
+    /// Restore up to `amount` of health, unless it's dead. Returns how much was restored.
+    pub fn heal(&mut self, amount: f32) -> f32 {
+        if self.is_dead() {
+            return 0f32;
+        }
+        let restored = amount.max(0f32).min(self.max - self.current);
+        self.current += restored;
+        restored
+    }
+
+    /// Let `delta` pass, regenerating `per_second` once `delay` has passed since it was last hurt
+    pub fn tick(&mut self, delta: Duration, delay: Duration, per_second: f32) {
+        self.invulnerable = self.invulnerable.saturating_sub(delta);
+        self.since_damage += delta;
+        if self.is_dead() || self.since_damage < delay {
+            return;
+        }
+        self.current = (self.current + per_second * delta.as_secs_f32()).min(self.max);
+    }
+
+    /// Come back to life with full health
+    pub fn revive(&mut self) {
+        *self = Health::new(self.max);
+    }
+}
+
+impl Saveable for Health {
+    const SAVE_KEY: &'static str = "health";
+}
+
+/// This component hurts the entities with [`Health`](crate::components::Health) inside a
+/// [`TriggerVolume`](crate::scripting::TriggerVolume), as soon as they come in and then every `interval`.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct HazardVolume {
+    damage: f32,
+    damage_type: DamageType,
+    interval: Duration,
+    elapsed: Duration,
+}
+
+impl HazardVolume {
+    pub fn new(damage: f32, damage_type: DamageType, interval_seconds: f32) -> Self {
+        let interval = Duration::from_secs_f32(interval_seconds.max(0f32));
+        HazardVolume {
+            damage,
+            damage_type,
+            interval,
+            elapsed: interval,
+        }
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+
+    /// Let `delta` pass, and return whether the hazard hurts its occupants now. An empty hazard
+    /// hurts the next thing to come in straight away.
+    pub fn tick(&mut self, delta: Duration, occupied: bool) -> bool {
+        if !occupied {
+            self.elapsed = self.interval;
+            return false;
+        }
+        if self.elapsed >= self.interval {
+            self.elapsed = Duration::ZERO;
+        }
+        let hurts = self.elapsed.is_zero();
+        self.elapsed += delta;
+        hurts
+    }
+}
+
+/// This component tracks the vertical velocity of a body, so the speed it lands at can be told
+/// from how much a contact slowed it down
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FallTracker {
+    vertical_velocity: f32,
+}
+
+impl FallTracker {
+    /// Track the body's vertical velocity this frame, and return how much it went up since the
+    /// last one
+    pub fn update(&mut self, vertical_velocity: f32) -> f32 {
+        let change = vertical_velocity - self.vertical_velocity;
+        self.vertical_velocity = vertical_velocity;
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF_SECOND: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn test_invulnerable_after_damage() {
+        let mut health = Health::new(100f32);
+        assert_eq!(health.damage(30f32, DamageType::Fire, HALF_SECOND), 30f32);
+        assert_eq!(health.damage(30f32, DamageType::Fire, HALF_SECOND), 0f32);
+        // Falls still hurt
+        assert_eq!(health.damage(10f32, DamageType::Fall, HALF_SECOND), 10f32);
+        health.tick(HALF_SECOND, Duration::from_secs(5), 10f32);
+        assert_eq!(
+            health.damage(100f32, DamageType::Generic, HALF_SECOND),
+            60f32
+        );
+        assert!(health.is_dead());
+        assert_eq!(health.damage(10f32, DamageType::Fall, HALF_SECOND), 0f32);
+    }
+
+    #[test]
+    fn test_regenerates_after_delay() {
+        let mut health = Health::new(100f32);
+        health.damage(50f32, DamageType::Generic, Duration::ZERO);
+        health.tick(Duration::from_secs(1), Duration::from_secs(2), 10f32);
+        assert_eq!(health.current(), 50f32);
+        health.tick(Duration::from_secs(1), Duration::from_secs(2), 10f32);
+        assert_eq!(health.current(), 60f32);
+        health.tick(Duration::from_secs(10), Duration::from_secs(2), 10f32);
+        assert_eq!(health.current(), 100f32);
+
+        // The dead stay dead until they're revived
+        health.damage(100f32, DamageType::Generic, Duration::ZERO);
+        health.tick(Duration::from_secs(10), Duration::from_secs(2), 10f32);
+        assert!(health.is_dead());
+        health.revive();
+        assert_eq!(health.current(), health.max());
+    }
+
+    #[test]
+    fn test_heal_up_to_max() {
+        let mut health = Health::new(100f32);
+        health.damage(30f32, DamageType::Generic, Duration::ZERO);
+        assert_eq!(health.heal(20f32), 20f32);
+        assert_eq!(health.heal(20f32), 10f32);
+        assert_eq!(health.current(), 100f32);
+        health.damage(100f32, DamageType::Generic, Duration::ZERO);
+        assert_eq!(health.heal(20f32), 0f32);
+    }
+
+    #[test]
+    fn test_hazard_hurts_on_entering_then_every_interval() {
+        let mut hazard = HazardVolume::new(10f32, DamageType::Fire, 1f32);
+        assert!(!hazard.tick(Duration::from_secs(5), false));
+        assert!(hazard.tick(HALF_SECOND, true));
+        assert!(!hazard.tick(HALF_SECOND, true));
+        assert!(hazard.tick(HALF_SECOND, true));
+        // Leaving and coming back hurts straight away
+        assert!(!hazard.tick(HALF_SECOND, false));
+        assert!(hazard.tick(HALF_SECOND, true));
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::ecs::component::Component;
+use bevy::input::gamepad::Gamepad;
+
+/// This enum defines the device a local player controls their [`FirstPersonSubject`](crate::components::FirstPersonSubject) with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSource {
+    KeyboardMouse,
+    Gamepad(Gamepad),
+    /// A client of the server, by its id. Its intents arrive as [`InputFrame`](crate::network::InputFrame)s
+    /// instead of being read from a device.
+    Network(u32),
+}
+
+impl InputSource {
+    pub fn uses_keyboard_mouse(&self) -> bool {
+        *self == InputSource::KeyboardMouse
+    }
+
+    /// The gamepad this source reads, if any
+    pub fn gamepad(&self) -> Option<Gamepad> {
+        match self {
+            InputSource::Gamepad(gamepad) => Some(*gamepad),
+            InputSource::KeyboardMouse | InputSource::Network(_) => None,
+        }
+    }
+}
+
+/// This component ties a [`FirstPersonSubject`](crate::components::FirstPersonSubject) to one local player
+/// in split-screen, or to one client on a server. The first person systems only read this player's
+/// `input` for the subject.
+///
+/// A subject without this component is controlled by every device, which is what a single
+/// player expects.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalPlayer {
+    index: usize,
+    input: InputSource,
+}
+
+impl LocalPlayer {
+    pub fn new(index: usize, input: InputSource) -> Self {
+        LocalPlayer { index, input }
+    }
+
+    /// The player's number, starting from `0`
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn input(&self) -> InputSource {
+        self.input
+    }
+}
+
+/// This component defines the part of the window a local player's camera covers, as fractions of
+/// the window from its top left corner.
+///
+/// Note: In split-screen, every camera is drawn into its viewport by the
+/// [`SplitScreenPlugin`](crate::plugins::SplitScreenPlugin). A single player's camera is the
+/// active 3D camera, which covers the whole window anyway.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct SplitScreenViewport {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl SplitScreenViewport {
+    /// The viewport of player `index` when `player_count` players share the window.
+    /// Two players are stacked top and bottom, three or four get a quarter of the window each.
+    pub fn for_player(index: usize, player_count: usize) -> Self {
+        match player_count {
+            0 | 1 => SplitScreenViewport {
+                x: 0f32,
+                y: 0f32,
+                width: 1f32,
+                height: 1f32,
+            },
+            2 => SplitScreenViewport {
+                x: 0f32,
+                y: 0.5 * index.min(1) as f32,
+                width: 1f32,
+                height: 0.5,
+            },
+            _ => SplitScreenViewport {
+                x: 0.5 * (index % 2) as f32,
+                y: 0.5 * (index.min(3) / 2) as f32,
+                width: 0.5,
+                height: 0.5,
+            },
+        }
+    }
+
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// The viewport in pixels of a `width` by `height` target, as `[x, y, width, height]`
+    pub fn in_pixels(&self, width: u32, height: u32) -> [f32; 4] {
+        let (width, height) = (width as f32, height as f32);
+        [
+            self.x * width,
+            self.y * height,
+            self.width * width,
+            self.height * height,
+        ]
+    }
+
+    /// The aspect ratio of the viewport in a `width` by `height` window
+    pub fn aspect_ratio(&self, width: f32, height: f32) -> f32 {
+        (self.width * width) / (self.height * height).max(1f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport_single_player() {
+        let viewport = SplitScreenViewport::for_player(0, 1);
+        assert_eq!(
+            (
+                viewport.x(),
+                viewport.y(),
+                viewport.width(),
+                viewport.height()
+            ),
+            (0f32, 0f32, 1f32, 1f32)
+        );
+    }
+
+    #[test]
+    fn test_viewport_two_players() {
+        let top = SplitScreenViewport::for_player(0, 2);
+        let bottom = SplitScreenViewport::for_player(1, 2);
+        assert_eq!((top.y(), top.height()), (0f32, 0.5));
+        assert_eq!((bottom.y(), bottom.height()), (0.5, 0.5));
+        assert_eq!(top.width(), 1f32);
+    }
+
+    #[test]
+    fn test_viewport_four_players() {
+        let corners: Vec<(f32, f32)> = (0..4)
+            .map(|index| {
+                let viewport = SplitScreenViewport::for_player(index, 4);
+                (viewport.x(), viewport.y())
+            })
+            .collect();
+        assert_eq!(
+            corners,
+            vec![(0f32, 0f32), (0.5, 0f32), (0f32, 0.5), (0.5, 0.5)]
+        );
+        // With three players the bottom right quarter is left empty
+        assert_eq!(
+            SplitScreenViewport::for_player(2, 3),
+            SplitScreenViewport::for_player(2, 4)
+        );
+    }
+
+    #[test]
+    fn test_viewport_in_window() {
+        let bottom = SplitScreenViewport::for_player(1, 2);
+        assert_eq!(bottom.in_pixels(800, 600), [0f32, 300f32, 800f32, 300f32]);
+        assert_eq!(bottom.aspect_ratio(800f32, 600f32), 800f32 / 300f32);
+        let top_right = SplitScreenViewport::for_player(1, 4);
+        assert_eq!(
+            top_right.in_pixels(800, 600),
+            [400f32, 0f32, 400f32, 300f32]
+        );
+        assert_eq!(top_right.aspect_ratio(800f32, 600f32), 400f32 / 300f32);
+    }
+}
@@ -1,24 +1,44 @@
 use bevy::ecs::component::Component;
+mod checkpoint;
+mod health;
+mod local_player;
 mod lookaround;
 mod movement;
+mod surface;
+mod tags;
+mod weapon_intents;
 
+pub use self::checkpoint::*;
+pub use self::health::*;
+pub use self::local_player::*;
 pub use self::lookaround::*;
 pub use self::movement::*;
+pub use self::surface::*;
+pub use self::tags::*;
+pub use self::weapon_intents::*;
 
 /// This component is used to define an entity that can be controlled by the player.
 /// It should be used on an entity that also has a [`Movement`](crate::components::Movement) and /
 /// or [`Lookaround`](crate::components::Lookaround) component.
 ///
-/// Note: There must only be _one_ entity with this component
-/// whenever [`FirstPersonControlSettings`](crate::states::FirstPersonControlSettings) is enabled, otherwise a
-/// panic may occur.
+/// In split-screen, every subject with a [`LocalPlayer`](crate::components::LocalPlayer) component is
+/// controlled by that player. Otherwise, if there's more than one entity with this component, the
+/// one controlled is the one with an [`ActiveSubject`](crate::components::ActiveSubject) component.
 #[derive(Component)]
 pub struct FirstPersonSubject;
 
+/// This component picks which [`FirstPersonSubject`](crate::components::FirstPersonSubject) is controlled
+/// when there are several. It isn't needed when there's only one.
+///
+/// Note: If there are several subjects and this component isn't on exactly one of them, no subject
+/// is controlled and a [`SubjectDiagnostic`](crate::systems::SubjectDiagnostic) event is sent.
+#[derive(Component)]
+pub struct ActiveSubject;
+
 #[derive(Component)]
 pub struct FirstPersonHead;
 
 /// This component is used to define an entity that exists in one level at a time. It should
 /// be set up and torn down with every level transition.
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct LevelObject;
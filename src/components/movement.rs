@@ -37,6 +37,17 @@ impl PartialEq for MovementDirection {
 }
 impl Eq for MovementDirection {}
 
+impl MovementDirection {
+    pub fn magnitude(&self) -> f32 {
+        match *self {
+            MovementDirection::Left(magnitude)
+            | MovementDirection::Right(magnitude)
+            | MovementDirection::Forward(magnitude)
+            | MovementDirection::Back(magnitude) => magnitude,
+        }
+    }
+}
+
 /// This struct defines how an entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component should change its position.
 #[derive(Component, PartialEq, Eq, Debug)]
 pub struct Movement {
@@ -88,6 +99,13 @@ impl Movement {
     pub fn forward_back(&self) -> MovementDirection {
         self.forward_back
     }
+
+    /// How hard the player is pushing in any direction, from 0 to 1
+    pub fn magnitude(&self) -> f32 {
+        self.left_right
+            .magnitude()
+            .max(self.forward_back.magnitude())
+    }
 }
 
 #[cfg(test)]
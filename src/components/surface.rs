@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+/// This component makes a collider out of the named
+/// [`SurfaceMaterial`](crate::resources::SurfaceMaterial) in the
+/// [`GameConfig`](crate::resources::GameConfig), ie "ice". It decides the collider's friction and
+/// restitution, the sounds of walking on and hitting it, and how fast players move on it.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Surface(String);
+
+impl Surface {
+    pub fn new(material: impl Into<String>) -> Self {
+        Surface(material.into())
+    }
+
+    pub fn material(&self) -> &str {
+        &self.0
+    }
+}
+
+/// This component is what a [`FirstPersonSubject`](crate::components::FirstPersonSubject) is
+/// standing on
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Footing {
+    ground: Option<Entity>,
+    speed_modifier: f32,
+}
+
+impl Default for Footing {
+    fn default() -> Self {
+        Footing {
+            ground: None,
+            speed_modifier: 1f32,
+        }
+    }
+}
+
+impl Footing {
+    pub fn new(ground: Option<Entity>, speed_modifier: f32) -> Self {
+        Footing {
+            ground,
+            speed_modifier,
+        }
+    }
+
+    /// The collider underfoot, or `None` in the air
+    pub fn ground(&self) -> Option<Entity> {
+        self.ground
+    }
+
+    /// How fast the subject can move, compared to its max speed and movement force. It's 1 in
+    /// the air and on colliders without a [`Surface`](crate::components::Surface).
+    pub fn speed_modifier(&self) -> f32 {
+        self.speed_modifier
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+/// This component gives an entity names that level scripts can refer to it by, ie to filter
+/// which entities a [`TriggerVolume`](crate::scripting::TriggerVolume) reacts to, or to pick the
+/// moving platforms an action enables.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags(Vec<String>);
+
+impl Tags {
+    pub fn new<I, S>(tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Tags(tags.into_iter().map(Into::into).collect())
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|own_tag| own_tag == tag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
@@ -0,0 +1,52 @@
+use bevy::ecs::component::Component;
+
+/// Which weapon the player wants an entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component to switch to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponSwitch {
+    Next,
+    Previous,
+    /// The weapon in this slot, counting from `0`
+    Slot(usize),
+}
+
+/// This struct defines what an entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component
+/// should do with the weapons in its head's [`Armory`](crate::weapons::Armory).
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WeaponIntents {
+    fire_held: bool,
+    fire_pressed: bool,
+    reload: bool,
+    switch: Option<WeaponSwitch>,
+}
+
+impl WeaponIntents {
+    /// Whether the fire action is held, and whether it was pressed this frame
+    pub fn set_fire(&mut self, held: bool, pressed: bool) {
+        self.fire_held = held;
+        self.fire_pressed = pressed;
+    }
+
+    pub fn set_reload(&mut self, reload: bool) {
+        self.reload = reload;
+    }
+
+    pub fn set_switch(&mut self, switch: Option<WeaponSwitch>) {
+        self.switch = switch;
+    }
+
+    pub fn fire_held(&self) -> bool {
+        self.fire_held
+    }
+
+    pub fn fire_pressed(&self) -> bool {
+        self.fire_pressed
+    }
+
+    pub fn reload(&self) -> bool {
+        self.reload
+    }
+
+    pub fn switch(&self) -> Option<WeaponSwitch> {
+        self.switch
+    }
+}
@@ -0,0 +1,455 @@
+use crate::components::{FirstPersonHead, FirstPersonSubject, Movement, MovementDirection};
+use crate::console::{
+    cvars, format_cvar_value, set_cvar, Console, ConsoleCommand, ConsoleCommandEntered,
+    ConsoleCommands,
+};
+use crate::debug::PhysicsDebugOverlay;
+use crate::resources::{GameClock, GameConfig, GameSettings};
+use crate::scripting::level_named;
+use crate::states::GameLevel;
+use bevy::app::{Events, ManualEventReader};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How many times faster than their max speed players fly in noclip
+const NOCLIP_SPEED_MULTIPLIER: f32 = 3f32;
+/// The most entities `entities` lists
+const MAX_LISTED_ENTITIES: usize = 100;
+
+/// The commands named `name` entered since `events` was last read
+fn entered(events: &mut EventReader<ConsoleCommandEntered>, name: &str) -> Vec<ConsoleCommand> {
+    events
+        .iter()
+        .filter(|event| event.command.name == name)
+        .map(|event| event.command.clone())
+        .collect()
+}
+
+/// `help` lists every command, `help <command>` explains one
+pub fn run_help_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    console_commands: Res<ConsoleCommands>,
+) {
+    for command in entered(&mut events, "help") {
+        match command.args.as_slice() {
+            [] => {
+                for (name, info) in console_commands.iter() {
+                    console.print(format!("{} {} - {}", name, info.usage(), info.help()));
+                }
+                console.print(
+                    "Enter a cvar's name to see its value, or follow it with a value to set it",
+                );
+            }
+            [name] => match console_commands.get(name) {
+                Some(info) => console.print(format!("{} {} - {}", name, info.usage(), info.help())),
+                None => console.print(format!("There's no command named {}", name)),
+            },
+            _ => console.print("Usage: help [command]"),
+        }
+    }
+}
+
+pub fn run_clear_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+) {
+    if !entered(&mut events, "clear").is_empty() {
+        console.clear();
+    }
+}
+
+/// Show or set the cvars entered, and list them with `cvars [prefix]`.
+///
+/// Note: The [`GameConfig`](crate::resources::GameConfig) and
+/// [`GameSettings`](crate::resources::GameSettings) are only replaced when a cvar actually
+/// changes, so systems watching them for changes aren't woken up by mistakes.
+pub fn run_cvar_commands(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    console_commands: Res<ConsoleCommands>,
+    mut game_config: ResMut<GameConfig>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for event in events.iter() {
+        let command = &event.command;
+        if command.name == "cvars" {
+            let prefix = command.args.first().map_or("", String::as_str);
+            for (name, value) in cvars(&game_config, &settings) {
+                if name.starts_with(prefix) {
+                    console.print(format!("{} = {}", name, format_cvar_value(&value)));
+                }
+            }
+            continue;
+        }
+        if console_commands.contains(&command.name) {
+            continue;
+        }
+        match command.args.as_slice() {
+            [] => match cvars(&game_config, &settings).get(&command.name) {
+                Some(value) => {
+                    console.print(format!("{} = {}", command.name, format_cvar_value(value)))
+                }
+                None => console.print(format!("There's no cvar named {}", command.name)),
+            },
+            [raw] => {
+                let mut new_config = game_config.clone();
+                let mut new_settings = settings.clone();
+                match set_cvar(&mut new_config, &mut new_settings, &command.name, raw) {
+                    Ok(value) => {
+                        if new_config.content_hash() != game_config.content_hash() {
+                            *game_config = new_config;
+                        }
+                        if new_settings != *settings {
+                            *settings = new_settings;
+                        }
+                        console.print(format!("{} = {}", command.name, format_cvar_value(&value)));
+                    }
+                    Err(cvar_err) => console.print(cvar_err),
+                }
+            }
+            _ => console.print(format!("Usage: {} [value]", command.name)),
+        }
+    }
+}
+
+/// `teleport <x> <y> <z>` moves every player there, and stops them
+pub fn run_teleport_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    mut query: Query<
+        (
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+        ),
+        With<FirstPersonSubject>,
+    >,
+) {
+    for command in entered(&mut events, "teleport") {
+        let coordinates = match command.parse_args::<f32>() {
+            Ok(coordinates) if coordinates.len() == 3 => coordinates,
+            Ok(_) => {
+                console.print("Usage: teleport <x> <y> <z>");
+                continue;
+            }
+            Err(args_err) => {
+                console.print(args_err);
+                continue;
+            }
+        };
+        let translation = vector![coordinates[0], coordinates[1], coordinates[2]];
+        for (mut position, mut velocity) in query.iter_mut() {
+            position.position.translation.vector = translation;
+            position.next_position.translation.vector = translation;
+            velocity.linvel = Vector::zeros();
+            velocity.angvel = Vector::zeros();
+        }
+        console.print(format!(
+            "Teleported to {} {} {}",
+            coordinates[0], coordinates[1], coordinates[2]
+        ));
+    }
+}
+
+/// This component lets a [`FirstPersonSubject`](crate::components::FirstPersonSubject) fly through
+/// walls, see [`fly_noclip_subjects`](crate::console::fly_noclip_subjects)
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Noclip;
+
+/// `noclip` turns every player's body into a sensor that flies where it's looking, or back
+#[allow(clippy::type_complexity)]
+pub fn run_noclip_command(
+    mut commands: Commands,
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    mut query: Query<
+        (Entity, Option<&Noclip>, &mut RigidBodyVelocityComponent),
+        With<FirstPersonSubject>,
+    >,
+) {
+    for _ in entered(&mut events, "noclip") {
+        for (subject, noclip, mut velocity) in query.iter_mut() {
+            velocity.linvel = Vector::zeros();
+            let mut subject_commands = commands.entity(subject);
+            if noclip.is_some() {
+                subject_commands
+                    .remove::<Noclip>()
+                    .insert(RigidBodyTypeComponent::from(RigidBodyType::Dynamic))
+                    .insert(ColliderTypeComponent::from(ColliderType::Solid));
+                console.print("noclip off");
+            } else {
+                subject_commands
+                    .insert(Noclip)
+                    .insert(RigidBodyTypeComponent::from(
+                        RigidBodyType::KinematicVelocityBased,
+                    ))
+                    .insert(ColliderTypeComponent::from(ColliderType::Sensor));
+                console.print("noclip on");
+            }
+        }
+    }
+}
+
+/// Fly every [`Noclip`](crate::console::Noclip) subject the way it's moving, relative to where its
+/// head is looking, so it can go up and down
+#[allow(clippy::type_complexity)]
+pub fn fly_noclip_subjects(
+    mut subject_query: Query<
+        (
+            Entity,
+            &Movement,
+            &mut RigidBodyVelocityComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        (With<FirstPersonSubject>, With<Noclip>),
+    >,
+    head_query: Query<(&GlobalTransform, &Parent), With<FirstPersonHead>>,
+    game_config: Res<GameConfig>,
+) {
+    let speed = game_config.player().max_speed() * NOCLIP_SPEED_MULTIPLIER;
+    for (subject, movement, mut velocity, mut activation) in subject_query.iter_mut() {
+        let head_transform = match head_query.iter().find(|(_, parent)| parent.0 == subject) {
+            Some((head_transform, _)) => head_transform,
+            None => continue,
+        };
+        let forward_back = match movement.forward_back() {
+            MovementDirection::Forward(magnitude) => magnitude,
+            MovementDirection::Back(magnitude) => -magnitude,
+            _ => 0f32,
+        };
+        let left_right = match movement.left_right() {
+            MovementDirection::Right(magnitude) => magnitude,
+            MovementDirection::Left(magnitude) => -magnitude,
+            _ => 0f32,
+        };
+        let direction = (-head_transform.local_z() * forward_back
+            + head_transform.local_x() * left_right)
+            .normalize_or_zero();
+        velocity.linvel = (direction * speed * movement.magnitude()).into();
+        activation.wake_up(true);
+    }
+}
+
+/// `level <id>` loads a level, the way a level script's `change_level` action names it
+pub fn run_level_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    for command in entered(&mut events, "level") {
+        let id = match command.args.as_slice() {
+            [id] => id,
+            _ => {
+                console.print("Usage: level <id>");
+                continue;
+            }
+        };
+        match level_named(id) {
+            Some(level) => match game_level.replace(level) {
+                Ok(()) => console.print(format!("Loading {}", id)),
+                Err(state_err) => console.print(format!("Can't load {}: {:?}", id, state_err)),
+            },
+            None => console.print(format!("There's no level {}", id)),
+        }
+    }
+}
+
+/// `timescale` shows how fast game time passes, `timescale <scale>` sets it
+pub fn run_timescale_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    mut game_clock: ResMut<GameClock>,
+) {
+    for command in entered(&mut events, "timescale") {
+        match command.parse_args::<f32>().as_deref() {
+            Ok([]) => {}
+            Ok([time_scale]) => game_clock.set_time_scale(*time_scale),
+            Ok(_) => {
+                console.print("Usage: timescale [scale]");
+                continue;
+            }
+            Err(args_err) => {
+                console.print(args_err);
+                continue;
+            }
+        }
+        console.print(format!("timescale = {}", game_clock.time_scale()));
+    }
+}
+
+/// `physics_debug` turns the physics debug overlay on and off. There's only an overlay with the
+/// `dev-tools` feature, which adds the `PhysicsDebugPlugin`.
+pub fn run_physics_debug_command(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut console: ResMut<Console>,
+    mut overlay: Option<ResMut<PhysicsDebugOverlay>>,
+) {
+    for _ in entered(&mut events, "physics_debug") {
+        match overlay.as_mut() {
+            Some(overlay) => {
+                overlay.toggle();
+                console.print(format!(
+                    "Physics debug overlay {}",
+                    if overlay.enabled { "on" } else { "off" }
+                ));
+            }
+            None => console.print("There's no physics debug overlay without the dev-tools feature"),
+        }
+    }
+}
+
+/// A type's name without the paths of its modules, ie `Handle<Mesh>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or_default());
+            path.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+/// The short names of an entity's components, in alphabetical order
+fn component_names(world: &World, entity: Entity) -> Vec<String> {
+    let mut names: Vec<String> = world
+        .entity(entity)
+        .archetype()
+        .components()
+        .filter_map(|component_id| world.components().get_info(component_id))
+        .map(|info| short_type_name(info.name()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// An entity's id and name, if it has one
+fn describe_entity(world: &World, entity: Entity) -> String {
+    match world.get::<Name>(entity) {
+        Some(name) => format!("{:?} \"{}\"", entity, name.as_str()),
+        None => format!("{:?}", entity),
+    }
+}
+
+/// Every entity, or the ones with a component named `component`
+fn list_entities(world: &mut World, component: Option<&str>) -> String {
+    let mut entities: Vec<Entity> = world.query::<Entity>().iter(world).collect();
+    entities.sort();
+    let world: &World = world;
+    let mut lines: Vec<String> = entities
+        .into_iter()
+        .filter_map(|entity| {
+            let names = component_names(world, entity);
+            match component {
+                Some(component) if !names.iter().any(|name| name == component) => None,
+                _ => Some(format!(
+                    "{} ({} components)",
+                    describe_entity(world, entity),
+                    names.len()
+                )),
+            }
+        })
+        .collect();
+    let count = lines.len();
+    if count > MAX_LISTED_ENTITIES {
+        lines.truncate(MAX_LISTED_ENTITIES);
+        lines.push(format!("...and {} more", count - MAX_LISTED_ENTITIES));
+    }
+    lines.push(format!("{} entities", count));
+    lines.join("\n")
+}
+
+/// Every component of the entity with the id `id`, ie `12` or `12v0` as entities are listed
+fn dump_entity(world: &mut World, id: &str) -> String {
+    let index = match id
+        .split('v')
+        .next()
+        .and_then(|index| index.parse::<u32>().ok())
+    {
+        Some(index) => index,
+        None => return format!("{} isn't an entity", id),
+    };
+    let entity = match world
+        .query::<Entity>()
+        .iter(world)
+        .find(|entity| entity.id() == index)
+    {
+        Some(entity) => entity,
+        None => return format!("There's no entity {}", id),
+    };
+    let mut lines = vec![describe_entity(world, entity)];
+    if let Some(transform) = world.get::<GlobalTransform>(entity) {
+        lines.push(format!(
+            "  at {} facing {}",
+            transform.translation,
+            -transform.local_z()
+        ));
+    }
+    lines.extend(
+        component_names(world, entity)
+            .into_iter()
+            .map(|name| format!("  {}", name)),
+    );
+    lines.join("\n")
+}
+
+/// This resource is where [`run_entity_commands`](crate::console::run_entity_commands) is up to in
+/// the [`ConsoleCommandEntered`](crate::console::ConsoleCommandEntered) events
+#[derive(Default)]
+pub struct EntityCommandReader(ManualEventReader<ConsoleCommandEntered>);
+
+/// `entities [component]` lists every entity, or the ones with a component, and `dump <entity>`
+/// lists an entity's components. This needs the whole world to find every component.
+pub fn run_entity_commands(world: &mut World) {
+    let commands: Vec<ConsoleCommand> =
+        world.resource_scope(|world, mut reader: Mut<EntityCommandReader>| {
+            let events = world
+                .get_resource::<Events<ConsoleCommandEntered>>()
+                .expect("ConsoleCommandEntered events are added by the ConsolePlugin");
+            reader
+                .0
+                .iter(events)
+                .filter(|event| ["entities", "dump"].contains(&event.command.name.as_str()))
+                .map(|event| event.command.clone())
+                .collect()
+        });
+    for command in commands {
+        let output = match (command.name.as_str(), command.args.as_slice()) {
+            ("entities", []) => list_entities(world, None),
+            ("entities", [component]) => list_entities(world, Some(component)),
+            ("entities", _) => String::from("Usage: entities [component]"),
+            (_, [id]) => dump_entity(world, id),
+            _ => String::from("Usage: dump <entity>"),
+        };
+        if let Some(mut console) = world.get_resource_mut::<Console>() {
+            console.print(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(
+            short_type_name("bevy_transform::components::transform::Transform"),
+            "Transform"
+        );
+        assert_eq!(
+            short_type_name("bevy_asset::handle::Handle<bevy_render::mesh::mesh::Mesh>"),
+            "Handle<Mesh>"
+        );
+        assert_eq!(
+            short_type_name("core::option::Option<(alloc::string::String, u8)>"),
+            "Option<(String, u8)>"
+        );
+        assert_eq!(short_type_name("Noclip"), "Noclip");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::console::ConsoleCommand;
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+/// How a console command is used, for `help` and autocomplete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleCommandInfo {
+    usage: String,
+    help: String,
+}
+
+impl ConsoleCommandInfo {
+    /// The command's arguments, ie `<x> <y> <z>`
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+}
+
+/// This resource is every command the console knows, by name. Cvars, the fields of the
+/// [`GameConfig`](crate::resources::GameConfig) and [`GameSettings`](crate::resources::GameSettings),
+/// aren't in it, see [`cvars`](crate::console::cvars).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<String, ConsoleCommandInfo>,
+}
+
+impl ConsoleCommands {
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        usage: impl Into<String>,
+        help: impl Into<String>,
+    ) {
+        self.commands.insert(
+            name.into(),
+            ConsoleCommandInfo {
+                usage: usage.into(),
+                help: help.into(),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommandInfo> {
+        self.commands.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(name)
+    }
+
+    /// The commands and how they're used, in alphabetical order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConsoleCommandInfo)> {
+        self.commands
+            .iter()
+            .map(|(name, info)| (name.as_str(), info))
+    }
+}
+
+/// This event is sent for every command entered into the console that's either registered in the
+/// [`ConsoleCommands`](crate::console::ConsoleCommands) or a cvar. The systems that run a
+/// command look for its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleCommandEntered {
+    pub command: ConsoleCommand,
+}
+
+pub trait RegisterConsoleCommand {
+    /// Add a command to the console, to be run by a system reading
+    /// [`ConsoleCommandEntered`](crate::console::ConsoleCommandEntered) events. This needs the
+    /// [`ConsolePlugin`](crate::plugins::ConsolePlugin).
+    fn register_console_command(&mut self, name: &str, usage: &str, help: &str) -> &mut Self;
+}
+
+impl RegisterConsoleCommand for App {
+    fn register_console_command(&mut self, name: &str, usage: &str, help: &str) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .register(name, usage, help);
+        self
+    }
+}
@@ -0,0 +1,236 @@
+use crate::resources::{GameConfig, GameSettings};
+use std::collections::BTreeMap;
+use toml::Value;
+
+/// The sections of the [`GameConfig`](crate::resources::GameConfig) that are cvars. The rest of it
+/// is only read at startup.
+const CONFIG_SECTIONS: [&str; 3] = ["player", "npc", "materials"];
+/// What the [`GameSettings`](crate::resources::GameSettings) are called
+const SETTINGS_SECTION: &str = "settings";
+
+fn flatten(path: &str, value: &Value, cvars: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                flatten(&format!("{}.{}", path, key), value, cvars);
+            }
+        }
+        _ => {
+            cvars.insert(path.to_string(), value.clone());
+        }
+    }
+}
+
+/// Every cvar and its value
+pub fn cvars(game_config: &GameConfig, settings: &GameSettings) -> BTreeMap<String, Value> {
+    let mut cvars = BTreeMap::new();
+    if let Ok(Value::Table(config)) = Value::try_from(game_config) {
+        for section in CONFIG_SECTIONS {
+            if let Some(value) = config.get(section) {
+                flatten(section, value, &mut cvars);
+            }
+        }
+    }
+    if let Ok(settings) = Value::try_from(settings) {
+        flatten(SETTINGS_SECTION, &settings, &mut cvars);
+    }
+    cvars
+}
+
+/// A cvar's value as it's typed into the console
+pub fn format_cvar_value(value: &Value) -> String {
+    match value {
+        // Every float is an f32 in the resources, which reads better without the f64 noise
+        Value::Float(float) => (*float as f32).to_string(),
+        Value::String(string) => string.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Parse what was typed into the console as a new value for a cvar whose value is `current`. Whole
+/// numbers can be given to float cvars.
+pub fn parse_cvar_value(raw: &str, current: &Value) -> Result<Value, String> {
+    match current {
+        Value::Float(_) => match raw.parse::<f64>() {
+            Ok(float) if float.is_finite() => Ok(Value::Float(float)),
+            _ => Err(format!("{} isn't a number", raw)),
+        },
+        Value::Integer(_) => raw
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| format!("{} isn't a whole number", raw)),
+        Value::Boolean(_) => match raw {
+            "true" | "on" | "1" => Ok(Value::Boolean(true)),
+            "false" | "off" | "0" => Ok(Value::Boolean(false)),
+            _ => Err(format!("{} isn't true or false", raw)),
+        },
+        Value::String(_) => Ok(Value::String(raw.to_string())),
+        _ => Err(String::from("This can't be set from the console")),
+    }
+}
+
+/// Replace the value at `path` in `root` with `raw` parsed like the value that's there
+fn set_path(root: &mut Value, path: &str, raw: &str) -> Result<(), String> {
+    let mut value = root;
+    for key in path.split('.') {
+        value = value
+            .get_mut(key)
+            .ok_or_else(|| format!("There's no cvar named {}", path))?;
+    }
+    if value.is_table() {
+        return Err(format!("There's no cvar named {}", path));
+    }
+    *value = parse_cvar_value(raw, value)?;
+    Ok(())
+}
+
+/// Set the cvar `name` to `raw`, returning its new value. If it can't be parsed or isn't valid for
+/// the field, ie a negative capsule radius, nothing changes.
+pub fn set_cvar(
+    game_config: &mut GameConfig,
+    settings: &mut GameSettings,
+    name: &str,
+    raw: &str,
+) -> Result<Value, String> {
+    let section = name.split('.').next().unwrap_or(name);
+    let settings_path = name
+        .strip_prefix(SETTINGS_SECTION)
+        .and_then(|path| path.strip_prefix('.'));
+    if let Some(path) = settings_path {
+        let mut root = Value::try_from(&*settings).map_err(|ser_err| ser_err.to_string())?;
+        set_path(&mut root, path, raw)?;
+        *settings = root.try_into().map_err(|de_err| de_err.to_string())?;
+    } else if CONFIG_SECTIONS.contains(&section) {
+        let mut root = Value::try_from(&*game_config).map_err(|ser_err| ser_err.to_string())?;
+        set_path(&mut root, name, raw)?;
+        let new_config: GameConfig = root.try_into().map_err(|de_err| de_err.to_string())?;
+        new_config.validate()?;
+        *game_config = new_config;
+    } else {
+        return Err(format!("There's no cvar named {}", name));
+    }
+    cvars(game_config, settings)
+        .remove(name)
+        .ok_or_else(|| format!("There's no cvar named {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::CombineRule;
+
+    #[test]
+    fn test_cvars() {
+        let cvars = cvars(&GameConfig::default(), &GameSettings::default());
+        assert_eq!(cvars.get("player.max_speed"), Some(&Value::Float(5.0)));
+        assert_eq!(cvars.get("npc.walk_speed").map(Value::is_float), Some(true));
+        assert_eq!(
+            cvars.get("materials.ice.footsteps"),
+            Some(&Value::String(String::from("ice")))
+        );
+        assert_eq!(
+            cvars.get("settings.master_volume"),
+            Some(&Value::Integer(100))
+        );
+        // Only read at startup
+        assert!(!cvars.contains_key("log_level"));
+        assert!(!cvars.contains_key("player"));
+    }
+
+    #[test]
+    fn test_parse_cvar_value() {
+        let float = Value::Float(1.0);
+        assert_eq!(parse_cvar_value("8", &float), Ok(Value::Float(8.0)));
+        assert!(parse_cvar_value("fast", &float).is_err());
+        assert!(parse_cvar_value("inf", &float).is_err());
+        let integer = Value::Integer(1);
+        assert_eq!(parse_cvar_value("12", &integer), Ok(Value::Integer(12)));
+        assert!(parse_cvar_value("1.5", &integer).is_err());
+        let boolean = Value::Boolean(false);
+        assert_eq!(parse_cvar_value("on", &boolean), Ok(Value::Boolean(true)));
+        assert!(parse_cvar_value("maybe", &boolean).is_err());
+        assert_eq!(
+            format_cvar_value(&Value::Float(0.02f32 as f64)),
+            String::from("0.02")
+        );
+    }
+
+    #[test]
+    fn test_set_cvar() {
+        let mut game_config = GameConfig::default();
+        let mut settings = GameSettings::default();
+        assert_eq!(
+            set_cvar(&mut game_config, &mut settings, "player.max_speed", "8"),
+            Ok(Value::Float(8.0))
+        );
+        assert_eq!(game_config.player().max_speed(), 8f32);
+        set_cvar(
+            &mut game_config,
+            &mut settings,
+            "materials.ice.friction_combine",
+            "max",
+        )
+        .unwrap();
+        assert_eq!(
+            game_config.material("ice").unwrap().friction_combine(),
+            CombineRule::Max
+        );
+
+        assert_eq!(
+            set_cvar(
+                &mut game_config,
+                &mut settings,
+                "settings.sfx_volume",
+                "250"
+            ),
+            Ok(Value::Integer(100))
+        );
+        assert_eq!(settings.sfx_volume(), 100);
+        assert_eq!(
+            set_cvar(
+                &mut game_config,
+                &mut settings,
+                "settings.master_volume",
+                "1000"
+            ),
+            Ok(Value::Integer(100))
+        );
+        set_cvar(
+            &mut game_config,
+            &mut settings,
+            "settings.horizontal_sensitivity",
+            "7",
+        )
+        .unwrap();
+        assert_eq!(settings.horizontal_sensitivity(), 7);
+    }
+
+    #[test]
+    fn test_set_cvar_keeps_the_old_value_on_errors() {
+        let mut game_config = GameConfig::default();
+        let mut settings = GameSettings::default();
+        for (name, raw) in [
+            ("player.max_speed", "fast"),
+            ("player.inventory_slots", "-1"),
+            ("player.capsule_radius", "-1"),
+            ("materials.ice.friction_combine", "sideways"),
+            ("settings.master_volume", "-1"),
+            ("settings.master_volume", "loud"),
+            ("player.no_such_field", "1"),
+            ("player", "1"),
+            ("settings", "1"),
+            ("log_level", "trace"),
+        ] {
+            assert!(
+                set_cvar(&mut game_config, &mut settings, name, raw).is_err(),
+                "{} {}",
+                name,
+                raw
+            );
+        }
+        assert_eq!(
+            cvars(&game_config, &settings),
+            cvars(&GameConfig::default(), &GameSettings::default())
+        );
+    }
+}
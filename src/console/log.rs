@@ -0,0 +1,129 @@
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::{Event, Level, Subscriber};
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Registry};
+
+/// How many log lines are kept until the console takes them
+const MAX_PENDING_LINES: usize = 200;
+
+/// This resource is the log lines waiting to be echoed in the console. It's shared with the
+/// [`ConsoleLogLayer`](crate::console::ConsoleLogLayer), which fills it from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleLogBuffer(Arc<Mutex<VecDeque<String>>>);
+
+impl ConsoleLogBuffer {
+    fn push(&self, line: String) {
+        if let Ok(mut lines) = self.0.lock() {
+            if lines.len() == MAX_PENDING_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+
+    /// Take every line logged since the last call
+    pub fn drain(&self) -> Vec<String> {
+        match self.0.lock() {
+            Ok(mut lines) => lines.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Writes an event's message, followed by its other fields
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            // Where the events of the `log` crate came from
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={}", name, value);
+            }
+        }
+    }
+}
+
+/// A tracing layer that copies every event it's given into a
+/// [`ConsoleLogBuffer`](crate::console::ConsoleLogBuffer), as `LEVEL message field=value`
+pub struct ConsoleLogLayer {
+    buffer: ConsoleLogBuffer,
+}
+
+impl ConsoleLogLayer {
+    pub fn new(buffer: ConsoleLogBuffer) -> Self {
+        ConsoleLogLayer { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        self.buffer.push(format!(
+            "{} {}{}",
+            event.metadata().level(),
+            visitor.message,
+            visitor.fields
+        ));
+    }
+}
+
+/// Set logging up like bevy's `LogPlugin` does, with the same `RUST_LOG` override, but also echo
+/// everything that's logged in the console through `buffer`. The `LogPlugin` mustn't be added too.
+pub fn init_logging(level: Level, filter: &str, buffer: ConsoleLogBuffer) {
+    let default_filter = format!("{},{}", level, filter);
+    let filter_layer = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&default_filter))
+        .unwrap();
+    let subscriber = Registry::default()
+        .with(filter_layer)
+        .with(tracing_subscriber::fmt::layer())
+        .with(ConsoleLogLayer::new(buffer));
+    if subscriber.try_init().is_err() {
+        eprintln!("Logging was already set up, so it won't be echoed in the console");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+
+    #[test]
+    fn test_log_lines_are_buffered() {
+        let buffer = ConsoleLogBuffer::default();
+        let subscriber = Registry::default().with(ConsoleLogLayer::new(buffer.clone()));
+        bevy::utils::tracing::subscriber::with_default(subscriber, || {
+            info!("Loaded {} levels", 2);
+            warn!(entity = 7, "Something's off");
+        });
+        assert_eq!(
+            buffer.drain(),
+            vec!["INFO Loaded 2 levels", "WARN Something's off entity=7"]
+        );
+        assert!(buffer.drain().is_empty());
+    }
+}
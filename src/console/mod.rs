@@ -0,0 +1,28 @@
+//! A drop-down developer console, opened with the backtick key.
+//!
+//! A line typed into it is split into words by [`parse_command_line`](crate::console::parse_command_line),
+//! and the first word names either a command or a cvar. Commands are registered in the
+//! [`ConsoleCommands`](crate::console::ConsoleCommands) resource, ie with
+//! [`RegisterConsoleCommand`](crate::console::RegisterConsoleCommand), and run by the systems that
+//! read their [`ConsoleCommandEntered`](crate::console::ConsoleCommandEntered) events. Cvars are the
+//! fields of the [`GameConfig`](crate::resources::GameConfig) sections and of the
+//! [`GameSettings`](crate::resources::GameSettings), named by their TOML path, ie `player.max_speed`
+//! or `settings.master_volume`: `player.max_speed` shows its value and `player.max_speed 8` sets it.
+//!
+//! Everything that's logged is echoed in the console too, when logging is set up with
+//! [`init_logging`](crate::console::init_logging).
+mod builtins;
+mod commands;
+mod cvars;
+mod log;
+mod parser;
+mod state;
+mod systems;
+
+pub use self::builtins::*;
+pub use self::commands::*;
+pub use self::cvars::*;
+pub use self::log::*;
+pub use self::parser::*;
+pub use self::state::*;
+pub use self::systems::*;
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A line typed into the console, split into the command's name and its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    pub fn new(name: impl Into<String>, args: &[&str]) -> Self {
+        ConsoleCommand {
+            name: name.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// Every argument parsed as a `T`, or a message saying which one isn't one
+    pub fn parse_args<T: FromStr>(&self) -> Result<Vec<T>, String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                arg.parse::<T>()
+                    .map_err(|_| format!("{}: {} isn't a valid argument", self.name, arg))
+            })
+            .collect()
+    }
+}
+
+/// Why a console line couldn't be split into words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// A quote was opened but never closed
+    UnclosedQuote,
+    /// The line ended with a backslash, so there's nothing for it to escape
+    TrailingEscape,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnclosedQuote => write!(f, "A quote was never closed"),
+            ParseError::TrailingEscape => write!(f, "The line ends with a lone \\"),
+        }
+    }
+}
+
+/// Split a line into words at whitespace. Words can be quoted with `"` or `'` to keep their
+/// whitespace, and a backslash makes the next character part of the word, ie `\"`.
+pub fn tokenize(line: &str) -> Result<Vec<String>, ParseError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // Whether there's a word being read, which can be an empty quoted one
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                word.push(chars.next().ok_or(ParseError::TrailingEscape)?);
+                in_word = true;
+            }
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => word.push(c),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (c, None) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (c, None) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(ParseError::UnclosedQuote);
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Parse a line typed into the console. A blank line is `None`.
+pub fn parse_command_line(line: &str) -> Result<Option<ConsoleCommand>, ParseError> {
+    let mut words = tokenize(line)?.into_iter();
+    Ok(words.next().map(|name| ConsoleCommand {
+        name,
+        args: words.collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("  teleport 1  2.5\t-3 ").unwrap(),
+            vec!["teleport", "1", "2.5", "-3"]
+        );
+        assert_eq!(
+            tokenize("say \"hello world\" 'it''s' \"\"").unwrap(),
+            vec!["say", "hello world", "its", ""]
+        );
+        assert_eq!(
+            tokenize(r#"say \"quoted\" back\\slash a\ b"#).unwrap(),
+            vec!["say", "\"quoted\"", "back\\slash", "a b"]
+        );
+        assert_eq!(tokenize("   ").unwrap(), Vec::<String>::new());
+        assert_eq!(tokenize("say \"oops"), Err(ParseError::UnclosedQuote));
+        assert_eq!(tokenize("say oops\\"), Err(ParseError::TrailingEscape));
+    }
+
+    #[test]
+    fn test_parse_command_line() {
+        assert_eq!(
+            parse_command_line("player.max_speed 8").unwrap(),
+            Some(ConsoleCommand::new("player.max_speed", &["8"]))
+        );
+        assert_eq!(
+            parse_command_line("noclip").unwrap(),
+            Some(ConsoleCommand::new("noclip", &[]))
+        );
+        assert_eq!(parse_command_line("").unwrap(), None);
+        assert_eq!(parse_command_line("'oops"), Err(ParseError::UnclosedQuote));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let teleport = parse_command_line("teleport 1 2.5 -3").unwrap().unwrap();
+        assert_eq!(teleport.parse_args::<f32>(), Ok(vec![1f32, 2.5, -3f32]));
+        let bad = ConsoleCommand::new("teleport", &["1", "up"]);
+        assert_eq!(
+            bad.parse_args::<f32>(),
+            Err(String::from("teleport: up isn't a valid argument"))
+        );
+    }
+}
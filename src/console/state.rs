@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+
+/// How many lines of output the console keeps
+const MAX_LINES: usize = 500;
+/// How many entered lines the console remembers
+const MAX_HISTORY: usize = 100;
+
+/// The longest string every one of `words` starts with
+fn common_prefix<'a>(mut words: impl Iterator<Item = &'a str>) -> String {
+    let first = match words.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let length = words.fold(first.len(), |length, word| {
+        first[..length]
+            .char_indices()
+            .zip(word.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(length.min(word.len()), |((index, _), _)| index)
+    });
+    first[..length].to_string()
+}
+
+/// This resource is the drop-down developer console: whether it's open, what's being typed into it,
+/// what was entered before and what it printed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Console {
+    open: bool,
+    input: String,
+    /// Entered lines, oldest first
+    history: Vec<String>,
+    /// The entry of the history being shown in the input, while browsing it
+    history_index: Option<usize>,
+    lines: VecDeque<String>,
+    /// Lines entered since they were last taken to be run
+    entered: Vec<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// The output, oldest first
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// Add a line to the output, ie the result of a command
+    pub fn print(&mut self, line: impl Into<String>) {
+        for line in line.into().lines() {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line.to_string());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        self.input.push(c);
+        self.history_index = None;
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.history_index = None;
+    }
+
+    /// Enter a line, as if it was typed and submitted. It's echoed and remembered in the history,
+    /// and run on the next update.
+    pub fn enter(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        self.print(format!("> {}", line));
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        self.entered.push(line);
+    }
+
+    /// Enter what's been typed in, and clear the input
+    pub fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.history_index = None;
+        self.enter(line);
+    }
+
+    pub fn has_entered(&self) -> bool {
+        !self.entered.is_empty()
+    }
+
+    /// Take the lines entered since the last call, to run them
+    pub fn take_entered(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.entered)
+    }
+
+    /// Show the line entered before the one being shown
+    pub fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    /// Show the line entered after the one being shown, or nothing past the last one
+    pub fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.input = self.history[index + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+    }
+
+    /// Complete the name being typed with one of `names`. When several match, it's completed as
+    /// far as they agree, and if that's no further, they're all printed.
+    pub fn complete<'a>(&mut self, names: impl Iterator<Item = &'a str>) {
+        // Only the name of the command is completed
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+        let matches: Vec<&str> = names.filter(|name| name.starts_with(&self.input)).collect();
+        match matches.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{} ", name),
+            _ => {
+                let prefix = common_prefix(matches.iter().copied());
+                if prefix.len() > self.input.len() {
+                    self.input = prefix;
+                } else {
+                    self.print(matches.join("  "));
+                }
+            }
+        }
+        self.history_index = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_prefix() {
+        assert_eq!(
+            common_prefix(["player.max_speed", "player.max_health"].into_iter()),
+            "player.max_"
+        );
+        assert_eq!(common_prefix(["noclip"].into_iter()), "noclip");
+        assert_eq!(common_prefix(["ab", "a", "abc"].into_iter()), "a");
+        assert_eq!(common_prefix(["npc", "level"].into_iter()), "");
+        assert_eq!(common_prefix(std::iter::empty()), "");
+    }
+
+    #[test]
+    fn test_submit_and_history() {
+        let mut console = Console::default();
+        for c in "noclip".chars() {
+            console.type_char(c);
+        }
+        console.submit();
+        console.enter("timescale 0.5");
+        console.enter("timescale 0.5");
+        console.enter("");
+        assert_eq!(console.input(), "");
+        assert_eq!(console.history(), ["noclip", "timescale 0.5"]);
+        assert_eq!(
+            console.take_entered(),
+            vec!["noclip", "timescale 0.5", "timescale 0.5", ""]
+        );
+        assert!(console.take_entered().is_empty());
+        assert_eq!(console.lines().next(), Some("> noclip"));
+
+        console.history_next();
+        assert_eq!(console.input(), "");
+        console.history_previous();
+        assert_eq!(console.input(), "timescale 0.5");
+        console.history_previous();
+        console.history_previous();
+        assert_eq!(console.input(), "noclip");
+        console.history_next();
+        assert_eq!(console.input(), "timescale 0.5");
+        console.history_next();
+        assert_eq!(console.input(), "");
+    }
+
+    #[test]
+    fn test_complete() {
+        let names = ["noclip", "npc.walk_speed", "npc.chase_speed", "level"];
+        let mut console = Console::default();
+        console.type_char('n');
+        console.complete(names.into_iter());
+        // "noclip" and "npc." only agree on the "n"
+        assert_eq!(console.input(), "n");
+        assert_eq!(
+            console.lines().last(),
+            Some("noclip  npc.walk_speed  npc.chase_speed")
+        );
+        console.type_char('p');
+        console.complete(names.into_iter());
+        assert_eq!(console.input(), "npc.");
+        console.type_char('w');
+        console.complete(names.into_iter());
+        assert_eq!(console.input(), "npc.walk_speed ");
+        // Arguments aren't completed
+        console.type_char('l');
+        console.complete(names.into_iter());
+        assert_eq!(console.input(), "npc.walk_speed l");
+    }
+
+    #[test]
+    fn test_output_is_capped() {
+        let mut console = Console::default();
+        console.print("first\nsecond");
+        assert_eq!(console.lines().len(), 2);
+        for index in 0..MAX_LINES {
+            console.print(index.to_string());
+        }
+        assert_eq!(console.lines().len(), MAX_LINES);
+        assert_eq!(console.lines().next(), Some("0"));
+    }
+}
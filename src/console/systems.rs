@@ -0,0 +1,231 @@
+use crate::console::{
+    cvars, parse_command_line, Console, ConsoleCommandEntered, ConsoleCommands, ConsoleLogBuffer,
+};
+use crate::resources::{GameConfig, GameSettings, UiTheme};
+use crate::widgets::{self, UiContext};
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+
+/// How much of the window the console covers, in percent of its height
+const CONSOLE_HEIGHT: f32 = 40f32;
+/// How many lines of output the console shows above the input
+const VISIBLE_LINES: usize = 10;
+
+/// This component is on every entity of the console's UI
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleUi;
+
+/// This component is the panel the console is drawn on, which is hidden while it's closed
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsolePanel;
+
+/// This component is the console's text
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleText;
+
+/// Open and close the console with the backtick key. While it's open, it's typed into: Enter runs
+/// the line, Tab completes the name of a command or cvar, Up and Down go through the history and
+/// Escape closes it.
+///
+/// Note: While the console is open, the keys and mouse buttons it's given are taken from the rest of
+/// the game, so the players don't walk, shoot or pause while typing. This must run right after the
+/// input is read.
+#[allow(clippy::too_many_arguments)]
+pub fn read_console_input(
+    mut console: ResMut<Console>,
+    mut char_events: EventReader<ReceivedCharacter>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    console_commands: Res<ConsoleCommands>,
+    game_config: Res<GameConfig>,
+    settings: Res<GameSettings>,
+) {
+    // Read even while closed, so what was typed before isn't typed into the console when it opens
+    let typed: Vec<char> = char_events.iter().map(|event| event.char).collect();
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        let open = !console.is_open();
+        console.set_open(open);
+        keyboard_input.reset(KeyCode::Grave);
+        return;
+    }
+    if !console.is_open() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        console.set_open(false);
+    } else if keyboard_input.just_pressed(KeyCode::Return)
+        || keyboard_input.just_pressed(KeyCode::NumpadEnter)
+    {
+        console.submit();
+    } else if keyboard_input.just_pressed(KeyCode::Back) {
+        console.backspace();
+    } else if keyboard_input.just_pressed(KeyCode::Tab) {
+        let cvars = cvars(&game_config, &settings);
+        console.complete(
+            console_commands
+                .iter()
+                .map(|(name, _)| name)
+                .chain(cvars.keys().map(String::as_str)),
+        );
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        console.history_previous();
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        console.history_next();
+    }
+    // Enter, Backspace and the like are typed as control characters
+    for c in typed.into_iter().filter(|c| !c.is_control() && *c != '`') {
+        console.type_char(c);
+    }
+
+    let pressed_keys: Vec<KeyCode> = keyboard_input.get_pressed().cloned().collect();
+    for key in pressed_keys {
+        keyboard_input.reset(key);
+    }
+    let pressed_buttons: Vec<MouseButton> = mouse_input.get_pressed().cloned().collect();
+    for button in pressed_buttons {
+        mouse_input.reset(button);
+    }
+}
+
+/// Run the lines entered into the console. Commands in the
+/// [`ConsoleCommands`](crate::console::ConsoleCommands) and cvars are sent as a
+/// [`ConsoleCommandEntered`](crate::console::ConsoleCommandEntered) event, anything else is an
+/// error.
+pub fn run_entered_console_lines(
+    mut console: ResMut<Console>,
+    console_commands: Res<ConsoleCommands>,
+    game_config: Res<GameConfig>,
+    settings: Res<GameSettings>,
+    mut entered_events: EventWriter<ConsoleCommandEntered>,
+) {
+    if !console.has_entered() {
+        return;
+    }
+    for line in console.take_entered() {
+        match parse_command_line(&line) {
+            Ok(Some(command)) => {
+                if console_commands.contains(&command.name)
+                    || cvars(&game_config, &settings).contains_key(&command.name)
+                {
+                    entered_events.send(ConsoleCommandEntered { command });
+                } else {
+                    console.print(format!("Unknown command {}, see help", command.name));
+                }
+            }
+            Ok(None) => {}
+            Err(parse_err) => console.print(parse_err.to_string()),
+        }
+    }
+}
+
+/// Echo what's been logged in the console, if logging was set up with a
+/// [`ConsoleLogBuffer`](crate::console::ConsoleLogBuffer)
+pub fn echo_log_to_console(
+    log_buffer: Option<Res<ConsoleLogBuffer>>,
+    mut console: ResMut<Console>,
+) {
+    if let Some(log_buffer) = log_buffer {
+        for line in log_buffer.drain() {
+            console.print(line);
+        }
+    }
+}
+
+/// Add the console's UI, hidden, if there's a renderer to draw it with. It stays up for the whole
+/// game, on top of every level.
+pub fn spawn_console_ui(
+    mut commands: Commands,
+    theme: Option<Res<UiTheme>>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let (theme, asset_server) = match (theme, asset_server) {
+        (Some(theme), Some(asset_server)) => (theme, asset_server),
+        _ => return,
+    };
+    let ui = UiContext::new(&theme, &asset_server, ConsoleUi);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100f32), Val::Percent(CONSOLE_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(0f32),
+                    left: Val::Px(0f32),
+                    ..Default::default()
+                },
+                // Keep the text at the bottom of the panel, by the input
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexStart,
+                padding: Rect::all(Val::Px(8f32)),
+                display: Display::None,
+                ..Default::default()
+            },
+            color: theme.overlay_color().into(),
+            ..Default::default()
+        })
+        .insert(ConsoleUi)
+        .insert(ConsolePanel)
+        .with_children(|panel| {
+            widgets::Label::small("")
+                .spawn(panel, &ui)
+                .insert(ConsoleText);
+        });
+}
+
+/// The last lines of the console's output, followed by the input with a cursor
+fn console_text(console: &Console) -> String {
+    let lines = console.lines();
+    let skipped = lines.len().saturating_sub(VISIBLE_LINES);
+    let mut text: String = lines
+        .skip(skipped)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    text.push_str(&format!("> {}_", console.input()));
+    text
+}
+
+/// Show and hide the console, and draw its text
+pub fn update_console_ui(
+    console: Res<Console>,
+    mut panel_query: Query<&mut Style, With<ConsolePanel>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut style in panel_query.iter_mut() {
+        style.display = if console.is_open() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    if !console.is_open() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            section.value = console_text(&console);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_text() {
+        let mut console = Console::default();
+        for index in 0..VISIBLE_LINES + 5 {
+            console.print(index.to_string());
+        }
+        console.type_char('h');
+        let text = console_text(&console);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), VISIBLE_LINES + 1);
+        assert_eq!(lines[0], "5");
+        assert_eq!(lines[VISIBLE_LINES], "> h_");
+    }
+}
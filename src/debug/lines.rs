@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::PrimitiveTopology;
+
+/// How many segments a circle is drawn with
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// Line segments in world space, to be drawn as a wireframe
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebugLines {
+    segments: Vec<[Vec3; 2]>,
+}
+
+/// Two unit vectors perpendicular to `axis` and to each other
+fn perpendiculars(axis: Vec3) -> (Vec3, Vec3) {
+    let other = if axis.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+    let u = other.cross(axis).normalize();
+    (u, axis.cross(u))
+}
+
+impl DebugLines {
+    pub fn segments(&self) -> &[[Vec3; 2]] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    pub fn line(&mut self, from: Vec3, to: Vec3) {
+        self.segments.push([from, to]);
+    }
+
+    /// A small cross marking a point
+    pub fn cross(&mut self, center: Vec3, size: f32) {
+        let half = size / 2f32;
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.line(center - axis * half, center + axis * half);
+        }
+    }
+
+    /// An arc around `center` in the plane of `u` and `v`, from `start` to `end` radians, where 0 is
+    /// towards `u`
+    fn arc(&mut self, center: Vec3, u: Vec3, v: Vec3, radius: f32, start: f32, end: f32) {
+        let segments = ((end - start).abs() / std::f32::consts::TAU * CIRCLE_SEGMENTS as f32)
+            .ceil()
+            .max(1f32) as usize;
+        let point = |angle: f32| center + (u * angle.cos() + v * angle.sin()) * radius;
+        for index in 0..segments {
+            let from = start + (end - start) * index as f32 / segments as f32;
+            let to = start + (end - start) * (index + 1) as f32 / segments as f32;
+            self.line(point(from), point(to));
+        }
+    }
+
+    fn circle(&mut self, center: Vec3, u: Vec3, v: Vec3, radius: f32) {
+        self.arc(center, u, v, radius, 0f32, std::f32::consts::TAU);
+    }
+
+    /// The edges of a box with `half_extents`, placed by `transform`
+    pub fn cuboid(&mut self, transform: &Transform, half_extents: Vec3) {
+        let corner = |x: f32, y: f32, z: f32| transform.mul_vec3(half_extents * Vec3::new(x, y, z));
+        for (a, b) in [(-1f32, -1f32), (-1f32, 1f32), (1f32, 1f32), (1f32, -1f32)] {
+            // Along X, Y and Z
+            self.line(corner(-1f32, a, b), corner(1f32, a, b));
+            self.line(corner(a, -1f32, b), corner(a, 1f32, b));
+            self.line(corner(a, b, -1f32), corner(a, b, 1f32));
+        }
+    }
+
+    /// A ball as three circles around its center, one in every plane of `transform`
+    pub fn ball(&mut self, transform: &Transform, radius: f32) {
+        let center = transform.translation;
+        let [x, y, z] = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| transform.rotation * axis);
+        self.circle(center, x, y, radius);
+        self.circle(center, y, z, radius);
+        self.circle(center, z, x, radius);
+    }
+
+    /// A capsule around the segment from `a` to `b`, which `transform` places: a circle around
+    /// each end, the lines between them and the rounded caps
+    pub fn capsule(&mut self, transform: &Transform, a: Vec3, b: Vec3, radius: f32) {
+        let (a, b) = (transform.mul_vec3(a), transform.mul_vec3(b));
+        let mut axis = (b - a).normalize_or_zero();
+        if axis == Vec3::ZERO {
+            axis = transform.rotation * Vec3::Y;
+        }
+        let (u, v) = perpendiculars(axis);
+        let pi = std::f32::consts::PI;
+        for (end, outwards) in [(a, -axis), (b, axis)] {
+            self.circle(end, u, v, radius);
+            self.arc(end, u, outwards, radius, 0f32, pi);
+            self.arc(end, v, outwards, radius, 0f32, pi);
+        }
+        for side in [u, -u, v, -v] {
+            self.line(a + side * radius, b + side * radius);
+        }
+    }
+
+    /// The edges of an axis-aligned box from `mins` to `maxs`
+    pub fn aabb(&mut self, mins: Vec3, maxs: Vec3) {
+        self.cuboid(
+            &Transform::from_translation((mins + maxs) / 2f32),
+            (maxs - mins) / 2f32,
+        );
+    }
+
+    /// A mesh of the lines. A mesh without any vertices can't be drawn, so with no lines it's a
+    /// single line with no length.
+    pub fn to_mesh(&self) -> Mesh {
+        let mut positions: Vec<[f32; 3]> = self
+            .segments
+            .iter()
+            .flat_map(|segment| segment.iter().map(|point| point.to_array()))
+            .collect();
+        if positions.is_empty() {
+            positions = vec![[0f32; 3]; 2];
+        }
+        // Lines are unlit, but the PBR pipeline needs these anyway
+        let normals = vec![[0f32, 1f32, 0f32]; positions.len()];
+        let uvs = vec![[0f32; 2]; positions.len()];
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(lines: &DebugLines) -> impl Iterator<Item = Vec3> + '_ {
+        lines
+            .segments()
+            .iter()
+            .flat_map(|segment| segment.iter().copied())
+    }
+
+    #[test]
+    fn test_cuboid_edges() {
+        let mut lines = DebugLines::default();
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0);
+        lines.cuboid(&transform, Vec3::new(1.0, 0.5, 2.0));
+        assert_eq!(lines.segments().len(), 12);
+        for point in points(&lines) {
+            let offset = (point - transform.translation).abs();
+            assert!((offset - Vec3::new(1.0, 0.5, 2.0)).abs().max_element() < 1e-5);
+        }
+        // Every edge is as long as the box is along one of its axes
+        for [from, to] in lines.segments() {
+            let length = from.distance(*to);
+            assert!([2f32, 1f32, 4f32]
+                .iter()
+                .any(|edge| (length - edge).abs() < 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_ball_and_capsule_outlines() {
+        let mut ball = DebugLines::default();
+        ball.ball(&Transform::from_xyz(0.0, 5.0, 0.0), 2f32);
+        assert_eq!(ball.segments().len(), 3 * CIRCLE_SEGMENTS);
+        assert!(points(&ball)
+            .all(|point| (point.distance(Vec3::new(0.0, 5.0, 0.0)) - 2f32).abs() < 1e-4));
+
+        let mut capsule = DebugLines::default();
+        capsule.capsule(
+            &Transform::identity(),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.5,
+        );
+        let top = points(&capsule)
+            .map(|point| point.y)
+            .fold(f32::MIN, f32::max);
+        let bottom = points(&capsule)
+            .map(|point| point.y)
+            .fold(f32::MAX, f32::min);
+        assert!((top - 1.5).abs() < 1e-4);
+        assert!((bottom + 1.5).abs() < 1e-4);
+        assert!(points(&capsule).all(|point| Vec2::new(point.x, point.z).length() < 0.5 + 1e-4));
+    }
+
+    #[test]
+    fn test_empty_lines_still_make_a_mesh() {
+        let mesh = DebugLines::default().to_mesh();
+        assert_eq!(mesh.count_vertices(), 2);
+        let mut lines = DebugLines::default();
+        lines.cross(Vec3::ZERO, 1f32);
+        assert_eq!(lines.to_mesh().count_vertices(), 6);
+    }
+}
@@ -0,0 +1,11 @@
+//! A debug overlay that draws the physics world over the game, to see why bodies move as they do.
+//!
+//! Every collider is drawn as a wireframe, with sensors in a different color, along with the
+//! ground probe players jump with, contact points and normals, and the velocities of dynamic
+//! bodies. It's turned on and off with the [`PhysicsDebugOverlay`](crate::debug::PhysicsDebugOverlay)
+//! resource, or with F3 when the game is built with the `dev-tools` feature.
+mod lines;
+mod overlay;
+
+pub use self::lines::*;
+pub use self::overlay::*;
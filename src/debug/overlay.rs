@@ -0,0 +1,277 @@
+use crate::components::FirstPersonSubject;
+use crate::debug::DebugLines;
+use crate::resources::GameConfig;
+use crate::systems::player::{find_ground, ground_probe, GROUND_PROBE_LENGTH};
+use bevy::prelude::*;
+use bevy::render::view::NoFrustumCulling;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashMap;
+
+/// How long the velocity arrows are, in seconds of travel
+const VELOCITY_SECONDS: f32 = 0.25;
+/// How long contact normals are drawn, in meters
+const CONTACT_NORMAL_LENGTH: f32 = 0.3;
+/// The size of the crosses marking points, in meters
+const MARKER_SIZE: f32 = 0.1;
+
+/// What a line of the physics debug overlay shows, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugLayer {
+    /// Solid colliders
+    Colliders,
+    Sensors,
+    /// A ground probe that found ground to jump off
+    GroundProbeHit,
+    /// A ground probe that didn't, so the player can't jump
+    GroundProbeMiss,
+    /// Contact points and their normals
+    Contacts,
+    /// The velocities of dynamic bodies
+    Velocities,
+}
+
+impl DebugLayer {
+    pub const ALL: [DebugLayer; 6] = [
+        DebugLayer::Colliders,
+        DebugLayer::Sensors,
+        DebugLayer::GroundProbeHit,
+        DebugLayer::GroundProbeMiss,
+        DebugLayer::Contacts,
+        DebugLayer::Velocities,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            DebugLayer::Colliders => Color::CYAN,
+            DebugLayer::Sensors => Color::YELLOW,
+            DebugLayer::GroundProbeHit => Color::LIME_GREEN,
+            DebugLayer::GroundProbeMiss => Color::RED,
+            DebugLayer::Contacts => Color::FUCHSIA,
+            DebugLayer::Velocities => Color::ORANGE,
+        }
+    }
+}
+
+/// This resource turns the physics debug overlay on and off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhysicsDebugOverlay {
+    pub enabled: bool,
+}
+
+impl PhysicsDebugOverlay {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+}
+
+/// This resource is the lines the physics debug overlay draws this frame, by layer. They're all
+/// empty while it's off.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhysicsDebugLines {
+    layers: HashMap<DebugLayer, DebugLines>,
+}
+
+impl PhysicsDebugLines {
+    pub fn layer(&self, layer: DebugLayer) -> Option<&DebugLines> {
+        self.layers.get(&layer)
+    }
+
+    fn layer_mut(&mut self, layer: DebugLayer) -> &mut DebugLines {
+        self.layers.entry(layer).or_default()
+    }
+
+    fn clear(&mut self) {
+        for lines in self.layers.values_mut() {
+            lines.clear();
+        }
+    }
+}
+
+/// This component is the mesh one [`DebugLayer`](crate::debug::DebugLayer) of the overlay is drawn
+/// with
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicsDebugMesh(pub DebugLayer);
+
+fn vec3(vector: &Vector<Real>) -> Vec3 {
+    Vec3::new(vector.x, vector.y, vector.z)
+}
+
+fn transform_of(isometry: &Isometry<Real>) -> Transform {
+    let rotation = isometry.rotation;
+    Transform::from_translation(vec3(&isometry.translation.vector)).with_rotation(Quat::from_xyzw(
+        rotation.i, rotation.j, rotation.k, rotation.w,
+    ))
+}
+
+/// Switch the overlay on and off with F3
+#[cfg(feature = "dev-tools")]
+pub fn toggle_physics_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<PhysicsDebugOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.toggle();
+        info!(
+            "Physics debug overlay {}",
+            if overlay.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+/// Gather the lines of the overlay while it's on: the shape of every collider, the ground probe of
+/// every [`FirstPersonSubject`](crate::components::FirstPersonSubject) as
+/// [`find_ground`](crate::systems::player::find_ground) casts it, every contact point with
+/// its normal, and the velocity of every dynamic body.
+///
+/// Note: Balls, cuboids and capsules are drawn as they are, other shapes as their bounding box.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn collect_physics_debug_lines(
+    overlay: Res<PhysicsDebugOverlay>,
+    mut debug_lines: ResMut<PhysicsDebugLines>,
+    shape_query: Query<(
+        &ColliderShapeComponent,
+        &ColliderPositionComponent,
+        &ColliderTypeComponent,
+    )>,
+    body_query: Query<(
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        &RigidBodyTypeComponent,
+    )>,
+    subject_query: Query<(Entity, &GlobalTransform), With<FirstPersonSubject>>,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    narrow_phase: Res<NarrowPhase>,
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    game_config: Res<GameConfig>,
+) {
+    debug_lines.clear();
+    if !overlay.enabled {
+        return;
+    }
+
+    for (shape, position, collider_type) in shape_query.iter() {
+        let lines = debug_lines.layer_mut(match collider_type.0 {
+            ColliderType::Sensor => DebugLayer::Sensors,
+            ColliderType::Solid => DebugLayer::Colliders,
+        });
+        let isometry = &position.0 .0;
+        let transform = transform_of(isometry);
+        if let Some(ball) = shape.as_ball() {
+            lines.ball(&transform, ball.radius);
+        } else if let Some(cuboid) = shape.as_cuboid() {
+            lines.cuboid(&transform, vec3(&cuboid.half_extents));
+        } else if let Some(capsule) = shape.as_capsule() {
+            lines.capsule(
+                &transform,
+                vec3(&capsule.segment.a.coords),
+                vec3(&capsule.segment.b.coords),
+                capsule.radius,
+            );
+        } else {
+            let aabb = shape.compute_aabb(isometry);
+            lines.aabb(vec3(&aabb.mins.coords), vec3(&aabb.maxs.coords));
+        }
+    }
+
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for (subject, subject_transform) in subject_query.iter() {
+        let ray = ground_probe(
+            subject_transform.translation,
+            game_config.player().capsule_height(),
+        );
+        let hit = find_ground(
+            &query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            subject_transform.translation,
+            game_config.player().capsule_height(),
+        );
+        let lines = debug_lines.layer_mut(match hit {
+            Some(_) => DebugLayer::GroundProbeHit,
+            None => DebugLayer::GroundProbeMiss,
+        });
+        let origin = vec3(&ray.origin.coords);
+        lines.cross(origin, MARKER_SIZE);
+        lines.line(origin, origin + vec3(&ray.dir) * GROUND_PROBE_LENGTH);
+    }
+
+    let contacts = debug_lines.layer_mut(DebugLayer::Contacts);
+    for contact_pair in narrow_phase.contact_pairs() {
+        if !contact_pair.has_any_active_contact {
+            continue;
+        }
+        for manifold in contact_pair.manifolds.iter() {
+            let normal = vec3(&manifold.data.normal);
+            for contact in manifold.data.solver_contacts.iter() {
+                let point = vec3(&contact.point.coords);
+                contacts.cross(point, MARKER_SIZE);
+                contacts.line(point, point + normal * CONTACT_NORMAL_LENGTH);
+            }
+        }
+    }
+
+    let velocities = debug_lines.layer_mut(DebugLayer::Velocities);
+    for (position, velocity, body_type) in body_query.iter() {
+        if body_type.0 != RigidBodyType::Dynamic {
+            continue;
+        }
+        let center = vec3(&position.position.translation.vector);
+        velocities.line(center, center + vec3(&velocity.linvel) * VELOCITY_SECONDS);
+    }
+}
+
+/// Spawn a line mesh for every [`DebugLayer`](crate::debug::DebugLayer), if there's a renderer to
+/// draw them with
+pub fn spawn_physics_debug_meshes(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    let (mut meshes, mut materials) = match (meshes, materials) {
+        (Some(meshes), Some(materials)) => (meshes, materials),
+        _ => return,
+    };
+    for layer in DebugLayer::ALL {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: meshes.add(DebugLines::default().to_mesh()),
+                material: materials.add(StandardMaterial {
+                    base_color: layer.color(),
+                    unlit: true,
+                    ..Default::default()
+                }),
+                visibility: Visibility { is_visible: false },
+                ..Default::default()
+            })
+            // The lines move every frame, so the bounds computed for the first mesh don't hold
+            .insert(NoFrustumCulling)
+            .insert(PhysicsDebugMesh(layer));
+    }
+}
+
+/// Draw the overlay's lines, and hide the layers that don't have any
+pub fn update_physics_debug_meshes(
+    debug_lines: Res<PhysicsDebugLines>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    mut mesh_query: Query<(&PhysicsDebugMesh, &Handle<Mesh>, &mut Visibility)>,
+) {
+    let mut meshes = match meshes {
+        Some(meshes) => meshes,
+        None => return,
+    };
+    for (debug_mesh, handle, mut visibility) in mesh_query.iter_mut() {
+        let lines = debug_lines
+            .layer(debug_mesh.0)
+            .filter(|lines| !lines.is_empty());
+        let is_visible = lines.is_some();
+        if visibility.is_visible != is_visible {
+            visibility.is_visible = is_visible;
+        }
+        if let (Some(lines), Some(mesh)) = (lines, meshes.get_mut(handle)) {
+            *mesh = lines.to_mesh();
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::states::GameLevel;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often the watched files are checked for changes, in seconds
+const POLL_SECONDS: f32 = 0.5;
+
+/// A file that's checked for changes by when it was last modified and how long it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedFile {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl WatchedFile {
+    /// Watch a file from how it is now, so it's only read once it changes
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stamp = file_stamp(&path);
+        WatchedFile { path, stamp }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file's contents, if it's changed since it was last read. A file that's gone, ie while an
+    /// editor replaces it, isn't read until it's back.
+    pub fn poll(&mut self) -> Option<Result<String, String>> {
+        let stamp = file_stamp(&self.path)?;
+        if self.stamp == Some(stamp) {
+            return None;
+        }
+        self.stamp = Some(stamp);
+        Some(
+            fs::read_to_string(&self.path)
+                .map_err(|io_err| format!("Could not read {}: {}", self.path.display(), io_err)),
+        )
+    }
+}
+
+/// What a watched file holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchedAsset {
+    GameConfig,
+    LevelScript(GameLevel),
+}
+
+/// A watched file that changed, and what's in it now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    pub asset: WatchedAsset,
+    pub path: PathBuf,
+    pub contents: Result<String, String>,
+}
+
+/// This resource is the files reloaded while the game runs
+#[derive(Debug, Clone)]
+pub struct HotReloadFiles {
+    files: Vec<(WatchedAsset, WatchedFile)>,
+    poll_interval: Duration,
+    since_poll: Duration,
+}
+
+impl Default for HotReloadFiles {
+    fn default() -> Self {
+        HotReloadFiles {
+            files: Vec::new(),
+            poll_interval: Duration::from_secs_f32(POLL_SECONDS),
+            since_poll: Duration::ZERO,
+        }
+    }
+}
+
+impl HotReloadFiles {
+    /// Reload an asset when its file changes
+    pub fn watch(mut self, asset: WatchedAsset, path: impl Into<PathBuf>) -> Self {
+        self.files.push((asset, WatchedFile::new(path)));
+        self
+    }
+
+    /// Check the files this often instead, ie every frame with zero
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The files that changed, once every poll interval
+    pub fn poll(&mut self, delta: Duration) -> Vec<ChangedFile> {
+        self.since_poll += delta;
+        if self.since_poll < self.poll_interval {
+            return Vec::new();
+        }
+        self.since_poll = Duration::ZERO;
+        self.files
+            .iter_mut()
+            .filter_map(|(asset, file)| {
+                file.poll().map(|contents| ChangedFile {
+                    asset: asset.clone(),
+                    path: file.path().to_path_buf(),
+                    contents,
+                })
+            })
+            .collect()
+    }
+}
+
+/// This resource is why the files that couldn't be reloaded couldn't be, by their path. Their old
+/// values are used until they're fixed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HotReloadErrors(BTreeMap<PathBuf, String>);
+
+impl HotReloadErrors {
+    pub fn set(&mut self, path: PathBuf, error: String) {
+        self.0.insert(path, error);
+    }
+
+    pub fn clear(&mut self, path: &Path) {
+        self.0.remove(path);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.0
+            .iter()
+            .map(|(path, error)| (path.as_path(), error.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_file() {
+        let path = std::env::temp_dir().join(format!(
+            "bevy-fp-template-watched-{}.toml",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut files = HotReloadFiles::default()
+            .with_poll_interval(Duration::from_secs(1))
+            .watch(WatchedAsset::GameConfig, &path);
+        // Missing files are skipped until they're there
+        assert!(files.poll(Duration::from_secs(1)).is_empty());
+
+        fs::write(&path, "a = 1").unwrap();
+        // Not time to check yet
+        assert!(files.poll(Duration::from_millis(500)).is_empty());
+        assert_eq!(
+            files.poll(Duration::from_millis(500)),
+            vec![ChangedFile {
+                asset: WatchedAsset::GameConfig,
+                path: path.clone(),
+                contents: Ok(String::from("a = 1")),
+            }]
+        );
+        // Nothing changed since
+        assert!(files.poll(Duration::from_secs(1)).is_empty());
+
+        fs::write(&path, "a = 100").unwrap();
+        let changed = files.poll(Duration::from_secs(1));
+        assert_eq!(changed[0].contents, Ok(String::from("a = 100")));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,15 @@
+//! Reloading the game's config and level scripts while it runs, so they can be tuned without a
+//! restart. It's only built with the `dev-tools` feature.
+//!
+//! The files in the [`HotReloadFiles`](crate::hot_reload::HotReloadFiles) resource are checked for
+//! changes a couple of times a second. A changed config is parsed, validated and replaces the
+//! [`GameConfig`](crate::resources::GameConfig), and the systems using it pick up its new values, ie
+//! players' capsules are rebuilt. A changed level script replaces the level's entry in the
+//! [`LevelScripts`](crate::scripting::LevelScripts), and if the level is being played, its
+//! [`ScriptObject`](crate::scripting::ScriptObject)s are spawned again. A file that can't be read,
+//! parsed or validated is shown as an error on screen, and the old values are kept until it's fixed.
+mod files;
+mod systems;
+
+pub use self::files::*;
+pub use self::systems::*;
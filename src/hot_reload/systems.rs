@@ -0,0 +1,168 @@
+use crate::hot_reload::{HotReloadErrors, HotReloadFiles, WatchedAsset};
+use crate::resources::{GameConfig, UiTheme};
+use crate::scripting::{LevelScript, LevelScripts, ScriptObject};
+use crate::states::GameLevel;
+use crate::widgets::{self, UiContext};
+use bevy::prelude::*;
+
+/// This event is sent when a level's script is reloaded into the
+/// [`LevelScripts`](crate::scripting::LevelScripts)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelScriptReloaded {
+    pub level: GameLevel,
+}
+
+/// This component is on every entity of the reload errors' UI
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotReloadErrorUi;
+
+/// This component is the panel the reload errors are drawn on, which is hidden while there are none
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotReloadErrorPanel;
+
+/// This component is the reload errors' text
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotReloadErrorText;
+
+/// Apply the watched files that changed. The config replaces the
+/// [`GameConfig`](crate::resources::GameConfig) if it's valid and anything in it is different, and
+/// level scripts replace theirs in the [`LevelScripts`](crate::scripting::LevelScripts). A file that
+/// can't be read, parsed or validated goes in the
+/// [`HotReloadErrors`](crate::hot_reload::HotReloadErrors) instead.
+///
+/// Note: Logging is set up once, so the log level and filter don't change until a restart, and
+/// neither does the window title.
+pub fn reload_changed_files(
+    time: Res<Time>,
+    mut files: ResMut<HotReloadFiles>,
+    mut errors: ResMut<HotReloadErrors>,
+    mut game_config: ResMut<GameConfig>,
+    mut level_scripts: ResMut<LevelScripts>,
+    mut reloaded_events: EventWriter<LevelScriptReloaded>,
+) {
+    for changed in files.poll(time.delta()) {
+        let reloaded = match changed.asset {
+            WatchedAsset::GameConfig => changed
+                .contents
+                .and_then(GameConfig::try_from_toml)
+                .and_then(|new_config| new_config.validate().map(|_| new_config))
+                .map(|new_config| {
+                    if new_config.content_hash() != game_config.content_hash() {
+                        *game_config = new_config;
+                    }
+                }),
+            WatchedAsset::LevelScript(level) => changed
+                .contents
+                .and_then(|toml_str| {
+                    LevelScript::from_toml(&toml_str).map_err(|parse_err| parse_err.to_string())
+                })
+                .map(|level_script| {
+                    level_scripts.insert(level.clone(), level_script);
+                    reloaded_events.send(LevelScriptReloaded { level });
+                }),
+        };
+        match reloaded {
+            Ok(()) => {
+                info!("Reloaded {}", changed.path.display());
+                errors.clear(&changed.path);
+            }
+            Err(reload_err) => {
+                error!(
+                    "Could not reload {}, keeping the old one: {}",
+                    changed.path.display(),
+                    reload_err
+                );
+                errors.set(changed.path, reload_err);
+            }
+        }
+    }
+}
+
+/// Spawn a level's script again when it's reloaded while the level is played, paused or not. The
+/// doors, switches and pickups it spawns start over, like they do when the level's entered.
+pub fn respawn_reloaded_level_scripts(
+    mut commands: Commands,
+    mut reloaded_events: EventReader<LevelScriptReloaded>,
+    level_state: Res<State<GameLevel>>,
+    level_scripts: Res<LevelScripts>,
+    script_object_query: Query<Entity, With<ScriptObject>>,
+) {
+    for reloaded in reloaded_events.iter() {
+        let playing = level_state.current() == &reloaded.level
+            || level_state.inactives().contains(&reloaded.level);
+        let level_script = match level_scripts.get(&reloaded.level) {
+            Some(level_script) if playing => level_script,
+            _ => continue,
+        };
+        for entity in script_object_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        level_script.spawn(&mut commands);
+    }
+}
+
+/// Add the UI for the reload errors, hidden, if there's a renderer to draw it with. It stays up for
+/// the whole game, on top of every level.
+pub fn spawn_hot_reload_error_ui(
+    mut commands: Commands,
+    theme: Option<Res<UiTheme>>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let (theme, asset_server) = match (theme, asset_server) {
+        (Some(theme), Some(asset_server)) => (theme, asset_server),
+        _ => return,
+    };
+    let ui = UiContext::new(&theme, &asset_server, HotReloadErrorUi);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0f32),
+                    bottom: Val::Px(0f32),
+                    ..Default::default()
+                },
+                padding: Rect::all(Val::Px(8f32)),
+                display: Display::None,
+                ..Default::default()
+            },
+            color: theme.overlay_color().into(),
+            ..Default::default()
+        })
+        .insert(HotReloadErrorUi)
+        .insert(HotReloadErrorPanel)
+        .with_children(|panel| {
+            widgets::Label::small("")
+                .with_color(theme.warning_color())
+                .spawn(panel, &ui)
+                .insert(HotReloadErrorText);
+        });
+}
+
+/// Show the reload errors while there are any
+pub fn update_hot_reload_error_ui(
+    errors: Res<HotReloadErrors>,
+    mut panel_query: Query<&mut Style, With<HotReloadErrorPanel>>,
+    mut text_query: Query<&mut Text, With<HotReloadErrorText>>,
+) {
+    if !errors.is_changed() {
+        return;
+    }
+    for mut style in panel_query.iter_mut() {
+        style.display = if errors.is_empty() {
+            Display::None
+        } else {
+            Display::Flex
+        };
+    }
+    let text = errors
+        .iter()
+        .map(|(path, error)| format!("Could not reload {}: {}", path.display(), error))
+        .collect::<Vec<String>>()
+        .join("\n");
+    for mut label in text_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = text.clone();
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use crate::components::{
+    ActiveSubject, FirstPersonHead, FirstPersonSubject, InputSource, LocalPlayer,
+};
+use crate::interaction::cast_look_ray;
+use crate::resources::{GameClock, GameConfig};
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::time::Duration;
+
+/// The solver group player bodies are in. Held objects don't solve contacts with this group,
+/// so they can't push the player around.
+pub const PLAYER_SOLVER_GROUP: u32 = 1 << 1;
+/// How far ahead of the head held objects are kept, in meters
+pub const HOLD_DISTANCE: f32 = 3f32;
+/// How quickly held objects are pulled toward where they're held
+const HOLD_STIFFNESS: f32 = 12f32;
+/// How quickly held objects are turned toward how they're held
+const HOLD_ANGULAR_STIFFNESS: f32 = 10f32;
+/// The fastest held objects move toward where they're held, in meters per second
+const MAX_HOLD_SPEED: f32 = 20f32;
+/// Held objects that get stuck this far from where they're held, ie behind a wall, are dropped
+const BREAK_DISTANCE: f32 = 2.5f32;
+/// How quickly players turn held objects, in radians per second
+const ROTATE_SPEED: f32 = 2f32;
+/// How long a throw takes to charge fully
+pub const MAX_THROW_CHARGE: Duration = Duration::from_secs(1);
+/// The speed of a throw that wasn't charged, and of one that was charged fully, in meters per second
+const THROW_SPEED_RANGE: (f32, f32) = (4f32, 20f32);
+
+/// This component lets players pick up a dynamic rigid body, as long as it isn't heavier
+/// than the [`max_carry_mass`](crate::resources::PlayerConfig::max_carry_mass).
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Grabbable;
+
+/// This component is on an object while it's held by `carrier`
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Held {
+    pub carrier: Entity,
+}
+
+/// The settings a held object had before it was picked up, so they can be put back
+#[derive(Debug, Clone, Copy)]
+struct HeldObject {
+    entity: Entity,
+    /// The object's rotation relative to the head
+    rotation: Quat,
+    solver_groups: InteractionGroups,
+    gravity_scale: f32,
+    ccd_enabled: bool,
+}
+
+/// This component lets a [`FirstPersonSubject`](crate::components::FirstPersonSubject) pick up
+/// [`Grabbable`](crate::interaction::Grabbable) objects, carry them and throw them.
+#[derive(Component, Debug, Default, Clone)]
+pub struct Carrier {
+    held: Option<HeldObject>,
+    throw_charge: Option<Duration>,
+}
+
+impl Carrier {
+    /// The object being carried, if any
+    pub fn held(&self) -> Option<Entity> {
+        self.held.map(|held| held.entity)
+    }
+
+    /// Charge a throw while it's `held`. Returns how charged it was, from `0.0` to `1.0`,
+    /// once it's let go.
+    pub fn charge_throw(&mut self, held: bool, delta: Duration) -> Option<f32> {
+        match (self.throw_charge, held) {
+            (None, false) => None,
+            (None, true) => {
+                self.throw_charge = Some(delta.min(MAX_THROW_CHARGE));
+                None
+            }
+            (Some(charge), true) => {
+                self.throw_charge = Some((charge + delta).min(MAX_THROW_CHARGE));
+                None
+            }
+            (Some(charge), false) => {
+                self.throw_charge = None;
+                Some(charge.as_secs_f32() / MAX_THROW_CHARGE.as_secs_f32())
+            }
+        }
+    }
+}
+
+/// What the player controlling a carrier asked for this frame
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CarryInput {
+    pub grab_just_pressed: bool,
+    pub throw_held: bool,
+    /// How to turn the held object, around the head's up axis then its right axis
+    pub rotate: Vec2,
+}
+
+/// Read the carry controls of the player using `input`. F grabs and drops, holding the left
+/// mouse button charges a throw and R and T turn the held object. On gamepads, it's the left
+/// and right bumpers and the D-pad.
+pub fn carry_input(
+    input: Option<InputSource>,
+    keyboard_input: &Input<KeyCode>,
+    mouse_buttons: &Input<MouseButton>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> CarryInput {
+    let (uses_keyboard, subject_gamepads): (bool, Vec<Gamepad>) = match input {
+        None => (true, gamepads.iter().cloned().collect()),
+        Some(input) => (
+            input.uses_keyboard_mouse(),
+            input.gamepad().into_iter().collect(),
+        ),
+    };
+    let mut carry_input = CarryInput::default();
+    if uses_keyboard {
+        carry_input.grab_just_pressed |= keyboard_input.just_pressed(KeyCode::F);
+        carry_input.throw_held |= mouse_buttons.pressed(MouseButton::Left);
+        if keyboard_input.pressed(KeyCode::R) {
+            carry_input.rotate.x += 1f32;
+        }
+        if keyboard_input.pressed(KeyCode::T) {
+            carry_input.rotate.y += 1f32;
+        }
+    }
+    for gamepad in subject_gamepads {
+        let button = |button_type| GamepadButton(gamepad, button_type);
+        carry_input.grab_just_pressed |=
+            gamepad_buttons.just_pressed(button(GamepadButtonType::LeftTrigger));
+        carry_input.throw_held |= gamepad_buttons.pressed(button(GamepadButtonType::RightTrigger));
+        for (button_type, rotate) in [
+            (GamepadButtonType::DPadLeft, -Vec2::X),
+            (GamepadButtonType::DPadRight, Vec2::X),
+            (GamepadButtonType::DPadUp, -Vec2::Y),
+            (GamepadButtonType::DPadDown, Vec2::Y),
+        ] {
+            if gamepad_buttons.pressed(button(button_type)) {
+                carry_input.rotate += rotate;
+            }
+        }
+    }
+    carry_input
+}
+
+/// The velocity that pulls a held object from `current` toward `target`
+pub fn spring_velocity(current: Vec3, target: Vec3) -> Vec3 {
+    let velocity = (target - current) * HOLD_STIFFNESS;
+    if velocity.length() > MAX_HOLD_SPEED {
+        velocity.normalize() * MAX_HOLD_SPEED
+    } else {
+        velocity
+    }
+}
+
+/// The angular velocity that turns a held object from `current` toward `target`
+pub fn spring_angular_velocity(current: Quat, target: Quat) -> Vec3 {
+    let (axis, mut angle) = (target * current.inverse()).to_axis_angle();
+    // Take the short way around
+    if angle > std::f32::consts::PI {
+        angle -= 2f32 * std::f32::consts::PI;
+    }
+    if !axis.is_finite() || angle.abs() < f32::EPSILON {
+        return Vec3::ZERO;
+    }
+    axis * angle * HOLD_ANGULAR_STIFFNESS
+}
+
+fn translation_of(position: &RigidBodyPositionComponent) -> Vec3 {
+    let translation = position.position.translation.vector;
+    Vec3::new(translation.x, translation.y, translation.z)
+}
+
+fn rotation_of(position: &RigidBodyPositionComponent) -> Quat {
+    let rotation = position.position.rotation;
+    Quat::from_xyzw(rotation.i, rotation.j, rotation.k, rotation.w)
+}
+
+/// Put back what picking an object up changed
+fn release(
+    commands: &mut Commands,
+    held: &HeldObject,
+    forces: &mut RigidBodyForcesComponent,
+    ccd: &mut RigidBodyCcdComponent,
+    flags: &mut ColliderFlagsComponent,
+) {
+    forces.gravity_scale = held.gravity_scale;
+    ccd.ccd_enabled = held.ccd_enabled;
+    flags.solver_groups = held.solver_groups;
+    commands.entity(held.entity).remove::<Held>();
+}
+
+type GrabbableQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static RigidBodyTypeComponent,
+        &'static RigidBodyMassPropsComponent,
+        &'static RigidBodyPositionComponent,
+        &'static mut RigidBodyVelocityComponent,
+        &'static mut RigidBodyForcesComponent,
+        &'static mut RigidBodyCcdComponent,
+        &'static mut ColliderFlagsComponent,
+        &'static mut RigidBodyActivationComponent,
+    ),
+    With<Grabbable>,
+>;
+
+/// Pick up the grabbable object every controlled subject is looking at, drop it, or throw it
+#[allow(clippy::too_many_arguments)]
+pub fn grab_drop_and_throw(
+    mut commands: Commands,
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    subject_query: Query<
+        (Entity, Option<&LocalPlayer>, Option<&ActiveSubject>),
+        (With<FirstPersonSubject>, With<Carrier>),
+    >,
+    mut carrier_query: Query<&mut Carrier>,
+    head_query: Query<(&GlobalTransform, &Parent), With<FirstPersonHead>>,
+    mut object_query: GrabbableQuery,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    game_config: Res<GameConfig>,
+    game_clock: Res<GameClock>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
+) {
+    let subjects = subject_query
+        .iter()
+        .map(|(subject, local_player, active)| (subject, local_player.cloned(), active.is_some()));
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for (subject, input) in
+        select_controlled_subjects("grab_drop_and_throw", subjects, &mut diagnostics)
+    {
+        let head_transform = match head_query.iter().find(|(_, parent)| parent.0 == subject) {
+            Some((head_transform, _)) => head_transform,
+            None => continue,
+        };
+        let mut carrier = match carrier_query.get_mut(subject) {
+            Ok(carrier) => carrier,
+            Err(_) => continue,
+        };
+        let carry_input = carry_input(
+            input,
+            &keyboard_input,
+            &mouse_buttons,
+            &gamepads,
+            &gamepad_buttons,
+        );
+
+        if let Some(mut held) = carrier.held {
+            let (_, mass_props, _, mut velocity, mut forces, mut ccd, mut flags, _) =
+                match object_query.get_mut(held.entity) {
+                    Ok(object) => object,
+                    Err(_) => {
+                        // It was despawned
+                        carrier.held = None;
+                        carrier.throw_charge = None;
+                        continue;
+                    }
+                };
+            if carry_input.grab_just_pressed {
+                release(&mut commands, &held, &mut forces, &mut ccd, &mut flags);
+                carrier.held = None;
+                carrier.throw_charge = None;
+            } else if let Some(charge) =
+                carrier.charge_throw(carry_input.throw_held, game_clock.delta())
+            {
+                release(&mut commands, &held, &mut forces, &mut ccd, &mut flags);
+                carrier.held = None;
+                let (min_speed, max_speed) = THROW_SPEED_RANGE;
+                let speed = min_speed + (max_speed - min_speed) * charge;
+                let mass = mass_props.local_mprops.mass();
+                let impulse = head_transform.rotation * -Vec3::Z * speed * mass;
+                // The hold spring set the velocity, so start the throw from rest
+                velocity.linvel = Vec3::ZERO.into();
+                velocity.apply_impulse(&mass_props, impulse.into());
+            } else if carry_input.rotate != Vec2::ZERO {
+                let turn = carry_input.rotate * ROTATE_SPEED * game_clock.delta_seconds();
+                held.rotation =
+                    Quat::from_rotation_y(turn.x) * Quat::from_rotation_x(turn.y) * held.rotation;
+                carrier.held = Some(held);
+            }
+            continue;
+        }
+
+        if !carry_input.grab_just_pressed {
+            continue;
+        }
+        let target = cast_look_ray(
+            &query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            head_transform,
+            game_config.player().interaction_reach(),
+        );
+        let target = match target {
+            Some(target) => target,
+            None => continue,
+        };
+        if let Ok((body_type, mass_props, position, _, mut forces, mut ccd, mut flags, _)) =
+            object_query.get_mut(target)
+        {
+            if body_type.0 != RigidBodyType::Dynamic
+                || mass_props.local_mprops.mass() > game_config.player().max_carry_mass()
+            {
+                continue;
+            }
+            carrier.held = Some(HeldObject {
+                entity: target,
+                rotation: head_transform.rotation.inverse() * rotation_of(&position),
+                solver_groups: flags.solver_groups,
+                gravity_scale: forces.gravity_scale,
+                ccd_enabled: ccd.ccd_enabled,
+            });
+            carrier.throw_charge = None;
+            flags.solver_groups = InteractionGroups::new(
+                flags.solver_groups.memberships,
+                flags.solver_groups.filter & !PLAYER_SOLVER_GROUP,
+            );
+            forces.gravity_scale = 0f32;
+            // Keep it from tunneling through walls while it's pulled around
+            ccd.ccd_enabled = true;
+            commands.entity(target).insert(Held { carrier: subject });
+        }
+    }
+}
+
+/// Pull every held object toward a point ahead of its carrier's head, and drop the ones
+/// that got stuck
+pub fn pull_held_objects(
+    mut commands: Commands,
+    mut carrier_query: Query<(Entity, &mut Carrier)>,
+    head_query: Query<(&GlobalTransform, &Parent), With<FirstPersonHead>>,
+    mut object_query: GrabbableQuery,
+) {
+    for (subject, mut carrier) in carrier_query.iter_mut() {
+        let held = match carrier.held {
+            Some(held) => held,
+            None => continue,
+        };
+        let head_transform = match head_query.iter().find(|(_, parent)| parent.0 == subject) {
+            Some((head_transform, _)) => head_transform,
+            None => continue,
+        };
+        let (_, _, position, mut velocity, mut forces, mut ccd, mut flags, mut activation) =
+            match object_query.get_mut(held.entity) {
+                Ok(object) => object,
+                Err(_) => continue,
+            };
+        let target =
+            head_transform.translation + head_transform.rotation * -Vec3::Z * HOLD_DISTANCE;
+        let current = translation_of(&position);
+        if current.distance(target) > BREAK_DISTANCE {
+            release(&mut commands, &held, &mut forces, &mut ccd, &mut flags);
+            carrier.held = None;
+            carrier.throw_charge = None;
+            continue;
+        }
+        velocity.linvel = spring_velocity(current, target).into();
+        velocity.angvel = spring_angular_velocity(
+            rotation_of(&position),
+            head_transform.rotation * held.rotation,
+        )
+        .into();
+        activation.wake_up(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throw_charge() {
+        let mut carrier = Carrier::default();
+        let delta = Duration::from_millis(250);
+        assert_eq!(carrier.charge_throw(false, delta), None);
+        assert_eq!(carrier.charge_throw(true, delta), None);
+        assert_eq!(carrier.charge_throw(true, delta), None);
+        assert_eq!(carrier.charge_throw(false, delta), Some(0.5));
+        assert_eq!(carrier.charge_throw(false, delta), None);
+
+        // It stops charging once it's full
+        for _ in 0..10 {
+            carrier.charge_throw(true, delta);
+        }
+        assert_eq!(carrier.charge_throw(false, delta), Some(1f32));
+    }
+
+    #[test]
+    fn test_spring_velocity_is_capped() {
+        assert_eq!(spring_velocity(Vec3::ONE, Vec3::ONE), Vec3::ZERO);
+        let near = spring_velocity(Vec3::ZERO, Vec3::X * 0.5);
+        assert!((near - Vec3::X * 0.5 * HOLD_STIFFNESS).length() < 1e-5);
+        let far = spring_velocity(Vec3::ZERO, Vec3::X * 100f32);
+        assert!((far.length() - MAX_HOLD_SPEED).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_spring_angular_velocity_takes_the_short_way() {
+        assert_eq!(
+            spring_angular_velocity(Quat::IDENTITY, Quat::IDENTITY),
+            Vec3::ZERO
+        );
+        let turn = spring_angular_velocity(Quat::IDENTITY, Quat::from_rotation_y(-0.5));
+        assert!(turn.y < 0f32);
+        assert!((turn.length() - 0.5 * HOLD_ANGULAR_STIFFNESS).abs() < 1e-3);
+    }
+}
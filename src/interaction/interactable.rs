@@ -0,0 +1,209 @@
+use crate::components::InputSource;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// This component lets players use an entity by looking at it and pressing the use action. The
+/// entity needs a collider for the player to look at.
+///
+/// `action` completes the prompt the player is shown, ie "open" shows "Press E to open". If the
+/// interactable has a hold time, the player must hold the use action that long instead.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Interactable {
+    action: String,
+    hold: Option<Duration>,
+    enabled: bool,
+}
+
+impl Interactable {
+    pub fn new(action: impl Into<String>) -> Self {
+        Interactable {
+            action: action.into(),
+            hold: None,
+            enabled: true,
+        }
+    }
+
+    /// Make the player hold the use action for `seconds`
+    pub fn with_hold(mut self, seconds: f32) -> Self {
+        self.hold = Some(Duration::from_secs_f32(seconds.max(0f32)));
+        self
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// Change the prompt, ie when a door that was opened can now be closed
+    pub fn set_action(&mut self, action: impl Into<String>) {
+        self.action = action.into();
+    }
+
+    pub fn hold(&self) -> Option<Duration> {
+        self.hold
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabled interactables can't be targeted, ie a door that's locked
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// What to tell a player using `input` when they look at this
+    pub fn prompt(&self, input: Option<InputSource>) -> String {
+        let verb = if self.hold.is_some() { "Hold" } else { "Press" };
+        let button = match input {
+            Some(InputSource::Gamepad(_)) => "X",
+            _ => "E",
+        };
+        format!("{} {} to {}", verb, button, self.action)
+    }
+}
+
+/// This component tracks what a [`FirstPersonSubject`](crate::components::FirstPersonSubject) is looking
+/// at, and how long they've been holding the use action on it.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
+pub struct Interactor {
+    target: Option<Entity>,
+    hold: Option<Duration>,
+    hold_elapsed: Duration,
+    /// Set once a hold completes, so holding on doesn't interact again
+    hold_completed: bool,
+}
+
+impl Interactor {
+    /// The interactable the subject is looking at, if any
+    pub fn target(&self) -> Option<Entity> {
+        self.target
+    }
+
+    /// How far along holding the use action is, from `0.0` to `1.0`, or `None` if the target
+    /// doesn't need to be held
+    pub fn hold_progress(&self) -> Option<f32> {
+        let hold = self.hold?;
+        if self.hold_completed || hold.is_zero() {
+            return Some(1f32);
+        }
+        Some((self.hold_elapsed.as_secs_f32() / hold.as_secs_f32()).min(1f32))
+    }
+
+    /// Look at `target`, with the use action `held` and maybe `just_pressed`, for `delta`.
+    /// Returns the target if it was used.
+    pub fn update(
+        &mut self,
+        target: Option<(Entity, &Interactable)>,
+        held: bool,
+        just_pressed: bool,
+        delta: Duration,
+    ) -> Option<Entity> {
+        let target_entity = target.map(|(entity, _)| entity);
+        if target_entity != self.target {
+            // Progress doesn't carry over to something else
+            self.target = target_entity;
+            self.hold_elapsed = Duration::ZERO;
+            self.hold_completed = false;
+        }
+        let (entity, interactable) = match target {
+            Some(target) => target,
+            None => {
+                self.hold = None;
+                return None;
+            }
+        };
+        self.hold = interactable.hold();
+        let hold = match self.hold {
+            Some(hold) => hold,
+            None => return if just_pressed { Some(entity) } else { None },
+        };
+        if !held {
+            self.hold_elapsed = Duration::ZERO;
+            self.hold_completed = false;
+            return None;
+        }
+        if self.hold_completed {
+            return None;
+        }
+        self.hold_elapsed += delta;
+        if self.hold_elapsed >= hold {
+            self.hold_completed = true;
+            Some(entity)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::gamepad::Gamepad;
+
+    #[test]
+    fn test_prompt() {
+        let door = Interactable::new("open");
+        assert_eq!(door.prompt(None), "Press E to open");
+        assert_eq!(
+            door.prompt(Some(InputSource::Gamepad(Gamepad(0)))),
+            "Press X to open"
+        );
+        let lever = Interactable::new("pull").with_hold(1f32);
+        assert_eq!(
+            lever.prompt(Some(InputSource::KeyboardMouse)),
+            "Hold E to pull"
+        );
+    }
+
+    #[test]
+    fn test_press_to_interact() {
+        let target = Entity::from_raw(1);
+        let door = Interactable::new("open");
+        let mut interactor = Interactor::default();
+        let delta = Duration::from_millis(100);
+        assert_eq!(interactor.update(None, true, true, delta), None);
+        assert_eq!(
+            interactor.update(Some((target, &door)), true, false, delta),
+            None
+        );
+        assert_eq!(interactor.target(), Some(target));
+        assert_eq!(interactor.hold_progress(), None);
+        assert_eq!(
+            interactor.update(Some((target, &door)), true, true, delta),
+            Some(target)
+        );
+    }
+
+    #[test]
+    fn test_hold_to_interact() {
+        let target = Entity::from_raw(1);
+        let lever = Interactable::new("pull").with_hold(0.25);
+        let mut interactor = Interactor::default();
+        let delta = Duration::from_millis(100);
+        assert_eq!(
+            interactor.update(Some((target, &lever)), true, true, delta),
+            None
+        );
+        assert_eq!(
+            interactor.update(Some((target, &lever)), true, false, delta),
+            None
+        );
+        // Letting go starts over
+        interactor.update(Some((target, &lever)), false, false, delta);
+        assert_eq!(interactor.hold_progress(), Some(0f32));
+        for _ in 0..2 {
+            interactor.update(Some((target, &lever)), true, false, delta);
+        }
+        assert!(interactor.hold_progress().unwrap() > 0.6);
+        assert_eq!(
+            interactor.update(Some((target, &lever)), true, false, delta),
+            Some(target)
+        );
+        // Holding on doesn't interact again
+        assert_eq!(
+            interactor.update(Some((target, &lever)), true, false, delta),
+            None
+        );
+        assert_eq!(interactor.hold_progress(), Some(1f32));
+    }
+}
@@ -0,0 +1,15 @@
+//! Looking at things and using them.
+//!
+//! Every frame, a ray is cast from each controlled subject's [`FirstPersonHead`](crate::components::FirstPersonHead).
+//! The [`Interactable`](crate::interaction::Interactable) it hits within reach becomes the target of the subject's
+//! [`Interactor`](crate::interaction::Interactor), and using it sends an [`Interacted`](crate::interaction::Interacted) event.
+//!
+//! A subject with a [`Carrier`](crate::interaction::Carrier) can also pick up the [`Grabbable`](crate::interaction::Grabbable)
+//! object it's looking at, carry it in front of its head and throw it.
+mod carry;
+mod interactable;
+mod systems;
+
+pub use self::carry::*;
+pub use self::interactable::*;
+pub use self::systems::*;
@@ -0,0 +1,134 @@
+use crate::components::{
+    ActiveSubject, FirstPersonHead, FirstPersonSubject, InputSource, LocalPlayer,
+};
+use crate::interaction::{Interactable, Interactor};
+use crate::resources::{GameClock, GameConfig};
+use crate::systems::physics::blocks_rays;
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// This event is sent when a subject uses an [`Interactable`](crate::interaction::Interactable)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interacted {
+    pub subject: Entity,
+    pub target: Entity,
+}
+
+/// Whether the player controlling a subject is holding the use action, and whether they
+/// pressed it this frame. A single player uses any device (`input` is `None`), split-screen
+/// players their own.
+pub fn use_input(
+    input: Option<InputSource>,
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> (bool, bool) {
+    let (uses_keyboard, subject_gamepads): (bool, Vec<Gamepad>) = match input {
+        None => (true, gamepads.iter().cloned().collect()),
+        Some(input) => (
+            input.uses_keyboard_mouse(),
+            input.gamepad().into_iter().collect(),
+        ),
+    };
+    let buttons: Vec<GamepadButton> = subject_gamepads
+        .into_iter()
+        .map(|gamepad| GamepadButton(gamepad, GamepadButtonType::West))
+        .collect();
+    let held = (uses_keyboard && keyboard_input.pressed(KeyCode::E))
+        || buttons
+            .iter()
+            .any(|button| gamepad_buttons.pressed(*button));
+    let just_pressed = (uses_keyboard && keyboard_input.just_pressed(KeyCode::E))
+        || buttons
+            .iter()
+            .any(|button| gamepad_buttons.just_pressed(*button));
+    (held, just_pressed)
+}
+
+/// Find the entity a subject's head is looking at within `reach`. The ray passes through the
+/// subject's own body and through sensors, ie triggers.
+pub fn cast_look_ray(
+    query_pipeline: &QueryPipeline,
+    collider_set: &QueryPipelineColliderComponentsSet,
+    collider_type_query: &Query<&ColliderTypeComponent>,
+    subject: Entity,
+    head_transform: &GlobalTransform,
+    reach: f32,
+) -> Option<Entity> {
+    let ray = Ray::new(
+        head_transform.translation.into(),
+        (head_transform.rotation * -Vec3::Z).into(),
+    );
+    let filter: &dyn Fn(ColliderHandle) -> bool =
+        &|handle: ColliderHandle| blocks_rays(collider_type_query, &[subject], handle);
+    query_pipeline
+        .cast_ray(
+            collider_set,
+            &ray,
+            reach,
+            true,
+            InteractionGroups::all(),
+            Some(filter),
+        )
+        .map(|(handle, _)| handle.entity())
+}
+
+/// Cast a ray from every controlled subject's head to find the interactable it's looking at,
+/// and use it if the subject's player asks to
+#[allow(clippy::too_many_arguments)]
+pub fn interact_with_targets(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    subject_query: Query<
+        (Entity, Option<&LocalPlayer>, Option<&ActiveSubject>),
+        (With<FirstPersonSubject>, With<Interactor>),
+    >,
+    mut interactor_query: Query<&mut Interactor>,
+    head_query: Query<(&GlobalTransform, &Parent), With<FirstPersonHead>>,
+    interactable_query: Query<&Interactable>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    game_config: Res<GameConfig>,
+    game_clock: Res<GameClock>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
+    mut interacted_events: EventWriter<Interacted>,
+) {
+    let subjects = subject_query
+        .iter()
+        .map(|(subject, local_player, active)| (subject, local_player.cloned(), active.is_some()));
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    let reach = game_config.player().interaction_reach();
+    for (subject, input) in
+        select_controlled_subjects("interact_with_targets", subjects, &mut diagnostics)
+    {
+        let head_transform = match head_query.iter().find(|(_, parent)| parent.0 == subject) {
+            Some((head_transform, _)) => head_transform,
+            None => continue,
+        };
+        let target = cast_look_ray(
+            &query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            head_transform,
+            reach,
+        )
+        .and_then(|entity| {
+            interactable_query
+                .get(entity)
+                .ok()
+                .filter(|interactable| interactable.is_enabled())
+                .map(|interactable| (entity, interactable))
+        });
+        let (held, just_pressed) = use_input(input, &keyboard_input, &gamepads, &gamepad_buttons);
+        if let Ok(mut interactor) = interactor_query.get_mut(subject) {
+            if let Some(target) = interactor.update(target, held, just_pressed, game_clock.delta())
+            {
+                interacted_events.send(Interacted { subject, target });
+            }
+        }
+    }
+}
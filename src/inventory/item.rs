@@ -0,0 +1,110 @@
+use serde::Deserialize;
+
+/// The items there are, in `assets/items.toml`
+const ITEMS: &str = include_str!("../../assets/items.toml");
+
+fn default_max_stack() -> u32 {
+    1
+}
+
+/// What an item is, ie in `assets/items.toml`
+///
+/// ```toml
+/// [[items]]
+/// id = "medkit"
+/// name = "Medkit"
+/// description = "Heals 40 health."
+/// max_stack = 3
+/// weight = 1.0
+/// heal = 40.0
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ItemDefinition {
+    /// What inventories and level files call the item
+    id: String,
+    /// What the player sees
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// How many fit in one inventory slot
+    #[serde(default = "default_max_stack")]
+    max_stack: u32,
+    /// In kilograms
+    #[serde(default)]
+    weight: f32,
+    /// How much health using one restores. Items that don't heal can't be used.
+    #[serde(default)]
+    heal: Option<f32>,
+}
+
+impl ItemDefinition {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn max_stack(&self) -> u32 {
+        self.max_stack.max(1)
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight.max(0f32)
+    }
+
+    pub fn heal(&self) -> Option<f32> {
+        self.heal
+    }
+
+    /// Whether the player can use the item from their inventory, using one up
+    pub fn is_usable(&self) -> bool {
+        self.heal.is_some()
+    }
+}
+
+/// This resource holds every item there is. By default, they're the ones in `assets/items.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ItemDefinitions {
+    #[serde(default)]
+    items: Vec<ItemDefinition>,
+}
+
+impl Default for ItemDefinitions {
+    fn default() -> Self {
+        ItemDefinitions::from_toml(ITEMS)
+            .unwrap_or_else(|parse_err| panic!("Could not parse the items: {}", parse_err))
+    }
+}
+
+impl ItemDefinitions {
+    pub fn from_toml(toml_str: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_str)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.items.iter().find(|item| item.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items() {
+        let items = ItemDefinitions::default();
+        let medkit = items.get("medkit").unwrap();
+        assert_eq!(medkit.name(), "Medkit");
+        assert!(medkit.is_usable());
+        let coin = items.get("coin").unwrap();
+        assert!(!coin.is_usable());
+        assert!(coin.max_stack() > 1);
+        assert!(items.get("unicorn").is_none());
+    }
+}
@@ -0,0 +1,19 @@
+//! Items players carry.
+//!
+//! Every item is described by an [`ItemDefinition`](crate::inventory::ItemDefinition) in `assets/items.toml`.
+//! A subject's [`Inventory`](crate::inventory::Inventory) holds stacks of them, up to a number of slots and a
+//! weight limit, and it's saved with the game.
+//!
+//! Items are picked up from the [`ItemPickup`](crate::inventory::ItemPickup)s a level's
+//! [`LevelScript`](crate::scripting::LevelScript) lays out, either by touching or by using them. Adding,
+//! removing and using items sends [`ItemAdded`](crate::inventory::ItemAdded),
+//! [`ItemRemoved`](crate::inventory::ItemRemoved) and [`ItemUsed`](crate::inventory::ItemUsed) events.
+mod item;
+mod pickup;
+mod stack;
+mod systems;
+
+pub use self::item::*;
+pub use self::pickup::*;
+pub use self::stack::*;
+pub use self::systems::*;
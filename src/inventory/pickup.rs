@@ -0,0 +1,119 @@
+use crate::interaction::{Interactable, Interacted};
+use crate::inventory::{Inventory, ItemAdded, ItemDefinitions};
+use crate::save::Saveable;
+use crate::scripting::{ScriptMessage, TriggerEntered};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// This component makes an entity some items lying in a level. They're picked up by a subject
+/// with an [`Inventory`](crate::inventory::Inventory) that uses the entity, if it's
+/// [`Interactable`](crate::interaction::Interactable), or that enters it, if it's a
+/// [`TriggerVolume`](crate::scripting::TriggerVolume).
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct ItemPickup {
+    item: String,
+}
+
+impl ItemPickup {
+    pub fn new(item: impl Into<String>) -> Self {
+        ItemPickup { item: item.into() }
+    }
+
+    /// The item's [`ItemDefinition`](crate::inventory::ItemDefinition) id
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+}
+
+/// The saved state of an [`ItemPickup`](crate::inventory::ItemPickup), ie how many items are left.
+/// When they've all been picked up, it's hidden rather than despawned, so loading a save from
+/// before brings it back.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemPickupState {
+    pub count: u32,
+}
+
+impl Saveable for ItemPickupState {
+    const SAVE_KEY: &'static str = "item_pickup_state";
+}
+
+/// Put the items subjects use or touch in their [`Inventory`](crate::inventory::Inventory). What
+/// doesn't fit is left behind.
+pub fn collect_pickups(
+    mut interacted_events: EventReader<Interacted>,
+    mut entered_events: EventReader<TriggerEntered>,
+    item_definitions: Res<ItemDefinitions>,
+    mut pickup_query: Query<(&ItemPickup, &mut ItemPickupState)>,
+    mut inventory_query: Query<&mut Inventory>,
+    mut added_events: EventWriter<ItemAdded>,
+    mut message: ResMut<ScriptMessage>,
+) {
+    let used = interacted_events
+        .iter()
+        .map(|event| (event.subject, event.target));
+    let touched = entered_events
+        .iter()
+        .map(|event| (event.entity, event.trigger));
+    for (subject, target) in used.chain(touched) {
+        let (pickup, mut state) = match pickup_query.get_mut(target) {
+            Ok(pickup) => pickup,
+            Err(_) => continue,
+        };
+        let mut inventory = match inventory_query.get_mut(subject) {
+            Ok(inventory) => inventory,
+            Err(_) => continue,
+        };
+        if state.count == 0 {
+            continue;
+        }
+        let added = inventory.add(pickup.item(), state.count, &item_definitions);
+        if added == 0 {
+            message.show("There's no room for that", 3f32);
+            continue;
+        }
+        state.count -= added;
+        let name = item_definitions
+            .get(pickup.item())
+            .map_or(pickup.item(), |definition| definition.name());
+        if added == 1 {
+            message.show(format!("Picked up a {}", name), 3f32);
+        } else {
+            message.show(format!("Picked up {} x{}", name, added), 3f32);
+        }
+        added_events.send(ItemAdded {
+            subject,
+            item: pickup.item().to_string(),
+            count: added,
+        });
+    }
+}
+
+/// Hide pickups with no items left, and stop them colliding, being looked at or being touched. The
+/// mesh is added after the pickup is spawned, so it's hidden again when it is.
+pub fn hide_collected_pickups(
+    mut pickup_query: Query<
+        (
+            &ItemPickupState,
+            Option<&mut Interactable>,
+            &mut ColliderFlagsComponent,
+            Option<&mut Visibility>,
+        ),
+        Or<(Changed<ItemPickupState>, Added<Visibility>)>,
+    >,
+) {
+    for (state, interactable, mut flags, visibility) in pickup_query.iter_mut() {
+        let collected = state.count == 0;
+        if let Some(mut interactable) = interactable {
+            interactable.set_enabled(!collected);
+        }
+        flags.collision_groups = if collected {
+            InteractionGroups::none()
+        } else {
+            InteractionGroups::all()
+        };
+        if let Some(mut visibility) = visibility {
+            visibility.is_visible = !collected;
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::inventory::ItemDefinitions;
+use crate::save::Saveable;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Some of one item, in one slot of an [`Inventory`](crate::inventory::Inventory)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    item: String,
+    count: u32,
+}
+
+impl ItemStack {
+    /// The item's [`ItemDefinition`](crate::inventory::ItemDefinition) id
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// This component holds the items a subject carries, in stacks no bigger than their item's
+/// `max_stack`. It has a number of slots, one per stack, and a limit on how much its items weigh.
+/// It's saved with the game.
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: usize,
+    max_weight: f32,
+    /// Stacks are kept in the order they were started, without gaps
+    stacks: Vec<ItemStack>,
+}
+
+impl Inventory {
+    pub fn new(slots: usize, max_weight: f32) -> Self {
+        Inventory {
+            slots,
+            max_weight,
+            stacks: Vec::new(),
+        }
+    }
+
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    pub fn max_weight(&self) -> f32 {
+        self.max_weight
+    }
+
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+
+    /// How many of `item` there are, in every stack
+    pub fn count(&self, item: &str) -> u32 {
+        self.stacks
+            .iter()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// How much everything weighs, in kilograms. Items without a definition weigh nothing.
+    pub fn weight(&self, items: &ItemDefinitions) -> f32 {
+        self.stacks
+            .iter()
+            .filter_map(|stack| {
+                items
+                    .get(&stack.item)
+                    .map(|definition| definition.weight() * stack.count as f32)
+            })
+            .sum()
+    }
+
+    /// Add up to `count` of `item`, topping up its stacks before starting new ones. Returns how
+    /// many were added, which is fewer when the slots run out or they'd be too heavy, and none if
+    /// the item has no definition.
+    pub fn add(&mut self, item: &str, count: u32, items: &ItemDefinitions) -> u32 {
+        let definition = match items.get(item) {
+            Some(definition) => definition,
+            None => return 0,
+        };
+        let mut remaining = count;
+        if definition.weight() > 0f32 {
+            let spare_weight = (self.max_weight - self.weight(items)).max(0f32);
+            // A little leeway, so rounding doesn't turn away an item that fits exactly
+            let fits = ((spare_weight + 1e-4) / definition.weight()).floor() as u32;
+            remaining = remaining.min(fits);
+        }
+        let mut added = 0;
+        for stack in self.stacks.iter_mut().filter(|stack| stack.item == item) {
+            let topped_up = remaining.min(definition.max_stack().saturating_sub(stack.count));
+            stack.count += topped_up;
+            remaining -= topped_up;
+            added += topped_up;
+        }
+        while remaining > 0 && self.stacks.len() < self.slots {
+            let count = remaining.min(definition.max_stack());
+            self.stacks.push(ItemStack {
+                item: item.to_string(),
+                count,
+            });
+            remaining -= count;
+            added += count;
+        }
+        added
+    }
+
+    /// Remove up to `count` of `item`, from its last stacks first. Returns how many were removed.
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let mut remaining = count;
+        for stack in self
+            .stacks
+            .iter_mut()
+            .rev()
+            .filter(|stack| stack.item == item)
+        {
+            let taken = remaining.min(stack.count);
+            stack.count -= taken;
+            remaining -= taken;
+        }
+        self.stacks.retain(|stack| stack.count > 0);
+        count - remaining
+    }
+}
+
+impl Saveable for Inventory {
+    const SAVE_KEY: &'static str = "inventory";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: &str = r#"
+        [[items]]
+        id = "coin"
+        name = "Coin"
+        max_stack = 10
+
+        [[items]]
+        id = "brick"
+        name = "Brick"
+        max_stack = 5
+        weight = 2.0
+    "#;
+
+    #[test]
+    fn test_stacks_fill_slots() {
+        let items = ItemDefinitions::from_toml(ITEMS).unwrap();
+        let mut inventory = Inventory::new(3, 100f32);
+        assert_eq!(inventory.add("coin", 15, &items), 15);
+        assert_eq!(inventory.stacks().len(), 2);
+        assert_eq!(inventory.add("coin", 10, &items), 10);
+        // The third slot is full too, so only 5 more fit
+        assert_eq!(inventory.add("coin", 10, &items), 5);
+        assert_eq!(inventory.count("coin"), 30);
+        assert_eq!(inventory.add("unicorn", 1, &items), 0);
+
+        assert_eq!(inventory.remove("coin", 12), 12);
+        assert_eq!(inventory.stacks().len(), 2);
+        assert_eq!(inventory.remove("coin", 100), 18);
+        assert!(inventory.stacks().is_empty());
+    }
+
+    #[test]
+    fn test_weight_limit() {
+        let items = ItemDefinitions::from_toml(ITEMS).unwrap();
+        let mut inventory = Inventory::new(10, 7f32);
+        assert_eq!(inventory.add("brick", 5, &items), 3);
+        assert_eq!(inventory.weight(&items), 6f32);
+        // Weightless items still fit
+        assert_eq!(inventory.add("coin", 5, &items), 5);
+        assert_eq!(inventory.add("brick", 1, &items), 0);
+    }
+
+    #[test]
+    fn test_inventory_round_trips() {
+        let items = ItemDefinitions::from_toml(ITEMS).unwrap();
+        let mut inventory = Inventory::new(4, 20f32);
+        inventory.add("coin", 12, &items);
+        inventory.add("brick", 1, &items);
+        let saved = toml::Value::try_from(&inventory).unwrap();
+        assert_eq!(saved.try_into::<Inventory>().unwrap(), inventory);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::components::Health;
+use crate::inventory::{Inventory, ItemDefinitions};
+use bevy::prelude::*;
+
+/// This event is sent when items are put in a subject's [`Inventory`](crate::inventory::Inventory)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemAdded {
+    pub subject: Entity,
+    pub item: String,
+    pub count: u32,
+}
+
+/// This event is sent when items are taken out of a subject's [`Inventory`](crate::inventory::Inventory)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemRemoved {
+    pub subject: Entity,
+    pub item: String,
+    pub count: u32,
+}
+
+/// This event is sent when a subject uses one of the items in its [`Inventory`](crate::inventory::Inventory),
+/// after it's been taken out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemUsed {
+    pub subject: Entity,
+    pub item: String,
+}
+
+/// Send this event to have a subject use one of the items in its [`Inventory`](crate::inventory::Inventory)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseItemRequested {
+    pub subject: Entity,
+    pub item: String,
+}
+
+/// Use up the items subjects ask to use, as long as they have one and it can be used
+pub fn use_items(
+    mut use_requests: EventReader<UseItemRequested>,
+    item_definitions: Res<ItemDefinitions>,
+    mut inventory_query: Query<&mut Inventory>,
+    mut removed_events: EventWriter<ItemRemoved>,
+    mut used_events: EventWriter<ItemUsed>,
+) {
+    for request in use_requests.iter() {
+        let usable = item_definitions
+            .get(&request.item)
+            .map_or(false, |definition| definition.is_usable());
+        if !usable {
+            continue;
+        }
+        let mut inventory = match inventory_query.get_mut(request.subject) {
+            Ok(inventory) => inventory,
+            Err(_) => continue,
+        };
+        if inventory.remove(&request.item, 1) == 0 {
+            continue;
+        }
+        debug!("{:?} used a {}", request.subject, request.item);
+        removed_events.send(ItemRemoved {
+            subject: request.subject,
+            item: request.item.clone(),
+            count: 1,
+        });
+        used_events.send(ItemUsed {
+            subject: request.subject,
+            item: request.item.clone(),
+        });
+    }
+}
+
+/// Heal the subjects that use an item that heals
+pub fn heal_on_item_used(
+    mut used_events: EventReader<ItemUsed>,
+    item_definitions: Res<ItemDefinitions>,
+    mut health_query: Query<&mut Health>,
+) {
+    for event in used_events.iter() {
+        let heal = match item_definitions
+            .get(&event.item)
+            .and_then(|definition| definition.heal())
+        {
+            Some(heal) => heal,
+            None => continue,
+        };
+        if let Ok(mut health) = health_query.get_mut(event.subject) {
+            health.heal(heal);
+        }
+    }
+}
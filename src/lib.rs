@@ -0,0 +1,25 @@
+//! A first person game template, built on Bevy and Rapier. The game itself is in `main.rs`, which
+//! puts these plugins together; keeping them in a library lets the tests in `tests/` run them
+//! headless like the game does.
+pub mod components;
+pub mod console;
+pub mod debug;
+#[cfg(feature = "dev-tools")]
+pub mod hot_reload;
+pub mod interaction;
+pub mod inventory;
+pub mod mechanisms;
+pub mod network;
+pub mod npc;
+pub mod perception;
+pub mod plugins;
+pub mod replay;
+pub mod resources;
+pub mod save;
+pub mod scripting;
+pub mod sound;
+pub mod split_screen;
+pub mod states;
+pub mod systems;
+pub mod weapons;
+pub mod widgets;
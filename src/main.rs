@@ -1,54 +1,215 @@
-use bevy::log::LogSettings;
+use bevy::app::ScheduleRunnerSettings;
+use bevy::log::{LogPlugin, LogSettings};
 use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use std::time::Duration;
 
 use bevy_rapier3d::physics::TimestepMode;
 use bevy_rapier3d::prelude::*;
 
-use plugins::levels::*;
-use plugins::FirstPersonControlPlugin;
-use resources::{GameConfig, GameSettings};
-use states::{FirstPersonControlSettings, GameLevel};
+use bevy_fp_template::console::{init_logging, ConsoleLogBuffer};
+#[cfg(feature = "dev-tools")]
+use bevy_fp_template::hot_reload;
+use bevy_fp_template::network::{spawn_server_level, NetworkClient, NetworkServer};
+use bevy_fp_template::plugins::levels::*;
+use bevy_fp_template::plugins::{
+    CarryPlugin, CheckpointPlugin, ConsolePlugin, FirstPersonControlPlugin, GameSettingsPlugin,
+    HealthPlugin, HudPlugin, InputRecorderPlugin, InteractionPlugin, InventoryPlugin,
+    MechanismsPlugin, NetworkClientPlugin, NetworkServerPlugin, NpcPlugin, PauseManagerPlugin,
+    PerceptionPlugin, ReplayPlugin, SavePlugin, ScriptingPlugin, SoundPlugin, SplitScreenPlugin,
+    SurfacePlugin, WeaponPlugin, WidgetPlugin,
+};
+#[cfg(feature = "dev-tools")]
+use bevy_fp_template::plugins::{HotReloadPlugin, PhysicsDebugPlugin};
+use bevy_fp_template::replay::{InputRecorder, ReplayPlayback};
+use bevy_fp_template::resources::{
+    GameClock, GameConfig, GameRng, GameSettings, LocalPlayers, GAME_CONFIG_PATH,
+};
+use bevy_fp_template::sound::RodioAudioBackend;
+use bevy_fp_template::states::{FirstPersonControlSettings, GameLevel};
+use bevy_fp_template::systems::{
+    apply_surface_materials, log_subject_diagnostics, scale_physics_timestep, tick_game_clock,
+    SubjectDiagnostic,
+};
 
-mod components;
-mod plugins;
-mod resources;
-mod states;
-mod systems;
+/// How often a dedicated server ticks, in ticks per second
+const SERVER_TICK_RATE: f64 = 60.0;
+
+/// Command line options. `--server <address>` hosts a headless dedicated server,
+/// `--connect <address>` plays on one. `--record <path>` records every run of the main level
+/// to a file, and `--replay <path>` plays one back.
+#[derive(Debug, Default, PartialEq)]
+struct LaunchOptions {
+    server: Option<String>,
+    connect: Option<String>,
+    record: Option<String>,
+    replay: Option<String>,
+}
+
+impl LaunchOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = LaunchOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--server" => options.server = args.next(),
+                "--connect" => options.connect = args.next(),
+                "--record" => options.record = args.next(),
+                "--replay" => options.replay = args.next(),
+                // Logging isn't set up yet
+                _ => eprintln!("Ignoring unknown argument {}", arg),
+            }
+        }
+        options
+    }
+}
 
 fn main() {
-    let game_config = GameConfig::default();
+    let options = LaunchOptions::parse(std::env::args().skip(1));
+    if let Some(address) = options.server {
+        run_dedicated_server(&address);
+        return;
+    }
+
+    let game_config = load_game_config();
+    // Logging is set up here instead of by the LogPlugin, so it's echoed in the console too
+    let console_log = ConsoleLogBuffer::default();
+    init_logging(
+        game_config.log_level(),
+        game_config.log_filter(),
+        console_log.clone(),
+    );
+
+    let mut app = App::new();
+    app.insert_resource(WindowDescriptor {
+        title: game_config.window_title().clone(),
+        width: 800.,
+        height: 700.,
+        ..Default::default()
+    })
+    .add_plugins_with(DefaultPlugins, |plugins| plugins.disable::<LogPlugin>())
+    .insert_resource(console_log)
+    .insert_resource(game_config)
+    // The player's settings, kept in settings.toml
+    .add_plugin(GameSettingsPlugin)
+    .insert_resource(LocalPlayers::default())
+    .insert_resource(GameRng::default())
+    // Gameplay time that stops while the game is paused
+    .insert_resource(GameClock::default())
+    .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock)
+    // Physics slows down and speeds up with gameplay time
+    .add_system_to_stage(CoreStage::PreUpdate, scale_physics_timestep)
+    // Enable First Person controls
+    .add_state(FirstPersonControlSettings::Disabled)
+    .add_plugin(FirstPersonControlPlugin)
+    .add_state(GameLevel::MainMenu)
+    // Configure and add physics
+    .insert_resource(RapierConfiguration {
+        gravity: Vector::y() * -9.81,
+        scale: 1.0,
+        // Turn off the sim to start
+        physics_pipeline_active: false,
+        query_pipeline_active: false,
+        timestep_mode: TimestepMode::VariableTimestep,
+    })
+    .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+    .add_plugin(SurfacePlugin)
+    .add_plugin(WidgetPlugin)
+    .add_plugin(ConsolePlugin)
+    .add_plugin(ConfirmDialogPlugin)
+    .add_plugin(MainMenuLevel)
+    .add_plugin(MainGameLevel)
+    .add_plugin(HudPlugin)
+    .add_plugin(PauseMenuLevel)
+    .add_plugin(InventoryLevel)
+    .add_plugin(PauseManagerPlugin)
+    .add_plugin(SavePlugin)
+    .add_plugin(CheckpointPlugin)
+    .add_plugin(HealthPlugin)
+    .add_plugin(ScriptingPlugin)
+    .add_plugin(InteractionPlugin)
+    .add_plugin(CarryPlugin)
+    .add_plugin(MechanismsPlugin)
+    .add_plugin(WeaponPlugin)
+    .add_plugin(InventoryPlugin)
+    .add_plugin(PerceptionPlugin)
+    .add_plugin(NpcPlugin)
+    .add_plugin(SoundPlugin::<RodioAudioBackend>::default())
+    .add_plugin(SplitScreenPlugin);
+    // Designers can tune the config and the levels without restarting
+    #[cfg(feature = "dev-tools")]
+    app.insert_resource(
+        hot_reload::HotReloadFiles::default()
+            .watch(hot_reload::WatchedAsset::GameConfig, GAME_CONFIG_PATH)
+            .watch(
+                hot_reload::WatchedAsset::LevelScript(GameLevel::Main),
+                "assets/levels/main.toml",
+            ),
+    )
+    .add_plugin(HotReloadPlugin)
+    // F3 draws the physics world over the game
+    .add_plugin(PhysicsDebugPlugin);
+    if let Some(address) = options.connect {
+        let client = NetworkClient::connect(address.as_str()).unwrap_or_else(|connect_err| {
+            panic!("Could not connect to {}: {}", address, connect_err)
+        });
+        app.insert_resource(client).add_plugin(NetworkClientPlugin);
+    }
+    if let Some(path) = options.record {
+        app.insert_resource(InputRecorder::to_file(path))
+            .add_plugin(InputRecorderPlugin);
+    }
+    if let Some(path) = options.replay {
+        let playback = ReplayPlayback::load(&path).unwrap_or_else(|load_err| {
+            panic!("Could not load the replay {}: {:?}", path, load_err)
+        });
+        app.insert_resource(playback).add_plugin(ReplayPlugin);
+    }
+    app.run();
+}
+
+/// The config in `assets/config.toml`, or the default one if it can't be loaded
+fn load_game_config() -> GameConfig {
+    GameConfig::load(GAME_CONFIG_PATH).unwrap_or_else(|load_err| {
+        // Logging isn't set up yet
+        eprintln!("Using the default config, {}", load_err);
+        GameConfig::default()
+    })
+}
+
+/// Host the game without a window, simulating physics and replicating it to clients
+fn run_dedicated_server(address: &str) {
+    let game_config = load_game_config();
+    let server = NetworkServer::bind(address)
+        .unwrap_or_else(|bind_err| panic!("Could not host on {}: {}", address, bind_err));
 
     App::new()
-        // Configure log plugin (added by DefaultPlugins)
         .insert_resource(LogSettings {
             level: game_config.log_level(),
             filter: game_config.log_filter().clone(),
         })
-        .insert_resource(WindowDescriptor {
-            title: game_config.window_title().clone(),
-            width: 800.,
-            height: 700.,
-            ..Default::default()
-        })
-        .add_plugins(DefaultPlugins)
-        .insert_resource(game_config)
-        .insert_resource(GameSettings::default())
-        // Enable First Person controls
-        .add_state(FirstPersonControlSettings::Disabled)
-        .add_plugin(FirstPersonControlPlugin)
-        .add_state(GameLevel::MainMenu)
-        // Configure and add physics
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / SERVER_TICK_RATE,
+        )))
+        .add_plugins(MinimalPlugins)
+        .add_plugin(LogPlugin)
+        .add_plugin(TransformPlugin)
         .insert_resource(RapierConfiguration {
             gravity: Vector::y() * -9.81,
             scale: 1.0,
-            // Turn off the sim to start
-            physics_pipeline_active: false,
-            query_pipeline_active: false,
+            physics_pipeline_active: true,
+            query_pipeline_active: true,
             timestep_mode: TimestepMode::VariableTimestep,
         })
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugin(MainMenuLevel)
-        .add_plugin(MainGameLevel)
-        .add_plugin(PauseMenuLevel)
+        .insert_resource(server)
+        // Players are moved like a local player, at the default sensitivity
+        .insert_resource(game_config)
+        .insert_resource(GameSettings::default())
+        .add_event::<SubjectDiagnostic>()
+        .add_system(log_subject_diagnostics)
+        .add_plugin(NetworkServerPlugin)
+        .add_startup_system(spawn_server_level)
+        // The ball is bouncy
+        .add_system(apply_surface_materials)
         .run();
 }
@@ -0,0 +1,244 @@
+use crate::components::Tags;
+use crate::interaction::{Interactable, Interacted};
+use crate::mechanisms::{KeyRing, LinkSignal, Signal};
+use crate::resources::GameClock;
+use crate::save::Saveable;
+use crate::scripting::ScriptMessage;
+use crate::systems::physics::body_position;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+fn default_open_angle() -> f32 {
+    90f32
+}
+
+/// How a [`Door`](crate::mechanisms::Door) opens. In a level file, the kind of door is its `kind`,
+/// ie `{ kind = "sliding", offset = [2.0, 0.0, 0.0] }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DoorKind {
+    /// Swings about a vertical hinge, `hinge` from the door's center, by `angle` degrees
+    Hinged {
+        hinge: [f32; 3],
+        #[serde(default = "default_open_angle")]
+        angle: f32,
+    },
+    /// Slides by `offset`, in the door's own frame
+    Sliding { offset: [f32; 3] },
+}
+
+/// This component animates a kinematic, position based rigid body between its closed pose and
+/// its open one, following its [`DoorState`](crate::mechanisms::DoorState).
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Door {
+    kind: DoorKind,
+    closed: Transform,
+    /// How long opening or closing takes
+    seconds: f32,
+    key: Option<String>,
+    /// How far open the door is, from `0.0` to `1.0`
+    progress: f32,
+}
+
+impl Door {
+    pub fn new(kind: DoorKind, closed: Transform, seconds: f32) -> Self {
+        Door {
+            kind,
+            closed,
+            seconds,
+            key: None,
+            progress: 0f32,
+        }
+    }
+
+    /// Make the door start locked, until a subject with `key` on its [`KeyRing`](crate::mechanisms::KeyRing)
+    /// uses it
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Where the door is when it's `progress` of the way open
+    pub fn pose(&self, progress: f32) -> Transform {
+        let (translation, rotation) = match &self.kind {
+            DoorKind::Hinged { hinge, angle } => {
+                let hinge = Vec3::from(*hinge);
+                let rotation = Quat::from_rotation_y(angle.to_radians() * progress);
+                // The center turns about the hinge
+                (hinge + rotation * -hinge, rotation)
+            }
+            DoorKind::Sliding { offset } => (Vec3::from(*offset) * progress, Quat::IDENTITY),
+        };
+        Transform {
+            translation: self.closed.translation + self.closed.rotation * translation,
+            rotation: self.closed.rotation * rotation,
+            scale: self.closed.scale,
+        }
+    }
+
+    /// Open or close the door for `delta_seconds`, and return where it is now
+    pub fn advance(&mut self, open: bool, delta_seconds: f32) -> Transform {
+        let target = if open { 1f32 } else { 0f32 };
+        let step = if self.seconds > f32::EPSILON {
+            delta_seconds / self.seconds
+        } else {
+            1f32
+        };
+        self.progress = if self.progress < target {
+            (self.progress + step).min(target)
+        } else {
+            (self.progress - step).max(target)
+        };
+        self.pose(self.progress)
+    }
+}
+
+/// The saved state of a [`Door`](crate::mechanisms::Door)
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DoorState {
+    pub open: bool,
+    pub locked: bool,
+}
+
+impl Saveable for DoorState {
+    const SAVE_KEY: &'static str = "door_state";
+}
+
+/// Open and close the doors subjects use. A locked door opens if the subject has its key,
+/// otherwise the player is told which key it needs.
+pub fn use_doors(
+    mut interacted_events: EventReader<Interacted>,
+    mut door_query: Query<(&Door, &mut DoorState)>,
+    key_ring_query: Query<&KeyRing>,
+    mut message: ResMut<ScriptMessage>,
+) {
+    for event in interacted_events.iter() {
+        let (door, mut state) = match door_query.get_mut(event.target) {
+            Ok(door) => door,
+            Err(_) => continue,
+        };
+        if state.locked {
+            let key = door.key().unwrap_or("key");
+            let has_key = key_ring_query
+                .get(event.subject)
+                .map_or(false, |key_ring| key_ring.contains(key));
+            if !has_key {
+                message.show(format!("It's locked. It needs the {}", key), 3f32);
+                continue;
+            }
+            state.locked = false;
+            message.show(format!("Unlocked with the {}", key), 3f32);
+        }
+        state.open = !state.open;
+    }
+}
+
+/// Open and close the doors a signal is sent to. Locked doors stay shut.
+pub fn signal_doors(
+    mut signal_events: EventReader<LinkSignal>,
+    mut door_query: Query<(&Tags, &mut DoorState), With<Door>>,
+) {
+    for event in signal_events.iter() {
+        for (tags, mut state) in door_query.iter_mut() {
+            if !tags.contains(&event.tag) || state.locked {
+                continue;
+            }
+            state.open = match event.signal {
+                Signal::On => true,
+                Signal::Off => false,
+                Signal::Toggle => !state.open,
+            };
+        }
+    }
+}
+
+pub fn move_doors(
+    game_clock: Res<GameClock>,
+    mut door_query: Query<(&mut Door, &DoorState, &mut RigidBodyPositionComponent)>,
+) {
+    for (mut door, state, mut position) in door_query.iter_mut() {
+        let target = if state.open { 1f32 } else { 0f32 };
+        if (door.progress - target).abs() < f32::EPSILON {
+            continue;
+        }
+        let pose = door.advance(state.open, game_clock.delta_seconds());
+        position.next_position = body_position(&pose);
+    }
+}
+
+/// Keep the prompt of doors players can use in line with what using them does
+pub fn update_door_prompts(
+    mut door_query: Query<(&DoorState, &mut Interactable), (With<Door>, Changed<DoorState>)>,
+) {
+    for (state, mut interactable) in door_query.iter_mut() {
+        interactable.set_action(if state.open {
+            "close the door"
+        } else {
+            "open the door"
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hinged_door_swings_about_its_hinge() {
+        let door = Door::new(
+            DoorKind::Hinged {
+                hinge: [-1f32, 0f32, 0f32],
+                angle: 90f32,
+            },
+            Transform::from_xyz(5f32, 1f32, 0f32),
+            1f32,
+        );
+        assert!((door.pose(0f32).translation - Vec3::new(5f32, 1f32, 0f32)).length() < 1e-5);
+        let open = door.pose(1f32);
+        assert!((open.translation - Vec3::new(4f32, 1f32, -1f32)).length() < 1e-5);
+        assert!(
+            open.rotation
+                .angle_between(Quat::from_rotation_y(90f32.to_radians()))
+                < 1e-3
+        );
+    }
+
+    #[test]
+    fn test_sliding_door_slides_in_its_own_frame() {
+        let door = Door::new(
+            DoorKind::Sliding {
+                offset: [2f32, 0f32, 0f32],
+            },
+            Transform::from_rotation(Quat::from_rotation_y(90f32.to_radians())),
+            1f32,
+        );
+        let open = door.pose(1f32);
+        assert!((open.translation - Vec3::new(0f32, 0f32, -2f32)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_door_opens_and_closes_over_time() {
+        let mut door = Door::new(
+            DoorKind::Sliding {
+                offset: [0f32, 2f32, 0f32],
+            },
+            Transform::default(),
+            2f32,
+        );
+        door.advance(true, 1f32);
+        assert!((door.progress() - 0.5).abs() < 1e-5);
+        door.advance(true, 5f32);
+        assert_eq!(door.progress(), 1f32);
+        door.advance(false, 0.5);
+        assert!((door.progress() - 0.75).abs() < 1e-5);
+    }
+}
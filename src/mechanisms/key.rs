@@ -0,0 +1,126 @@
+use crate::interaction::{Interactable, Interacted};
+use crate::save::Saveable;
+use crate::scripting::ScriptMessage;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// This component makes an entity a key subjects pick up by using it. The key's name is what
+/// [`Door::with_key`](crate::mechanisms::Door::with_key) refers to, ie "brass key".
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct KeyItem {
+    key: String,
+}
+
+impl KeyItem {
+    pub fn new(key: impl Into<String>) -> Self {
+        KeyItem { key: key.into() }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+/// The saved state of a [`KeyItem`](crate::mechanisms::KeyItem). A collected key is hidden rather
+/// than despawned, so loading a save from before it was picked up brings it back.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyItemState {
+    pub collected: bool,
+}
+
+impl Saveable for KeyItemState {
+    const SAVE_KEY: &'static str = "key_item_state";
+}
+
+/// This component holds the keys a subject has picked up
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRing {
+    keys: BTreeSet<String>,
+}
+
+impl KeyRing {
+    pub fn insert(&mut self, key: impl Into<String>) {
+        self.keys.insert(key.into());
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(String::as_str)
+    }
+}
+
+impl Saveable for KeyRing {
+    const SAVE_KEY: &'static str = "key_ring";
+}
+
+/// Put the keys subjects use on their [`KeyRing`](crate::mechanisms::KeyRing)
+pub fn collect_keys(
+    mut interacted_events: EventReader<Interacted>,
+    mut key_query: Query<(&KeyItem, &mut KeyItemState)>,
+    mut key_ring_query: Query<&mut KeyRing>,
+    mut message: ResMut<ScriptMessage>,
+) {
+    for event in interacted_events.iter() {
+        let (key_item, mut state) = match key_query.get_mut(event.target) {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        let mut key_ring = match key_ring_query.get_mut(event.subject) {
+            Ok(key_ring) => key_ring,
+            Err(_) => continue,
+        };
+        if state.collected {
+            continue;
+        }
+        key_ring.insert(key_item.key());
+        state.collected = true;
+        message.show(format!("Picked up the {}", key_item.key()), 3f32);
+    }
+}
+
+/// Hide collected keys, and stop them colliding or being looked at. The mesh is added after the
+/// key is spawned, so it's hidden again when it is.
+pub fn hide_collected_keys(
+    mut key_query: Query<
+        (
+            &KeyItemState,
+            &mut Interactable,
+            &mut ColliderFlagsComponent,
+            Option<&mut Visibility>,
+        ),
+        Or<(Changed<KeyItemState>, Added<Visibility>)>,
+    >,
+) {
+    for (state, mut interactable, mut flags, visibility) in key_query.iter_mut() {
+        interactable.set_enabled(!state.collected);
+        flags.collision_groups = if state.collected {
+            InteractionGroups::none()
+        } else {
+            InteractionGroups::all()
+        };
+        if let Some(mut visibility) = visibility {
+            visibility.is_visible = !state.collected;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_ring_round_trips() {
+        let mut key_ring = KeyRing::default();
+        key_ring.insert("brass key");
+        key_ring.insert("brass key");
+        assert!(key_ring.contains("brass key"));
+        assert!(!key_ring.contains("iron key"));
+        let saved = toml::Value::try_from(&key_ring).unwrap();
+        assert_eq!(saved.try_into::<KeyRing>().unwrap(), key_ring);
+    }
+}
@@ -0,0 +1,16 @@
+//! Doors, switches and keys for building levels.
+//!
+//! A [`Door`](crate::mechanisms::Door) swings about a hinge or slides open when a subject uses it, or
+//! when it's sent a [`LinkSignal`](crate::mechanisms::LinkSignal) by a [`Switch`](crate::mechanisms::Switch)
+//! or a trigger action. A locked door only opens for a subject with its key on its
+//! [`KeyRing`](crate::mechanisms::KeyRing), picked up from a [`KeyItem`](crate::mechanisms::KeyItem).
+//!
+//! Their state is kept in [`Saveable`](crate::save::Saveable) components, so it's saved with the
+//! game, and they're declared in a level's [`LevelScript`](crate::scripting::LevelScript) file.
+mod door;
+mod key;
+mod switch;
+
+pub use self::door::*;
+pub use self::key::*;
+pub use self::switch::*;
@@ -0,0 +1,165 @@
+use crate::components::Tags;
+use crate::interaction::Interacted;
+use crate::save::Saveable;
+use crate::scripting::{ActionTriggered, MovingPlatform, TriggerAction};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How far a [`Lever`](crate::mechanisms::SwitchKind::Lever) tilts either way, in radians
+pub const LEVER_TILT: f32 = 0.5;
+
+/// What a signal tells the entities it's sent to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Open, or start moving
+    On,
+    /// Close, or stop moving
+    Off,
+    Toggle,
+}
+
+/// This event is sent to every door and moving platform with the tag, ie when a
+/// [`Switch`](crate::mechanisms::Switch) linked to them is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkSignal {
+    pub tag: String,
+    pub signal: Signal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchKind {
+    /// Toggles the linked entities every time it's pressed
+    Button,
+    /// Turns the linked entities on or off, following its position
+    Lever,
+}
+
+/// This component sends a [`LinkSignal`](crate::mechanisms::LinkSignal) to the entities with its
+/// target tags when a subject uses it.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Switch {
+    kind: SwitchKind,
+    targets: Vec<String>,
+}
+
+impl Switch {
+    pub fn new<I, S>(kind: SwitchKind, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Switch {
+            kind,
+            targets: targets.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn kind(&self) -> SwitchKind {
+        self.kind
+    }
+
+    /// The prompt players are shown when they look at the switch
+    pub fn action(&self) -> &'static str {
+        match self.kind {
+            SwitchKind::Button => "press the button",
+            SwitchKind::Lever => "pull the lever",
+        }
+    }
+}
+
+/// The saved state of a [`Switch`](crate::mechanisms::Switch). Only levers stay on.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwitchState {
+    pub on: bool,
+}
+
+impl Saveable for SwitchState {
+    const SAVE_KEY: &'static str = "switch_state";
+}
+
+pub fn use_switches(
+    mut interacted_events: EventReader<Interacted>,
+    mut switch_query: Query<(&Switch, &mut SwitchState)>,
+    mut signal_events: EventWriter<LinkSignal>,
+) {
+    for event in interacted_events.iter() {
+        let (switch, mut state) = match switch_query.get_mut(event.target) {
+            Ok(switch) => switch,
+            Err(_) => continue,
+        };
+        let signal = match switch.kind {
+            SwitchKind::Button => Signal::Toggle,
+            SwitchKind::Lever => {
+                state.on = !state.on;
+                if state.on {
+                    Signal::On
+                } else {
+                    Signal::Off
+                }
+            }
+        };
+        for tag in switch.targets.iter() {
+            signal_events.send(LinkSignal {
+                tag: tag.clone(),
+                signal,
+            });
+        }
+    }
+}
+
+/// Turn the [`OpenDoor`](crate::scripting::TriggerAction::OpenDoor) and
+/// [`CloseDoor`](crate::scripting::TriggerAction::CloseDoor) actions into signals
+pub fn signal_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    mut signal_events: EventWriter<LinkSignal>,
+) {
+    for event in action_events.iter() {
+        let (tag, signal) = match &event.action {
+            TriggerAction::OpenDoor { tag } => (tag, Signal::On),
+            TriggerAction::CloseDoor { tag } => (tag, Signal::Off),
+            _ => continue,
+        };
+        signal_events.send(LinkSignal {
+            tag: tag.clone(),
+            signal,
+        });
+    }
+}
+
+/// Start and stop the moving platforms a signal is sent to
+pub fn signal_platforms(
+    mut signal_events: EventReader<LinkSignal>,
+    mut platform_query: Query<(&Tags, &mut MovingPlatform)>,
+) {
+    for event in signal_events.iter() {
+        for (tags, mut platform) in platform_query.iter_mut() {
+            if !tags.contains(&event.tag) {
+                continue;
+            }
+            let enabled = match event.signal {
+                Signal::On => true,
+                Signal::Off => false,
+                Signal::Toggle => !platform.is_enabled(),
+            };
+            platform.set_enabled(enabled);
+        }
+    }
+}
+
+/// Tilt levers to show whether they're on. The mesh is added after the lever is spawned, so
+/// they're tilted again when it is.
+pub fn tilt_levers(
+    mut lever_query: Query<
+        (&Switch, &SwitchState, &mut Transform),
+        Or<(Changed<SwitchState>, Added<Handle<Mesh>>)>,
+    >,
+) {
+    for (switch, state, mut transform) in lever_query.iter_mut() {
+        if switch.kind() != SwitchKind::Lever {
+            continue;
+        }
+        let tilt = if state.on { -LEVER_TILT } else { LEVER_TILT };
+        transform.rotation = Quat::from_rotation_x(tilt);
+    }
+}
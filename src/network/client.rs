@@ -0,0 +1,541 @@
+use crate::components::{FirstPersonSubject, LevelObject, Lookaround, Movement};
+use crate::network::{
+    ClientMessage, EntitySnapshot, InputFrame, NetworkId, PlayerState, ServerMessage, Snapshot,
+    MAX_PACKET_SIZE,
+};
+use crate::resources::GameSettings;
+use crate::systems::physics::body_position;
+use bevy::prelude::*;
+use bevy_rapier3d::na::{UnitQuaternion, Vector3};
+use bevy_rapier3d::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// How often to say hello until the server answers, in seconds
+const HELLO_INTERVAL: f64 = 0.25;
+
+/// Remote entities are drawn this far in the past, in seconds, so there's usually
+/// a snapshot on either side of the time being drawn
+pub const INTERPOLATION_DELAY: f64 = 0.1;
+
+/// The most inputs kept for reconciliation. If the server stops acknowledging them, the oldest are dropped.
+const MAX_PENDING_INPUTS: usize = 128;
+
+/// How far the predicted player can be from where the server has it before it's corrected, in meters
+pub const RECONCILE_DISTANCE: f32 = 0.1;
+
+/// How far the predicted player can be turned from the server's before it's corrected, in radians
+pub const RECONCILE_YAW: f32 = 0.05;
+
+/// This component marks the subject the local player controls over the network. Its body is
+/// moved by the first person systems straight away, and corrected when the server's snapshots
+/// say it ended up somewhere else.
+#[derive(Component, Debug, Default)]
+pub struct PredictedPlayer;
+
+/// This component is added to every entity replicated from the server other than the local
+/// player: the other players, and the level objects of the client's own level that the server
+/// replicates. It keeps recent snapshots so the entity can be drawn between them.
+#[derive(Component, Debug, Default)]
+pub struct RemoteEntity {
+    samples: VecDeque<(f64, Vec3, Quat)>,
+}
+
+impl RemoteEntity {
+    pub fn push_sample(&mut self, time: f64, translation: Vec3, rotation: Quat) {
+        // Snapshots can arrive out of order, but they're only useful in order
+        if let Some((last_time, _, _)) = self.samples.back() {
+            if time < *last_time {
+                return;
+            }
+        }
+        self.samples.push_back((time, translation, rotation));
+    }
+
+    /// Where the entity was at `render_time`, blending between the snapshots around it. Snapshots
+    /// older than the ones needed are dropped. Before the first snapshot arrives, there's nothing to draw.
+    pub fn sample(&mut self, render_time: f64) -> Option<(Vec3, Quat)> {
+        while self.samples.len() > 2 && self.samples[1].0 <= render_time {
+            self.samples.pop_front();
+        }
+        let (start_time, start_translation, start_rotation) = *self.samples.front()?;
+        let (end_time, end_translation, end_rotation) = match self.samples.get(1) {
+            Some(end) if start_time <= render_time => *end,
+            // Too early to blend, or only one snapshot
+            _ => return Some((start_translation, start_rotation)),
+        };
+        if render_time >= end_time || end_time <= start_time {
+            return Some((end_translation, end_rotation));
+        }
+        let fraction = ((render_time - start_time) / (end_time - start_time)) as f32;
+        Some((
+            start_translation.lerp(end_translation, fraction),
+            start_rotation.slerp(end_rotation, fraction),
+        ))
+    }
+}
+
+/// An input sent to the server, and where the predicted player was once it was applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PredictedInput {
+    input: InputFrame,
+    state: Option<PlayerState>,
+}
+
+/// The client's socket and what it knows about its connection. Insert this resource along with
+/// the [`NetworkClientPlugin`](crate::plugins::NetworkClientPlugin) to join a server.
+pub struct NetworkClient {
+    socket: UdpSocket,
+    client_id: Option<u32>,
+    network_id: Option<u32>,
+    next_sequence: u32,
+    pending_inputs: VecDeque<PredictedInput>,
+    remote_entities: HashMap<u32, Entity>,
+    last_snapshot_tick: u32,
+    last_hello: Option<f64>,
+    interpolation_delay: f64,
+}
+
+impl NetworkClient {
+    pub fn connect(server_address: impl ToSocketAddrs) -> io::Result<Self> {
+        let server_address = server_address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No server address"))?;
+        let local_address: SocketAddr = if server_address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        }
+        .parse()
+        .expect("Could not parse the unspecified address!");
+        let socket = UdpSocket::bind(local_address)?;
+        socket.connect(server_address)?;
+        socket.set_nonblocking(true)?;
+        Ok(NetworkClient {
+            socket,
+            client_id: None,
+            network_id: None,
+            next_sequence: 1,
+            pending_inputs: VecDeque::new(),
+            remote_entities: HashMap::new(),
+            last_snapshot_tick: 0,
+            last_hello: None,
+            interpolation_delay: INTERPOLATION_DELAY,
+        })
+    }
+
+    /// Draw remote entities this far in the past instead, in seconds, ie at their latest
+    /// snapshot with zero
+    pub fn with_interpolation_delay(mut self, interpolation_delay: f64) -> Self {
+        self.interpolation_delay = interpolation_delay;
+        self
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client_id.is_some()
+    }
+
+    /// The [`NetworkId`](crate::network::NetworkId) of the local player, once the server has welcomed us
+    pub fn network_id(&self) -> Option<u32> {
+        self.network_id
+    }
+
+    fn send(&self, message: &ClientMessage) {
+        if let Err(send_err) = self.socket.send(&message.encode()) {
+            debug!("Could not send to the server: {}", send_err);
+        }
+    }
+
+    /// Tell the server we're leaving, so it doesn't wait for us to time out
+    pub fn disconnect(&mut self) {
+        if self.is_connected() {
+            self.send(&ClientMessage::Goodbye);
+        }
+        self.client_id = None;
+        self.network_id = None;
+    }
+}
+
+/// Say hello to the server until it welcomes us
+pub fn send_hello_until_welcomed(mut client: ResMut<NetworkClient>, time: Res<Time>) {
+    if client.is_connected() {
+        return;
+    }
+    let now = time.seconds_since_startup();
+    let due = match client.last_hello {
+        Some(last_hello) => now - last_hello >= HELLO_INTERVAL,
+        None => true,
+    };
+    if due {
+        client.send(&ClientMessage::Hello);
+        client.last_hello = Some(now);
+    }
+}
+
+/// Once the server welcomes us, the local [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// with a body becomes the [`PredictedPlayer`](crate::network::PredictedPlayer), ie when the level
+/// it's in is entered
+pub fn attach_predicted_player(
+    mut commands: Commands,
+    client: Res<NetworkClient>,
+    subject_query: Query<
+        Entity,
+        (
+            With<FirstPersonSubject>,
+            With<RigidBodyPositionComponent>,
+            Without<NetworkId>,
+        ),
+    >,
+) {
+    let network_id = match client.network_id {
+        Some(network_id) => network_id,
+        None => return,
+    };
+    if let Some(subject) = subject_query.iter().next() {
+        commands
+            .entity(subject)
+            .insert(NetworkId(network_id))
+            .insert(PredictedPlayer);
+    }
+}
+
+/// Send this frame's intents to the server. The first person systems move the local player by
+/// them straight away, instead of waiting for the server to answer.
+pub fn send_client_input(
+    mut client: ResMut<NetworkClient>,
+    settings: Res<GameSettings>,
+    query: Query<(&Movement, &Lookaround), With<PredictedPlayer>>,
+) {
+    if !client.is_connected() {
+        return;
+    }
+    for (movement, lookaround) in query.iter() {
+        let sequence = client.next_sequence;
+        client.next_sequence += 1;
+        let input = InputFrame::from_intents(sequence, movement, lookaround, &settings);
+        client
+            .pending_inputs
+            .push_back(PredictedInput { input, state: None });
+        if client.pending_inputs.len() > MAX_PENDING_INPUTS {
+            client.pending_inputs.pop_front();
+        }
+        client.send(&ClientMessage::Input(input));
+    }
+}
+
+/// Remember where physics moved the predicted player to by the inputs sent this frame, to check
+/// against the server's snapshots
+pub fn record_predicted_states(
+    mut client: ResMut<NetworkClient>,
+    query: Query<&Transform, With<PredictedPlayer>>,
+) {
+    let state = match query.iter().next() {
+        Some(transform) => PlayerState::from_transform(transform),
+        None => return,
+    };
+    for predicted in client.pending_inputs.iter_mut().rev() {
+        if predicted.state.is_some() {
+            break;
+        }
+        predicted.state = Some(state);
+    }
+}
+
+/// Read every packet the server sent since the last frame. Snapshots correct the prediction and
+/// move the remote entities.
+#[allow(clippy::type_complexity)]
+pub fn receive_server_messages(
+    mut commands: Commands,
+    mut client: ResMut<NetworkClient>,
+    time: Res<Time>,
+    mut predicted_query: Query<&mut RigidBodyPositionComponent, With<PredictedPlayer>>,
+    mut remote_query: Query<&mut RemoteEntity, Without<LevelObject>>,
+    mut level_object_query: Query<
+        (Entity, &NetworkId, Option<&mut RemoteEntity>),
+        With<LevelObject>,
+    >,
+) {
+    let now = time.seconds_since_startup();
+    let mut buffer = [0u8; MAX_PACKET_SIZE];
+    loop {
+        let length = match client.socket.recv(&mut buffer) {
+            Ok(length) => length,
+            Err(recv_err) if recv_err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(recv_err) => {
+                // ie the server isn't up yet, so our hello bounced
+                debug!("Error while receiving from the server: {}", recv_err);
+                break;
+            }
+        };
+        let message = match ServerMessage::decode(&buffer[..length]) {
+            Ok(message) => message,
+            Err(decode_err) => {
+                debug!("Ignoring bad packet from the server: {:?}", decode_err);
+                continue;
+            }
+        };
+        match message {
+            ServerMessage::Welcome {
+                client_id,
+                network_id,
+            } => {
+                if client.is_connected() {
+                    continue;
+                }
+                info!("Joined the server as client {}", client_id);
+                client.client_id = Some(client_id);
+                client.network_id = Some(network_id);
+            }
+            ServerMessage::Snapshot(snapshot) => {
+                if !client.is_connected() || snapshot.tick < client.last_snapshot_tick {
+                    continue;
+                }
+                client.last_snapshot_tick = snapshot.tick;
+                apply_snapshot(
+                    &mut commands,
+                    &mut client,
+                    now,
+                    snapshot,
+                    &mut predicted_query,
+                    &mut remote_query,
+                    &mut level_object_query,
+                );
+            }
+        }
+    }
+}
+
+/// Check where the server has the predicted player against where it was predicted to be after
+/// the same input. If they're too far apart, the predicted player is moved by the difference, and
+/// so are the predictions of the inputs the server hasn't applied yet.
+fn reconcile(
+    client: &mut NetworkClient,
+    last_processed_input: u32,
+    server_state: PlayerState,
+    predicted_query: &mut Query<&mut RigidBodyPositionComponent, With<PredictedPlayer>>,
+) {
+    let mut predicted_state = None;
+    // The server has applied these inputs, so they're part of the snapshot already
+    while let Some(predicted) = client.pending_inputs.front() {
+        if predicted.input.sequence > last_processed_input {
+            break;
+        }
+        if predicted.input.sequence == last_processed_input {
+            predicted_state = predicted.state;
+        }
+        client.pending_inputs.pop_front();
+    }
+    // Physics hasn't moved the player by that input yet
+    let predicted_state = match predicted_state {
+        Some(predicted_state) => predicted_state,
+        None => return,
+    };
+    let (translation_error, yaw_error) = predicted_state.error_to(&server_state);
+    if translation_error.length() <= RECONCILE_DISTANCE && yaw_error.abs() <= RECONCILE_YAW {
+        return;
+    }
+    let turn = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw_error);
+    let shift = Vector3::new(
+        translation_error.x,
+        translation_error.y,
+        translation_error.z,
+    );
+    for mut body in predicted_query.iter_mut() {
+        body.position.translation.vector += shift;
+        body.position.rotation = turn * body.position.rotation;
+        body.next_position.translation.vector += shift;
+        body.next_position.rotation = turn * body.next_position.rotation;
+    }
+    for predicted in client.pending_inputs.iter_mut() {
+        if let Some(state) = predicted.state.as_mut() {
+            state.translation += translation_error;
+            state.yaw += yaw_error;
+        }
+    }
+}
+
+/// Correct the prediction, and move the remote entities. Level objects are looked up in the
+/// client's own level, and turned into kinematic bodies the server moves the first time it does.
+/// Anything else is another player, which is spawned the first time it's seen.
+#[allow(clippy::type_complexity)]
+fn apply_snapshot(
+    commands: &mut Commands,
+    client: &mut NetworkClient,
+    now: f64,
+    snapshot: Snapshot,
+    predicted_query: &mut Query<&mut RigidBodyPositionComponent, With<PredictedPlayer>>,
+    remote_query: &mut Query<&mut RemoteEntity, Without<LevelObject>>,
+    level_object_query: &mut Query<
+        (Entity, &NetworkId, Option<&mut RemoteEntity>),
+        With<LevelObject>,
+    >,
+) {
+    for EntitySnapshot {
+        network_id,
+        translation,
+        rotation,
+    } in snapshot.entities
+    {
+        if Some(network_id) == client.network_id {
+            let server_state = PlayerState::from_transform(&Transform {
+                translation,
+                rotation,
+                ..Default::default()
+            });
+            reconcile(
+                client,
+                snapshot.last_processed_input,
+                server_state,
+                predicted_query,
+            );
+            continue;
+        }
+        if NetworkId(network_id).is_level_object() {
+            let level_object = level_object_query
+                .iter_mut()
+                .find(|(_, id, _)| id.0 == network_id);
+            match level_object {
+                Some((_, _, Some(mut remote_entity))) => {
+                    remote_entity.push_sample(now, translation, rotation)
+                }
+                Some((entity, _, None)) => {
+                    let mut remote_entity = RemoteEntity::default();
+                    remote_entity.push_sample(now, translation, rotation);
+                    commands.entity(entity).insert(remote_entity).insert(
+                        RigidBodyTypeComponent::from(RigidBodyType::KinematicPositionBased),
+                    );
+                }
+                // ie the level hasn't been entered yet
+                None => {}
+            }
+            continue;
+        }
+        match client.remote_entities.get(&network_id) {
+            Some(entity) => {
+                if let Ok(mut remote_entity) = remote_query.get_mut(*entity) {
+                    remote_entity.push_sample(now, translation, rotation);
+                }
+            }
+            None => {
+                let mut remote_entity = RemoteEntity::default();
+                remote_entity.push_sample(now, translation, rotation);
+                let entity = commands
+                    .spawn()
+                    .insert(NetworkId(network_id))
+                    .insert(remote_entity)
+                    .insert(Transform {
+                        translation,
+                        rotation,
+                        ..Default::default()
+                    })
+                    .insert(GlobalTransform::default())
+                    .id();
+                client.remote_entities.insert(network_id, entity);
+            }
+        }
+    }
+}
+
+/// Draw remote entities a little in the past, between the two snapshots around that time,
+/// so they move smoothly even though snapshots arrive in bursts. Level objects' bodies follow, see
+/// [`move_remote_level_objects`](crate::network::move_remote_level_objects).
+pub fn interpolate_remote_entities(
+    client: Res<NetworkClient>,
+    time: Res<Time>,
+    mut query: Query<(&mut RemoteEntity, &mut Transform)>,
+) {
+    let render_time = time.seconds_since_startup() - client.interpolation_delay;
+    for (mut remote_entity, mut transform) in query.iter_mut() {
+        if let Some((translation, rotation)) = remote_entity.sample(render_time) {
+            transform.translation = translation;
+            transform.rotation = rotation;
+        }
+    }
+}
+
+/// Move the bodies of the level objects the server replicates to where they're drawn, so the
+/// client's player bumps into them there
+pub fn move_remote_level_objects(
+    mut query: Query<
+        (&Transform, &mut RigidBodyPositionComponent),
+        (With<RemoteEntity>, With<LevelObject>),
+    >,
+) {
+    for (transform, mut position) in query.iter_mut() {
+        position.next_position = body_position(transform);
+    }
+}
+
+/// Give the other players something to draw. The server doesn't say what they look like, so
+/// they're drawn as capsules the size of a player. Level objects already look like themselves,
+/// and headless clients have no meshes, so they're skipped.
+pub fn add_remote_entity_meshes(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    query: Query<(Entity, &Transform), (Added<RemoteEntity>, Without<LevelObject>)>,
+) {
+    let (mut meshes, mut materials) = match (meshes, materials) {
+        (Some(meshes), Some(materials)) => (meshes, materials),
+        _ => return,
+    };
+    for (entity, transform) in query.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(bevy::prelude::shape::Capsule {
+                radius: 0.5,
+                depth: 1f32,
+                ..Default::default()
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::ORANGE,
+                ..Default::default()
+            }),
+            transform: *transform,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_entity_no_samples() {
+        let mut remote_entity = RemoteEntity::default();
+        assert_eq!(remote_entity.sample(1.0), None);
+    }
+
+    #[test]
+    fn test_remote_entity_interpolates() {
+        let mut remote_entity = RemoteEntity::default();
+        remote_entity.push_sample(1.0, Vec3::ZERO, Quat::IDENTITY);
+        remote_entity.push_sample(2.0, Vec3::new(2f32, 0f32, 0f32), Quat::IDENTITY);
+        let (translation, _) = remote_entity.sample(1.5).unwrap();
+        assert!((translation - Vec3::new(1f32, 0f32, 0f32)).length() < 0.0001);
+        // Before the first sample the entity waits there, after the last it stays there
+        let (translation, _) = remote_entity.sample(0.5).unwrap();
+        assert_eq!(translation, Vec3::ZERO);
+        let (translation, _) = remote_entity.sample(3.0).unwrap();
+        assert_eq!(translation, Vec3::new(2f32, 0f32, 0f32));
+    }
+
+    #[test]
+    fn test_remote_entity_drops_old_samples() {
+        let mut remote_entity = RemoteEntity::default();
+        for second in 0..5 {
+            remote_entity.push_sample(
+                second as f64,
+                Vec3::new(second as f32, 0f32, 0f32),
+                Quat::IDENTITY,
+            );
+        }
+        // Out of order samples are ignored
+        remote_entity.push_sample(1.5, Vec3::new(100f32, 0f32, 0f32), Quat::IDENTITY);
+        let (translation, _) = remote_entity.sample(3.25).unwrap();
+        assert!((translation - Vec3::new(3.25, 0f32, 0f32)).length() < 0.0001);
+        assert_eq!(remote_entity.samples.len(), 2);
+    }
+}
@@ -0,0 +1,58 @@
+//! Client/server networking over UDP.
+//!
+//! The server is the authority: clients send the intents of their [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+//! as [`InputFrame`](crate::network::InputFrame)s, the server turns them back into intents for the same first person
+//! systems and Rapier bodies a local game uses, and replicates the transform of every
+//! [`LevelObject`](crate::components::LevelObject) with a [`NetworkId`](crate::network::NetworkId) in snapshots.
+//! Clients predict their own subject by simulating it locally, correct it when a snapshot puts its
+//! [`PlayerState`](crate::network::PlayerState) somewhere else, and draw the other players and the level objects
+//! of their own level a little in the past.
+use crate::save::SaveId;
+use bevy::ecs::component::Component;
+
+mod client;
+mod protocol;
+mod server;
+mod simulation;
+
+pub use self::client::*;
+pub use self::protocol::*;
+pub use self::server::*;
+pub use self::simulation::*;
+
+/// This component identifies a replicated entity, with the same id on the server and every client.
+/// The server picks players' ids, counting up from 1. Level objects are spawned on both sides, so their
+/// id comes from their [`SaveId`](crate::save::SaveId), see [`NetworkId::level_object`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetworkId(pub u32);
+
+/// Set in the ids of level objects, and never in the ids the server picks
+const LEVEL_OBJECT_BIT: u32 = 1 << 31;
+
+impl NetworkId {
+    /// The id of the level object spawned with `save_id`
+    pub fn level_object(save_id: &SaveId) -> Self {
+        let hash = save_id.as_str().bytes().fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+        NetworkId(hash | LEVEL_OBJECT_BIT)
+    }
+
+    pub fn is_level_object(&self) -> bool {
+        self.0 & LEVEL_OBJECT_BIT != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_object_ids() {
+        let ball = NetworkId::level_object(&SaveId::new("ball"));
+        assert_eq!(ball, NetworkId::level_object(&SaveId::new("ball")));
+        assert_ne!(ball, NetworkId::level_object(&SaveId::new("crate")));
+        assert!(ball.is_level_object());
+        assert!(!NetworkId(1).is_level_object());
+    }
+}
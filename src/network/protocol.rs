@@ -0,0 +1,342 @@
+use bevy::math::{Quat, Vec3};
+
+/// Bumped whenever the layout of a message changes, so old clients are turned away
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// The biggest packet either side sends. It stays under the usual MTU so packets aren't fragmented.
+pub const MAX_PACKET_SIZE: usize = 1200;
+
+/// How many entities fit in one snapshot packet
+pub const MAX_ENTITIES_PER_SNAPSHOT: usize = 32;
+
+const CLIENT_HELLO: u8 = 0;
+const CLIENT_INPUT: u8 = 1;
+const CLIENT_GOODBYE: u8 = 2;
+const SERVER_WELCOME: u8 = 0;
+const SERVER_SNAPSHOT: u8 = 1;
+
+/// What the player wanted their subject to do during one client frame. Clients send these
+/// instead of positions, so the server stays the authority on where everyone is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputFrame {
+    /// Counts up from `1`, so the server can tell the client which inputs it has applied
+    pub sequence: u32,
+    /// From `-1.0` (back) to `1.0` (forward)
+    pub forward: f32,
+    /// From `-1.0` (left) to `1.0` (right)
+    pub right: f32,
+    /// How far to turn around the vertical axis, in radians. Positive turns left.
+    pub yaw: f32,
+}
+
+/// The state of one replicated entity in a snapshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntitySnapshot {
+    pub network_id: u32,
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+/// The state of the server's world at one tick, as seen by one client
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub tick: u32,
+    /// The sequence of the last input from this client the server has applied
+    pub last_processed_input: u32,
+    pub entities: Vec<EntitySnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Hello,
+    Input(InputFrame),
+    Goodbye,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// Sent in reply to every `Hello`, since either one can be lost
+    Welcome {
+        client_id: u32,
+        network_id: u32,
+    },
+    Snapshot(Snapshot),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    Empty,
+    UnknownVersion(u8),
+    UnknownKind(u8),
+    Truncated,
+}
+
+impl ClientMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            ClientMessage::Hello => writer.put_u8(CLIENT_HELLO),
+            ClientMessage::Input(input) => {
+                writer.put_u8(CLIENT_INPUT);
+                writer.put_u32(input.sequence);
+                writer.put_f32(input.forward);
+                writer.put_f32(input.right);
+                writer.put_f32(input.yaw);
+            }
+            ClientMessage::Goodbye => writer.put_u8(CLIENT_GOODBYE),
+        }
+        writer.bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes)?;
+        match reader.u8()? {
+            CLIENT_HELLO => Ok(ClientMessage::Hello),
+            CLIENT_INPUT => Ok(ClientMessage::Input(InputFrame {
+                sequence: reader.u32()?,
+                forward: reader.f32()?,
+                right: reader.f32()?,
+                yaw: reader.f32()?,
+            })),
+            CLIENT_GOODBYE => Ok(ClientMessage::Goodbye),
+            kind => Err(DecodeError::UnknownKind(kind)),
+        }
+    }
+}
+
+impl ServerMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            ServerMessage::Welcome {
+                client_id,
+                network_id,
+            } => {
+                writer.put_u8(SERVER_WELCOME);
+                writer.put_u32(*client_id);
+                writer.put_u32(*network_id);
+            }
+            ServerMessage::Snapshot(snapshot) => {
+                writer.put_u8(SERVER_SNAPSHOT);
+                writer.put_u32(snapshot.tick);
+                writer.put_u32(snapshot.last_processed_input);
+                writer.put_u8(snapshot.entities.len() as u8);
+                for entity in snapshot.entities.iter() {
+                    writer.put_u32(entity.network_id);
+                    for value in entity.translation.to_array() {
+                        writer.put_f32(value);
+                    }
+                    for value in entity.rotation.to_array() {
+                        writer.put_f32(value);
+                    }
+                }
+            }
+        }
+        writer.bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes)?;
+        match reader.u8()? {
+            SERVER_WELCOME => Ok(ServerMessage::Welcome {
+                client_id: reader.u32()?,
+                network_id: reader.u32()?,
+            }),
+            SERVER_SNAPSHOT => {
+                let tick = reader.u32()?;
+                let last_processed_input = reader.u32()?;
+                let entity_count = reader.u8()?;
+                let mut entities = Vec::with_capacity(entity_count as usize);
+                for _ in 0..entity_count {
+                    entities.push(EntitySnapshot {
+                        network_id: reader.u32()?,
+                        translation: Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?),
+                        rotation: Quat::from_xyzw(
+                            reader.f32()?,
+                            reader.f32()?,
+                            reader.f32()?,
+                            reader.f32()?,
+                        ),
+                    });
+                }
+                Ok(ServerMessage::Snapshot(Snapshot {
+                    tick,
+                    last_processed_input,
+                    entities,
+                }))
+            }
+            kind => Err(DecodeError::UnknownKind(kind)),
+        }
+    }
+}
+
+/// Split the entities of a tick into snapshots that each fit in a packet
+pub fn split_snapshot(
+    tick: u32,
+    last_processed_input: u32,
+    entities: &[EntitySnapshot],
+) -> Vec<Snapshot> {
+    if entities.is_empty() {
+        return vec![Snapshot {
+            tick,
+            last_processed_input,
+            entities: Vec::new(),
+        }];
+    }
+    entities
+        .chunks(MAX_ENTITIES_PER_SNAPSHOT)
+        .map(|chunk| Snapshot {
+            tick,
+            last_processed_input,
+            entities: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// Every message starts with the protocol version. Numbers are little endian.
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Writer {
+            bytes: vec![PROTOCOL_VERSION],
+        }
+    }
+
+    fn put_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes };
+        match reader.u8() {
+            Ok(PROTOCOL_VERSION) => Ok(reader),
+            Ok(version) => Err(DecodeError::UnknownVersion(version)),
+            Err(_) => Err(DecodeError::Empty),
+        }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < count {
+            return Err(DecodeError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.take(4)?.try_into().expect("Took 4 bytes for a u32!");
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn f32(&mut self) -> Result<f32, DecodeError> {
+        let bytes = self.take(4)?.try_into().expect("Took 4 bytes for an f32!");
+        Ok(f32::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_message_round_trip() {
+        let messages = vec![
+            ClientMessage::Hello,
+            ClientMessage::Input(InputFrame {
+                sequence: 42,
+                forward: 1f32,
+                right: -0.5,
+                yaw: 0.01,
+            }),
+            ClientMessage::Goodbye,
+        ];
+        for message in messages {
+            assert_eq!(ClientMessage::decode(&message.encode()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_server_message_round_trip() {
+        let snapshot = ServerMessage::Snapshot(Snapshot {
+            tick: 7,
+            last_processed_input: 3,
+            entities: vec![EntitySnapshot {
+                network_id: 1,
+                translation: Vec3::new(1f32, 2f32, 3f32),
+                rotation: Quat::from_rotation_y(0.5),
+            }],
+        });
+        let encoded = snapshot.encode();
+        assert!(encoded.len() <= MAX_PACKET_SIZE);
+        assert_eq!(ServerMessage::decode(&encoded), Ok(snapshot));
+
+        let welcome = ServerMessage::Welcome {
+            client_id: 2,
+            network_id: 9,
+        };
+        assert_eq!(ServerMessage::decode(&welcome.encode()), Ok(welcome));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(ClientMessage::decode(&[]), Err(DecodeError::Empty));
+        assert_eq!(
+            ClientMessage::decode(&[PROTOCOL_VERSION + 1, CLIENT_HELLO]),
+            Err(DecodeError::UnknownVersion(PROTOCOL_VERSION + 1))
+        );
+        assert_eq!(
+            ClientMessage::decode(&[PROTOCOL_VERSION, 200]),
+            Err(DecodeError::UnknownKind(200))
+        );
+        let mut input = ClientMessage::Input(InputFrame {
+            sequence: 1,
+            forward: 0f32,
+            right: 0f32,
+            yaw: 0f32,
+        })
+        .encode();
+        input.pop();
+        assert_eq!(ClientMessage::decode(&input), Err(DecodeError::Truncated));
+    }
+
+    #[test]
+    fn test_split_snapshot_fits_in_packets() {
+        let entities: Vec<EntitySnapshot> = (0..70)
+            .map(|network_id| EntitySnapshot {
+                network_id,
+                translation: Vec3::ZERO,
+                rotation: Quat::IDENTITY,
+            })
+            .collect();
+        let snapshots = split_snapshot(1, 0, &entities);
+        assert_eq!(snapshots.len(), 3);
+        for snapshot in snapshots {
+            let encoded = ServerMessage::Snapshot(snapshot).encode();
+            assert!(encoded.len() <= MAX_PACKET_SIZE);
+        }
+        // A world with nothing to replicate still acknowledges inputs
+        assert_eq!(split_snapshot(1, 5, &[]).len(), 1);
+    }
+}
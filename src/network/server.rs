@@ -0,0 +1,335 @@
+use crate::components::{
+    FirstPersonSubject, InputSource, LevelObject, LocalPlayer, Lookaround, Movement, Surface,
+};
+use crate::network::{
+    split_snapshot, ClientMessage, EntitySnapshot, InputFrame, NetworkId, ServerMessage,
+    MAX_PACKET_SIZE,
+};
+use crate::resources::{GameConfig, GameSettings};
+use crate::save::SaveId;
+use crate::systems::player::{player_collider, player_rigid_body};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Clients that haven't sent anything for this long are dropped, in seconds
+pub const CLIENT_TIMEOUT: f64 = 5.0;
+
+/// The most inputs merged for one client per tick. Anything above this is left for the next
+/// tick, so a client can't turn faster by sending inputs in bursts.
+pub const MAX_INPUTS_PER_TICK: usize = 8;
+
+/// The most inputs queued for one client. Older inputs are dropped first.
+const MAX_QUEUED_INPUTS: usize = 64;
+
+/// Where joining players' feet are spawned, on the ground and spaced out along the X axis
+const SPAWN_POINT: Vec3 = Vec3::new(0f32, 0.1, 7f32);
+
+/// This component is added to the entity the server spawns for every connected client.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerPlayer {
+    client_id: u32,
+}
+
+impl ServerPlayer {
+    pub fn client_id(&self) -> u32 {
+        self.client_id
+    }
+}
+
+struct ConnectedClient {
+    client_id: u32,
+    network_id: u32,
+    entity: Entity,
+    pending_inputs: VecDeque<InputFrame>,
+    last_queued_input: u32,
+    last_processed_input: u32,
+    last_heard: f64,
+}
+
+/// The server's socket and everyone connected to it. Insert this resource along with the
+/// [`NetworkServerPlugin`](crate::plugins::NetworkServerPlugin) to host a game.
+pub struct NetworkServer {
+    socket: UdpSocket,
+    clients: HashMap<SocketAddr, ConnectedClient>,
+    next_client_id: u32,
+    next_network_id: u32,
+    tick: u32,
+}
+
+impl NetworkServer {
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(NetworkServer {
+            socket,
+            clients: HashMap::new(),
+            next_client_id: 1,
+            next_network_id: 1,
+            tick: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    fn next_network_id(&mut self) -> u32 {
+        let network_id = self.next_network_id;
+        self.next_network_id += 1;
+        network_id
+    }
+
+    fn send(&self, message: &ServerMessage, address: SocketAddr) {
+        if let Err(send_err) = self.socket.send_to(&message.encode(), address) {
+            debug!("Could not send to client {}: {}", address, send_err);
+        }
+    }
+}
+
+/// The level a dedicated server hosts: the ground and the bouncing ball of the main game level,
+/// without anything to draw them with. The ball has the same [`SaveId`](crate::save::SaveId) as
+/// the main level's, so clients see it where the server has it.
+pub fn spawn_server_level(mut commands: Commands) {
+    commands
+        .spawn_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(100.0, 0.1, 100.0).into(),
+            ..Default::default()
+        })
+        .insert(LevelObject);
+
+    commands
+        .spawn_bundle(RigidBodyBundle {
+            position: Vec3::new(0.0, 10.0, 0.0).into(),
+            ..Default::default()
+        })
+        .insert_bundle(ColliderBundle {
+            shape: ColliderShape::ball(0.5).into(),
+            ..Default::default()
+        })
+        .insert(Surface::new("bouncy"))
+        .insert(LevelObject)
+        .insert(SaveId::new("ball"))
+        .insert(Transform::default())
+        .insert(GlobalTransform::default())
+        .insert(RigidBodyPositionSync::Discrete);
+}
+
+/// Give every [`LevelObject`](crate::components::LevelObject) with a [`SaveId`](crate::save::SaveId)
+/// its [`NetworkId`](crate::network::NetworkId), so the server and its clients agree on it. Players
+/// are left to the server to number.
+#[allow(clippy::type_complexity)]
+pub fn assign_network_ids(
+    mut commands: Commands,
+    query: Query<
+        (Entity, &SaveId),
+        (
+            With<LevelObject>,
+            Without<FirstPersonSubject>,
+            Without<NetworkId>,
+        ),
+    >,
+) {
+    for (entity, save_id) in query.iter() {
+        commands
+            .entity(entity)
+            .insert(NetworkId::level_object(save_id));
+    }
+}
+
+/// Read every packet that arrived since the last tick. Clients join by saying hello,
+/// and are dropped when they say goodbye or time out.
+pub fn receive_client_messages(
+    mut commands: Commands,
+    mut server: ResMut<NetworkServer>,
+    time: Res<Time>,
+) {
+    let now = time.seconds_since_startup();
+    let mut buffer = [0u8; MAX_PACKET_SIZE];
+    loop {
+        let (length, address) = match server.socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(recv_err) if recv_err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(recv_err) => {
+                // ie the previous packet to a client bounced, which isn't fatal for the server
+                debug!("Error while receiving from clients: {}", recv_err);
+                continue;
+            }
+        };
+        let message = match ClientMessage::decode(&buffer[..length]) {
+            Ok(message) => message,
+            Err(decode_err) => {
+                debug!("Ignoring bad packet from {}: {:?}", address, decode_err);
+                continue;
+            }
+        };
+        if let Some(client) = server.clients.get_mut(&address) {
+            client.last_heard = now;
+        }
+        match message {
+            ClientMessage::Hello => {
+                if !server.clients.contains_key(&address) {
+                    let client_id = server.next_client_id;
+                    server.next_client_id += 1;
+                    let network_id = server.next_network_id();
+                    let spawn_point = SPAWN_POINT + Vec3::X * 2f32 * (server.clients.len() as f32);
+                    let entity = commands
+                        .spawn()
+                        .insert(ServerPlayer { client_id })
+                        .insert(NetworkId(network_id))
+                        .insert(LevelObject)
+                        .insert(Transform::from_translation(spawn_point))
+                        .insert(GlobalTransform::default())
+                        .id();
+                    info!("Client {} joined from {}", client_id, address);
+                    server.clients.insert(
+                        address,
+                        ConnectedClient {
+                            client_id,
+                            network_id,
+                            entity,
+                            pending_inputs: VecDeque::new(),
+                            last_queued_input: 0,
+                            last_processed_input: 0,
+                            last_heard: now,
+                        },
+                    );
+                }
+                let client = &server.clients[&address];
+                let welcome = ServerMessage::Welcome {
+                    client_id: client.client_id,
+                    network_id: client.network_id,
+                };
+                server.send(&welcome, address);
+            }
+            ClientMessage::Input(input) => {
+                if let Some(client) = server.clients.get_mut(&address) {
+                    // Packets can arrive out of order, and late ones are useless
+                    if input.sequence > client.last_queued_input {
+                        client.last_queued_input = input.sequence;
+                        client.pending_inputs.push_back(input);
+                        if client.pending_inputs.len() > MAX_QUEUED_INPUTS {
+                            client.pending_inputs.pop_front();
+                        }
+                    }
+                }
+            }
+            ClientMessage::Goodbye => {
+                if let Some(client) = server.clients.remove(&address) {
+                    info!("Client {} left", client.client_id);
+                    commands.entity(client.entity).despawn_recursive();
+                }
+            }
+        }
+    }
+
+    let timed_out: Vec<SocketAddr> = server
+        .clients
+        .iter()
+        .filter(|(_, client)| now - client.last_heard > CLIENT_TIMEOUT)
+        .map(|(address, _)| *address)
+        .collect();
+    for address in timed_out {
+        if let Some(client) = server.clients.remove(&address) {
+            info!("Client {} timed out", client.client_id);
+            commands.entity(client.entity).despawn_recursive();
+        }
+    }
+}
+
+/// Turn the inputs each client sent since the last tick into its player's intents, which the first
+/// person systems move its body by like they move a local player's. The server is the authority,
+/// clients only predict this.
+///
+/// Several inputs in one tick are merged, their turns adding up and the last one saying which way
+/// to walk. A tick without inputs, ie when they were lost, keeps walking the same way.
+pub fn apply_client_inputs(
+    mut server: ResMut<NetworkServer>,
+    settings: Res<GameSettings>,
+    mut player_query: Query<(&mut Movement, &mut Lookaround), With<ServerPlayer>>,
+) {
+    for client in server.clients.values_mut() {
+        let (mut movement, mut lookaround) = match player_query.get_mut(client.entity) {
+            Ok(intents) => intents,
+            // The player's body isn't added yet, its inputs wait for the next tick
+            Err(_) => continue,
+        };
+        let mut merged: Option<InputFrame> = None;
+        for _ in 0..MAX_INPUTS_PER_TICK {
+            let input = match client.pending_inputs.pop_front() {
+                Some(input) => input,
+                None => break,
+            };
+            client.last_processed_input = input.sequence;
+            merged = Some(match merged {
+                Some(merged) => InputFrame {
+                    yaw: merged.yaw + input.yaw,
+                    ..input
+                },
+                None => input,
+            });
+        }
+        match merged {
+            Some(input) => {
+                let (new_movement, new_lookaround) = input.to_intents(&settings);
+                *movement = new_movement;
+                *lookaround = new_lookaround;
+            }
+            None => *lookaround = Lookaround::default(),
+        }
+    }
+}
+
+/// Give joining players the same dynamic body and intents as a local player, standing on the
+/// spawn point. Each is controlled by its client, through a
+/// [`LocalPlayer`](crate::components::LocalPlayer) component with a network input.
+pub fn add_server_player_bodies(
+    mut commands: Commands,
+    game_config: Res<GameConfig>,
+    query: Query<(Entity, &ServerPlayer, &Transform), Added<ServerPlayer>>,
+) {
+    let player_config = game_config.player();
+    for (entity, server_player, transform) in query.iter() {
+        let center = transform.translation + Vec3::Y * (player_config.capsule_height() / 2f32);
+        commands
+            .entity(entity)
+            .insert(FirstPersonSubject)
+            .insert(LocalPlayer::new(
+                server_player.client_id as usize - 1,
+                InputSource::Network(server_player.client_id),
+            ))
+            .insert(Movement::default())
+            .insert(Lookaround::default())
+            .insert(RigidBodyPositionSync::Discrete)
+            .insert_bundle(player_collider(player_config))
+            .insert_bundle(player_rigid_body(center));
+    }
+}
+
+/// Send every client the state of every replicated entity, along with the last of its
+/// inputs the server has applied so it can reconcile its prediction.
+pub fn send_snapshots(
+    mut server: ResMut<NetworkServer>,
+    query: Query<(&NetworkId, &Transform), With<LevelObject>>,
+) {
+    server.tick += 1;
+    let entities: Vec<EntitySnapshot> = query
+        .iter()
+        .map(|(network_id, transform)| EntitySnapshot {
+            network_id: network_id.0,
+            translation: transform.translation,
+            rotation: transform.rotation,
+        })
+        .collect();
+    for (address, client) in server.clients.iter() {
+        for snapshot in split_snapshot(server.tick, client.last_processed_input, &entities) {
+            server.send(&ServerMessage::Snapshot(snapshot), *address);
+        }
+    }
+}
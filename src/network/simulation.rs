@@ -0,0 +1,172 @@
+use crate::components::{Lookaround, LookaroundDirection, Movement, MovementDirection};
+use crate::network::InputFrame;
+use crate::resources::GameSettings;
+use crate::systems::player::body_turn_rate;
+use bevy::prelude::*;
+
+/// The part of a networked player's state the client predicts and the server corrects. Both
+/// move their player's dynamic body with the same first person systems, and compare where it
+/// ended up after each input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerState {
+    pub translation: Vec3,
+    pub yaw: f32,
+}
+
+impl PlayerState {
+    pub fn from_transform(transform: &Transform) -> Self {
+        let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        PlayerState {
+            translation: transform.translation,
+            yaw,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw)
+    }
+
+    /// How far `other` is from this state: the distance between them, and the turn around the
+    /// vertical axis from this state's yaw to theirs, in radians
+    pub fn error_to(&self, other: &PlayerState) -> (Vec3, f32) {
+        let yaw_error = (other.yaw - self.yaw + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        (other.translation - self.translation, yaw_error)
+    }
+}
+
+impl InputFrame {
+    /// Turn the intents the first person systems produced this frame into an input to send. The
+    /// turn is sent in radians, so it doesn't depend on the server's sensitivity settings.
+    pub fn from_intents(
+        sequence: u32,
+        movement: &Movement,
+        lookaround: &Lookaround,
+        settings: &GameSettings,
+    ) -> Self {
+        let forward = match movement.forward_back() {
+            MovementDirection::Forward(magnitude) => magnitude,
+            MovementDirection::Back(magnitude) => -magnitude,
+            _ => 0f32,
+        };
+        let right = match movement.left_right() {
+            MovementDirection::Right(magnitude) => magnitude,
+            MovementDirection::Left(magnitude) => -magnitude,
+            _ => 0f32,
+        };
+        let yaw = match lookaround.left_right() {
+            LookaroundDirection::Left(magnitude) => magnitude * body_turn_rate(settings),
+            LookaroundDirection::Right(magnitude) => -magnitude * body_turn_rate(settings),
+            _ => 0f32,
+        };
+        InputFrame {
+            sequence,
+            forward,
+            right,
+            yaw,
+        }
+    }
+
+    /// Turn an input back into the intents the first person systems move a player's body by
+    pub fn to_intents(&self, settings: &GameSettings) -> (Movement, Lookaround) {
+        let forward = self.forward.clamp(-1f32, 1f32);
+        let right = self.right.clamp(-1f32, 1f32);
+        let movement = Movement::from_components(
+            if right < 0f32 {
+                MovementDirection::Left(-right)
+            } else {
+                MovementDirection::Right(right)
+            },
+            if forward < 0f32 {
+                MovementDirection::Back(-forward)
+            } else {
+                MovementDirection::Forward(forward)
+            },
+        );
+        let turn_rate = body_turn_rate(settings);
+        let magnitude = if turn_rate > 0f32 {
+            self.yaw.clamp(-std::f32::consts::PI, std::f32::consts::PI) / turn_rate
+        } else {
+            0f32
+        };
+        let lookaround = Lookaround::from_components(
+            if magnitude < 0f32 {
+                LookaroundDirection::Right(-magnitude)
+            } else {
+                LookaroundDirection::Left(magnitude)
+            },
+            LookaroundDirection::Up(0f32),
+        );
+        (movement, lookaround)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_error() {
+        let state = PlayerState {
+            translation: Vec3::new(1f32, 2f32, 3f32),
+            yaw: 3f32,
+        };
+        let other = PlayerState {
+            translation: Vec3::new(1f32, 2f32, 4f32),
+            yaw: -3f32,
+        };
+        let (translation_error, yaw_error) = state.error_to(&other);
+        assert_eq!(translation_error, Vec3::new(0f32, 0f32, 1f32));
+        // The short way round, through PI
+        assert!((yaw_error - (std::f32::consts::TAU - 6f32)).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_state_from_transform() {
+        let transform = Transform {
+            translation: Vec3::new(1f32, 2f32, 3f32),
+            rotation: Quat::from_rotation_y(0.75),
+            ..Default::default()
+        };
+        let state = PlayerState::from_transform(&transform);
+        assert_eq!(state.translation, transform.translation);
+        assert!((state.yaw - 0.75).abs() < 0.0001);
+        assert!(state.rotation().angle_between(transform.rotation) < 0.0001);
+    }
+
+    #[test]
+    fn test_from_intents() {
+        let movement =
+            Movement::from_components(MovementDirection::Left(1f32), MovementDirection::Back(0.5));
+        let lookaround = Lookaround::default();
+        let input = InputFrame::from_intents(3, &movement, &lookaround, &GameSettings::default());
+        assert_eq!(input.sequence, 3);
+        assert_eq!(input.right, -1f32);
+        assert_eq!(input.forward, -0.5);
+        assert_eq!(input.yaw, 0f32);
+    }
+
+    #[test]
+    fn test_intents_round_trip() {
+        let movement = Movement::from_components(
+            MovementDirection::Right(0.25),
+            MovementDirection::Forward(1f32),
+        );
+        let lookaround = Lookaround::from_components(
+            LookaroundDirection::Right(30f32),
+            LookaroundDirection::Up(0f32),
+        );
+        let mut client_settings = GameSettings::default();
+        client_settings.set_horizontal_sensitivity(8);
+        let input = InputFrame::from_intents(1, &movement, &lookaround, &client_settings);
+        // The server turns the client's player as far as the client did, at its own sensitivity
+        let server_settings = GameSettings::default();
+        let (server_movement, server_lookaround) = input.to_intents(&server_settings);
+        assert_eq!(server_movement, movement);
+        assert_eq!(
+            server_lookaround.left_right(),
+            LookaroundDirection::Right(30f32 * 8f32 / 5f32)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::npc::{find_path, NavGrid};
+use crate::resources::GameConfig;
+use bevy::prelude::*;
+use bevy_rapier3d::na::{UnitQuaternion, Vector3};
+use bevy_rapier3d::prelude::*;
+
+/// How close an NPC has to get to a waypoint, on the ground, before heading for the next one
+const WAYPOINT_RADIUS: f32 = 0.3;
+/// How far from its last waypoint an NPC starts slowing down, in meters
+const SLOWING_DISTANCE: f32 = 1f32;
+/// How far a destination has to move before the path to it is planned again, in meters
+const REPLAN_DISTANCE: f32 = 1f32;
+
+/// Where a [`NavAgent`](crate::npc::NavAgent) is with getting to its destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavStatus {
+    /// It has nowhere to go
+    Idle,
+    /// It's on its way, or about to be once its path is planned
+    Moving,
+    Arrived,
+    /// There's no way there over the [`NavGrid`](crate::npc::NavGrid)
+    Unreachable,
+}
+
+/// This component steers an NPC's rigid body to a destination, along a path over the
+/// [`NavGrid`](crate::npc::NavGrid)
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct NavAgent {
+    destination: Option<Vec3>,
+    /// In meters per second
+    speed: f32,
+    status: NavStatus,
+    needs_path: bool,
+    path: Vec<Vec3>,
+    next_waypoint: usize,
+}
+
+impl Default for NavAgent {
+    fn default() -> Self {
+        NavAgent {
+            destination: None,
+            speed: 0f32,
+            status: NavStatus::Idle,
+            needs_path: false,
+            path: Vec::new(),
+            next_waypoint: 0,
+        }
+    }
+}
+
+impl NavAgent {
+    pub fn destination(&self) -> Option<Vec3> {
+        self.destination
+    }
+
+    pub fn status(&self) -> NavStatus {
+        self.status
+    }
+
+    /// The waypoints left to walk through
+    pub fn path(&self) -> &[Vec3] {
+        &self.path[self.next_waypoint.min(self.path.len())..]
+    }
+
+    /// Head for `destination` at `speed` meters per second. The path is only planned again if it's
+    /// moved far enough from the last destination, so this can be called every frame.
+    pub fn set_destination(&mut self, destination: Vec3, speed: f32) {
+        self.speed = speed;
+        let moved = self.destination.map_or(true, |previous| {
+            previous.distance(destination) > REPLAN_DISTANCE
+        });
+        if moved {
+            self.destination = Some(destination);
+            self.status = NavStatus::Moving;
+            self.needs_path = true;
+        }
+    }
+
+    /// Stand still, forgetting the destination
+    pub fn stop(&mut self) {
+        *self = NavAgent::default();
+    }
+
+    fn follow(&mut self, path: Vec<Vec3>) {
+        self.path = path;
+        self.next_waypoint = 0;
+        self.needs_path = false;
+    }
+
+    fn give_up(&mut self) {
+        self.path.clear();
+        self.next_waypoint = 0;
+        self.needs_path = false;
+        self.status = NavStatus::Unreachable;
+    }
+}
+
+fn body_translation(position: &RigidBodyPositionComponent) -> Vec3 {
+    let translation = position.position.translation.vector;
+    Vec3::new(translation.x, translation.y, translation.z)
+}
+
+/// Plan a path for the NPCs whose destination changed
+pub fn plan_npc_paths(
+    nav_grid: Res<NavGrid>,
+    mut agent_query: Query<(Entity, &mut NavAgent, &RigidBodyPositionComponent)>,
+) {
+    for (entity, mut agent, position) in agent_query.iter_mut() {
+        if !agent.needs_path {
+            continue;
+        }
+        let destination = match agent.destination {
+            Some(destination) => destination,
+            None => continue,
+        };
+        match find_path(&nav_grid, body_translation(position), destination) {
+            Some(path) => agent.follow(path),
+            None => {
+                debug!("{:?} can't find a way to {}", entity, destination);
+                agent.give_up();
+            }
+        }
+    }
+}
+
+/// Steer NPCs along their path by applying a force to their rigid body, like
+/// [`move_player_body`](crate::systems::player::move_player_body) does for players, and turn them to face
+/// the way they're going. The force grows with how far the body's velocity is from the one it
+/// wants, up to the NPC movement force, so NPCs slow down and stop by themselves.
+pub fn steer_npc_bodies(
+    mut agent_query: Query<(
+        &mut NavAgent,
+        &mut RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        &mut RigidBodyForcesComponent,
+    )>,
+    game_config: Res<GameConfig>,
+) {
+    let npc_config = game_config.npc();
+    let gain = npc_config.movement_force() / npc_config.chase_speed().max(0.1);
+    for (mut agent, mut position, velocity, mut forces) in agent_query.iter_mut() {
+        let translation = body_translation(&position);
+        // Pass every waypoint that's been reached
+        while let Some(waypoint) = agent.path.get(agent.next_waypoint) {
+            let offset = Vec2::new(waypoint.x - translation.x, waypoint.z - translation.z);
+            if offset.length() > WAYPOINT_RADIUS {
+                break;
+            }
+            agent.next_waypoint += 1;
+            if agent.next_waypoint == agent.path.len() {
+                agent.status = NavStatus::Arrived;
+            }
+        }
+
+        let desired = match agent.path.get(agent.next_waypoint) {
+            Some(waypoint) if agent.status == NavStatus::Moving => {
+                let offset =
+                    Vec3::new(waypoint.x - translation.x, 0f32, waypoint.z - translation.z);
+                let remaining: f32 = offset.length()
+                    + agent.path[agent.next_waypoint..]
+                        .windows(2)
+                        .map(|pair| pair[0].distance(pair[1]))
+                        .sum::<f32>();
+                let speed = agent.speed * (remaining / SLOWING_DISTANCE).min(1f32);
+                offset.normalize_or_zero() * speed
+            }
+            _ => Vec3::ZERO,
+        };
+        let current = Vec3::new(velocity.linvel.x, 0f32, velocity.linvel.z);
+        let steering = ((desired - current) * gain).clamp_length_max(npc_config.movement_force());
+        forces.force = Vector3::new(steering.x, 0f32, steering.z);
+
+        if desired.length() > 0.1 {
+            // Bevy's forward is -Z
+            let yaw = (-desired.x).atan2(-desired.z);
+            position.position.rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::npc::{NavAgent, NavStatus};
+use crate::perception::{Perception, PerceptionMemory};
+use crate::resources::{GameClock, GameConfig};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// What an NPC is doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NpcState {
+    /// Standing still
+    Idle,
+    /// Walking from waypoint to waypoint, waiting a moment at each
+    Patrol,
+    /// Running after a player it can see, or to where it last saw or heard them
+    Chase { target: Entity, last_seen: Vec3 },
+}
+
+/// This component is an NPC's state machine. It patrols between its waypoints, or stands idle if it
+/// has none, until it sees or hears a [`FirstPersonSubject`](crate::components::FirstPersonSubject).
+/// Then it chases them, and goes back to what it was doing once it's lost them.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct NpcBehavior {
+    state: NpcState,
+    patrol: Vec<Vec3>,
+    next_waypoint: usize,
+    /// When it's done waiting at a waypoint, on the [`GameClock`](crate::resources::GameClock)
+    wait_until: Option<Duration>,
+}
+
+impl NpcBehavior {
+    pub fn new(patrol: Vec<Vec3>) -> Self {
+        let mut behavior = NpcBehavior {
+            state: NpcState::Idle,
+            patrol,
+            next_waypoint: 0,
+            wait_until: None,
+        };
+        behavior.state = behavior.resting_state();
+        behavior
+    }
+
+    pub fn state(&self) -> NpcState {
+        self.state
+    }
+
+    /// What the NPC does when there's nobody to chase
+    fn resting_state(&self) -> NpcState {
+        if self.patrol.is_empty() {
+            NpcState::Idle
+        } else {
+            NpcState::Patrol
+        }
+    }
+}
+
+/// Move NPCs through their [`NpcBehavior`](crate::npc::NpcBehavior), pointing their
+/// [`NavAgent`](crate::npc::NavAgent) where it says to go. An NPC chases the player its
+/// [`Perception`](crate::perception::Perception) sees, or goes to where its
+/// [`PerceptionMemory`](crate::perception::PerceptionMemory) last had them. Once it's there and they
+/// aren't, it forgets them.
+pub fn update_npc_behavior(
+    mut npc_query: Query<(
+        &mut NpcBehavior,
+        &mut NavAgent,
+        &Perception,
+        &mut PerceptionMemory,
+    )>,
+    game_config: Res<GameConfig>,
+    game_clock: Res<GameClock>,
+) {
+    let npc_config = game_config.npc();
+    for (mut behavior, mut agent, perception, mut memory) in npc_query.iter_mut() {
+        let seen = perception.seen();
+        let target = seen
+            .map(|seen| (seen.entity, seen.position))
+            .or_else(|| memory.last_known().map(|last| (last.entity, last.position)));
+        if let Some((target, last_seen)) = target {
+            behavior.state = NpcState::Chase { target, last_seen };
+            behavior.wait_until = None;
+        }
+        match behavior.state {
+            NpcState::Idle => agent.stop(),
+            NpcState::Chase { last_seen, .. } => {
+                agent.set_destination(last_seen, npc_config.chase_speed());
+                // Lost them, and there's no sign of them where they were last seen
+                if seen.is_none()
+                    && matches!(agent.status(), NavStatus::Arrived | NavStatus::Unreachable)
+                {
+                    memory.forget();
+                    behavior.state = behavior.resting_state();
+                }
+            }
+            NpcState::Patrol => {
+                let waypoint = behavior.patrol[behavior.next_waypoint % behavior.patrol.len()];
+                agent.set_destination(waypoint, npc_config.walk_speed());
+                if !matches!(agent.status(), NavStatus::Arrived | NavStatus::Unreachable) {
+                    continue;
+                }
+                match behavior.wait_until {
+                    None => {
+                        behavior.wait_until = Some(game_clock.elapsed() + npc_config.patrol_wait())
+                    }
+                    Some(wait_until) if game_clock.elapsed() >= wait_until => {
+                        behavior.next_waypoint =
+                            (behavior.next_waypoint + 1) % behavior.patrol.len();
+                        behavior.wait_until = None;
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+}
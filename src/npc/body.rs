@@ -0,0 +1,90 @@
+use crate::components::Surface;
+use crate::npc::NavAgent;
+use crate::perception::{Perception, PerceptionMemory, Senses};
+use crate::resources::{GameConfig, NpcConfig};
+use crate::scripting::ScriptShape;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// This component makes an entity an NPC. It gets a capsule-shaped rigid body, sized by the
+/// [`NpcConfig`](crate::resources::NpcConfig), a [`NavAgent`](crate::npc::NavAgent) to steer it, and
+/// [`Senses`](crate::perception::Senses) to find players with. What it does is up to its [`NpcBehavior`](crate::npc::NpcBehavior).
+/// It's made of the frictionless "npc" [`SurfaceMaterial`](crate::resources::SurfaceMaterial), so
+/// steering alone decides how fast it goes.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Npc;
+
+/// The shape of an NPC's body, a capsule standing up around its center
+fn npc_capsule(npc_config: &NpcConfig) -> ScriptShape {
+    ScriptShape::Capsule {
+        half_height: (npc_config.capsule_height() / 2f32 - npc_config.capsule_radius()).max(0f32),
+        radius: npc_config.capsule_radius(),
+    }
+}
+
+/// Give new NPCs their body, where their transform puts them
+pub fn add_npc_bodies(
+    mut commands: Commands,
+    npc_query: Query<(Entity, &Transform), Added<Npc>>,
+    game_config: Res<GameConfig>,
+) {
+    let npc_config = game_config.npc();
+    let shape = npc_capsule(npc_config);
+    for (entity, transform) in npc_query.iter() {
+        commands
+            .entity(entity)
+            .insert_bundle(RigidBodyBundle {
+                position: transform.translation.into(),
+                // NPCs are turned by their NavAgent, not knocked over
+                mass_properties: RigidBodyMassPropsFlags::ROTATION_LOCKED.into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: shape.collider().into(),
+                ..Default::default()
+            })
+            .insert(Surface::new("npc"))
+            .insert(RigidBodyPositionSync::Discrete)
+            .insert(shape.clone())
+            .insert(NavAgent::default())
+            .insert(Senses::from(npc_config))
+            .insert(Perception::default())
+            .insert(PerceptionMemory::default());
+    }
+}
+
+/// Rebuild the body and [`Senses`](crate::perception::Senses) of every NPC when the
+/// [`NpcConfig`](crate::resources::NpcConfig) changes, ie after the config is reloaded
+pub fn resize_npc_capsules(
+    game_config: Res<GameConfig>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut npc_query: Query<
+        (
+            &mut ScriptShape,
+            &mut ColliderShapeComponent,
+            &mut Senses,
+            Option<&mut Handle<Mesh>>,
+        ),
+        With<Npc>,
+    >,
+) {
+    if !game_config.is_changed() {
+        return;
+    }
+    let npc_config = game_config.npc();
+    let shape = npc_capsule(npc_config);
+    let senses = Senses::from(npc_config);
+    for (mut npc_shape, mut collider_shape, mut npc_senses, mesh) in npc_query.iter_mut() {
+        if *npc_senses != senses {
+            *npc_senses = senses;
+        }
+        if *npc_shape == shape {
+            continue;
+        }
+        *npc_shape = shape.clone();
+        collider_shape.0 = shape.collider();
+        if let (Some(meshes), Some(mut mesh)) = (meshes.as_mut(), mesh) {
+            *mesh = meshes.add(shape.mesh());
+        }
+    }
+}
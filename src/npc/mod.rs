@@ -0,0 +1,21 @@
+//! Non-player characters that find their own way around a level.
+//!
+//! A level's static colliders are baked into a [`NavGrid`](crate::npc::NavGrid) of where NPCs can walk.
+//! Every [`Npc`](crate::npc::Npc) has a [`NavAgent`](crate::npc::NavAgent) that plans a path over it with A*,
+//! smooths it, and steers the NPC's rigid body along it. Where the agent heads is decided by the NPC's
+//! [`NpcBehavior`](crate::npc::NpcBehavior): idling, patrolling between waypoints, or chasing a
+//! [`FirstPersonSubject`](crate::components::FirstPersonSubject) its [`Senses`](crate::perception::Senses)
+//! picked up.
+//!
+//! None of it needs a window, so it runs on a headless app too.
+mod agent;
+mod behavior;
+mod body;
+mod nav_grid;
+mod pathfinding;
+
+pub use self::agent::*;
+pub use self::behavior::*;
+pub use self::body::*;
+pub use self::nav_grid::*;
+pub use self::pathfinding::*;
@@ -0,0 +1,427 @@
+use crate::resources::{GameConfig, NpcConfig};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::rapier::parry::query::RayCast;
+use bevy_rapier3d::rapier::parry::shape::Shape;
+
+/// The most cells a [`NavGrid`](crate::npc::NavGrid) has. Bigger levels get bigger cells.
+const MAX_NAV_CELLS: usize = 1_000_000;
+
+/// How a [`NavGrid`](crate::npc::NavGrid) is baked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavGridSettings {
+    /// In meters
+    pub cell_size: f32,
+    /// How far walkable cells are kept from walls and ledges, ie an NPC's radius
+    pub agent_radius: f32,
+    /// The highest ledge NPCs can step up or down, in meters
+    pub max_step: f32,
+    /// The steepest ground NPCs can walk on, in radians
+    pub max_slope: f32,
+}
+
+impl From<&NpcConfig> for NavGridSettings {
+    fn from(config: &NpcConfig) -> Self {
+        NavGridSettings {
+            cell_size: config.nav_cell_size(),
+            agent_radius: config.capsule_radius(),
+            max_step: config.nav_max_step(),
+            max_slope: config.nav_max_slope(),
+        }
+    }
+}
+
+/// The ground in one cell of a [`NavGrid`](crate::npc::NavGrid)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NavCell {
+    height: f32,
+    walkable: bool,
+}
+
+/// This resource is a grid over a level, saying where NPCs can walk and how high the ground is.
+/// It's baked by looking down on the level's static colliders, so it only knows about the top of
+/// everything: there's one floor, and the ground under an overhang can't be walked on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NavGrid {
+    /// The corner of the first cell, on the X and Z axes
+    origin: Vec2,
+    cell_size: f32,
+    width: usize,
+    depth: usize,
+    max_step: f32,
+    cells: Vec<Option<NavCell>>,
+}
+
+impl NavGrid {
+    /// Bake a grid covering `colliders`, casting a ray down through the middle of every cell to
+    /// find its ground. Ground that's too steep isn't walkable, and neither is anything closer than
+    /// the agent radius to a wall, a ledge or the edge of the level.
+    pub fn bake<'a>(
+        colliders: impl IntoIterator<Item = (&'a dyn Shape, &'a Isometry<Real>)>,
+        settings: &NavGridSettings,
+    ) -> Self {
+        let colliders: Vec<_> = colliders
+            .into_iter()
+            .map(|(shape, position)| (shape, position, shape.compute_aabb(position)))
+            .collect();
+        let (mins, maxs) = match colliders.iter().map(|(_, _, aabb)| aabb).fold(
+            None,
+            |bounds: Option<(Vec2, Vec2)>, aabb| {
+                let (mins, maxs) = (
+                    Vec2::new(aabb.mins.x, aabb.mins.z),
+                    Vec2::new(aabb.maxs.x, aabb.maxs.z),
+                );
+                Some(bounds.map_or((mins, maxs), |(bounds_mins, bounds_maxs)| {
+                    (bounds_mins.min(mins), bounds_maxs.max(maxs))
+                }))
+            },
+        ) {
+            Some(bounds) => bounds,
+            None => return NavGrid::default(),
+        };
+        let extent = maxs - mins;
+        let cell_size = settings
+            .cell_size
+            .max((extent.x * extent.y / MAX_NAV_CELLS as f32).sqrt())
+            .max(0.01);
+        let width = ((extent.x / cell_size).ceil() as usize).max(1);
+        let depth = ((extent.y / cell_size).ceil() as usize).max(1);
+        let mut grid = NavGrid {
+            origin: mins,
+            cell_size,
+            width,
+            depth,
+            max_step: settings.max_step,
+            cells: vec![None; width * depth],
+        };
+
+        let min_normal_y = settings.max_slope.cos();
+        for (shape, position, aabb) in colliders.iter() {
+            let top = aabb.maxs.y + 1f32;
+            let max_toi = top - aabb.mins.y + 1f32;
+            let (x_range, z_range) = match grid.cells_between(
+                Vec2::new(aabb.mins.x, aabb.mins.z),
+                Vec2::new(aabb.maxs.x, aabb.maxs.z),
+            ) {
+                Some(ranges) => ranges,
+                None => continue,
+            };
+            for z in z_range {
+                for x in x_range.clone() {
+                    let center = grid.cell_center((x, z));
+                    let ray = Ray::new(point![center.x, top, center.z], vector![0f32, -1f32, 0f32]);
+                    let intersection =
+                        match shape.cast_ray_and_get_normal(position, &ray, max_toi, true) {
+                            Some(intersection) => intersection,
+                            None => continue,
+                        };
+                    let cell = NavCell {
+                        height: top - intersection.toi,
+                        walkable: intersection.normal.y >= min_normal_y,
+                    };
+                    // The highest ground is the one seen from above
+                    let index = grid.index((x, z));
+                    if grid.cells[index].map_or(true, |existing| existing.height < cell.height) {
+                        grid.cells[index] = Some(cell);
+                    }
+                }
+            }
+        }
+        grid.erode(settings.agent_radius);
+        grid
+    }
+
+    /// Keep walkable cells at least `radius` away from anything that blocks NPCs
+    fn erode(&mut self, radius: f32) {
+        let blocking: Vec<bool> = (0..self.depth)
+            .flat_map(|z| (0..self.width).map(move |x| (x, z)))
+            .map(|cell| {
+                let own = match self.cells[self.index(cell)] {
+                    Some(own) if own.walkable => own,
+                    _ => return true,
+                };
+                let (x, z) = (cell.0 as isize, cell.1 as isize);
+                [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dz)| {
+                    match self.offset_cell((x + dx, z + dz)) {
+                        Some(neighbor) => self.cells[self.index(neighbor)]
+                            .map_or(true, |neighbor| {
+                                (neighbor.height - own.height).abs() > self.max_step
+                            }),
+                        None => true,
+                    }
+                })
+            })
+            .collect();
+        let reach = (radius / self.cell_size).ceil() as isize;
+        let mut eroded = self.cells.clone();
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                let index = self.index((x, z));
+                let near_blocking = (-reach..=reach).any(|dz| {
+                    (-reach..=reach).any(|dx| {
+                        dx * dx + dz * dz <= reach * reach
+                            && self
+                                .offset_cell((x as isize + dx, z as isize + dz))
+                                .map_or(false, |cell| blocking[self.index(cell)])
+                    })
+                });
+                if near_blocking {
+                    if let Some(cell) = eroded[index].as_mut() {
+                        cell.walkable = false;
+                    }
+                }
+            }
+        }
+        self.cells = eroded;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// The index of a cell, ie to keep something for every cell in a `Vec`
+    pub fn index(&self, cell: (usize, usize)) -> usize {
+        cell.1 * self.width + cell.0
+    }
+
+    /// The cell at `offset`, if it's in the grid
+    pub fn offset_cell(&self, offset: (isize, isize)) -> Option<(usize, usize)> {
+        let (x, z) = offset;
+        if x < 0 || z < 0 || x as usize >= self.width || z as usize >= self.depth {
+            None
+        } else {
+            Some((x as usize, z as usize))
+        }
+    }
+
+    /// The cell under `position`, if it's over the grid
+    pub fn cell_at(&self, position: Vec3) -> Option<(usize, usize)> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let x = ((position.x - self.origin.x) / self.cell_size).floor();
+        let z = ((position.z - self.origin.y) / self.cell_size).floor();
+        self.offset_cell((x as isize, z as isize))
+    }
+
+    /// The middle of a cell, on its ground if it has some
+    pub fn cell_center(&self, cell: (usize, usize)) -> Vec3 {
+        Vec3::new(
+            self.origin.x + (cell.0 as f32 + 0.5) * self.cell_size,
+            self.height(cell).unwrap_or_default(),
+            self.origin.y + (cell.1 as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// The x and z ranges of the cells between two corners, if any are in the grid
+    fn cells_between(
+        &self,
+        mins: Vec2,
+        maxs: Vec2,
+    ) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+        let first = ((mins - self.origin) / self.cell_size)
+            .floor()
+            .max(Vec2::ZERO);
+        let last = ((maxs - self.origin) / self.cell_size).ceil();
+        let x_range = first.x as usize..(last.x.max(0f32) as usize).min(self.width);
+        let z_range = first.y as usize..(last.y.max(0f32) as usize).min(self.depth);
+        if x_range.is_empty() || z_range.is_empty() {
+            None
+        } else {
+            Some((x_range, z_range))
+        }
+    }
+
+    /// The height of a cell's ground, if it has any
+    pub fn height(&self, cell: (usize, usize)) -> Option<f32> {
+        self.cells
+            .get(self.index(cell))
+            .and_then(|cell| cell.map(|cell| cell.height))
+    }
+
+    pub fn is_walkable(&self, cell: (usize, usize)) -> bool {
+        self.cells
+            .get(self.index(cell))
+            .map_or(false, |cell| cell.map_or(false, |cell| cell.walkable))
+    }
+
+    /// Whether an NPC can walk from one cell to the one next to it
+    pub fn can_step(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        if !self.is_walkable(from) || !self.is_walkable(to) {
+            return false;
+        }
+        match (self.height(from), self.height(to)) {
+            (Some(from), Some(to)) => (to - from).abs() <= self.max_step,
+            _ => false,
+        }
+    }
+
+    /// The walkable cell closest to `cell`, no more than `max_distance` cells away
+    pub fn nearest_walkable(
+        &self,
+        cell: (usize, usize),
+        max_distance: usize,
+    ) -> Option<(usize, usize)> {
+        let (x, z) = (cell.0 as isize, cell.1 as isize);
+        (0..=max_distance as isize).find_map(|distance| {
+            // The cells on the square ring `distance` cells away, closest first
+            let mut ring: Vec<(isize, isize)> = (-distance..=distance)
+                .flat_map(|dz| (-distance..=distance).map(move |dx| (dx, dz)))
+                .filter(|(dx, dz)| dx.abs().max(dz.abs()) == distance)
+                .collect();
+            ring.sort_by_key(|(dx, dz)| dx * dx + dz * dz);
+            ring.into_iter()
+                .filter_map(|(dx, dz)| self.offset_cell((x + dx, z + dz)))
+                .find(|cell| self.is_walkable(*cell))
+        })
+    }
+
+    /// Whether an NPC can walk in a straight line between two points, without leaving walkable
+    /// ground or stepping up or down too far
+    pub fn is_clear_line(&self, from: Vec3, to: Vec3) -> bool {
+        let (mut previous, last) = match (self.cell_at(from), self.cell_at(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return false,
+        };
+        if !self.is_walkable(previous) {
+            return false;
+        }
+        let flat = Vec2::new(to.x - from.x, to.z - from.z);
+        // Small steps, so the line can't cut across the corner of a cell
+        let steps = (flat.length() / (self.cell_size * 0.25)).ceil() as usize;
+        for step in 1..=steps {
+            let position = from.lerp(to, step as f32 / steps as f32);
+            let cell = match self.cell_at(position) {
+                Some(cell) => cell,
+                None => return false,
+            };
+            if cell != previous {
+                if !self.can_step(previous, cell) {
+                    return false;
+                }
+                previous = cell;
+            }
+        }
+        previous == last
+    }
+}
+
+/// Bake the [`NavGrid`](crate::npc::NavGrid) again when static colliders are added, ie when a level
+/// is set up, or when the [`GameConfig`](crate::resources::GameConfig) changes. Colliders with a
+/// rigid body move, so they aren't part of it, and neither are sensors.
+pub fn bake_nav_grid(
+    added_query: Query<
+        (),
+        (
+            Added<ColliderShapeComponent>,
+            Without<RigidBodyTypeComponent>,
+            Without<ColliderParentComponent>,
+        ),
+    >,
+    collider_query: Query<
+        (
+            &ColliderShapeComponent,
+            &ColliderPositionComponent,
+            &ColliderTypeComponent,
+        ),
+        (
+            Without<RigidBodyTypeComponent>,
+            Without<ColliderParentComponent>,
+        ),
+    >,
+    game_config: Res<GameConfig>,
+    mut nav_grid: ResMut<NavGrid>,
+) {
+    if added_query.iter().next().is_none() && !game_config.is_changed() {
+        return;
+    }
+    let colliders = collider_query
+        .iter()
+        .filter(|(_, _, collider_type)| collider_type.0 == ColliderType::Solid)
+        .map(|(shape, position, _)| (&*shape.0, &position.0 .0));
+    *nav_grid = NavGrid::bake(colliders, &NavGridSettings::from(game_config.npc()));
+    debug!(
+        "Baked a {}x{} navigation grid",
+        nav_grid.width(),
+        nav_grid.depth()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> NavGridSettings {
+        NavGridSettings {
+            cell_size: 0.5,
+            agent_radius: 0.4,
+            max_step: 0.35,
+            max_slope: 40f32.to_radians(),
+        }
+    }
+
+    #[test]
+    fn test_bake_ground_and_wall() {
+        let ground = SharedShape::cuboid(5.0, 0.1, 5.0);
+        let wall = SharedShape::cuboid(0.3, 1.0, 5.0);
+        let ground_position = Isometry::identity();
+        let wall_position = Isometry::translation(0.0, 1.0, 0.0);
+        let grid = NavGrid::bake(
+            [(&*ground, &ground_position), (&*wall, &wall_position)],
+            &settings(),
+        );
+        assert_eq!((grid.width(), grid.depth()), (20, 20));
+
+        let open = grid.cell_at(Vec3::new(-3.0, 0.0, 1.0)).unwrap();
+        assert!(grid.is_walkable(open));
+        assert!((grid.height(open).unwrap() - 0.1).abs() < 1e-4);
+        // The top of the wall can't be reached, and neither can the ground right next to it
+        let on_wall = grid.cell_at(Vec3::new(0.1, 0.0, 1.0)).unwrap();
+        assert!((grid.height(on_wall).unwrap() - 2.0).abs() < 1e-4);
+        assert!(!grid.is_walkable(on_wall));
+        assert!(!grid.is_walkable(grid.cell_at(Vec3::new(-0.9, 0.0, 1.0)).unwrap()));
+        // Nor can the edge of the level
+        assert!(!grid.is_walkable(grid.cell_at(Vec3::new(-4.9, 0.0, 1.0)).unwrap()));
+
+        assert!(grid.is_clear_line(Vec3::new(-3.0, 0.0, -3.0), Vec3::new(-3.0, 0.0, 3.0)));
+        assert!(!grid.is_clear_line(Vec3::new(-3.0, 0.0, 1.0), Vec3::new(3.0, 0.0, 1.0)));
+        assert_eq!(grid.nearest_walkable(on_wall, 1), None);
+        assert!(grid.nearest_walkable(on_wall, 4).is_some());
+    }
+
+    #[test]
+    fn test_steps_and_slopes() {
+        let ground = SharedShape::cuboid(5.0, 0.1, 5.0);
+        let step = SharedShape::cuboid(1.5, 0.2, 1.5);
+        let slope = SharedShape::cuboid(1.5, 0.1, 1.5);
+        let ground_position = Isometry::identity();
+        let step_position = Isometry::translation(-2.5, 0.1, 0.0);
+        // Too steep to walk up
+        let slope_position = Isometry::new(
+            vector![2.5, 0.5, 0.0],
+            vector![0.0, 0.0, 60f32.to_radians()],
+        );
+        let grid = NavGrid::bake(
+            [
+                (&*ground, &ground_position),
+                (&*step, &step_position),
+                (&*slope, &slope_position),
+            ],
+            &settings(),
+        );
+        let on_step = grid.cell_at(Vec3::new(-2.5, 0.0, 0.0)).unwrap();
+        assert!(grid.is_walkable(on_step));
+        assert!((grid.height(on_step).unwrap() - 0.3).abs() < 1e-4);
+        // A 0.2m step is low enough to walk up
+        assert!(grid.is_clear_line(Vec3::new(-2.5, 0.0, -3.5), Vec3::new(-2.5, 0.0, 0.0)));
+        assert!(!grid.is_walkable(grid.cell_at(Vec3::new(2.5, 0.0, 0.0)).unwrap()));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::npc::NavGrid;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// How many cells away from an unwalkable start or goal to look for one that's walkable, ie when an
+/// NPC or the player is standing right against a wall
+const SNAP_CELLS: usize = 4;
+
+/// A cell waiting to be searched, by its estimated total cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpenCell {
+    estimate: f32,
+    index: usize,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the heap pops the cheapest cell first
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The octile distance between two cells, ie the cost of the shortest path between them on open ground
+fn octile_distance(from: (usize, usize), to: (usize, usize), cell_size: f32) -> f32 {
+    let dx = (from.0 as f32 - to.0 as f32).abs();
+    let dz = (from.1 as f32 - to.1 as f32).abs();
+    (dx.max(dz) + (std::f32::consts::SQRT_2 - 1f32) * dx.min(dz)) * cell_size
+}
+
+/// The cells an NPC can walk to from `cell`, and what it costs. It can't cut across the corner of
+/// a cell it couldn't walk onto.
+fn neighbors(
+    grid: &NavGrid,
+    cell: (usize, usize),
+) -> impl Iterator<Item = ((usize, usize), f32)> + '_ {
+    let (x, z) = (cell.0 as isize, cell.1 as isize);
+    [
+        (1, 0),
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ]
+    .into_iter()
+    .filter_map(move |(dx, dz)| {
+        let neighbor = grid.offset_cell((x + dx, z + dz))?;
+        if !grid.can_step(cell, neighbor) {
+            return None;
+        }
+        if dx != 0 && dz != 0 {
+            let sides = (
+                grid.offset_cell((x + dx, z))?,
+                grid.offset_cell((x, z + dz))?,
+            );
+            if !grid.can_step(cell, sides.0) || !grid.can_step(cell, sides.1) {
+                return None;
+            }
+        }
+        let climb = (grid.height(neighbor)? - grid.height(cell)?).abs();
+        Some((
+            neighbor,
+            octile_distance(cell, neighbor, grid.cell_size()) + climb,
+        ))
+    })
+}
+
+/// Find the cells from `start` to `goal` with A*, including both
+fn search(
+    grid: &NavGrid,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Vec<(usize, usize)>> {
+    let cell_count = grid.width() * grid.depth();
+    let mut costs = vec![f32::INFINITY; cell_count];
+    let mut came_from = vec![usize::MAX; cell_count];
+    let mut open = BinaryHeap::new();
+    costs[grid.index(start)] = 0f32;
+    open.push(OpenCell {
+        estimate: octile_distance(start, goal, grid.cell_size()),
+        index: grid.index(start),
+    });
+    while let Some(OpenCell { estimate, index }) = open.pop() {
+        let cell = (index % grid.width(), index / grid.width());
+        if cell == goal {
+            let mut path = vec![goal];
+            let mut index = index;
+            while came_from[index] != usize::MAX {
+                index = came_from[index];
+                path.push((index % grid.width(), index / grid.width()));
+            }
+            path.reverse();
+            return Some(path);
+        }
+        // Skip cells that were found again more cheaply after being queued
+        if estimate > costs[index] + octile_distance(cell, goal, grid.cell_size()) + 1e-4 {
+            continue;
+        }
+        for (neighbor, step_cost) in neighbors(grid, cell) {
+            let neighbor_index = grid.index(neighbor);
+            let cost = costs[index] + step_cost;
+            if cost < costs[neighbor_index] {
+                costs[neighbor_index] = cost;
+                came_from[neighbor_index] = index;
+                open.push(OpenCell {
+                    estimate: cost + octile_distance(neighbor, goal, grid.cell_size()),
+                    index: neighbor_index,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Drop the waypoints an NPC can skip by walking straight to a later one. `from` is where the path
+/// starts, which isn't one of the waypoints.
+pub fn smooth_path(grid: &NavGrid, from: Vec3, waypoints: &[Vec3]) -> Vec<Vec3> {
+    let mut smoothed = Vec::new();
+    let mut anchor = from;
+    let mut index = 0;
+    while index < waypoints.len() {
+        // Walk as far along the path as there's a straight line to
+        let mut farthest = index;
+        while farthest + 1 < waypoints.len() && grid.is_clear_line(anchor, waypoints[farthest + 1])
+        {
+            farthest += 1;
+        }
+        anchor = waypoints[farthest];
+        smoothed.push(anchor);
+        index = farthest + 1;
+    }
+    smoothed
+}
+
+/// Find a path over the grid from `from` to `to`, with A* then smoothed so it only turns where it
+/// has to. It's the waypoints after `from`, on the ground, ending at `to`. A start or goal
+/// that's just off the walkable ground, ie against a wall, is moved to the closest walkable cell.
+pub fn find_path(grid: &NavGrid, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+    let start = grid.nearest_walkable(grid.cell_at(from)?, SNAP_CELLS)?;
+    let goal_cell = grid.cell_at(to)?;
+    let goal = grid.nearest_walkable(goal_cell, SNAP_CELLS)?;
+    let cells = search(grid, start, goal)?;
+    let mut waypoints: Vec<Vec3> = cells
+        .into_iter()
+        .skip(1)
+        .map(|cell| grid.cell_center(cell))
+        .collect();
+    // Go right up to the goal when it's walkable, not just to the middle of its cell
+    let end = if goal == goal_cell {
+        Vec3::new(to.x, grid.cell_center(goal).y, to.z)
+    } else {
+        grid.cell_center(goal)
+    };
+    match waypoints.last_mut() {
+        Some(last) => *last = end,
+        None => waypoints.push(end),
+    }
+    Some(smooth_path(grid, from, &waypoints))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::NavGridSettings;
+    use bevy_rapier3d::prelude::*;
+
+    /// A 10m square of ground with a wall across it, leaving a gap on the right if `gap` is set
+    fn walled_grid(gap: bool) -> NavGrid {
+        let ground = SharedShape::cuboid(5.0, 0.1, 5.0);
+        let wall_half_width = if gap { 3.5 } else { 5.0 };
+        let wall = SharedShape::cuboid(wall_half_width, 1.0, 0.3);
+        let ground_position = Isometry::identity();
+        let wall_position = Isometry::translation(wall_half_width - 5.0, 1.0, 0.0);
+        NavGrid::bake(
+            [(&*ground, &ground_position), (&*wall, &wall_position)],
+            &NavGridSettings {
+                cell_size: 0.5,
+                agent_radius: 0.4,
+                max_step: 0.35,
+                max_slope: 40f32.to_radians(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_straight_path_on_open_ground() {
+        let grid = walled_grid(true);
+        let from = Vec3::new(-3.0, 0.1, -3.0);
+        let to = Vec3::new(3.0, 0.1, -2.2);
+        let path = find_path(&grid, from, to).unwrap();
+        assert_eq!(path.len(), 1);
+        assert!(path[0].abs_diff_eq(to, 1e-4));
+    }
+
+    #[test]
+    fn test_path_goes_through_the_gap() {
+        let grid = walled_grid(true);
+        let from = Vec3::new(-3.0, 0.1, -3.0);
+        let to = Vec3::new(-3.0, 0.1, 3.0);
+        let path = find_path(&grid, from, to).unwrap();
+        assert!(path.len() > 1);
+        assert!(path.last().unwrap().abs_diff_eq(to, 1e-4));
+        // It crosses the wall's line past the end of the wall
+        assert!(path.iter().any(|waypoint| waypoint.x > 2.0));
+        let mut previous = from;
+        for waypoint in path.iter() {
+            assert!(grid.is_clear_line(previous, *waypoint));
+            previous = *waypoint;
+        }
+    }
+
+    #[test]
+    fn test_no_path_through_a_wall() {
+        let grid = walled_grid(false);
+        let from = Vec3::new(-3.0, 0.1, -3.0);
+        assert!(find_path(&grid, from, Vec3::new(-3.0, 0.1, 3.0)).is_none());
+        // Off the grid
+        assert!(find_path(&grid, from, Vec3::new(30.0, 0.1, 0.0)).is_none());
+        // Right against the wall, but still on the same side
+        assert!(find_path(&grid, from, Vec3::new(0.0, 0.1, -0.35)).is_some());
+    }
+}
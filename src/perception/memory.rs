@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Where a [`PerceptionMemory`](crate::perception::PerceptionMemory) last knew someone to be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LastKnownPosition {
+    pub entity: Entity,
+    pub position: Vec3,
+    /// How sure it still is, from 1 when it's just been seen down to 0 when it's forgotten
+    pub confidence: f32,
+}
+
+/// This component remembers where an agent last saw or heard a player. It fades over the agent's
+/// [`Senses`](crate::perception::Senses) memory, and is forgotten once it's gone.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerceptionMemory {
+    last_known: Option<LastKnownPosition>,
+}
+
+impl PerceptionMemory {
+    pub fn last_known(&self) -> Option<LastKnownPosition> {
+        self.last_known
+    }
+
+    /// Remember `entity` at `position`, over whatever was remembered before, since it's newer
+    pub fn remember(&mut self, entity: Entity, position: Vec3, confidence: f32) {
+        self.last_known = Some(LastKnownPosition {
+            entity,
+            position,
+            confidence: confidence.clamp(0f32, 1f32),
+        });
+    }
+
+    /// Fade the memory for `delta`, when a full one lasts for `memory`
+    pub fn decay(&mut self, delta: Duration, memory: Duration) {
+        let last_known = match self.last_known.as_mut() {
+            Some(last_known) => last_known,
+            None => return,
+        };
+        if memory.is_zero() {
+            self.last_known = None;
+            return;
+        }
+        last_known.confidence -= delta.as_secs_f32() / memory.as_secs_f32();
+        if last_known.confidence <= 0f32 {
+            self.last_known = None;
+        }
+    }
+
+    pub fn forget(&mut self) {
+        self.last_known = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fades() {
+        let mut memory = PerceptionMemory::default();
+        let entity = Entity::from_raw(1);
+        let ten_seconds = Duration::from_secs(10);
+        memory.decay(Duration::from_secs(1), ten_seconds);
+        assert_eq!(memory.last_known(), None);
+
+        memory.remember(entity, Vec3::X, 1f32);
+        memory.decay(Duration::from_secs(4), ten_seconds);
+        let last_known = memory.last_known().unwrap();
+        assert_eq!(last_known.position, Vec3::X);
+        assert!((last_known.confidence - 0.6).abs() < 1e-4);
+
+        // Hearing them somewhere else is newer, even if it's less sure
+        memory.remember(entity, Vec3::Z, 0.5);
+        memory.decay(Duration::from_secs(4), ten_seconds);
+        assert_eq!(memory.last_known().unwrap().position, Vec3::Z);
+        memory.decay(Duration::from_secs(2), ten_seconds);
+        assert_eq!(memory.last_known(), None);
+
+        memory.remember(entity, Vec3::X, 1f32);
+        memory.forget();
+        assert_eq!(memory.last_known(), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! What AI agents can see, hear and remember of the players.
+//!
+//! An agent with [`Senses`](crate::perception::Senses) sees players inside its view cone, unless
+//! there's level geometry in the way, and hears the [`NoiseEvent`](crate::perception::NoiseEvent)s
+//! players send with their footsteps, jumps and landings. Sneaking and walking slowly is quieter.
+//! What it picks up each frame is its [`Perception`](crate::perception::Perception), and where it
+//! last knew a player to be is kept in its [`PerceptionMemory`](crate::perception::PerceptionMemory)
+//! until it fades. Other systems, like an [`NpcBehavior`](crate::npc::NpcBehavior), read those
+//! components to decide what to do.
+mod memory;
+mod noise;
+mod senses;
+
+pub use self::memory::*;
+pub use self::noise::*;
+pub use self::senses::*;
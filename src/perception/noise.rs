@@ -0,0 +1,276 @@
+use crate::components::{FirstPersonSubject, Movement};
+use crate::resources::{GameClock, GameConfig};
+use crate::systems::player::find_ground;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How far a player walks between footsteps, in meters
+const STRIDE_LENGTH: f32 = 1.6;
+/// How far away a footstep at full speed can be heard, in meters
+const FOOTSTEP_LOUDNESS: f32 = 12f32;
+/// How far away a jump can be heard, in meters
+const JUMP_LOUDNESS: f32 = 8f32;
+/// How far away a landing can be heard, in meters, before how hard it was is added
+const LANDING_LOUDNESS: f32 = 4f32;
+/// How much further away a landing can be heard for every meter per second it landed at
+const LANDING_LOUDNESS_PER_SPEED: f32 = 1.5;
+/// How fast a body has to be falling for its landing to make a noise, in meters per second
+const MIN_LANDING_SPEED: f32 = 2f32;
+/// How fast a body has to be going up when it leaves the ground for it to be a jump, in meters per
+/// second
+const MIN_JUMP_SPEED: f32 = 1f32;
+/// Pushing less than this hard is sneaking, see [`Movement::magnitude`](crate::components::Movement::magnitude)
+const SNEAK_INPUT: f32 = 0.6;
+
+/// What made a [`NoiseEvent`](crate::perception::NoiseEvent)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    Footstep,
+    Jump,
+    Landing,
+}
+
+/// This event is a noise that [`Senses`](crate::perception::Senses) can hear
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseEvent {
+    /// Who made the noise
+    pub source: Entity,
+    pub position: Vec3,
+    pub kind: NoiseKind,
+    /// How far away it can be heard with nothing in the way, in meters
+    pub loudness: f32,
+    /// What the source was standing on, if anything
+    pub ground: Option<Entity>,
+}
+
+/// How a player is moving, which decides how much noise they make
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stance {
+    /// Only pushing part of the way, ie with a gently tilted stick or the sneak key held
+    Sneaking,
+    Walking,
+}
+
+impl Stance {
+    pub fn from_movement(movement: &Movement) -> Self {
+        let magnitude = movement.magnitude();
+        if magnitude > 0f32 && magnitude < SNEAK_INPUT {
+            Stance::Sneaking
+        } else {
+            Stance::Walking
+        }
+    }
+
+    /// How loud the noises made in this stance are, compared to walking
+    pub fn loudness(&self) -> f32 {
+        match self {
+            Stance::Sneaking => 0.35,
+            Stance::Walking => 1f32,
+        }
+    }
+}
+
+/// This component makes a [`FirstPersonSubject`](crate::components::FirstPersonSubject) send a
+/// [`NoiseEvent`](crate::perception::NoiseEvent) for its footsteps, jumps and landings
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct NoiseMaker {
+    /// How far it's walked since its last footstep, in meters
+    stride: f32,
+    grounded: bool,
+    /// How fast it's fallen since it left the ground, in meters per second
+    fall_speed: f32,
+}
+
+impl Default for NoiseMaker {
+    fn default() -> Self {
+        NoiseMaker {
+            stride: 0f32,
+            // Don't take a body that's spawned in the air for one that's jumped
+            grounded: false,
+            fall_speed: 0f32,
+        }
+    }
+}
+
+impl NoiseMaker {
+    /// Track the body for `delta` seconds, and return the noise it made, with how far away it
+    /// can be heard. Landing is louder the faster it fell, and footsteps are louder the closer the
+    /// body is to `max_speed`.
+    pub fn update(
+        &mut self,
+        grounded: bool,
+        velocity: Vec3,
+        stance: Stance,
+        delta: f32,
+        max_speed: f32,
+    ) -> Option<(NoiseKind, f32)> {
+        let was_grounded = self.grounded;
+        self.grounded = grounded;
+        if !grounded {
+            self.fall_speed = self.fall_speed.max(-velocity.y);
+            return (was_grounded && velocity.y > MIN_JUMP_SPEED)
+                .then(|| (NoiseKind::Jump, JUMP_LOUDNESS * stance.loudness()));
+        }
+        if !was_grounded {
+            let fall_speed = std::mem::take(&mut self.fall_speed);
+            self.stride = 0f32;
+            return (fall_speed >= MIN_LANDING_SPEED).then(|| {
+                (
+                    NoiseKind::Landing,
+                    (LANDING_LOUDNESS + LANDING_LOUDNESS_PER_SPEED * fall_speed)
+                        * stance.loudness(),
+                )
+            });
+        }
+        let speed = Vec2::new(velocity.x, velocity.z).length();
+        self.stride += speed * delta;
+        if self.stride < STRIDE_LENGTH {
+            return None;
+        }
+        self.stride %= STRIDE_LENGTH;
+        let pace = (speed / max_speed.max(0.1)).clamp(0.25, 1.5);
+        Some((
+            NoiseKind::Footstep,
+            FOOTSTEP_LOUDNESS * pace * stance.loudness(),
+        ))
+    }
+}
+
+/// Send a [`NoiseEvent`](crate::perception::NoiseEvent) for the noises players make. A player's on
+/// the ground when [`find_ground`](crate::systems::player::find_ground) finds some, like when they jump.
+#[allow(clippy::type_complexity)]
+pub fn emit_player_noises(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    mut subject_query: Query<
+        (
+            Entity,
+            &mut NoiseMaker,
+            &RigidBodyPositionComponent,
+            &RigidBodyVelocityComponent,
+            Option<&Movement>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    game_config: Res<GameConfig>,
+    game_clock: Res<GameClock>,
+    mut noise_events: EventWriter<NoiseEvent>,
+) {
+    let player_config = game_config.player();
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for (subject, mut noise_maker, position, velocity, movement) in subject_query.iter_mut() {
+        let translation = position.position.translation.vector;
+        let center = Vec3::new(translation.x, translation.y, translation.z);
+        let ground = find_ground(
+            &query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            center,
+            player_config.capsule_height(),
+        );
+        let linvel = velocity.linvel;
+        let stance = movement.map_or(Stance::Walking, Stance::from_movement);
+        let noise = noise_maker.update(
+            ground.is_some(),
+            Vec3::new(linvel.x, linvel.y, linvel.z),
+            stance,
+            game_clock.delta().as_secs_f32(),
+            player_config.max_speed(),
+        );
+        if let Some((kind, loudness)) = noise {
+            noise_events.send(NoiseEvent {
+                source: subject,
+                position: center,
+                kind,
+                loudness,
+                ground,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::MovementDirection;
+
+    const FRAME: f32 = 1f32 / 60f32;
+
+    /// Walk on the ground at `speed` for `seconds`, and return the noises
+    fn walk(
+        noise_maker: &mut NoiseMaker,
+        speed: f32,
+        stance: Stance,
+        seconds: f32,
+    ) -> Vec<(NoiseKind, f32)> {
+        let frames = (seconds / FRAME).round() as usize;
+        (0..frames)
+            .filter_map(|_| {
+                noise_maker.update(true, Vec3::new(speed, 0.0, 0.0), stance, FRAME, 6f32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_footsteps_depend_on_speed_and_stance() {
+        let mut noise_maker = NoiseMaker::default();
+        // Landing softly doesn't make a noise
+        assert_eq!(
+            noise_maker.update(true, Vec3::ZERO, Stance::Walking, FRAME, 6f32),
+            None
+        );
+        let running = walk(&mut noise_maker, 6f32, Stance::Walking, 2f32);
+        assert!(running.iter().all(|(kind, _)| *kind == NoiseKind::Footstep));
+        // 12m in 2s is 7 strides and a half
+        assert_eq!(running.len(), 7);
+        let strolling = walk(&mut noise_maker, 3f32, Stance::Walking, 2f32);
+        let sneaking = walk(&mut noise_maker, 3f32, Stance::Sneaking, 2f32);
+        assert!(strolling.len() < running.len());
+        assert!(strolling[0].1 < running[0].1);
+        assert!(sneaking[0].1 < strolling[0].1);
+        assert!(walk(&mut noise_maker, 0f32, Stance::Walking, 2f32).is_empty());
+    }
+
+    #[test]
+    fn test_jumps_and_landings() {
+        let mut noise_maker = NoiseMaker::default();
+        noise_maker.update(true, Vec3::ZERO, Stance::Walking, FRAME, 6f32);
+        assert_eq!(
+            noise_maker.update(false, Vec3::Y * 4f32, Stance::Walking, FRAME, 6f32),
+            Some((NoiseKind::Jump, JUMP_LOUDNESS))
+        );
+        assert_eq!(
+            noise_maker.update(false, Vec3::Y * -6f32, Stance::Walking, FRAME, 6f32),
+            None
+        );
+        let (kind, hard_landing) = noise_maker
+            .update(true, Vec3::ZERO, Stance::Walking, FRAME, 6f32)
+            .unwrap();
+        assert_eq!(kind, NoiseKind::Landing);
+        // Stepping off a ledge isn't a jump, but landing sneakily is quieter
+        assert_eq!(
+            noise_maker.update(false, Vec3::Y * -0.5, Stance::Sneaking, FRAME, 6f32),
+            None
+        );
+        noise_maker.update(false, Vec3::Y * -3f32, Stance::Sneaking, FRAME, 6f32);
+        let (_, soft_landing) = noise_maker
+            .update(true, Vec3::ZERO, Stance::Sneaking, FRAME, 6f32)
+            .unwrap();
+        assert!(soft_landing < hard_landing);
+    }
+
+    #[test]
+    fn test_stance_from_movement() {
+        let stance = |magnitude| {
+            Stance::from_movement(&Movement::from_components(
+                MovementDirection::Left(0f32),
+                MovementDirection::Forward(magnitude),
+            ))
+        };
+        assert_eq!(stance(0f32), Stance::Walking);
+        assert_eq!(stance(0.5), Stance::Sneaking);
+        assert_eq!(stance(1f32), Stance::Walking);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::components::{FirstPersonSubject, Health};
+use crate::perception::{NoiseEvent, NoiseKind, PerceptionMemory};
+use crate::resources::{GameClock, NpcConfig};
+use crate::systems::physics::blocks_rays;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::time::Duration;
+
+/// How much of a noise's range is left once it's gone through a wall
+const OCCLUDED_HEARING: f32 = 0.5;
+/// How sure an agent is of where a player is when it only heard them
+const HEARD_CONFIDENCE: f32 = 0.75;
+
+/// This component lets an agent see and hear players, and remember where they were in its
+/// [`PerceptionMemory`](crate::perception::PerceptionMemory)
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Senses {
+    /// How wide the view cone is, from one edge to the other, in radians
+    view_angle: f32,
+    /// In meters
+    sight_distance: f32,
+    /// How far above the body's center its eyes are, in meters
+    eye_height: f32,
+    memory: Duration,
+}
+
+impl Senses {
+    pub fn new(view_angle: f32, sight_distance: f32, eye_height: f32, memory: Duration) -> Self {
+        Senses {
+            view_angle,
+            sight_distance,
+            eye_height,
+            memory,
+        }
+    }
+
+    pub fn eye_height(&self) -> f32 {
+        self.eye_height
+    }
+
+    pub fn memory(&self) -> Duration {
+        self.memory
+    }
+
+    /// Whether something `offset` from the eyes is close enough and inside the view cone, when
+    /// they're looking towards `forward`. It doesn't check if anything's in the way.
+    pub fn is_in_view(&self, forward: Vec3, offset: Vec3) -> bool {
+        let distance = offset.length();
+        distance > f32::EPSILON
+            && distance <= self.sight_distance
+            && forward.angle_between(offset) <= self.view_angle / 2f32
+    }
+}
+
+/// NPCs see with their eyes near the top of their capsule
+impl From<&NpcConfig> for Senses {
+    fn from(npc_config: &NpcConfig) -> Self {
+        Senses::new(
+            npc_config.view_angle(),
+            npc_config.sight_distance(),
+            npc_config.capsule_height() / 2f32 - npc_config.capsule_radius(),
+            npc_config.memory(),
+        )
+    }
+}
+
+/// A player an agent can see
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeenTarget {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub distance: f32,
+}
+
+/// A noise an agent heard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeardNoise {
+    pub source: Entity,
+    pub position: Vec3,
+    pub kind: NoiseKind,
+    /// How loud it was where the agent is, from 1 right next to it down to 0 at the edge of its
+    /// range
+    pub volume: f32,
+}
+
+/// This component is what an agent's [`Senses`](crate::perception::Senses) picked up this frame
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Perception {
+    seen: Option<SeenTarget>,
+    heard: Option<HeardNoise>,
+}
+
+impl Perception {
+    /// The closest player the agent can see
+    pub fn seen(&self) -> Option<SeenTarget> {
+        self.seen
+    }
+
+    /// The loudest noise the agent heard
+    pub fn heard(&self) -> Option<HeardNoise> {
+        self.heard
+    }
+}
+
+/// Update what agents perceive. An agent sees a living
+/// [`FirstPersonSubject`](crate::components::FirstPersonSubject) in its view cone when there's nothing
+/// but sensors between its eyes and the player's body, and it looks the way its body faces. It hears
+/// a [`NoiseEvent`](crate::perception::NoiseEvent) within range, and walls in the way make the range
+/// shorter. What it sees or hears is remembered, and its memory fades.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn update_perception(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    mut noise_events: EventReader<NoiseEvent>,
+    mut perceiver_query: Query<(
+        Entity,
+        &Senses,
+        &mut Perception,
+        &mut PerceptionMemory,
+        &RigidBodyPositionComponent,
+    )>,
+    subject_query: Query<
+        (Entity, &RigidBodyPositionComponent, Option<&Health>),
+        With<FirstPersonSubject>,
+    >,
+    game_clock: Res<GameClock>,
+) {
+    let noises: Vec<NoiseEvent> = noise_events.iter().copied().collect();
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    // Whether there's something solid between `from` and `to`, other than the two entities
+    let is_occluded = |from: Vec3, to: Vec3, ignored: [Entity; 2]| {
+        let distance = from.distance(to);
+        if distance <= f32::EPSILON {
+            return false;
+        }
+        let ray = Ray::new(from.into(), ((to - from) / distance).into());
+        let filter: &dyn Fn(ColliderHandle) -> bool =
+            &|handle: ColliderHandle| blocks_rays(&collider_type_query, &ignored, handle);
+        query_pipeline
+            .cast_ray(
+                &collider_set,
+                &ray,
+                distance,
+                true,
+                InteractionGroups::all(),
+                Some(filter),
+            )
+            .is_some()
+    };
+
+    for (perceiver, senses, mut perception, mut memory, position) in perceiver_query.iter_mut() {
+        let translation = position.position.translation.vector;
+        let eye =
+            Vec3::new(translation.x, translation.y, translation.z) + Vec3::Y * senses.eye_height();
+        // Bevy's forward is -Z
+        let facing = position.position.rotation * -Vector::z();
+        let forward = Vec3::new(facing.x, facing.y, facing.z);
+
+        let seen = subject_query
+            .iter()
+            .filter(|(_, _, health)| health.map_or(true, |health| !health.is_dead()))
+            .filter_map(|(subject, subject_position, _)| {
+                let translation = subject_position.position.translation.vector;
+                let target = Vec3::new(translation.x, translation.y, translation.z);
+                if !senses.is_in_view(forward, target - eye)
+                    || is_occluded(eye, target, [perceiver, subject])
+                {
+                    return None;
+                }
+                Some(SeenTarget {
+                    entity: subject,
+                    position: target,
+                    distance: eye.distance(target),
+                })
+            })
+            .min_by(|a, b| {
+                a.distance
+                    .partial_cmp(&b.distance)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+        let heard = noises
+            .iter()
+            .filter(|noise| noise.source != perceiver && noise.loudness > 0f32)
+            .filter_map(|noise| {
+                let mut range = noise.loudness;
+                if is_occluded(noise.position, eye, [perceiver, noise.source]) {
+                    range *= OCCLUDED_HEARING;
+                }
+                let volume = 1f32 - noise.position.distance(eye) / range;
+                (volume > 0f32).then(|| HeardNoise {
+                    source: noise.source,
+                    position: noise.position,
+                    kind: noise.kind,
+                    volume,
+                })
+            })
+            .max_by(|a, b| {
+                a.volume
+                    .partial_cmp(&b.volume)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+        perception.seen = seen;
+        perception.heard = heard;
+        memory.decay(game_clock.delta(), senses.memory());
+        if let Some(seen) = seen {
+            memory.remember(seen.entity, seen.position, 1f32);
+        } else if let Some(heard) = heard {
+            memory.remember(heard.source, heard.position, HEARD_CONFIDENCE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_cone() {
+        let senses = Senses::new(90f32.to_radians(), 10f32, 0.6, Duration::from_secs(5));
+        let forward = -Vec3::Z;
+        assert!(senses.is_in_view(forward, Vec3::new(0.0, 0.0, -5.0)));
+        assert!(senses.is_in_view(forward, Vec3::new(3.0, -1.0, -5.0)));
+        // Too far
+        assert!(!senses.is_in_view(forward, Vec3::new(0.0, 0.0, -11.0)));
+        // Outside the cone, and behind
+        assert!(!senses.is_in_view(forward, Vec3::new(5.0, 0.0, -3.0)));
+        assert!(!senses.is_in_view(forward, Vec3::new(0.0, 0.0, 5.0)));
+        assert!(!senses.is_in_view(forward, Vec3::ZERO));
+    }
+}
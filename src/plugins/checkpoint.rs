@@ -0,0 +1,134 @@
+use crate::resources::{CheckpointProgress, RespawnFade};
+use crate::states::GameLevel;
+use crate::systems::{
+    add_respawn_overlay, advance_respawn_fade, kill_subjects_below_kill_plane, reach_checkpoints,
+    reset_checkpoints, start_respawn_on_player_died, PlayerDied,
+};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin takes a snapshot of the level whenever a player reaches a new
+/// [`Checkpoint`](crate::components::Checkpoint), and puts everyone back there when a player dies.
+///
+/// A [`PlayerDied`](crate::systems::PlayerDied) event fades the screen out, restores the snapshot and
+/// fades back in, see [`RespawnFade`](crate::resources::RespawnFade). Players die when they fall below
+/// the [`KILL_PLANE_HEIGHT`](crate::systems::KILL_PLANE_HEIGHT).
+///
+/// Note: Snapshots are taken and restored by the [`SavePlugin`](crate::plugins::SavePlugin), so it
+/// must be added too.
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CheckpointProgress::default())
+            .init_resource::<RespawnFade>()
+            .add_event::<PlayerDied>()
+            .add_system_set(SystemSet::on_enter(GameLevel::Main).with_system(add_respawn_overlay))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(reach_checkpoints.before("begin-save"))
+                    .with_system(kill_subjects_below_kill_plane.label("kill-subjects"))
+                    .with_system(
+                        start_respawn_on_player_died
+                            .label("start-respawn")
+                            .after("kill-subjects"),
+                    )
+                    .with_system(
+                        advance_respawn_fade
+                            .after("start-respawn")
+                            .before("read-save"),
+                    ),
+            )
+            .add_system_set(SystemSet::on_exit(GameLevel::Main).with_system(reset_checkpoints));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Checkpoint, FirstPersonSubject};
+    use crate::plugins::test_app::{headless_physics_app, unused_save_slots};
+    use crate::plugins::SavePlugin;
+    use crate::resources::GameClock;
+    use crate::save::SaveManager;
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+    use std::time::Duration;
+
+    fn setup_app() -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(Input::<KeyCode>::default())
+            .insert_resource(GameClock::default())
+            // Respawn straight away
+            .insert_resource(RespawnFade::new(Duration::ZERO))
+            .add_state(GameLevel::Main)
+            .add_plugin(SavePlugin)
+            .insert_resource(unused_save_slots("checkpoint"))
+            .add_plugin(CheckpointPlugin)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        app.world
+            .spawn()
+            .insert(Checkpoint::new(Transform::from_xyz(0f32, 1f32, 0f32)))
+            .insert_bundle(ColliderBundle {
+                collider_type: ColliderType::Sensor.into(),
+                shape: ColliderShape::cuboid(2f32, 2f32, 2f32).into(),
+                flags: ActiveEvents::INTERSECTION_EVENTS.into(),
+                ..Default::default()
+            });
+        let player = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0.5f32, 0.5f32, 0f32).into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5f32).into(),
+                ..Default::default()
+            })
+            .id();
+        (app, player)
+    }
+
+    fn player_translation(app: &App, player: Entity) -> Vec3 {
+        let translation = app
+            .world
+            .get::<RigidBodyPositionComponent>(player)
+            .unwrap()
+            .position
+            .translation;
+        Vec3::new(translation.x, translation.y, translation.z)
+    }
+
+    #[test]
+    fn test_respawn_at_checkpoint_after_falling() {
+        let (mut app, player) = setup_app();
+        for _ in 0..5 {
+            app.update();
+        }
+        let snapshot = app
+            .world
+            .get_resource::<SaveManager>()
+            .unwrap()
+            .snapshot()
+            .cloned()
+            .expect("The checkpoint wasn't reached!");
+        assert_eq!(snapshot.players.len(), 1);
+        assert_eq!(snapshot.players[0].translation, [0f32, 1f32, 0f32]);
+
+        // Fall off the level
+        let fallen = Vec3::new(0f32, -50f32, 0f32).into();
+        let mut position = app
+            .world
+            .get_mut::<RigidBodyPositionComponent>(player)
+            .unwrap();
+        position.position = fallen;
+        position.next_position = fallen;
+        for _ in 0..5 {
+            app.update();
+        }
+
+        assert!((player_translation(&app, player) - Vec3::new(0f32, 1f32, 0f32)).length() < 0.01);
+        assert!(!app.world.get_resource::<RespawnFade>().unwrap().is_active());
+    }
+}
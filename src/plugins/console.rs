@@ -0,0 +1,306 @@
+use crate::console::{
+    echo_log_to_console, fly_noclip_subjects, read_console_input, run_clear_command,
+    run_cvar_commands, run_entered_console_lines, run_entity_commands, run_help_command,
+    run_level_command, run_noclip_command, run_physics_debug_command, run_teleport_command,
+    run_timescale_command, spawn_console_ui, update_console_ui, Console, ConsoleCommandEntered,
+    ConsoleCommands, EntityCommandReader, RegisterConsoleCommand,
+};
+use crate::states::GameLevel;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin adds the developer console, see [`Console`](crate::console::Console), with
+/// its built-in commands and cvars.
+///
+/// The console reads the keyboard right after it's updated, and the lines entered into it are run
+/// under the "run-console-lines" label. Systems running a command should come after it. Type `help`
+/// in the console for the commands.
+///
+/// Note: The console needs the [`GameConfig`](crate::resources::GameConfig),
+/// [`GameSettings`](crate::resources::GameSettings) and [`GameClock`](crate::resources::GameClock)
+/// resources, and `level` needs the [`GameLevel`](crate::states::GameLevel) state.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<ConsoleCommands>()
+            .init_resource::<EntityCommandReader>()
+            .add_event::<ConsoleCommandEntered>()
+            .register_console_command("help", "[command]", "List the commands, or explain one")
+            .register_console_command("clear", "", "Clear the console")
+            .register_console_command("cvars", "[prefix]", "List the cvars and their values")
+            .register_console_command("teleport", "<x> <y> <z>", "Move the players there")
+            .register_console_command("noclip", "", "Fly through walls, or stop")
+            .register_console_command("level", "<id>", "Load a level, ie main or main_menu")
+            .register_console_command("timescale", "[scale]", "Show or set how fast time passes")
+            .register_console_command(
+                "physics_debug",
+                "",
+                "Turn the physics debug overlay on or off",
+            )
+            .register_console_command(
+                "entities",
+                "[component]",
+                "List the entities, or the ones with a component",
+            )
+            .register_console_command("dump", "<entity>", "List an entity's components")
+            .add_startup_system(spawn_console_ui)
+            .add_system_to_stage(CoreStage::PreUpdate, read_console_input.after(InputSystem))
+            .add_system(echo_log_to_console.before("run-console-lines"))
+            .add_system(run_entered_console_lines.label("run-console-lines"))
+            .add_system_set(
+                SystemSet::new()
+                    .label("console-commands")
+                    .after("run-console-lines")
+                    .with_system(run_help_command)
+                    .with_system(run_clear_command)
+                    .with_system(run_cvar_commands)
+                    .with_system(run_teleport_command)
+                    .with_system(run_noclip_command)
+                    .with_system(run_level_command)
+                    .with_system(run_timescale_command)
+                    .with_system(run_physics_debug_command),
+            )
+            // It needs the whole world, so it runs after everything else
+            .add_system(run_entity_commands.exclusive_system().at_end())
+            .add_system(update_console_ui.after("console-commands"))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main).with_system(
+                    fly_noclip_subjects
+                        .after("first-person-intents")
+                        .before("player-body"),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonHead, FirstPersonSubject, Movement, MovementDirection};
+    use crate::console::Noclip;
+    use crate::plugins::test_app::send_key;
+    use crate::resources::{GameClock, GameConfig, GameSettings};
+    use bevy::input::ElementState;
+    use bevy::input::InputPlugin;
+    use bevy::transform::TransformPlugin;
+    use bevy::window::{ReceivedCharacter, WindowId, WindowPlugin};
+    use bevy_rapier3d::prelude::*;
+
+    fn setup_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(WindowPlugin::default())
+            .add_plugin(InputPlugin)
+            .insert_resource(GameConfig::default())
+            .insert_resource(GameSettings::default())
+            .insert_resource(GameClock::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(ConsolePlugin);
+        app
+    }
+
+    fn press_key(app: &mut App, key_code: KeyCode) {
+        send_key(app, key_code, ElementState::Pressed);
+        app.update();
+        send_key(app, key_code, ElementState::Released);
+        app.update();
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        let mut char_events = app
+            .world
+            .get_resource_mut::<Events<ReceivedCharacter>>()
+            .unwrap();
+        for c in text.chars() {
+            char_events.send(ReceivedCharacter {
+                id: WindowId::primary(),
+                char: c,
+            });
+        }
+        app.update();
+    }
+
+    /// Enter a line into the console, without typing it
+    fn enter(app: &mut App, line: &str) {
+        app.world.get_resource_mut::<Console>().unwrap().enter(line);
+        app.update();
+        app.update();
+    }
+
+    fn last_line(app: &App) -> String {
+        app.world
+            .get_resource::<Console>()
+            .unwrap()
+            .lines()
+            .last()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    #[test]
+    fn test_typing_sets_cvars() {
+        let mut app = setup_app();
+        // Typed while the console's closed
+        type_text(&mut app, "w");
+        press_key(&mut app, KeyCode::Grave);
+        assert!(app.world.get_resource::<Console>().unwrap().is_open());
+
+        type_text(&mut app, "player.max_sp");
+        press_key(&mut app, KeyCode::Tab);
+        assert_eq!(
+            app.world.get_resource::<Console>().unwrap().input(),
+            "player.max_speed "
+        );
+        type_text(&mut app, "8");
+        // Keys don't reach the game while the console's open
+        send_key(&mut app, KeyCode::Return, ElementState::Pressed);
+        app.update();
+        assert!(!app
+            .world
+            .get_resource::<Input<KeyCode>>()
+            .unwrap()
+            .pressed(KeyCode::Return));
+        send_key(&mut app, KeyCode::Return, ElementState::Released);
+        app.update();
+        assert_eq!(
+            app.world
+                .get_resource::<GameConfig>()
+                .unwrap()
+                .player()
+                .max_speed(),
+            8f32
+        );
+        assert_eq!(last_line(&app), "player.max_speed = 8");
+
+        press_key(&mut app, KeyCode::Up);
+        assert_eq!(
+            app.world.get_resource::<Console>().unwrap().input(),
+            "player.max_speed 8"
+        );
+        press_key(&mut app, KeyCode::Escape);
+        assert!(!app.world.get_resource::<Console>().unwrap().is_open());
+
+        enter(&mut app, "settings.master_volume 40");
+        assert_eq!(
+            app.world
+                .get_resource::<GameSettings>()
+                .unwrap()
+                .master_volume(),
+            40
+        );
+        enter(&mut app, "player.max_speed fast");
+        assert_eq!(last_line(&app), "fast isn't a number");
+        enter(&mut app, "jump_higher 2");
+        assert_eq!(last_line(&app), "Unknown command jump_higher, see help");
+    }
+
+    #[test]
+    fn test_teleport_and_noclip() {
+        let mut app = setup_app();
+        let subject = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Movement::default())
+            .insert(Transform::default())
+            .insert(GlobalTransform::default())
+            .insert_bundle(RigidBodyBundle::default())
+            .insert_bundle(ColliderBundle::default())
+            .with_children(|subject| {
+                subject
+                    .spawn()
+                    .insert(FirstPersonHead)
+                    .insert(Transform::default())
+                    .insert(GlobalTransform::default());
+            })
+            .id();
+
+        enter(&mut app, "teleport 1 2.5 -3");
+        let position = app
+            .world
+            .get::<RigidBodyPositionComponent>(subject)
+            .unwrap()
+            .position
+            .translation;
+        assert_eq!(
+            Vec3::new(position.x, position.y, position.z),
+            Vec3::new(1.0, 2.5, -3.0)
+        );
+        enter(&mut app, "teleport 1 2");
+        assert_eq!(last_line(&app), "Usage: teleport <x> <y> <z>");
+
+        enter(&mut app, "noclip");
+        assert!(app.world.get::<Noclip>(subject).is_some());
+        assert_eq!(
+            app.world.get::<RigidBodyTypeComponent>(subject).unwrap().0,
+            RigidBodyType::KinematicVelocityBased
+        );
+        assert_eq!(
+            app.world.get::<ColliderTypeComponent>(subject).unwrap().0,
+            ColliderType::Sensor
+        );
+        // The head looks down the -Z axis, so forward is that way
+        app.world
+            .get_mut::<Movement>(subject)
+            .unwrap()
+            .set_forward_back(MovementDirection::Forward(1f32));
+        app.update();
+        let linvel = app
+            .world
+            .get::<RigidBodyVelocityComponent>(subject)
+            .unwrap()
+            .linvel;
+        let max_speed = GameConfig::default().player().max_speed();
+        assert!((linvel.z + max_speed * 3f32).abs() < 1e-4);
+
+        enter(&mut app, "noclip");
+        assert!(app.world.get::<Noclip>(subject).is_none());
+        assert_eq!(
+            app.world.get::<RigidBodyTypeComponent>(subject).unwrap().0,
+            RigidBodyType::Dynamic
+        );
+        assert_eq!(
+            app.world.get::<ColliderTypeComponent>(subject).unwrap().0,
+            ColliderType::Solid
+        );
+    }
+
+    #[test]
+    fn test_timescale_level_and_dump() {
+        let mut app = setup_app();
+        enter(&mut app, "timescale 0.5");
+        assert_eq!(
+            app.world.get_resource::<GameClock>().unwrap().time_scale(),
+            0.5
+        );
+        assert_eq!(last_line(&app), "timescale = 0.5");
+
+        enter(&mut app, "level main_menu");
+        assert_eq!(
+            app.world
+                .get_resource::<State<GameLevel>>()
+                .unwrap()
+                .current(),
+            &GameLevel::MainMenu
+        );
+        enter(&mut app, "level nowhere");
+        assert_eq!(last_line(&app), "There's no level nowhere");
+
+        let named = app
+            .world
+            .spawn()
+            .insert(Name::new("lamp"))
+            .insert(Noclip)
+            .id();
+        enter(&mut app, &format!("dump {:?}", named));
+        let console = app.world.get_resource::<Console>().unwrap();
+        let dump: Vec<&str> = console.lines().rev().take(2).collect();
+        assert_eq!(dump, ["  Noclip", "  Name"]);
+
+        enter(&mut app, "entities Noclip");
+        assert_eq!(last_line(&app), "1 entities");
+    }
+}
@@ -1,31 +1,43 @@
+use crate::resources::WeaponBindings;
 use crate::states::FirstPersonControlSettings;
-use crate::systems::{first_person_lookaround, first_person_movement};
+use crate::systems::{
+    first_person_lookaround, first_person_movement, first_person_weapons, log_subject_diagnostics,
+    SubjectDiagnostic,
+};
 use bevy::prelude::*;
 
 /// TL;DR: This plugin enables first person controls for an entity. It's configured using [`FirstPersonControlSettings`](crate::states::FirstPersonControlSettings).
 ///
-/// This plugin adds the [`first_person_movement`](crate::systems::first_person_movement) and
-/// [`first_person_lookaround`](crate::systems::first_person_lookaround) systems. The systems will only
+/// This plugin adds the [`first_person_movement`](crate::systems::first_person_movement),
+/// [`first_person_lookaround`](crate::systems::first_person_lookaround) and
+/// [`first_person_weapons`](crate::systems::first_person_weapons) systems. The systems will only
 /// execute if a [`FirstPersonControlSettings`](crate::states::FirstPersonControlSettings) state has been
-/// added to the App and is set to `Enabled`.
+/// added to the App and is set to `Enabled`. Weapons follow the [`WeaponBindings`](crate::resources::WeaponBindings)
+/// resource, which is added with the default bindings if the App doesn't have one yet.
 ///
 /// Note: An entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component must exist when this plugin is
-/// enabled for it to function, otherwise a panic may occur.
+/// enabled for it to function. If there are several, the one to control is marked with an
+/// [`ActiveSubject`](crate::components::ActiveSubject) component. Misconfiguration is reported with a
+/// [`SubjectDiagnostic`](crate::systems::SubjectDiagnostic) event, which this plugin logs.
 pub struct FirstPersonControlPlugin;
 
 impl Plugin for FirstPersonControlPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_enter(FirstPersonControlSettings::Enabled).with_system(lock_pointer),
-        )
-        .add_system_set(
-            SystemSet::on_update(FirstPersonControlSettings::Enabled)
-                .with_system(first_person_movement)
-                .with_system(first_person_lookaround),
-        )
-        .add_system_set(
-            SystemSet::on_exit(FirstPersonControlSettings::Enabled).with_system(unlock_pointer),
-        );
+        app.init_resource::<WeaponBindings>()
+            .add_event::<SubjectDiagnostic>()
+            .add_system(log_subject_diagnostics)
+            .add_system_set(
+                SystemSet::on_enter(FirstPersonControlSettings::Enabled).with_system(lock_pointer),
+            )
+            .add_system_set(
+                SystemSet::on_update(FirstPersonControlSettings::Enabled)
+                    .with_system(first_person_movement.label("first-person-intents"))
+                    .with_system(first_person_lookaround.label("first-person-intents"))
+                    .with_system(first_person_weapons.label("first-person-intents")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(FirstPersonControlSettings::Enabled).with_system(unlock_pointer),
+            );
     }
 }
 
@@ -0,0 +1,70 @@
+use crate::resources::{GameSettings, GameSettingsFile};
+use crate::systems::save_changed_game_settings;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin loads the [`GameSettings`](crate::resources::GameSettings) from their file when
+/// it's added, and saves them back to it whenever they change.
+///
+/// The file is the one in the [`GameSettingsFile`](crate::resources::GameSettingsFile) resource,
+/// `settings.toml` by default. The default settings are used if it can't be read, ie the first time
+/// the game runs.
+///
+/// Note: Insert a [`GameSettingsFile`](crate::resources::GameSettingsFile) before adding this plugin
+/// to keep the settings somewhere else.
+pub struct GameSettingsPlugin;
+
+impl Plugin for GameSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world
+            .get_resource_or_insert_with(GameSettingsFile::default)
+            .0
+            .clone();
+        let settings = GameSettings::load(&path).unwrap_or_else(|load_err| {
+            info!("Using the default settings, {}", load_err);
+            GameSettings::default()
+        });
+        app.insert_resource(settings)
+            .add_system_to_stage(CoreStage::Last, save_changed_game_settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_settings_are_loaded_and_saved() {
+        let path = std::env::temp_dir().join(format!(
+            "bevy-fp-template-settings-plugin-{}.toml",
+            std::process::id()
+        ));
+        fs::write(&path, "master_volume = 40\n").unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(GameSettingsFile(path.clone()))
+            .add_plugin(GameSettingsPlugin);
+        assert_eq!(
+            app.world
+                .get_resource::<GameSettings>()
+                .unwrap()
+                .master_volume(),
+            40
+        );
+
+        // Loading them doesn't write them back
+        app.update();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "master_volume = 40\n");
+
+        app.world
+            .get_resource_mut::<GameSettings>()
+            .unwrap()
+            .set_sfx_volume(10);
+        app.update();
+        let saved = GameSettings::load(&path).unwrap();
+        assert_eq!(saved.master_volume(), 40);
+        assert_eq!(saved.sfx_volume(), 10);
+        let _ = fs::remove_file(&path);
+    }
+}
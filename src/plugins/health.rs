@@ -0,0 +1,184 @@
+use crate::components::Health;
+use crate::save::RegisterSaveable;
+use crate::states::GameLevel;
+use crate::systems::{
+    apply_damage, damage_in_hazards, deal_fall_damage, regenerate_health,
+    revive_players_on_respawn, DamageEvent,
+};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin hurts entities with [`Health`](crate::components::Health) in
+/// [`GameLevel::Main`](crate::states::GameLevel), and respawns the players that run out of it.
+///
+/// Damage is dealt by sending a [`DamageEvent`](crate::systems::DamageEvent). Bodies with a
+/// [`FallTracker`](crate::components::FallTracker) are hurt when they land too hard, which needs
+/// [`ActiveEvents::CONTACT_EVENTS`](bevy_rapier3d::prelude::ActiveEvents) on their collider, and
+/// [`HazardVolume`](crate::components::HazardVolume)s hurt what's inside them. How much, and how health
+/// regenerates, is set in the [`PlayerConfig`](crate::resources::PlayerConfig).
+///
+/// Note: A player that dies sends a [`PlayerDied`](crate::systems::PlayerDied) event, so the
+/// [`CheckpointPlugin`](crate::plugins::CheckpointPlugin) must be added too. Hazards are trigger volumes,
+/// so the [`ScriptingPlugin`](crate::plugins::ScriptingPlugin) must be added for them to work.
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .register_saveable::<Health>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    // Loading puts bodies somewhere else at a different speed, which isn't a fall
+                    .with_system(
+                        deal_fall_damage
+                            .label("deal-damage")
+                            .after("apply-loaded-players"),
+                    )
+                    .with_system(
+                        damage_in_hazards
+                            .label("deal-damage")
+                            .after("detect-triggers"),
+                    )
+                    .with_system(
+                        apply_damage
+                            .label("apply-damage")
+                            .after("deal-damage")
+                            .before("start-respawn"),
+                    )
+                    .with_system(regenerate_health.after("apply-damage"))
+                    .with_system(revive_players_on_respawn.after("read-save")),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{DamageType, FallTracker, FirstPersonSubject, HazardVolume};
+    use crate::plugins::test_app::{headless_physics_app, unused_save_slots};
+    use crate::plugins::{CheckpointPlugin, SavePlugin, ScriptingPlugin};
+    use crate::resources::{GameClock, GameConfig, RespawnFade};
+    use crate::scripting::TriggerVolume;
+    use crate::systems::{tick_game_clock, PlayerDied};
+    use bevy_rapier3d::na::Vector3;
+    use bevy_rapier3d::prelude::*;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct DiedLog(Vec<PlayerDied>);
+
+    fn log_deaths(mut events: EventReader<PlayerDied>, mut log: ResMut<DiedLog>) {
+        log.0.extend(events.iter().cloned());
+    }
+
+    fn setup_app() -> App {
+        let mut app = headless_physics_app();
+        app.insert_resource(Input::<KeyCode>::default())
+            .insert_resource(GameConfig::default())
+            .insert_resource(GameClock::default())
+            .insert_resource(DiedLog::default())
+            // Respawn straight away
+            .insert_resource(RespawnFade::new(Duration::ZERO))
+            .add_state(GameLevel::Main)
+            .add_plugin(SavePlugin)
+            .insert_resource(unused_save_slots("health"))
+            .add_plugin(CheckpointPlugin)
+            .add_plugin(ScriptingPlugin)
+            .add_plugin(HealthPlugin)
+            .add_system(log_deaths.after("start-respawn"))
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        app
+    }
+
+    fn spawn_player(app: &mut App, translation: Vec3, linvel: Vec3) -> Entity {
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Health::new(100f32))
+            .insert(FallTracker::default())
+            .insert_bundle(RigidBodyBundle {
+                position: translation.into(),
+                velocity: RigidBodyVelocity {
+                    linvel: Vector3::new(linvel.x, linvel.y, linvel.z),
+                    ..Default::default()
+                }
+                .into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                flags: ActiveEvents::CONTACT_EVENTS.into(),
+                ..Default::default()
+            })
+            .id()
+    }
+
+    fn health(app: &App, entity: Entity) -> f32 {
+        app.world.get::<Health>(entity).unwrap().current()
+    }
+
+    #[test]
+    fn test_hazard_hurts_what_is_inside() {
+        let mut app = setup_app();
+        app.world
+            .spawn()
+            .insert(TriggerVolume::default())
+            .insert(HazardVolume::new(30f32, DamageType::Fire, 100f32))
+            .insert_bundle(ColliderBundle {
+                collider_type: ColliderType::Sensor.into(),
+                shape: ColliderShape::cuboid(2f32, 2f32, 2f32).into(),
+                flags: ActiveEvents::INTERSECTION_EVENTS.into(),
+                ..Default::default()
+            });
+        let inside = spawn_player(&mut app, Vec3::ZERO, Vec3::ZERO);
+        let outside = spawn_player(&mut app, Vec3::new(10f32, 0f32, 0f32), Vec3::ZERO);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(health(&app, inside), 70f32);
+        assert_eq!(health(&app, outside), 100f32);
+    }
+
+    #[test]
+    fn test_player_that_runs_out_of_health_respawns() {
+        let mut app = setup_app();
+        let player = spawn_player(&mut app, Vec3::ZERO, Vec3::ZERO);
+        app.update();
+        app.world
+            .get_resource_mut::<Events<DamageEvent>>()
+            .unwrap()
+            .send(DamageEvent {
+                subject: player,
+                amount: 150f32,
+                damage_type: DamageType::Generic,
+                source: None,
+            });
+        for _ in 0..5 {
+            app.update();
+        }
+        let deaths = &app.world.get_resource::<DiedLog>().unwrap().0;
+        assert_eq!(deaths, &vec![PlayerDied { subject: player }]);
+        // There's no checkpoint, so they're brought back where they are
+        assert_eq!(health(&app, player), 100f32);
+    }
+
+    #[test]
+    fn test_landing_too_hard_hurts() {
+        let mut app = setup_app();
+        for x in [0f32, 10f32] {
+            app.world.spawn().insert_bundle(ColliderBundle {
+                shape: ColliderShape::cuboid(2f32, 0.5, 2f32).into(),
+                position: Vec3::new(x, 0f32, 0f32).into(),
+                ..Default::default()
+            });
+        }
+        let hard = spawn_player(&mut app, Vec3::new(0f32, 3f32, 0f32), -Vec3::Y * 20f32);
+        let soft = spawn_player(&mut app, Vec3::new(10f32, 3f32, 0f32), -Vec3::Y * 5f32);
+        for _ in 0..30 {
+            app.update();
+        }
+        // Landing at 20 m/s is 5 m/s too fast, so about 40 damage
+        let hard_health = health(&app, hard);
+        assert!(hard_health > 50f32 && hard_health < 70f32);
+        assert_eq!(health(&app, soft), 100f32);
+    }
+}
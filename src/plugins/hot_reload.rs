@@ -0,0 +1,193 @@
+use crate::hot_reload::{
+    reload_changed_files, respawn_reloaded_level_scripts, spawn_hot_reload_error_ui,
+    update_hot_reload_error_ui, HotReloadErrors, HotReloadFiles, LevelScriptReloaded,
+};
+use crate::scripting::LevelScripts;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin reloads the config and level scripts in the
+/// [`HotReloadFiles`](crate::hot_reload::HotReloadFiles) resource when they change, and shows why
+/// they couldn't be when they can't.
+///
+/// Changes are applied under the "reload-changed-files" label, so systems reacting to the
+/// [`GameConfig`](crate::resources::GameConfig) changing can come after it.
+///
+/// Note: This plugin needs the [`GameConfig`](crate::resources::GameConfig) resource and the
+/// [`GameLevel`](crate::states::GameLevel) state. It's only built with the `dev-tools` feature.
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HotReloadFiles>()
+            .init_resource::<HotReloadErrors>()
+            .init_resource::<LevelScripts>()
+            .add_event::<LevelScriptReloaded>()
+            .add_startup_system(spawn_hot_reload_error_ui)
+            .add_system(reload_changed_files.label("reload-changed-files"))
+            .add_system(respawn_reloaded_level_scripts.after("reload-changed-files"))
+            .add_system(update_hot_reload_error_ui.after("reload-changed-files"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::FirstPersonSubject;
+    use crate::hot_reload::WatchedAsset;
+    use crate::resources::{GameConfig, GAME_CONFIG_PATH};
+    use crate::scripting::ScriptObject;
+    use crate::states::GameLevel;
+    use crate::systems::player::{player_capsule_shape, resize_player_capsules};
+    use bevy::transform::TransformPlugin;
+    use bevy_rapier3d::prelude::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    const FLOOR: &str = "
+        [[floors]]
+        position = [0.0, 0.1, 0.0]
+        half_extents = [1.0, 0.1, 1.0]
+        material = \"wood\"
+        ";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "bevy-fp-template-hot-reload-{}-{}.toml",
+            name,
+            std::process::id()
+        ))
+    }
+
+    /// The player's capsule height and how far up its center is
+    fn player_capsule(app: &mut App) -> (f32, f32) {
+        let (shape, position) = app
+            .world
+            .query::<(&ColliderShapeComponent, &RigidBodyPositionComponent)>()
+            .iter(&app.world)
+            .next()
+            .unwrap();
+        let capsule = shape.as_capsule().unwrap();
+        (
+            2f32 * (capsule.half_height() + capsule.radius),
+            position.position.translation.y,
+        )
+    }
+
+    fn script_object_count(app: &mut App) -> usize {
+        app.world
+            .query_filtered::<(), With<ScriptObject>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn test_reloading_config_and_level_script() {
+        let (config_path, level_path) = (temp_path("config"), temp_path("level"));
+        let config_toml = fs::read_to_string(GAME_CONFIG_PATH).unwrap();
+        fs::write(&config_path, &config_toml).unwrap();
+        fs::write(&level_path, FLOOR).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .insert_resource(GameConfig::default())
+            .insert_resource(
+                HotReloadFiles::default()
+                    .with_poll_interval(Duration::ZERO)
+                    .watch(WatchedAsset::GameConfig, &config_path)
+                    .watch(WatchedAsset::LevelScript(GameLevel::Main), &level_path),
+            )
+            .add_state(GameLevel::Main)
+            .add_plugin(HotReloadPlugin)
+            .add_system(resize_player_capsules.after("reload-changed-files"));
+        let player_config = GameConfig::default().player().clone();
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0f32, 4f32, 0f32).into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: player_capsule_shape(&player_config).into(),
+                ..Default::default()
+            });
+        app.update();
+        assert_eq!(player_capsule(&mut app), (8f32, 4f32));
+
+        // A taller, faster player, standing where it was
+        fs::write(
+            &config_path,
+            config_toml
+                .replace("capsule_height = 8.0", "capsule_height = 10.0")
+                .replace("max_speed = 5.0", "max_speed = 8.0"),
+        )
+        .unwrap();
+        app.update();
+        let game_config = app.world.get_resource::<GameConfig>().unwrap();
+        assert_eq!(game_config.player().max_speed(), 8f32);
+        let (height, center) = player_capsule(&mut app);
+        assert!((height - 10f32).abs() < 1e-4);
+        assert!((center - 5f32).abs() < 1e-4);
+
+        // Broken and invalid configs are shown, and the old one's kept
+        fs::write(&config_path, "max_speed = ").unwrap();
+        app.update();
+        fs::write(
+            &config_path,
+            config_toml.replace("capsule_radius = 1.0", "capsule_radius = -1.0"),
+        )
+        .unwrap();
+        app.update();
+        let errors = app.world.get_resource::<HotReloadErrors>().unwrap();
+        let (error_path, error) = errors.iter().next().unwrap();
+        assert_eq!(error_path, config_path.as_path());
+        assert_eq!(error, "player.capsule_radius must be more than 0, not -1");
+        let game_config = app.world.get_resource::<GameConfig>().unwrap();
+        assert_eq!(game_config.player().max_speed(), 8f32);
+        assert_eq!(game_config.player().capsule_height(), 10f32);
+
+        // The level being played is spawned again
+        fs::write(&level_path, format!("{}{}", FLOOR, FLOOR)).unwrap();
+        app.update();
+        app.update();
+        assert_eq!(script_object_count(&mut app), 2);
+        assert_eq!(
+            app.world
+                .get_resource::<LevelScripts>()
+                .unwrap()
+                .get(&GameLevel::Main)
+                .unwrap()
+                .floors
+                .len(),
+            2
+        );
+        fs::write(&level_path, "[[floors]").unwrap();
+        app.update();
+        app.update();
+        assert_eq!(script_object_count(&mut app), 2);
+        assert_eq!(
+            app.world
+                .get_resource::<HotReloadErrors>()
+                .unwrap()
+                .iter()
+                .count(),
+            2
+        );
+
+        // Fixing a file clears its error
+        fs::write(&config_path, &config_toml).unwrap();
+        app.update();
+        assert_eq!(
+            app.world
+                .get_resource::<HotReloadErrors>()
+                .unwrap()
+                .iter()
+                .count(),
+            1
+        );
+        let _ = fs::remove_file(&config_path);
+        let _ = fs::remove_file(&level_path);
+    }
+}
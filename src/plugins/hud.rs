@@ -0,0 +1,276 @@
+use crate::components::{FirstPersonSubject, Health, LevelObject, LocalPlayer};
+use crate::interaction::{Interactable, Interactor};
+use crate::resources::UiTheme;
+use crate::scripting::ScriptMessage;
+use crate::states::GameLevel;
+use crate::widgets::{self, UiContext};
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct ScriptMessageLabel;
+
+#[derive(Component)]
+struct InteractionPromptLabel;
+
+#[derive(Component)]
+struct HealthLabel;
+
+#[derive(Component)]
+struct HoldProgressBar;
+
+#[derive(Component)]
+struct HoldProgressFill;
+
+/// TL;DR: This plugin draws the heads-up display of [`GameLevel::Main`](crate::states::GameLevel).
+///
+/// It shows the [`ScriptMessage`](crate::scripting::ScriptMessage) a level script is showing, what the
+/// first player can use and how far along holding the use action they are, and the first player's
+/// [`Health`](crate::components::Health). The HUD is made of [`LevelObject`](crate::components::LevelObject)s,
+/// so it's torn down with the level.
+///
+/// Note: The prompt and health are updated after the `"interact"` and `"apply-damage"` systems, so
+/// they show this frame's values when the [`InteractionPlugin`](crate::plugins::InteractionPlugin) and
+/// [`HealthPlugin`](crate::plugins::HealthPlugin) are added.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameLevel::Main)
+                .with_system(add_script_message_label)
+                .with_system(add_interaction_prompt)
+                .with_system(add_health_label),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(update_script_message_label)
+                .with_system(update_interaction_prompt.after("interact"))
+                .with_system(update_health_label.after("apply-damage")),
+        );
+    }
+}
+
+fn add_script_message_label(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+) {
+    let ui = UiContext::new(&theme, &asset_server, LevelObject);
+    widgets::Panel::overlay().spawn_root(&mut commands, &ui, |overlay| {
+        widgets::Label::title("")
+            .spawn(overlay, &ui)
+            .insert(ScriptMessageLabel);
+    });
+}
+
+fn update_script_message_label(
+    message: Res<ScriptMessage>,
+    mut label_query: Query<&mut Text, With<ScriptMessageLabel>>,
+) {
+    if !message.is_changed() {
+        return;
+    }
+    let text = message.text().unwrap_or_default();
+    for mut label in label_query.iter_mut() {
+        if label.sections[0].value != text {
+            label.sections[0].value = text.to_string();
+        }
+    }
+}
+
+fn add_interaction_prompt(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+) {
+    let ui = UiContext::new(&theme, &asset_server, LevelObject);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(30.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(0.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(LevelObject)
+        .with_children(|prompt_root| {
+            widgets::Label::small("")
+                .spawn(prompt_root, &ui)
+                .insert(InteractionPromptLabel);
+            prompt_root
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(200.0), Val::Px(8.0)),
+                        margin: Rect {
+                            top: Val::Px(6.0),
+                            ..Default::default()
+                        },
+                        display: Display::None,
+                        ..Default::default()
+                    },
+                    color: theme.panel_color().into(),
+                    ..Default::default()
+                })
+                .insert(HoldProgressBar)
+                .insert(LevelObject)
+                .with_children(|progress_bar| {
+                    progress_bar
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            color: theme.slider_fill_color().into(),
+                            ..Default::default()
+                        })
+                        .insert(HoldProgressFill)
+                        .insert(LevelObject);
+                });
+        });
+}
+
+fn add_health_label(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<UiTheme>) {
+    let ui = UiContext::new(&theme, &asset_server, LevelObject);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(16.0),
+                    top: Val::Px(16.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(LevelObject)
+        .with_children(|health_root| {
+            widgets::Label::small("")
+                .spawn(health_root, &ui)
+                .insert(HealthLabel);
+        });
+}
+
+/// Show the first player's health
+fn update_health_label(
+    health_query: Query<(&Health, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+    mut label_query: Query<&mut Text, With<HealthLabel>>,
+) {
+    let first_player = health_query
+        .iter()
+        .min_by_key(|(_, local_player)| local_player.map(|local_player| local_player.index()));
+    let text = match first_player {
+        Some((health, _)) => format!(
+            "Health {:.0} / {:.0}",
+            health.current().ceil(),
+            health.max()
+        ),
+        None => String::new(),
+    };
+    for mut label in label_query.iter_mut() {
+        if label.sections[0].value != text {
+            label.sections[0].value = text.clone();
+        }
+    }
+}
+
+/// Show the first player what they can use, and how far along holding the use action they are
+fn update_interaction_prompt(
+    interactor_query: Query<(&Interactor, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+    interactable_query: Query<&Interactable>,
+    mut label_query: Query<&mut Text, With<InteractionPromptLabel>>,
+    mut bar_query: Query<&mut Style, (With<HoldProgressBar>, Without<HoldProgressFill>)>,
+    mut fill_query: Query<&mut Style, (With<HoldProgressFill>, Without<HoldProgressBar>)>,
+) {
+    let first_player = interactor_query
+        .iter()
+        .min_by_key(|(_, local_player)| local_player.map(|local_player| local_player.index()));
+    let (interactor, local_player) = match first_player {
+        Some(first_player) => first_player,
+        None => return,
+    };
+    let prompt = interactor
+        .target()
+        .and_then(|target| interactable_query.get(target).ok())
+        .map(|interactable| interactable.prompt(local_player.map(LocalPlayer::input)))
+        .unwrap_or_default();
+    for mut label in label_query.iter_mut() {
+        if label.sections[0].value != prompt {
+            label.sections[0].value = prompt.clone();
+        }
+    }
+    let progress = interactor.hold_progress();
+    for mut style in bar_query.iter_mut() {
+        let display = if progress.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    for mut style in fill_query.iter_mut() {
+        let width = Val::Percent(progress.unwrap_or_default() * 100f32);
+        if style.size.width != width {
+            style.size.width = width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::InputSource;
+
+    fn spawn_health_label(app: &mut App) -> Entity {
+        app.world
+            .spawn()
+            .insert(HealthLabel)
+            .insert(Text::with_section(
+                "",
+                TextStyle::default(),
+                Default::default(),
+            ))
+            .id()
+    }
+
+    fn label_text(app: &App, label: Entity) -> String {
+        app.world.get::<Text>(label).unwrap().sections[0]
+            .value
+            .clone()
+    }
+
+    #[test]
+    fn test_health_label_shows_the_first_player() {
+        let mut app = App::new();
+        app.add_system(update_health_label);
+        let label = spawn_health_label(&mut app);
+        app.update();
+        assert_eq!(label_text(&app, label), "");
+
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Health::new(50f32))
+            .insert(LocalPlayer::new(1, InputSource::Gamepad(Gamepad(0))));
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Health::new(100f32))
+            .insert(LocalPlayer::new(0, InputSource::KeyboardMouse));
+        app.update();
+        assert_eq!(label_text(&app, label), "Health 100 / 100");
+    }
+}
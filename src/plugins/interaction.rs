@@ -0,0 +1,252 @@
+use crate::interaction::{
+    grab_drop_and_throw, interact_with_targets, pull_held_objects, Interacted,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin lets players use the [`Interactable`](crate::interaction::Interactable)s they
+/// look at in [`GameLevel::Main`](crate::states::GameLevel).
+///
+/// Only subjects with an [`Interactor`](crate::interaction::Interactor) component interact. When one
+/// uses its target, an [`Interacted`](crate::interaction::Interacted) event is sent. The use action is
+/// E on the keyboard and the west face button on gamepads.
+///
+/// Note: Targets are found with Rapier's [`QueryPipeline`](bevy_rapier3d::prelude::QueryPipeline),
+/// so [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too.
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Interacted>().add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(interact_with_targets.label("interact")),
+        );
+    }
+}
+
+/// TL;DR: This plugin lets players pick up the [`Grabbable`](crate::interaction::Grabbable) objects they
+/// look at in [`GameLevel::Main`](crate::states::GameLevel), carry them, turn them and throw them.
+///
+/// Only subjects with a [`Carrier`](crate::interaction::Carrier) component carry objects. Held objects are
+/// pulled toward a point ahead of the head by setting their velocity, so they still collide with walls, and
+/// they can't push the player. See [`carry_input`](crate::interaction::carry_input) for the controls.
+///
+/// Note: Player bodies must be in the [`PLAYER_SOLVER_GROUP`](crate::interaction::PLAYER_SOLVER_GROUP).
+pub struct CarryPlugin;
+
+impl Plugin for CarryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(grab_drop_and_throw.label("grab"))
+                .with_system(pull_held_objects.after("grab")),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonHead, FirstPersonSubject};
+    use crate::interaction::{Carrier, Grabbable, Held, Interactable, Interactor, HOLD_DISTANCE};
+    use crate::plugins::test_app::{headless_physics_app, send_key};
+    use crate::resources::{GameClock, GameConfig};
+    use crate::systems::SubjectDiagnostic;
+    use bevy::input::mouse::MouseButtonInput;
+    use bevy::input::{ElementState, InputPlugin};
+    use bevy_rapier3d::prelude::*;
+
+    #[derive(Default)]
+    struct InteractedLog(Vec<Interacted>);
+
+    fn log_interactions(mut events: EventReader<Interacted>, mut log: ResMut<InteractedLog>) {
+        log.0.extend(events.iter().cloned());
+    }
+
+    fn setup_app() -> (App, Entity, Entity) {
+        let mut app = headless_physics_app();
+        app.add_plugin(InputPlugin)
+            .insert_resource(GameConfig::default())
+            .insert_resource(GameClock::default())
+            .insert_resource(InteractedLog::default())
+            .add_event::<SubjectDiagnostic>()
+            .add_state(GameLevel::Main)
+            .add_plugin(InteractionPlugin)
+            .add_plugin(CarryPlugin)
+            .add_system(log_interactions.after("interact"));
+        let subject = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Interactor::default())
+            .insert(Carrier::default())
+            .insert(Transform::default())
+            .insert(GlobalTransform::default())
+            .insert_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::Static.into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(1f32).into(),
+                ..Default::default()
+            })
+            .with_children(|subject| {
+                subject
+                    .spawn()
+                    .insert(FirstPersonHead)
+                    .insert(Transform::default())
+                    .insert(GlobalTransform::default());
+            })
+            .id();
+        // Something to look through on the way
+        app.world.spawn().insert_bundle(ColliderBundle {
+            collider_type: ColliderType::Sensor.into(),
+            shape: ColliderShape::cuboid(1f32, 1f32, 0.1).into(),
+            position: Vec3::new(0f32, 0f32, -1.5).into(),
+            ..Default::default()
+        });
+        let door = app
+            .world
+            .spawn()
+            .insert(Interactable::new("open"))
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::cuboid(0.5, 0.5, 0.5).into(),
+                position: Vec3::new(0f32, 0f32, -3f32).into(),
+                ..Default::default()
+            })
+            .id();
+        (app, subject, door)
+    }
+
+    fn send_mouse_button(app: &mut App, button: MouseButton, state: ElementState) {
+        app.world
+            .get_resource_mut::<Events<MouseButtonInput>>()
+            .unwrap()
+            .send(MouseButtonInput { button, state });
+    }
+
+    fn press_grab(app: &mut App) {
+        send_key(app, KeyCode::F, ElementState::Pressed);
+        app.update();
+        send_key(app, KeyCode::F, ElementState::Released);
+        app.update();
+    }
+
+    // Take the door out of the way, and put a ball with this density in its place
+    fn spawn_ball(app: &mut App, door: Entity, density: f32) -> Entity {
+        app.world.despawn(door);
+        app.world
+            .spawn()
+            .insert(Grabbable)
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0f32, 0f32, -2.5).into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.3).into(),
+                mass_properties: ColliderMassProps::Density(density).into(),
+                ..Default::default()
+            })
+            .id()
+    }
+
+    fn ball_translation(app: &App, ball: Entity) -> Vec3 {
+        let translation = app
+            .world
+            .get::<RigidBodyPositionComponent>(ball)
+            .unwrap()
+            .position
+            .translation;
+        Vec3::new(translation.x, translation.y, translation.z)
+    }
+
+    #[test]
+    fn test_pick_up_carry_and_throw() {
+        let (mut app, subject, door) = setup_app();
+        let ball = spawn_ball(&mut app, door, 1f32);
+        for _ in 0..3 {
+            app.update();
+        }
+        press_grab(&mut app);
+        assert_eq!(
+            app.world.get::<Carrier>(subject).unwrap().held(),
+            Some(ball)
+        );
+        assert_eq!(
+            app.world.get::<Held>(ball),
+            Some(&Held { carrier: subject })
+        );
+
+        for _ in 0..60 {
+            app.update();
+        }
+        let hold_point = Vec3::new(0f32, 0f32, -HOLD_DISTANCE);
+        assert!((ball_translation(&app, ball) - hold_point).length() < 0.1);
+
+        send_mouse_button(&mut app, MouseButton::Left, ElementState::Pressed);
+        app.update();
+        app.update();
+        send_mouse_button(&mut app, MouseButton::Left, ElementState::Released);
+        app.update();
+        assert_eq!(app.world.get::<Carrier>(subject).unwrap().held(), None);
+        assert!(app.world.get::<Held>(ball).is_none());
+        let velocity = app
+            .world
+            .get::<RigidBodyVelocityComponent>(ball)
+            .unwrap()
+            .linvel;
+        // Thrown the way the head faces
+        assert!(velocity.z < -3f32);
+    }
+
+    #[test]
+    fn test_heavy_objects_cant_be_picked_up() {
+        let (mut app, subject, door) = setup_app();
+        spawn_ball(&mut app, door, 10000f32);
+        for _ in 0..3 {
+            app.update();
+        }
+        press_grab(&mut app);
+        assert_eq!(app.world.get::<Carrier>(subject).unwrap().held(), None);
+    }
+
+    #[test]
+    fn test_use_targeted_interactable() {
+        let (mut app, subject, door) = setup_app();
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.get::<Interactor>(subject).unwrap().target(),
+            Some(door)
+        );
+        assert!(app
+            .world
+            .get_resource::<InteractedLog>()
+            .unwrap()
+            .0
+            .is_empty());
+
+        send_key(&mut app, KeyCode::E, ElementState::Pressed);
+        app.update();
+        send_key(&mut app, KeyCode::E, ElementState::Released);
+        app.update();
+        assert_eq!(
+            app.world.get_resource::<InteractedLog>().unwrap().0,
+            vec![Interacted {
+                subject,
+                target: door
+            }]
+        );
+
+        // Locked doors can't be targeted
+        app.world
+            .get_mut::<Interactable>(door)
+            .unwrap()
+            .set_enabled(false);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(app.world.get::<Interactor>(subject).unwrap().target(), None);
+    }
+}
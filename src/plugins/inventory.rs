@@ -0,0 +1,183 @@
+use crate::inventory::{
+    collect_pickups, heal_on_item_used, hide_collected_pickups, use_items, Inventory, ItemAdded,
+    ItemDefinitions, ItemPickupState, ItemRemoved, ItemUsed, UseItemRequested,
+};
+use crate::save::RegisterSaveable;
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin lets players pick up the [`ItemPickup`](crate::inventory::ItemPickup)s in
+/// [`GameLevel::Main`](crate::states::GameLevel), carry them in their [`Inventory`](crate::inventory::Inventory)
+/// and use them.
+///
+/// Items are used by sending a [`UseItemRequested`](crate::inventory::UseItemRequested) event, ie from the
+/// inventory screen. That works while the game is paused too, so those systems run in every level.
+/// Inventories and pickups are saved with the game. Unless [`ItemDefinitions`](crate::inventory::ItemDefinitions)
+/// were inserted already, the items are the ones in `assets/items.toml`.
+///
+/// Note: Pickups are used through the [`InteractionPlugin`](crate::plugins::InteractionPlugin) and touched
+/// through the [`ScriptingPlugin`](crate::plugins::ScriptingPlugin), and they're saved by the
+/// [`SavePlugin`](crate::plugins::SavePlugin), so they must be added too.
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemDefinitions>()
+            .add_event::<ItemAdded>()
+            .add_event::<ItemRemoved>()
+            .add_event::<ItemUsed>()
+            .add_event::<UseItemRequested>()
+            .register_saveable::<Inventory>()
+            .register_saveable::<ItemPickupState>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(
+                        collect_pickups
+                            .label("collect-items")
+                            .after("interact")
+                            .after("detect-triggers"),
+                    )
+                    .with_system(hide_collected_pickups.after("collect-items")),
+            )
+            .add_system(use_items.label("use-items"))
+            .add_system(heal_on_item_used.after("use-items"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{DamageType, FirstPersonSubject, Health, Tags};
+    use crate::interaction::Interacted;
+    use crate::inventory::ItemPickup;
+    use crate::plugins::test_app::{headless_physics_app, unused_save_slots};
+    use crate::plugins::{SavePlugin, ScriptingPlugin};
+    use crate::resources::GameClock;
+    use crate::scripting::LevelScript;
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+    use std::time::Duration;
+
+    const LEVEL_SCRIPT: &str = r#"
+        [[pickups]]
+        id = "medkits"
+        item = "medkit"
+        count = 5
+        position = [0.0, 0.0, 0.0]
+        on_touch = true
+
+        [[pickups]]
+        id = "battery"
+        item = "battery"
+        position = [10.0, 0.0, 0.0]
+    "#;
+
+    fn spawn_level_script(mut commands: Commands) {
+        LevelScript::from_toml(LEVEL_SCRIPT)
+            .unwrap()
+            .spawn(&mut commands);
+    }
+
+    fn setup_app(position: Vec3, slots: usize) -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(Input::<KeyCode>::default())
+            .insert_resource(GameClock::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(SavePlugin)
+            .insert_resource(unused_save_slots("inventory"))
+            .add_plugin(ScriptingPlugin)
+            .add_event::<Interacted>()
+            .add_plugin(InventoryPlugin)
+            .add_startup_system(spawn_level_script)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        let player = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Tags::new(["player"]))
+            .insert(Inventory::new(slots, 50f32))
+            .insert(Health::new(100f32))
+            .insert_bundle(RigidBodyBundle {
+                position: position.into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            })
+            .id();
+        (app, player)
+    }
+
+    fn pickup_count(app: &mut App, item: &str) -> u32 {
+        let mut query = app.world.query::<(&ItemPickup, &ItemPickupState)>();
+        query
+            .iter(&app.world)
+            .filter(|(pickup, _)| pickup.item() == item)
+            .map(|(_, state)| state.count)
+            .sum()
+    }
+
+    #[test]
+    fn test_touching_a_pickup_takes_what_fits() {
+        // Standing on the medkits, but the inventory only has room for 3 of them
+        let (mut app, player) = setup_app(Vec3::ZERO, 1);
+        for _ in 0..5 {
+            app.update();
+        }
+        let inventory = app.world.get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count("medkit"), 3);
+        assert_eq!(pickup_count(&mut app, "medkit"), 2);
+    }
+
+    #[test]
+    fn test_using_a_pickup_and_an_item() {
+        let (mut app, player) = setup_app(Vec3::new(20f32, 0f32, 0f32), 2);
+        app.update();
+        let mut query = app.world.query::<(Entity, &ItemPickup)>();
+        let battery = query
+            .iter(&app.world)
+            .find(|(_, pickup)| pickup.item() == "battery")
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.world
+            .get_resource_mut::<Events<Interacted>>()
+            .unwrap()
+            .send(Interacted {
+                subject: player,
+                target: battery,
+            });
+        app.update();
+        assert_eq!(
+            app.world.get::<Inventory>(player).unwrap().count("battery"),
+            1
+        );
+        assert_eq!(pickup_count(&mut app, "battery"), 0);
+
+        // Batteries can't be used, medkits can
+        let items = app.world.get_resource::<ItemDefinitions>().unwrap().clone();
+        app.world
+            .get_mut::<Inventory>(player)
+            .unwrap()
+            .add("medkit", 1, &items);
+        app.world.get_mut::<Health>(player).unwrap().damage(
+            50f32,
+            DamageType::Generic,
+            Duration::ZERO,
+        );
+        for item in ["battery", "medkit"] {
+            app.world
+                .get_resource_mut::<Events<UseItemRequested>>()
+                .unwrap()
+                .send(UseItemRequested {
+                    subject: player,
+                    item: item.to_string(),
+                });
+        }
+        app.update();
+        let inventory = app.world.get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count("battery"), 1);
+        assert_eq!(inventory.count("medkit"), 0);
+        assert_eq!(app.world.get::<Health>(player).unwrap().current(), 90f32);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::resources::UnsavedProgress;
+use crate::states::GameLevel;
+use crate::widgets::{self, MenuBack, UiContext, WidgetActivated};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+/// This enum defines the actions that must be confirmed by the player before they happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAction {
+    QuitToMainMenu,
+    QuitToDesktop,
+}
+
+impl ConfirmAction {
+    fn prompt(&self) -> &'static str {
+        match self {
+            ConfirmAction::QuitToMainMenu => "Quit to the main menu?",
+            ConfirmAction::QuitToDesktop => "Quit to the desktop?",
+        }
+    }
+}
+
+/// This component is added to the root of a confirm dialog. A level can query for it
+/// to check whether a dialog is already open.
+#[derive(Component)]
+pub struct ConfirmDialog {
+    action: ConfirmAction,
+}
+
+#[derive(Component)]
+struct ConfirmDialogButton {
+    dialog: Entity,
+    confirmed: bool,
+}
+
+/// This event is sent when the player answers a confirm dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmDialogResolved {
+    pub action: ConfirmAction,
+    pub confirmed: bool,
+}
+
+/// TL;DR: This plugin lets UI levels ask the player to confirm an action before it happens.
+///
+/// Open a dialog with [`spawn_confirm_dialog`](crate::plugins::levels::spawn_confirm_dialog). When the
+/// player answers, the dialog is despawned, a [`ConfirmDialogResolved`](crate::plugins::levels::ConfirmDialogResolved)
+/// event is sent and, if the player confirmed, the [`ConfirmAction`](crate::plugins::levels::ConfirmAction) is performed.
+pub struct ConfirmDialogPlugin;
+
+impl Plugin for ConfirmDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UnsavedProgress::default())
+            .add_event::<ConfirmDialogResolved>()
+            .add_system(resolve_confirm_dialog_on_activated.label("resolve-confirm-dialog"))
+            .add_system(cancel_confirm_dialog_on_back.label("resolve-confirm-dialog"))
+            .add_system(perform_confirmed_action.after("resolve-confirm-dialog"));
+    }
+}
+
+/// Open a modal dialog asking the player to confirm an action. If the action would discard
+/// progress, every warning registered in [`UnsavedProgress`](crate::resources::UnsavedProgress) is listed.
+///
+/// The dialog is spawned with the marker of `ui`, so it's torn down along with the level.
+/// While it's open, only its buttons can be focused, and going back cancels it.
+pub fn spawn_confirm_dialog<M: Component + Clone>(
+    commands: &mut Commands,
+    ui: &UiContext<M>,
+    unsaved_progress: &UnsavedProgress,
+    action: ConfirmAction,
+) {
+    let ui = &ui.modal();
+    let theme = ui.theme();
+    let mut dialog = widgets::Panel::overlay()
+        .with_color(theme.overlay_color())
+        .spawn_root(commands, ui, |_| {});
+    let dialog_entity = dialog.id();
+    dialog
+        .insert(ConfirmDialog { action })
+        .with_children(|dialog_root| {
+            widgets::Panel::boxed()
+                .with_color(theme.panel_color())
+                .spawn(dialog_root, ui, |dialog_panel| {
+                    widgets::Label::title(action.prompt()).spawn(dialog_panel, ui);
+                    for warning in unsaved_progress.warnings() {
+                        widgets::Label::small(format!("Unsaved: {}", warning))
+                            .with_color(theme.warning_color())
+                            .spawn(dialog_panel, ui);
+                    }
+                    widgets::Panel::row().spawn(dialog_panel, ui, |button_row| {
+                        for (label, confirmed) in [("Yes", true), ("No", false)] {
+                            widgets::Button::new(label)
+                                .with_width(150f32)
+                                .spawn(button_row, ui)
+                                .insert(ConfirmDialogButton {
+                                    dialog: dialog_entity,
+                                    confirmed,
+                                });
+                        }
+                    });
+                });
+        });
+}
+
+fn resolve_confirm_dialog_on_activated(
+    mut commands: Commands,
+    mut activated_events: EventReader<WidgetActivated>,
+    button_query: Query<&ConfirmDialogButton>,
+    dialog_query: Query<&ConfirmDialog>,
+    mut resolved_events: EventWriter<ConfirmDialogResolved>,
+) {
+    // Despawning is deferred, so keep track of the dialogs resolved this
+    // frame in case more than one of their buttons was activated
+    let mut resolved_dialogs: Vec<Entity> = Vec::new();
+    for WidgetActivated(entity) in activated_events.iter() {
+        let button = match button_query.get(*entity) {
+            Ok(button) => button,
+            Err(_) => continue,
+        };
+        if resolved_dialogs.contains(&button.dialog) {
+            continue;
+        }
+        if let Ok(dialog) = dialog_query.get(button.dialog) {
+            resolved_events.send(ConfirmDialogResolved {
+                action: dialog.action,
+                confirmed: button.confirmed,
+            });
+            commands.entity(button.dialog).despawn_recursive();
+            resolved_dialogs.push(button.dialog);
+        }
+    }
+}
+
+/// Going back out of a dialog is the same as answering "No"
+fn cancel_confirm_dialog_on_back(
+    mut commands: Commands,
+    mut back_events: EventReader<MenuBack>,
+    dialog_query: Query<(Entity, &ConfirmDialog)>,
+    mut resolved_events: EventWriter<ConfirmDialogResolved>,
+) {
+    if back_events.iter().last().is_none() {
+        return;
+    }
+    for (entity, dialog) in dialog_query.iter() {
+        resolved_events.send(ConfirmDialogResolved {
+            action: dialog.action,
+            confirmed: false,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn perform_confirmed_action(
+    mut resolved_events: EventReader<ConfirmDialogResolved>,
+    mut unsaved_progress: ResMut<UnsavedProgress>,
+    mut game_level: ResMut<State<GameLevel>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    for resolved in resolved_events.iter() {
+        if !resolved.confirmed {
+            continue;
+        }
+        match resolved.action {
+            ConfirmAction::QuitToMainMenu => {
+                // The player chose to throw away their progress
+                unsaved_progress.clear_all();
+                game_level
+                    .replace(GameLevel::MainMenu)
+                    .expect("Error occurred while quitting to the main menu!");
+            }
+            ConfirmAction::QuitToDesktop => {
+                app_exit_events.send(AppExit);
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::components::{FirstPersonSubject, LocalPlayer};
+use crate::inventory::{Inventory, ItemDefinitions, ItemRemoved, UseItemRequested};
+use crate::resources::UiTheme;
+use crate::states::{FirstPersonControlSettings, GameLevel};
+use crate::systems::pausing::{close_inventory, inventory_just_pressed, open_inventory};
+use crate::widgets::{self, MenuBack, UiContext, WidgetActivated};
+use bevy::prelude::*;
+
+#[derive(Component, Clone)]
+struct InventoryScreenObject;
+
+/// The button for a stack of an item that can be used
+#[derive(Component)]
+struct UseItemButton(String);
+
+#[derive(Component)]
+struct CloseButton;
+
+/// This plugin manages the inventory screen level, which lists what the first player carries
+/// and lets them use it. It's opened from [`GameLevel::Main`](crate::states::GameLevel) and
+/// pauses the game like the pause menu does.
+///
+/// Note: Items are used by the [`InventoryPlugin`](crate::plugins::InventoryPlugin), so it must be
+/// added too.
+pub struct InventoryLevel;
+
+impl Plugin for InventoryLevel {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameLevel::Main).with_system(open_inventory))
+            .add_system_set(SystemSet::on_enter(GameLevel::Inventory).with_system(setup_screen))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Inventory)
+                    .with_system(close_inventory_on_back)
+                    .with_system(use_item_on_activated)
+                    // Show what's left once the item's been used
+                    .with_system(rebuild_screen_on_item_removed.after("use-items")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameLevel::Inventory).with_system(teardown_inventory_level),
+            );
+    }
+}
+
+/// The first player, whose inventory the screen shows
+fn first_player<'a>(
+    subject_query: &'a Query<(Entity, &Inventory, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+) -> Option<(Entity, &'a Inventory)> {
+    subject_query
+        .iter()
+        .find(|(_, _, local_player)| local_player.map_or(true, |player| player.index() == 0))
+        .map(|(entity, inventory, _)| (entity, inventory))
+}
+
+fn spawn_screen(
+    commands: &mut Commands,
+    ui: &UiContext<InventoryScreenObject>,
+    items: &ItemDefinitions,
+    inventory: Option<&Inventory>,
+) {
+    widgets::Panel::screen()
+        .with_color(ui.theme().overlay_color())
+        .spawn_root(commands, ui, |window_root| {
+            widgets::Panel::column().spawn(window_root, ui, |center_column| {
+                widgets::Label::title("Inventory").spawn(center_column, ui);
+                let inventory = match inventory {
+                    Some(inventory) => inventory,
+                    None => {
+                        widgets::Label::small("There's nobody to carry anything")
+                            .spawn(center_column, ui);
+                        return;
+                    }
+                };
+                widgets::Label::small(format!(
+                    "{} / {} slots, {:.1} / {:.1} kg",
+                    inventory.stacks().len(),
+                    inventory.slots(),
+                    inventory.weight(items),
+                    inventory.max_weight()
+                ))
+                .spawn(center_column, ui);
+                if inventory.stacks().is_empty() {
+                    widgets::Label::text("Empty").spawn(center_column, ui);
+                }
+                for stack in inventory.stacks() {
+                    let definition = items.get(stack.item());
+                    let name = definition.map_or(stack.item(), |definition| definition.name());
+                    let text = format!("{} x{}", name, stack.count());
+                    if definition.map_or(false, |definition| definition.is_usable()) {
+                        widgets::Button::new(format!("Use {}", text))
+                            .with_width(350f32)
+                            .spawn(center_column, ui)
+                            .insert(UseItemButton(stack.item().to_string()));
+                    } else {
+                        widgets::Label::text(text).spawn(center_column, ui);
+                    }
+                }
+                // There's always something to focus, so going back works
+                widgets::Button::new("Close")
+                    .spawn(center_column, ui)
+                    .insert(CloseButton);
+            });
+        });
+}
+
+fn setup_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    items: Res<ItemDefinitions>,
+    subject_query: Query<(Entity, &Inventory, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+) {
+    let ui = UiContext::new(&theme, &asset_server, InventoryScreenObject);
+    let inventory = first_player(&subject_query).map(|(_, inventory)| inventory);
+    spawn_screen(&mut commands, &ui, &items, inventory);
+}
+
+/// Close the inventory when going back, activating the close button or pressing the inventory key again
+#[allow(clippy::too_many_arguments)]
+fn close_inventory_on_back(
+    mut activated_events: EventReader<WidgetActivated>,
+    mut back_events: EventReader<MenuBack>,
+    close_button_query: Query<Entity, With<CloseButton>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let close_activated = activated_events
+        .iter()
+        .any(|WidgetActivated(entity)| close_button_query.get(*entity).is_ok());
+    let went_back = back_events.iter().last().is_some();
+    let toggled = inventory_just_pressed(&keyboard_input, &gamepads, &gamepad_buttons);
+    if close_activated || went_back || toggled {
+        // Otherwise the main level would open the inventory again this frame
+        keyboard_input.reset(KeyCode::Tab);
+        keyboard_input.reset(KeyCode::I);
+        close_inventory(&mut fp_control_settings, &mut game_level);
+    }
+}
+
+fn use_item_on_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    button_query: Query<&UseItemButton>,
+    subject_query: Query<(Entity, &Inventory, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+    mut use_requests: EventWriter<UseItemRequested>,
+) {
+    let subject = match first_player(&subject_query) {
+        Some((subject, _)) => subject,
+        None => return,
+    };
+    for WidgetActivated(entity) in activated_events.iter() {
+        if let Ok(UseItemButton(item)) = button_query.get(*entity) {
+            use_requests.send(UseItemRequested {
+                subject,
+                item: item.clone(),
+            });
+        }
+    }
+}
+
+fn rebuild_screen_on_item_removed(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    items: Res<ItemDefinitions>,
+    mut removed_events: EventReader<ItemRemoved>,
+    subject_query: Query<(Entity, &Inventory, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+    screen_query: Query<Entity, With<InventoryScreenObject>>,
+) {
+    let (subject, inventory) = match first_player(&subject_query) {
+        Some(player) => player,
+        None => return,
+    };
+    let removed = removed_events
+        .iter()
+        .filter(|event| event.subject == subject)
+        .count();
+    if removed == 0 {
+        return;
+    }
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    let ui = UiContext::new(&theme, &asset_server, InventoryScreenObject);
+    spawn_screen(&mut commands, &ui, &items, Some(inventory));
+}
+
+fn teardown_inventory_level(
+    mut commands: Commands,
+    query: Query<Entity, With<InventoryScreenObject>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
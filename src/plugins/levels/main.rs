@@ -1,39 +1,76 @@
-use crate::components::LevelObject;
+use crate::components::{Checkpoint, LevelObject};
+use crate::scripting::{LevelScript, LevelScripts};
 use crate::states::{FirstPersonControlSettings, GameLevel};
-use crate::systems::pausing::{pause_game, resume_game};
+use crate::systems::pausing::pause_game;
 use crate::systems::player::{
-    add_player, jump_player_body, move_player_body, rotate_player_body, rotate_player_head,
+    add_player, jump_player_body, move_player_body, resize_player_capsules, rotate_player_body,
+    rotate_player_head,
+};
+use crate::systems::{
+    activate_physics, deactivate_physics, pause_game_clock, resume_game_clock, teardown_game_level,
 };
-use crate::systems::{activate_physics, deactivate_physics, teardown_game_level};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+/// The triggers, moving platforms, floors, walls, props, doors, switches and keys of the main level
+const MAIN_LEVEL_SCRIPT: &str = include_str!("../../../assets/levels/main.toml");
+
 /// This plugin manages gameplay for the main game level
 pub struct MainGameLevel;
 
 impl Plugin for MainGameLevel {
     fn build(&self, app: &mut App) {
+        let mut level_scripts = app.world.get_resource_or_insert_with(LevelScripts::default);
+        match LevelScript::from_toml(MAIN_LEVEL_SCRIPT) {
+            Ok(level_script) => level_scripts.insert(GameLevel::Main, level_script),
+            Err(parse_err) => error!("Could not read the main level's script: {}", parse_err),
+        }
         app.add_system_set(
             SystemSet::on_enter(GameLevel::Main)
                 .with_system(activate_physics)
+                .with_system(resume_game_clock)
                 .with_system(setup_level)
                 .with_system(add_player),
         )
-        .add_system_set(SystemSet::on_pause(GameLevel::Main).with_system(deactivate_physics))
+        .add_system_set(
+            SystemSet::on_pause(GameLevel::Main)
+                .with_system(deactivate_physics)
+                .with_system(pause_game_clock),
+        )
         .add_system_set(
             SystemSet::on_resume(GameLevel::Main)
                 .with_system(activate_physics)
-                .with_system(resume_game),
+                .with_system(resume_game_clock),
         )
         .add_system_set(
             SystemSet::on_update(GameLevel::Main)
-                .with_system(rotate_player_body)
-                .with_system(rotate_player_head)
+                // The player's body follows this frame's intents, not last frame's
+                .with_system(
+                    rotate_player_body
+                        .label("player-body")
+                        .after("first-person-intents"),
+                )
+                .with_system(
+                    rotate_player_head
+                        .label("player-body")
+                        .after("first-person-intents"),
+                )
                 .with_system(pause_game)
+                // The config can change while playing, ie when it's reloaded
+                .with_system(resize_player_capsules)
                 // Make sure jump system runs after movement to prevent
                 // the bug where the player can't jump without moving at the same time
-                .with_system(move_player_body.label("move-player-body"))
-                .with_system(jump_player_body.after("move-player-body")),
+                .with_system(
+                    move_player_body
+                        .label("move-player-body")
+                        .label("player-body")
+                        .after("first-person-intents"),
+                )
+                .with_system(
+                    jump_player_body
+                        .label("player-body")
+                        .after("move-player-body"),
+                ),
         )
         .add_system_set(
             SystemSet::on_exit(GameLevel::Main)
@@ -50,6 +87,7 @@ fn setup_level(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    level_scripts: Res<LevelScripts>,
 ) {
     /* Create the ground. */
     let collider = ColliderBundle {
@@ -58,35 +96,50 @@ fn setup_level(
     };
     commands.spawn_bundle(collider).insert(LevelObject);
 
-    /* Create the bouncing ball. */
-    commands
-        .spawn_bundle(RigidBodyBundle {
-            position: Vec3::new(0.0, 10.0, 0.0).into(),
-            ..Default::default()
-        })
-        .insert_bundle(ColliderBundle {
-            shape: ColliderShape::ball(0.5).into(),
-            material: ColliderMaterial {
-                restitution: 0.7,
-                ..Default::default()
-            }
-            .into(),
-            ..Default::default()
-        })
-        .insert(LevelObject)
-        .insert(Transform::default())
-        .insert(RigidBodyPositionSync::Discrete)
-        .insert_bundle(PbrBundle {
-            mesh: meshes.add(Mesh::from(bevy::prelude::shape::UVSphere {
-                radius: 0.5,
+    /* Create the checkpoints, one where the players spawn and one further along. */
+    let checkpoint_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(0.2, 0.6, 1.0, 0.25),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..Default::default()
+    });
+    for (center, half_extents, respawn) in [
+        (
+            Vec3::new(2.0, 4.0, 7.0),
+            Vec3::new(4.0, 4.0, 3.0),
+            Vec3::new(0.0, 7.0, 7.0),
+        ),
+        (
+            Vec3::new(0.0, 1.5, -20.0),
+            Vec3::new(3.0, 1.5, 1.0),
+            Vec3::new(0.0, 2.0, -20.0),
+        ),
+    ] {
+        commands
+            .spawn_bundle(ColliderBundle {
+                collider_type: ColliderType::Sensor.into(),
+                shape: ColliderShape::cuboid(half_extents.x, half_extents.y, half_extents.z).into(),
+                position: center.into(),
+                flags: ActiveEvents::INTERSECTION_EVENTS.into(),
                 ..Default::default()
-            })),
-            material: materials.add(StandardMaterial {
-                base_color: Color::LIME_GREEN,
+            })
+            .insert(Checkpoint::new(Transform::from_translation(respawn)))
+            .insert(LevelObject)
+            .insert_bundle(PbrBundle {
+                mesh: meshes.add(Mesh::from(bevy::prelude::shape::Box::new(
+                    2.0 * half_extents.x,
+                    2.0 * half_extents.y,
+                    2.0 * half_extents.z,
+                ))),
+                material: checkpoint_material.clone(),
+                transform: Transform::from_translation(center),
                 ..Default::default()
-            }),
-            ..Default::default()
-        });
+            });
+    }
+
+    if let Some(level_script) = level_scripts.get(&GameLevel::Main) {
+        level_script.spawn(&mut commands);
+    }
 
     commands.insert_resource(AmbientLight {
         color: Color::WHITE,
@@ -117,8 +170,71 @@ fn teardown_main_game_level(
     mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
 ) {
     commands.remove_resource::<AmbientLight>();
-    // Enable first person controls
-    fp_control_settings
-        .set(FirstPersonControlSettings::Disabled)
-        .expect("Could not disable First Person Controls while tearing down main game level!");
+    // Disable first person controls. They're already disabled if
+    // the level is exited from the pause menu
+    if fp_control_settings.current() == &FirstPersonControlSettings::Enabled {
+        fp_control_settings
+            .set(FirstPersonControlSettings::Disabled)
+            .expect("Could not disable First Person Controls while tearing down main game level!");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemDefinitions;
+    use crate::resources::GameConfig;
+
+    #[test]
+    fn test_main_level_script_parses() {
+        let level_script = LevelScript::from_toml(MAIN_LEVEL_SCRIPT).unwrap();
+        assert!(!level_script.triggers.is_empty());
+        assert!(!level_script.platforms.is_empty());
+        assert!(!level_script.hazards.is_empty());
+        assert!(!level_script.walls.is_empty());
+        assert!(!level_script.interactables.is_empty());
+        // Every pickup is a real item
+        let items = ItemDefinitions::default();
+        for pickup in level_script.pickups.iter() {
+            assert!(items.get(&pickup.item).is_some());
+        }
+        // Every locked door can be unlocked, and every switch is linked to something
+        for door in level_script.doors.iter() {
+            if let Some(key) = &door.key {
+                assert!(level_script
+                    .keys
+                    .iter()
+                    .any(|key_item| &key_item.key == key));
+            }
+        }
+        for switch in level_script.switches.iter() {
+            assert!(switch.targets.iter().all(|target| {
+                level_script
+                    .doors
+                    .iter()
+                    .any(|door| door.tags.contains(target))
+                    || level_script
+                        .platforms
+                        .iter()
+                        .any(|platform| platform.tags.contains(target))
+            }));
+        }
+        // Every material is in the config
+        let game_config = GameConfig::default();
+        let materials = level_script
+            .floors
+            .iter()
+            .map(|floor| Some(&floor.material))
+            .chain(
+                level_script
+                    .platforms
+                    .iter()
+                    .map(|platform| platform.material.as_ref()),
+            )
+            .chain(level_script.doors.iter().map(|door| door.material.as_ref()))
+            .chain(level_script.props.iter().map(|prop| prop.material.as_ref()));
+        for material in materials.flatten() {
+            assert!(game_config.material(material).is_some());
+        }
+    }
 }
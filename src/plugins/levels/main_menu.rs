@@ -1,12 +1,25 @@
-use crate::components::LevelObject;
-use crate::resources::GameConfig;
+use crate::components::{InputSource, LevelObject};
+use crate::plugins::levels::{spawn_confirm_dialog, ConfirmAction, ConfirmDialog};
+use crate::resources::{GameConfig, LocalPlayers, UiTheme, UnsavedProgress, MAX_LOCAL_PLAYERS};
+use crate::save::{unix_time_now, LoadGameRequested, SaveSlots};
 use crate::states::GameLevel;
 use crate::systems::teardown_game_level;
+use crate::widgets::{self, MenuBack, UiContext, WidgetActivated};
 use bevy::prelude::*;
 
 #[derive(Component)]
 struct PlayGameButton;
 
+#[derive(Component)]
+struct QuitToDesktopButton;
+
+/// A button in the load-game list, holding the slot it loads
+#[derive(Component)]
+struct LoadSlotButton(String);
+
+#[derive(Component)]
+struct LocalPlayersText;
+
 /// This plugin manages gameplay for the main menu level
 pub struct MainMenuLevel;
 
@@ -15,8 +28,11 @@ impl Plugin for MainMenuLevel {
         app.add_system_set(SystemSet::on_enter(GameLevel::MainMenu).with_system(setup_menu))
             .add_system_set(
                 SystemSet::on_update(GameLevel::MainMenu)
-                    .with_system(change_button_style_on_interaction)
-                    .with_system(enter_game_on_play_game_clicked),
+                    .with_system(enter_game_on_play_game_activated)
+                    .with_system(load_game_on_slot_activated)
+                    .with_system(confirm_quit_on_quit_activated)
+                    .with_system(join_local_players_on_start_pressed)
+                    .with_system(update_local_players_text),
             )
             .add_system_set(
                 SystemSet::on_exit(GameLevel::MainMenu).with_system(teardown_game_level),
@@ -27,143 +43,158 @@ impl Plugin for MainMenuLevel {
 fn setup_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
     game_config: Res<GameConfig>,
+    local_players: Res<LocalPlayers>,
+    save_slots: Res<SaveSlots>,
 ) {
-    commands
-        .spawn()
-        .insert(LevelObject)
-        .insert_bundle(UiCameraBundle::default());
-
-    commands
-        .spawn()
-        .insert(LevelObject)
-        .insert_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::Center,
-                ..Default::default()
-            },
-            color: Color::BLUE.into(),
-            ..Default::default()
-        })
-        .with_children(|window_root| {
-            window_root
-                .spawn()
-                .insert(LevelObject)
-                .insert_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Percent(66.0), Val::Percent(100.0)),
-                        justify_content: JustifyContent::Center,
-                        // horizontally center child text
-                        flex_direction: FlexDirection::ColumnReverse,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..Default::default()
-                    },
-                    color: Color::RED.into(),
-                    ..Default::default()
-                })
-                .with_children(|center_third_column| {
-                    // Add title text
-                    center_third_column
-                        .spawn()
-                        .insert(LevelObject)
-                        .insert(PlayGameButton)
-                        .insert_bundle(TextBundle {
-                            text: Text::with_section(
-                                game_config.name(),
-                                TextStyle {
-                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                    font_size: 40.0,
-                                    color: Color::WHITE.into(),
-                                },
-                                Default::default(),
-                            ),
-                            style: Style {
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        });
-
-                    // Add play game button
-                    center_third_column
-                        .spawn()
-                        .insert(LevelObject)
-                        .insert(PlayGameButton)
-                        .insert_bundle(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(200.0), Val::Px(65.0)),
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                // horizontally center child text
-                                justify_content: JustifyContent::Center,
-                                // vertically center child text
-                                align_items: AlignItems::Center,
-                                ..Default::default()
-                            },
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .with_children(|play_game_button| {
-                            play_game_button.spawn().insert(LevelObject).insert_bundle(
-                                TextBundle {
-                                    text: Text::with_section(
-                                        "Play Game",
-                                        TextStyle {
-                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                            font_size: 40.0,
-                                            color: Color::rgb(0.9, 0.9, 0.9),
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                },
-                            );
-                        });
+    let saves = save_slots.list();
+    let now = unix_time_now();
+    let ui = UiContext::new(&theme, &asset_server, LevelObject);
+    widgets::Panel::screen()
+        .with_color(theme.background_color())
+        .spawn_root(&mut commands, &ui, |window_root| {
+            widgets::Panel::column()
+                .with_color(theme.column_color())
+                .spawn(window_root, &ui, |center_third_column| {
+                    widgets::Label::title(game_config.name()).spawn(center_third_column, &ui);
+                    widgets::Label::small(local_players_text(&local_players))
+                        .spawn(center_third_column, &ui)
+                        .insert(LocalPlayersText);
+                    widgets::Button::new("Play Game")
+                        .spawn(center_third_column, &ui)
+                        .insert(PlayGameButton);
+                    widgets::Label::small("Load Game").spawn(center_third_column, &ui);
+                    if saves.is_empty() {
+                        widgets::Label::small("No saved games").spawn(center_third_column, &ui);
+                    }
+                    for save in saves {
+                        widgets::Button::new(format!("{} ({})", save.slot, save.age(now)))
+                            .with_width(350f32)
+                            .spawn(center_third_column, &ui)
+                            .insert(LoadSlotButton(save.slot));
+                    }
+                    widgets::Button::new("Quit to Desktop")
+                        .with_width(350f32)
+                        .spawn(center_third_column, &ui)
+                        .insert(QuitToDesktopButton);
                 });
         });
 }
 
-fn change_button_style_on_interaction(
-    mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
-    >,
+fn enter_game_on_play_game_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    play_game_query: Query<Entity, With<PlayGameButton>>,
+    mut game_level: ResMut<State<GameLevel>>,
 ) {
-    for (interaction, mut color) in interaction_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                *color = Color::rgb(0.35, 0.75, 0.35).into();
-            }
-            Interaction::Hovered => {
-                *color = Color::rgb(0.25, 0.25, 0.25).into();
-            }
-            Interaction::None => {
-                *color = Color::rgb(0.15, 0.15, 0.15).into();
-            }
-        }
+    let play_game_activated = activated_events
+        .iter()
+        .filter(|WidgetActivated(entity)| play_game_query.get(*entity).is_ok())
+        .last()
+        .is_some();
+    if play_game_activated {
+        game_level.set(GameLevel::Main).expect("Error occurred while setting GameLevel state to Main. Either the current state is Main, or another state transition is already queued!");
     }
 }
 
-fn enter_game_on_play_game_clicked(
-    interaction_query: Query<&Interaction, With<PlayGameButton>>,
-    mut game_level: ResMut<State<GameLevel>>,
+fn load_game_on_slot_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    slot_query: Query<&LoadSlotButton>,
+    mut load_requests: EventWriter<LoadGameRequested>,
+) {
+    let activated_slot = activated_events
+        .iter()
+        .filter_map(|WidgetActivated(entity)| slot_query.get(*entity).ok())
+        .last();
+    if let Some(LoadSlotButton(slot)) = activated_slot {
+        load_requests.send(LoadGameRequested { slot: slot.clone() });
+    }
+}
+
+/// Going back from the main menu asks to quit to the desktop
+#[allow(clippy::too_many_arguments)]
+fn confirm_quit_on_quit_activated(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    unsaved_progress: Res<UnsavedProgress>,
+    mut activated_events: EventReader<WidgetActivated>,
+    mut back_events: EventReader<MenuBack>,
+    quit_button_query: Query<Entity, With<QuitToDesktopButton>>,
+    dialog_query: Query<Entity, With<ConfirmDialog>>,
+) {
+    let quit_activated = activated_events
+        .iter()
+        .filter(|WidgetActivated(entity)| quit_button_query.get(*entity).is_ok())
+        .last()
+        .is_some();
+    let went_back = back_events.iter().last().is_some();
+    // Only one dialog can be open at a time
+    if dialog_query.iter().next().is_some() {
+        return;
+    }
+    if quit_activated || went_back {
+        let ui = UiContext::new(&theme, &asset_server, LevelObject);
+        spawn_confirm_dialog(
+            &mut commands,
+            &ui,
+            &unsaved_progress,
+            ConfirmAction::QuitToDesktop,
+        );
+    }
+}
+
+/// Describe who's playing and how more players can join
+fn local_players_text(local_players: &LocalPlayers) -> String {
+    let players = if local_players.count() == 1 {
+        String::from("1 player")
+    } else {
+        format!("{} players, split-screen", local_players.count())
+    };
+    if local_players.inputs().count() < MAX_LOCAL_PLAYERS {
+        format!(
+            "{}. Press Space, or Start on a controller, to join.",
+            players
+        )
+    } else {
+        players
+    }
+}
+
+/// Players join the next game by pressing Space on the keyboard or Start on a gamepad nobody
+/// uses yet, and leave it by pressing it again. The first device to join is player 1's.
+fn join_local_players_on_start_pressed(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut local_players: ResMut<LocalPlayers>,
 ) {
-    let interaction = interaction_query.get_single().expect(
-        "Could not find a PlayGameButton while setting it up to change GameLevel on click!",
-    );
-    match *interaction {
-        Interaction::Clicked => {
-            game_level.set(GameLevel::Main).expect("Error occurred while setting GameLevel state to Main. Either the current state is Main, or another state transition is already queued!");
+    let keyboard_pressed = keyboard_input
+        .just_pressed(KeyCode::Space)
+        .then(|| InputSource::KeyboardMouse);
+    let gamepads_pressed = gamepads
+        .iter()
+        .filter(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton(**gamepad, GamepadButtonType::Start))
+        })
+        .map(|gamepad| InputSource::Gamepad(*gamepad));
+    for input in keyboard_pressed.into_iter().chain(gamepads_pressed) {
+        if local_players.is_joined(input) {
+            local_players.leave(input);
+        } else if local_players.join(input).is_none() {
+            debug!("{:?} can't join, the screen is full", input);
         }
-        _ => {}
+    }
+}
+
+fn update_local_players_text(
+    local_players: Res<LocalPlayers>,
+    mut text_query: Query<&mut Text, With<LocalPlayersText>>,
+) {
+    if !local_players.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = local_players_text(&local_players);
     }
 }
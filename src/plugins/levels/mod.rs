@@ -1,7 +1,11 @@
+mod confirm_dialog;
+mod inventory;
 mod main;
 mod main_menu;
 mod pause_menu;
 
+pub use self::confirm_dialog::*;
+pub use self::inventory::*;
 pub use self::main::*;
 pub use self::main_menu::*;
 pub use self::pause_menu::*;
@@ -1,17 +1,55 @@
-use crate::states::GameLevel;
+use crate::plugins::levels::{spawn_confirm_dialog, ConfirmAction, ConfirmDialog};
+use crate::resources::{GameSettings, PauseReason, UiTheme, UnsavedProgress};
+use crate::save::RestoreSnapshotRequested;
+use crate::states::{FirstPersonControlSettings, GameLevel};
+use crate::systems::pausing::resume_game;
+use crate::widgets::{self, MenuBack, SliderValue, UiContext, WidgetActivated};
 use bevy::prelude::*;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct PauseMenuObject;
 
+#[derive(Component)]
+struct PauseNotice;
+
 #[derive(Component)]
 struct ResumeButton;
 
+#[derive(Component)]
+struct RestartFromCheckpointButton;
+
 #[derive(Component)]
 struct SettingsButton;
 
 #[derive(Component)]
-struct QuitButton;
+struct BackButton;
+
+#[derive(Component)]
+struct PauseMainColumn;
+
+#[derive(Component)]
+struct SettingsColumn;
+
+#[derive(Component)]
+struct HorizontalSensitivitySlider;
+
+#[derive(Component)]
+struct VerticalSensitivitySlider;
+
+#[derive(Component)]
+struct MasterVolumeSlider;
+
+#[derive(Component)]
+struct MusicVolumeSlider;
+
+#[derive(Component)]
+struct SfxVolumeSlider;
+
+#[derive(Component)]
+struct QuitToMainMenuButton;
+
+#[derive(Component)]
+struct QuitToDesktopButton;
 
 /// This plugin manages gameplay for the pause menu level
 pub struct PauseMenuLevel;
@@ -21,9 +59,12 @@ impl Plugin for PauseMenuLevel {
         app.add_system_set(SystemSet::on_enter(GameLevel::PauseMenu).with_system(setup_menu))
             .add_system_set(
                 SystemSet::on_update(GameLevel::PauseMenu)
-                    .with_system(change_button_style_on_interaction)
-                    .with_system(enter_game_on_resume_game_clicked)
-                    .with_system(quit_game_on_quit_game_clicked),
+                    .with_system(update_pause_notice)
+                    .with_system(navigate_pause_menu)
+                    .with_system(restart_from_checkpoint_on_activated)
+                    .with_system(apply_sensitivity_settings)
+                    .with_system(apply_volume_settings)
+                    .with_system(confirm_quit_on_quit_activated),
             )
             .add_system_set(
                 SystemSet::on_exit(GameLevel::PauseMenu).with_system(teardown_pause_level),
@@ -31,243 +72,248 @@ impl Plugin for PauseMenuLevel {
     }
 }
 
-fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands
-        .spawn()
-        .insert(PauseMenuObject)
-        .insert_bundle(UiCameraBundle::default());
+fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    settings: Res<GameSettings>,
+    pause_reason: Res<PauseReason>,
+) {
+    let ui = UiContext::new(&theme, &asset_server, PauseMenuObject);
+    widgets::Panel::screen()
+        .with_color(theme.overlay_color())
+        .spawn_root(&mut commands, &ui, |window_root| {
+            widgets::Panel::column()
+                .spawn(window_root, &ui, |center_column| {
+                    widgets::Label::title("Paused").spawn(center_column, &ui);
+                    // Explain why the game paused, if needed
+                    widgets::Label::small(pause_reason.notice().unwrap_or_default())
+                        .with_color(theme.warning_color())
+                        .spawn(center_column, &ui)
+                        .insert(PauseNotice);
+                    widgets::Button::new("Resume")
+                        .spawn(center_column, &ui)
+                        .insert(ResumeButton);
+                    widgets::Button::new("Restart from Checkpoint")
+                        .with_width(350f32)
+                        .spawn(center_column, &ui)
+                        .insert(RestartFromCheckpointButton);
+                    widgets::Button::new("Settings")
+                        .spawn(center_column, &ui)
+                        .insert(SettingsButton);
+                    widgets::Button::new("Quit to Main Menu")
+                        .with_width(350f32)
+                        .spawn(center_column, &ui)
+                        .insert(QuitToMainMenuButton);
+                    widgets::Button::new("Quit to Desktop")
+                        .with_width(350f32)
+                        .spawn(center_column, &ui)
+                        .insert(QuitToDesktopButton);
+                })
+                .insert(PauseMainColumn);
 
-    commands
-        .spawn()
-        .insert(PauseMenuObject)
-        .insert_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::Center,
-                ..Default::default()
-            },
-            color: Color::rgba(0f32, 0f32, 0f32, 0.75).into(),
-            ..Default::default()
-        })
-        .with_children(|window_root| {
-            window_root
-                .spawn()
-                .insert(PauseMenuObject)
-                .insert_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Percent(66.0), Val::Percent(100.0)),
-                        justify_content: JustifyContent::Center,
-                        // horizontally center child text
-                        flex_direction: FlexDirection::ColumnReverse,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..Default::default()
-                    },
-                    color: Color::NONE.into(),
-                    ..Default::default()
+            widgets::Panel::column()
+                .hidden()
+                .spawn(window_root, &ui, |settings_column| {
+                    widgets::Label::title("Settings").spawn(settings_column, &ui);
+                    widgets::Slider::new(
+                        "Horizontal Sensitivity",
+                        1f32,
+                        10f32,
+                        settings.horizontal_sensitivity() as f32,
+                    )
+                    .spawn(settings_column, &ui)
+                    .insert(HorizontalSensitivitySlider);
+                    widgets::Slider::new(
+                        "Vertical Sensitivity",
+                        1f32,
+                        10f32,
+                        settings.vertical_sensitivity() as f32,
+                    )
+                    .spawn(settings_column, &ui)
+                    .insert(VerticalSensitivitySlider);
+                    widgets::Slider::new(
+                        "Master Volume",
+                        0f32,
+                        100f32,
+                        settings.master_volume() as f32,
+                    )
+                    .with_step(5f32)
+                    .spawn(settings_column, &ui)
+                    .insert(MasterVolumeSlider);
+                    widgets::Slider::new(
+                        "Music Volume",
+                        0f32,
+                        100f32,
+                        settings.music_volume() as f32,
+                    )
+                    .with_step(5f32)
+                    .spawn(settings_column, &ui)
+                    .insert(MusicVolumeSlider);
+                    widgets::Slider::new(
+                        "Effects Volume",
+                        0f32,
+                        100f32,
+                        settings.sfx_volume() as f32,
+                    )
+                    .with_step(5f32)
+                    .spawn(settings_column, &ui)
+                    .insert(SfxVolumeSlider);
+                    widgets::Button::new("Back")
+                        .spawn(settings_column, &ui)
+                        .insert(BackButton);
                 })
-                .with_children(|center_column| {
-                    // Add title text
-                    center_column
-                        .spawn()
-                        .insert(PauseMenuObject)
-                        .insert(ResumeButton)
-                        .insert_bundle(TextBundle {
-                            text: Text::with_section(
-                                "Paused",
-                                TextStyle {
-                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                    font_size: 40.0,
-                                    color: Color::WHITE.into(),
-                                },
-                                Default::default(),
-                            ),
-                            style: Style {
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        });
+                .insert(SettingsColumn);
+        });
+}
 
-                    // Add resume game button
-                    center_column
-                        .spawn()
-                        .insert(PauseMenuObject)
-                        .insert(ResumeButton)
-                        .insert_bundle(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(200.0), Val::Px(65.0)),
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                // horizontally center child text
-                                justify_content: JustifyContent::Center,
-                                // vertically center child text
-                                align_items: AlignItems::Center,
-                                ..Default::default()
-                            },
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .with_children(|resume_game_button| {
-                            resume_game_button
-                                .spawn()
-                                .insert(PauseMenuObject)
-                                .insert_bundle(TextBundle {
-                                    text: Text::with_section(
-                                        "Resume",
-                                        TextStyle {
-                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                            font_size: 40.0,
-                                            color: Color::rgb(0.9, 0.9, 0.9),
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                });
-                        });
+/// Switch between the pause menu and its settings, and resume the game. Going back
+/// closes the settings if they're open, and resumes the game otherwise.
+#[allow(clippy::too_many_arguments)]
+fn navigate_pause_menu(
+    mut activated_events: EventReader<WidgetActivated>,
+    mut back_events: EventReader<MenuBack>,
+    resume_button_query: Query<Entity, With<ResumeButton>>,
+    settings_button_query: Query<Entity, With<SettingsButton>>,
+    back_button_query: Query<Entity, With<BackButton>>,
+    dialog_query: Query<Entity, With<ConfirmDialog>>,
+    mut main_column_query: Query<&mut Style, (With<PauseMainColumn>, Without<SettingsColumn>)>,
+    mut settings_column_query: Query<&mut Style, (With<SettingsColumn>, Without<PauseMainColumn>)>,
+    mut pause_reason: ResMut<PauseReason>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let mut resume_activated = false;
+    let mut settings_activated = false;
+    let mut back_activated = false;
+    for WidgetActivated(entity) in activated_events.iter() {
+        resume_activated |= resume_button_query.get(*entity).is_ok();
+        settings_activated |= settings_button_query.get(*entity).is_ok();
+        back_activated |= back_button_query.get(*entity).is_ok();
+    }
+    let went_back = back_events.iter().last().is_some();
+    // Going back while a dialog is open only closes the dialog
+    let went_back = went_back && dialog_query.iter().next().is_none();
 
-                    // Add settings button
-                    center_column
-                        .spawn()
-                        .insert(PauseMenuObject)
-                        .insert(SettingsButton)
-                        .insert_bundle(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(200.0), Val::Px(65.0)),
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                // horizontally center child text
-                                justify_content: JustifyContent::Center,
-                                // vertically center child text
-                                align_items: AlignItems::Center,
-                                ..Default::default()
-                            },
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .with_children(|settings_button| {
-                            settings_button
-                                .spawn()
-                                .insert(PauseMenuObject)
-                                .insert_bundle(TextBundle {
-                                    text: Text::with_section(
-                                        "Settings",
-                                        TextStyle {
-                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                            font_size: 40.0,
-                                            color: Color::rgb(0.9, 0.9, 0.9),
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                });
-                        });
+    let settings_open = settings_column_query
+        .iter()
+        .any(|style| style.display != Display::None);
+    let show_settings = if settings_activated {
+        true
+    } else if back_activated || (went_back && settings_open) {
+        false
+    } else {
+        // The player can't resume until they've got a way to control the game again
+        if (resume_activated || went_back) && !pause_reason.blocks_resume() {
+            resume_game(&mut pause_reason, &mut fp_control_settings, &mut game_level);
+        }
+        return;
+    };
+    for mut style in main_column_query.iter_mut() {
+        style.display = if show_settings {
+            Display::None
+        } else {
+            Display::Flex
+        };
+    }
+    for mut style in settings_column_query.iter_mut() {
+        style.display = if show_settings {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
 
-                    // Add quit button
-                    center_column
-                        .spawn()
-                        .insert(PauseMenuObject)
-                        .insert(QuitButton)
-                        .insert_bundle(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(200.0), Val::Px(65.0)),
-                                margin: Rect {
-                                    top: Val::Px(20.0),
-                                    bottom: Val::Px(20.0),
-                                    ..Default::default()
-                                },
-                                // horizontally center child text
-                                justify_content: JustifyContent::Center,
-                                // vertically center child text
-                                align_items: AlignItems::Center,
-                                ..Default::default()
-                            },
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .with_children(|settings_button| {
-                            settings_button
-                                .spawn()
-                                .insert(PauseMenuObject)
-                                .insert_bundle(TextBundle {
-                                    text: Text::with_section(
-                                        "Quit Game",
-                                        TextStyle {
-                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                            font_size: 40.0,
-                                            color: Color::rgb(0.9, 0.9, 0.9),
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                });
-                        });
-                });
-        });
+/// Resume the game and put the players back at the last checkpoint
+fn restart_from_checkpoint_on_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    restart_button_query: Query<Entity, With<RestartFromCheckpointButton>>,
+    mut restore_requests: EventWriter<RestoreSnapshotRequested>,
+    mut pause_reason: ResMut<PauseReason>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let restart_activated = activated_events
+        .iter()
+        .any(|WidgetActivated(entity)| restart_button_query.get(*entity).is_ok());
+    if restart_activated && !pause_reason.blocks_resume() {
+        restore_requests.send(RestoreSnapshotRequested);
+        resume_game(&mut pause_reason, &mut fp_control_settings, &mut game_level);
+    }
 }
 
-fn change_button_style_on_interaction(
-    mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
+fn apply_sensitivity_settings(
+    horizontal_query: Query<
+        &SliderValue,
+        (Changed<SliderValue>, With<HorizontalSensitivitySlider>),
     >,
+    vertical_query: Query<&SliderValue, (Changed<SliderValue>, With<VerticalSensitivitySlider>)>,
+    mut settings: ResMut<GameSettings>,
 ) {
-    for (interaction, mut color) in interaction_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                *color = Color::rgb(0.35, 0.75, 0.35).into();
-            }
-            Interaction::Hovered => {
-                *color = Color::rgb(0.25, 0.25, 0.25).into();
-            }
-            Interaction::None => {
-                *color = Color::rgb(0.15, 0.15, 0.15).into();
-            }
-        }
+    for slider_value in horizontal_query.iter() {
+        settings.set_horizontal_sensitivity(slider_value.value() as u8);
+    }
+    for slider_value in vertical_query.iter() {
+        settings.set_vertical_sensitivity(slider_value.value() as u8);
     }
 }
 
-fn enter_game_on_resume_game_clicked(
-    interaction_query: Query<&Interaction, With<ResumeButton>>,
-    mut game_level: ResMut<State<GameLevel>>,
+fn apply_volume_settings(
+    master_query: Query<&SliderValue, (Changed<SliderValue>, With<MasterVolumeSlider>)>,
+    music_query: Query<&SliderValue, (Changed<SliderValue>, With<MusicVolumeSlider>)>,
+    sfx_query: Query<&SliderValue, (Changed<SliderValue>, With<SfxVolumeSlider>)>,
+    mut settings: ResMut<GameSettings>,
 ) {
-    let interaction = interaction_query
-        .get_single()
-        .expect("Could not find a ResumeButton while setting it up to change GameLevel on click!");
-    match *interaction {
-        Interaction::Clicked => {
-            game_level
-                .pop()
-                .expect("Error occurred while popping GameLevel from the pause menu!");
-        }
-        _ => {}
+    for slider_value in master_query.iter() {
+        settings.set_master_volume(slider_value.value() as u8);
+    }
+    for slider_value in music_query.iter() {
+        settings.set_music_volume(slider_value.value() as u8);
+    }
+    for slider_value in sfx_query.iter() {
+        settings.set_sfx_volume(slider_value.value() as u8);
     }
 }
 
-fn quit_game_on_quit_game_clicked(
-    interaction_query: Query<&Interaction, With<QuitButton>>,
-    mut game_level: ResMut<State<GameLevel>>,
+fn update_pause_notice(
+    pause_reason: Res<PauseReason>,
+    mut notice_query: Query<&mut Text, With<PauseNotice>>,
 ) {
-    let interaction = interaction_query
-        .get_single()
-        .expect("Could not find a QuitButton while setting it up to quit the game on click!");
-    match *interaction {
-        Interaction::Clicked => {
-            game_level
-                .set(GameLevel::MainMenu)
-                .expect("Error occurred while setting GameLevel to main menu!");
+    if !pause_reason.is_changed() {
+        return;
+    }
+    for mut notice in notice_query.iter_mut() {
+        notice.sections[0].value = pause_reason.notice().unwrap_or_default();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn confirm_quit_on_quit_activated(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    unsaved_progress: Res<UnsavedProgress>,
+    mut activated_events: EventReader<WidgetActivated>,
+    quit_to_main_menu_query: Query<Entity, With<QuitToMainMenuButton>>,
+    quit_to_desktop_query: Query<Entity, With<QuitToDesktopButton>>,
+    dialog_query: Query<Entity, With<ConfirmDialog>>,
+) {
+    let mut action = None;
+    for WidgetActivated(entity) in activated_events.iter() {
+        if quit_to_main_menu_query.get(*entity).is_ok() {
+            action = Some(ConfirmAction::QuitToMainMenu);
+        } else if quit_to_desktop_query.get(*entity).is_ok() {
+            action = Some(ConfirmAction::QuitToDesktop);
         }
-        _ => {}
+    }
+    // Only one dialog can be open at a time
+    if dialog_query.iter().next().is_some() {
+        return;
+    }
+    if let Some(action) = action {
+        let ui = UiContext::new(&theme, &asset_server, PauseMenuObject);
+        spawn_confirm_dialog(&mut commands, &ui, &unsaved_progress, action);
     }
 }
 
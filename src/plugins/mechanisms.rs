@@ -0,0 +1,279 @@
+use crate::mechanisms::{
+    collect_keys, hide_collected_keys, move_doors, signal_doors, signal_on_action,
+    signal_platforms, tilt_levers, update_door_prompts, use_doors, use_switches, DoorState,
+    KeyItemState, KeyRing, LinkSignal, SwitchState,
+};
+use crate::save::RegisterSaveable;
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin runs the doors, switches and keys of [`GameLevel::Main`](crate::states::GameLevel),
+/// ie the ones spawned from its [`LevelScript`](crate::scripting::LevelScript).
+///
+/// Using a [`Switch`](crate::mechanisms::Switch), or an `open_door` or `close_door` trigger action, sends a
+/// [`LinkSignal`](crate::mechanisms::LinkSignal) to the doors and moving platforms with its tag. The state of
+/// doors, switches, keys and [`KeyRing`](crate::mechanisms::KeyRing)s is saved with the game.
+///
+/// Note: Mechanisms are used through the [`InteractionPlugin`](crate::plugins::InteractionPlugin), tell the
+/// player things through the [`ScriptingPlugin`](crate::plugins::ScriptingPlugin) and are saved by the
+/// [`SavePlugin`](crate::plugins::SavePlugin), so they must be added too.
+pub struct MechanismsPlugin;
+
+impl Plugin for MechanismsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LinkSignal>()
+            .register_saveable::<DoorState>()
+            .register_saveable::<SwitchState>()
+            .register_saveable::<KeyItemState>()
+            .register_saveable::<KeyRing>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(use_switches.label("send-link-signals").after("interact"))
+                    .with_system(
+                        signal_on_action
+                            .label("send-link-signals")
+                            .after("run-trigger-actions"),
+                    )
+                    .with_system(collect_keys.label("collect-keys").after("interact"))
+                    .with_system(
+                        use_doors
+                            .label("change-doors")
+                            .after("interact")
+                            .after("collect-keys"),
+                    )
+                    .with_system(
+                        signal_doors
+                            .label("change-doors")
+                            .after("send-link-signals"),
+                    )
+                    .with_system(signal_platforms.after("send-link-signals"))
+                    .with_system(move_doors.after("change-doors"))
+                    .with_system(update_door_prompts.after("change-doors"))
+                    .with_system(tilt_levers.after("send-link-signals"))
+                    .with_system(hide_collected_keys.after("collect-keys")),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonSubject, LevelObject};
+    use crate::interaction::{Interactable, Interacted};
+    use crate::mechanisms::KeyItem;
+    use crate::plugins::test_app::{headless_physics_app, send, unused_save_slots};
+    use crate::plugins::{SavePlugin, ScriptingPlugin};
+    use crate::resources::GameClock;
+    use crate::save::{RestoreSnapshotRequested, SaveId, SnapshotRequested};
+    use crate::scripting::{LevelScript, ScriptMessage};
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+
+    const LEVEL_SCRIPT: &str = r#"
+        [[doors]]
+        id = "gate"
+        kind = "sliding"
+        offset = [2.0, 0.0, 0.0]
+        position = [0.0, 1.0, 0.0]
+        half_extents = [1.0, 1.0, 0.1]
+        seconds = 0.0
+        interactable = false
+        tags = ["gate"]
+
+        [[doors]]
+        id = "vault"
+        kind = "hinged"
+        hinge = [-1.0, 0.0, 0.0]
+        position = [0.0, 1.0, 5.0]
+        half_extents = [1.0, 1.0, 0.1]
+        seconds = 0.0
+        key = "brass key"
+
+        [[switches]]
+        id = "gate_lever"
+        kind = "lever"
+        position = [3.0, 0.5, 0.0]
+        targets = ["gate"]
+
+        [[keys]]
+        id = "brass_key"
+        key = "brass key"
+        position = [0.0, 0.5, 3.0]
+    "#;
+
+    fn spawn_level_script(mut commands: Commands) {
+        LevelScript::from_toml(LEVEL_SCRIPT)
+            .unwrap()
+            .spawn(&mut commands);
+    }
+
+    fn setup_app() -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(Input::<KeyCode>::default())
+            .insert_resource(GameClock::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(SavePlugin)
+            .insert_resource(unused_save_slots("mechanisms"))
+            .add_plugin(ScriptingPlugin)
+            .add_event::<Interacted>()
+            .add_plugin(MechanismsPlugin)
+            .add_startup_system(spawn_level_script)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        let player = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(LevelObject)
+            .insert(SaveId::new("player_1"))
+            .insert(KeyRing::default())
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(10f32, 0f32, 10f32).into(),
+                ..Default::default()
+            })
+            .id();
+        app.update();
+        (app, player)
+    }
+
+    fn find(app: &mut App, id: &str) -> Entity {
+        app.world
+            .query::<(Entity, &SaveId)>()
+            .iter(&app.world)
+            .find(|(_, save_id)| save_id.as_str() == id)
+            .map(|(entity, _)| entity)
+            .unwrap()
+    }
+
+    fn interact(app: &mut App, subject: Entity, id: &str) {
+        let target = find(app, id);
+        send(app, Interacted { subject, target });
+        for _ in 0..3 {
+            app.update();
+        }
+    }
+
+    fn door_translation(app: &mut App, id: &str) -> Vec3 {
+        let door = find(app, id);
+        let translation = app
+            .world
+            .get::<RigidBodyPositionComponent>(door)
+            .unwrap()
+            .position
+            .translation;
+        Vec3::new(translation.x, translation.y, translation.z)
+    }
+
+    fn door_state(app: &mut App, id: &str) -> DoorState {
+        let door = find(app, id);
+        *app.world.get::<DoorState>(door).unwrap()
+    }
+
+    fn message(app: &App) -> Option<String> {
+        app.world
+            .get_resource::<ScriptMessage>()
+            .unwrap()
+            .text()
+            .map(String::from)
+    }
+
+    #[test]
+    fn test_lever_opens_and_closes_linked_door() {
+        let (mut app, player) = setup_app();
+        assert!((door_translation(&mut app, "gate") - Vec3::new(0f32, 1f32, 0f32)).length() < 1e-4);
+
+        interact(&mut app, player, "gate_lever");
+        let lever = find(&mut app, "gate_lever");
+        assert_eq!(
+            app.world.get::<SwitchState>(lever),
+            Some(&SwitchState { on: true })
+        );
+        assert!(door_state(&mut app, "gate").open);
+        assert!((door_translation(&mut app, "gate") - Vec3::new(2f32, 1f32, 0f32)).length() < 1e-4);
+
+        interact(&mut app, player, "gate_lever");
+        assert!(!door_state(&mut app, "gate").open);
+        assert!((door_translation(&mut app, "gate") - Vec3::new(0f32, 1f32, 0f32)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_locked_door_opens_with_its_key() {
+        let (mut app, player) = setup_app();
+        interact(&mut app, player, "vault");
+        assert_eq!(
+            door_state(&mut app, "vault"),
+            DoorState {
+                open: false,
+                locked: true,
+            }
+        );
+        assert_eq!(
+            message(&app).as_deref(),
+            Some("It's locked. It needs the brass key")
+        );
+
+        interact(&mut app, player, "brass_key");
+        assert!(app
+            .world
+            .get::<KeyRing>(player)
+            .unwrap()
+            .contains("brass key"));
+        let key = find(&mut app, "brass_key");
+        assert_eq!(
+            app.world.get::<KeyItemState>(key),
+            Some(&KeyItemState { collected: true })
+        );
+        assert!(!app.world.get::<Interactable>(key).unwrap().is_enabled());
+        assert_eq!(app.world.get::<KeyItem>(key).unwrap().key(), "brass key");
+
+        interact(&mut app, player, "vault");
+        assert_eq!(
+            door_state(&mut app, "vault"),
+            DoorState {
+                open: true,
+                locked: false,
+            }
+        );
+        assert!(
+            (door_translation(&mut app, "vault") - Vec3::new(-1f32, 1f32, 4f32)).length() < 1e-4
+        );
+        let vault = find(&mut app, "vault");
+        assert_eq!(
+            app.world.get::<Interactable>(vault).unwrap().action(),
+            "close the door"
+        );
+    }
+
+    #[test]
+    fn test_restoring_a_snapshot_brings_keys_back() {
+        let (mut app, player) = setup_app();
+        send(
+            &mut app,
+            SnapshotRequested {
+                respawn: Transform::default(),
+            },
+        );
+        app.update();
+        interact(&mut app, player, "brass_key");
+        assert!(app
+            .world
+            .get::<KeyRing>(player)
+            .unwrap()
+            .contains("brass key"));
+
+        send(&mut app, RestoreSnapshotRequested);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(!app
+            .world
+            .get::<KeyRing>(player)
+            .unwrap()
+            .contains("brass key"));
+        let key = find(&mut app, "brass_key");
+        assert_eq!(
+            app.world.get::<KeyItemState>(key),
+            Some(&KeyItemState { collected: false })
+        );
+        assert!(app.world.get::<Interactable>(key).unwrap().is_enabled());
+    }
+}
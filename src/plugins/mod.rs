@@ -1,4 +1,54 @@
+mod checkpoint;
+mod console;
 mod first_person_control;
+mod game_settings;
+mod health;
+#[cfg(feature = "dev-tools")]
+mod hot_reload;
+mod hud;
+mod interaction;
+mod inventory;
 pub mod levels;
+mod mechanisms;
+mod network;
+mod npc;
+mod pause_manager;
+mod perception;
+#[cfg(feature = "dev-tools")]
+mod physics_debug;
+mod replay;
+mod save;
+mod scripting;
+mod sound;
+mod split_screen;
+mod surface;
+#[cfg(test)]
+pub(crate) mod test_app;
+mod weapons;
+mod widget;
 
+pub use self::checkpoint::*;
+pub use self::console::*;
 pub use self::first_person_control::*;
+pub use self::game_settings::*;
+pub use self::health::*;
+#[cfg(feature = "dev-tools")]
+pub use self::hot_reload::*;
+pub use self::hud::*;
+pub use self::interaction::*;
+pub use self::inventory::*;
+pub use self::mechanisms::*;
+pub use self::network::*;
+pub use self::npc::*;
+pub use self::pause_manager::*;
+pub use self::perception::*;
+#[cfg(feature = "dev-tools")]
+pub use self::physics_debug::*;
+pub use self::replay::*;
+pub use self::save::*;
+pub use self::scripting::*;
+pub use self::sound::*;
+pub use self::split_screen::*;
+pub use self::surface::*;
+pub use self::weapons::*;
+pub use self::widget::*;
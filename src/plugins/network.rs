@@ -0,0 +1,83 @@
+use crate::network::{
+    add_remote_entity_meshes, add_server_player_bodies, apply_client_inputs, assign_network_ids,
+    attach_predicted_player, interpolate_remote_entities, move_remote_level_objects,
+    receive_client_messages, receive_server_messages, record_predicted_states, send_client_input,
+    send_hello_until_welcomed, send_snapshots,
+};
+use crate::systems::player::{move_player_body, rotate_player_body};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin runs the authoritative side of a networked game. It needs a
+/// [`NetworkServer`](crate::network::NetworkServer) resource.
+///
+/// Every tick it reads the clients' inputs, turns them into their players' intents under the
+/// "first-person-intents" label, and moves their dynamic bodies with the same systems as a local
+/// game. Snapshots of the transforms of every [`LevelObject`](crate::components::LevelObject) with a
+/// [`NetworkId`](crate::network::NetworkId) are sent in `PostUpdate`, after physics has moved everything
+/// for the tick. Level objects get their id from their [`SaveId`](crate::save::SaveId), so clients can
+/// find them in their own level.
+///
+/// Note: This plugin needs the [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin),
+/// the [`GameConfig`](crate::resources::GameConfig) and [`GameSettings`](crate::resources::GameSettings)
+/// resources, and [`SubjectDiagnostic`](crate::systems::SubjectDiagnostic) events. It doesn't need a
+/// window or a renderer, so it runs headless with `MinimalPlugins`.
+pub struct NetworkServerPlugin;
+
+impl Plugin for NetworkServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(receive_client_messages.label("receive-client-messages"))
+            .add_system(
+                apply_client_inputs
+                    .label("first-person-intents")
+                    .after("receive-client-messages"),
+            )
+            .add_system(assign_network_ids.after("receive-client-messages"))
+            .add_system(add_server_player_bodies)
+            .add_system(
+                rotate_player_body
+                    .label("player-body")
+                    .after("first-person-intents"),
+            )
+            .add_system(
+                move_player_body
+                    .label("player-body")
+                    .after("first-person-intents"),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, send_snapshots);
+    }
+}
+
+/// TL;DR: This plugin joins a networked game. It needs a [`NetworkClient`](crate::network::NetworkClient) resource.
+///
+/// Once the server welcomes the client, the [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// becomes the [`PredictedPlayer`](crate::network::PredictedPlayer): its intents are sent to the server
+/// and its body is moved by them locally straight away, then corrected when snapshots put it
+/// somewhere else. The other players, and the level objects of the client's level the server
+/// replicates, are [`RemoteEntity`](crate::network::RemoteEntity)s drawn between snapshots. Those
+/// level objects become kinematic bodies the server moves.
+///
+/// Note: The subject's body is moved by the first person systems of its level, so inputs are sent
+/// after the "first-person-intents" label.
+pub struct NetworkClientPlugin;
+
+impl Plugin for NetworkClientPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(send_hello_until_welcomed)
+            .add_system(assign_network_ids)
+            .add_system(receive_server_messages.label("receive-server-messages"))
+            .add_system(attach_predicted_player.after("receive-server-messages"))
+            .add_system(
+                send_client_input
+                    .after("receive-server-messages")
+                    .after("first-person-intents"),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, record_predicted_states)
+            .add_system(
+                interpolate_remote_entities
+                    .label("interpolate-remote-entities")
+                    .after("receive-server-messages"),
+            )
+            .add_system(move_remote_level_objects.after("interpolate-remote-entities"))
+            .add_system(add_remote_entity_meshes);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::npc::{
+    add_npc_bodies, bake_nav_grid, plan_npc_paths, resize_npc_capsules, steer_npc_bodies,
+    update_npc_behavior, NavGrid,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin gives the [`Npc`](crate::npc::Npc)s in [`GameLevel::Main`](crate::states::GameLevel)
+/// a body, and has them patrol and chase players over the level's [`NavGrid`](crate::npc::NavGrid).
+///
+/// The grid is baked again whenever static colliders are added, ie when the level is set up, or the
+/// config changes. How NPCs are built is set in the [`NpcConfig`](crate::resources::NpcConfig), and
+/// they're rebuilt when it changes.
+///
+/// Note: NPCs are rigid bodies steered by Rapier, so
+/// [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too. They find
+/// players with their [`Senses`](crate::perception::Senses), so the
+/// [`PerceptionPlugin`](crate::plugins::PerceptionPlugin) must be added as well. They're only
+/// frictionless with the [`SurfacePlugin`](crate::plugins::SurfacePlugin).
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>().add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(add_npc_bodies)
+                .with_system(resize_npc_capsules)
+                .with_system(bake_nav_grid.label("bake-nav-grid"))
+                .with_system(update_npc_behavior.label("npc-behavior").after("perceive"))
+                .with_system(
+                    plan_npc_paths
+                        .label("plan-npc-paths")
+                        .after("npc-behavior")
+                        .after("bake-nav-grid"),
+                )
+                .with_system(steer_npc_bodies.after("plan-npc-paths")),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::FirstPersonSubject;
+    use crate::npc::{Npc, NpcBehavior, NpcState};
+    use crate::perception::{NoiseEvent, NoiseKind};
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::plugins::{PerceptionPlugin, SurfacePlugin};
+    use crate::resources::{GameClock, GameConfig};
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+
+    /// A 24m square of ground, with a 2m high wall across the middle of it from `wall_from` to
+    /// `wall_to` on the X axis
+    fn setup_app(
+        wall_from: f32,
+        wall_to: f32,
+        npc_position: Vec3,
+        patrol: Vec<Vec3>,
+    ) -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameConfig::default())
+            .insert_resource(GameClock::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(SurfacePlugin)
+            .add_plugin(PerceptionPlugin)
+            .add_plugin(NpcPlugin)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        app.world.spawn().insert_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(12.0, 0.1, 12.0).into(),
+            ..Default::default()
+        });
+        app.world.spawn().insert_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid((wall_to - wall_from) / 2f32, 1.0, 0.3).into(),
+            position: Vec3::new((wall_from + wall_to) / 2f32, 1.0, 0.0).into(),
+            ..Default::default()
+        });
+        let npc = app
+            .world
+            .spawn()
+            .insert(Npc)
+            .insert(NpcBehavior::new(patrol))
+            .insert(Transform::from_translation(npc_position))
+            .insert(GlobalTransform::default())
+            .id();
+        (app, npc)
+    }
+
+    fn npc_position(app: &App, npc: Entity) -> Vec3 {
+        let translation = app
+            .world
+            .get::<RigidBodyPositionComponent>(npc)
+            .unwrap()
+            .position
+            .translation
+            .vector;
+        Vec3::new(translation.x, translation.y, translation.z)
+    }
+
+    #[test]
+    fn test_npc_patrols_around_a_wall() {
+        // The wall leaves a gap between x = 4 and the edge of the ground
+        let waypoint = Vec3::new(-3.0, 1.15, 3.0);
+        let (mut app, npc) = setup_app(-12.0, 4.0, Vec3::new(-3.0, 1.15, -3.0), vec![waypoint]);
+        let mut arrived = false;
+        for _ in 0..1200 {
+            app.update();
+            if app.world.get::<RigidBodyPositionComponent>(npc).is_none() {
+                continue;
+            }
+            let position = npc_position(&app, npc);
+            // It only crosses the wall's line through the gap
+            if position.z.abs() < 0.5 {
+                assert!(position.x > 3.5, "Walked through the wall at {}", position);
+            }
+            if Vec2::new(position.x - waypoint.x, position.z - waypoint.z).length() < 0.5 {
+                arrived = true;
+                break;
+            }
+        }
+        assert!(arrived, "Never got to {}", waypoint);
+        assert_eq!(
+            app.world.get::<NpcBehavior>(npc).unwrap().state(),
+            NpcState::Patrol
+        );
+    }
+
+    fn spawn_player(app: &mut App, position: Vec3) -> Entity {
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::Static.into(),
+                position: position.into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            })
+            .id()
+    }
+
+    #[test]
+    fn test_npc_chases_a_player_it_sees() {
+        // NPCs face -Z until they've moved
+        let start = Vec3::new(0.0, 1.15, 6.0);
+        let (mut app, npc) = setup_app(-12.0, -8.0, start, Vec::new());
+        let player = spawn_player(&mut app, Vec3::new(0.0, 1.0, -6.0));
+        for _ in 0..120 {
+            app.update();
+        }
+        match app.world.get::<NpcBehavior>(npc).unwrap().state() {
+            NpcState::Chase { target, .. } => assert_eq!(target, player),
+            state => panic!("Expected a chase, got {:?}", state),
+        }
+        assert!(npc_position(&app, npc).z < start.z - 1f32);
+    }
+
+    #[test]
+    fn test_npc_cant_see_through_walls() {
+        let start = Vec3::new(0.0, 1.15, 6.0);
+        let (mut app, npc) = setup_app(-12.0, 12.0, start, Vec::new());
+        spawn_player(&mut app, Vec3::new(0.0, 1.0, -6.0));
+        for _ in 0..120 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.get::<NpcBehavior>(npc).unwrap().state(),
+            NpcState::Idle
+        );
+        assert!(npc_position(&app, npc).distance(start) < 0.1);
+    }
+
+    #[test]
+    fn test_npc_cant_see_behind_it() {
+        let start = Vec3::new(0.0, 1.15, -6.0);
+        let (mut app, npc) = setup_app(-12.0, -8.0, start, Vec::new());
+        spawn_player(&mut app, Vec3::new(0.0, 1.0, 6.0));
+        for _ in 0..120 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.get::<NpcBehavior>(npc).unwrap().state(),
+            NpcState::Idle
+        );
+    }
+
+    #[test]
+    fn test_npc_goes_to_a_noise_behind_it() {
+        let start = Vec3::new(0.0, 1.15, -6.0);
+        let (mut app, npc) = setup_app(-12.0, -8.0, start, Vec::new());
+        // Off to the side, where it won't come into view
+        let player = spawn_player(&mut app, Vec3::new(-10.0, 1.0, -3.0));
+        for _ in 0..10 {
+            app.update();
+        }
+        app.world
+            .get_resource_mut::<Events<NoiseEvent>>()
+            .unwrap()
+            .send(NoiseEvent {
+                source: player,
+                position: Vec3::new(0.0, 1.0, 2.0),
+                kind: NoiseKind::Landing,
+                loudness: 12f32,
+                ground: None,
+            });
+        let mut chased = false;
+        for _ in 0..600 {
+            app.update();
+            if let NpcState::Chase { target, .. } =
+                app.world.get::<NpcBehavior>(npc).unwrap().state()
+            {
+                assert_eq!(target, player);
+                chased = true;
+            }
+        }
+        assert!(chased, "Never went to the noise");
+        // It got there, found nobody and forgot about them
+        assert!(npc_position(&app, npc).z > 0f32);
+        assert_eq!(
+            app.world.get::<NpcBehavior>(npc).unwrap().state(),
+            NpcState::Idle
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::resources::PauseReason;
+use crate::states::GameLevel;
+use crate::systems::pausing::{
+    allow_resume_on_input_restored, pause_game_on_focus_lost, pause_game_on_gamepad_disconnected,
+};
+use bevy::prelude::*;
+
+/// This plugin pauses the game automatically when the player can't be playing it, ie when
+/// the window loses focus or a gamepad disconnects during [`GameLevel::Main`](crate::states::GameLevel).
+///
+/// The reason for the most recent pause is stored in the [`PauseReason`](crate::resources::PauseReason)
+/// resource so the pause menu can show it.
+pub struct PauseManagerPlugin;
+
+impl Plugin for PauseManagerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PauseReason::default())
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(pause_game_on_focus_lost)
+                    .with_system(pause_game_on_gamepad_disconnected),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameLevel::PauseMenu)
+                    .with_system(allow_resume_on_input_restored),
+            );
+    }
+}
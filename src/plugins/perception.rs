@@ -0,0 +1,110 @@
+use crate::perception::{emit_player_noises, update_perception, NoiseEvent};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin lets agents with [`Senses`](crate::perception::Senses) see and hear the
+/// players in [`GameLevel::Main`](crate::states::GameLevel).
+///
+/// Players with a [`NoiseMaker`](crate::perception::NoiseMaker) send a
+/// [`NoiseEvent`](crate::perception::NoiseEvent) as they walk, jump and land. Every agent's
+/// [`Perception`](crate::perception::Perception) and [`PerceptionMemory`](crate::perception::PerceptionMemory)
+/// are updated after that, under the "perceive" label. How far and how wide NPCs see, and how long
+/// they remember, is set in the [`NpcConfig`](crate::resources::NpcConfig).
+///
+/// Note: Sight and hearing are Rapier ray casts, so
+/// [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too.
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>().add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(
+                    emit_player_noises
+                        .label("emit-noises")
+                        .after("move-player-body"),
+                )
+                .with_system(update_perception.label("perceive").after("emit-noises")),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perception::{NoiseKind, Perception, PerceptionMemory, Senses};
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::{GameClock, GameConfig};
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+    use std::time::Duration;
+
+    /// An agent at the origin looking down -Z, with a wall 3m in front of it if `wall` is set
+    fn setup_app(wall: bool) -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameConfig::default())
+            .insert_resource(GameClock::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(PerceptionPlugin)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        if wall {
+            app.world.spawn().insert_bundle(ColliderBundle {
+                shape: ColliderShape::cuboid(5.0, 2.0, 0.2).into(),
+                position: Vec3::new(0.0, 0.0, -3.0).into(),
+                ..Default::default()
+            });
+        }
+        let agent = app
+            .world
+            .spawn()
+            .insert_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::Static.into(),
+                ..Default::default()
+            })
+            .insert(Senses::new(
+                90f32.to_radians(),
+                10f32,
+                0f32,
+                Duration::from_secs(5),
+            ))
+            .insert(Perception::default())
+            .insert(PerceptionMemory::default())
+            .id();
+        (app, agent)
+    }
+
+    /// Make a noise 6m in front of the agent, and return how loud it heard it
+    fn hear(app: &mut App, agent: Entity, loudness: f32) -> Option<f32> {
+        let source = app.world.spawn().id();
+        app.update();
+        app.world
+            .get_resource_mut::<Events<NoiseEvent>>()
+            .unwrap()
+            .send(NoiseEvent {
+                source,
+                position: Vec3::new(0.0, 0.0, -6.0),
+                kind: NoiseKind::Footstep,
+                loudness,
+                ground: None,
+            });
+        app.update();
+        let heard = app.world.get::<Perception>(agent).unwrap().heard()?;
+        assert_eq!(heard.source, source);
+        let remembered = app.world.get::<PerceptionMemory>(agent).unwrap();
+        assert_eq!(remembered.last_known().unwrap().entity, source);
+        Some(heard.volume)
+    }
+
+    #[test]
+    fn test_walls_muffle_noises() {
+        let (mut app, agent) = setup_app(false);
+        let open_volume = hear(&mut app, agent, 10f32).unwrap();
+        assert!((open_volume - 0.4).abs() < 1e-4);
+        assert_eq!(hear(&mut app, agent, 5f32), None);
+
+        let (mut app, agent) = setup_app(true);
+        assert_eq!(hear(&mut app, agent, 10f32), None);
+        let muffled_volume = hear(&mut app, agent, 20f32).unwrap();
+        assert!((muffled_volume - 0.4).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::debug::{
+    collect_physics_debug_lines, spawn_physics_debug_meshes, toggle_physics_debug_overlay,
+    update_physics_debug_meshes, PhysicsDebugLines, PhysicsDebugOverlay,
+};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin draws the physics debug overlay, while the
+/// [`PhysicsDebugOverlay`](crate::debug::PhysicsDebugOverlay) resource is enabled.
+///
+/// The lines are gathered into the [`PhysicsDebugLines`](crate::debug::PhysicsDebugLines) resource
+/// every frame, and drawn as unlit line meshes if there's a renderer. F3 turns the overlay on and off.
+///
+/// Note: The overlay draws Rapier's colliders, bodies and contacts, so
+/// [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too. It's only
+/// built with the `dev-tools` feature.
+pub struct PhysicsDebugPlugin;
+
+impl Plugin for PhysicsDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsDebugOverlay>()
+            .init_resource::<PhysicsDebugLines>()
+            .add_startup_system(spawn_physics_debug_meshes)
+            .add_system(toggle_physics_debug_overlay.before("collect-physics-debug-lines"))
+            .add_system(collect_physics_debug_lines.label("collect-physics-debug-lines"))
+            .add_system(update_physics_debug_meshes.after("collect-physics-debug-lines"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::FirstPersonSubject;
+    use crate::debug::DebugLayer;
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::GameConfig;
+    use bevy_rapier3d::prelude::*;
+
+    /// The ground, a sensor, a ball rolling along it and a player standing on it, without gravity
+    fn setup_app() -> App {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameConfig::default())
+            .add_plugin(PhysicsDebugPlugin);
+        app.world.spawn().insert_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(20.0, 0.1, 20.0).into(),
+            position: Vec3::new(0.0, -0.1, 0.0).into(),
+            ..Default::default()
+        });
+        app.world.spawn().insert_bundle(ColliderBundle {
+            collider_type: ColliderType::Sensor.into(),
+            shape: ColliderShape::cuboid(1.0, 1.0, 1.0).into(),
+            position: Vec3::new(5.0, 1.0, 0.0).into(),
+            ..Default::default()
+        });
+        app.world
+            .spawn()
+            .insert_bundle(RigidBodyBundle {
+                // Sunk into the ground a little, so they're touching
+                position: Vec3::new(-5.0, 0.45, 0.0).into(),
+                velocity: RigidBodyVelocity {
+                    linvel: Vector::new(2.0, 0.0, 0.0),
+                    angvel: Vector::zeros(),
+                }
+                .into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            })
+            .insert(RigidBodyPositionSync::Discrete);
+        // Standing just on the ground, with the default 8m capsule
+        let half_height = GameConfig::default().player().capsule_height() / 2f32;
+        app.world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(GlobalTransform::from_xyz(0.0, half_height + 0.015, 5.0));
+        app
+    }
+
+    fn segment_count(app: &App, layer: DebugLayer) -> usize {
+        app.world
+            .get_resource::<PhysicsDebugLines>()
+            .unwrap()
+            .layer(layer)
+            .map_or(0, |lines| lines.segments().len())
+    }
+
+    #[test]
+    fn test_overlay_draws_the_physics_world() {
+        let mut app = setup_app();
+        app.update();
+        app.update();
+        for layer in DebugLayer::ALL {
+            assert_eq!(segment_count(&app, layer), 0);
+        }
+
+        app.world
+            .get_resource_mut::<PhysicsDebugOverlay>()
+            .unwrap()
+            .enabled = true;
+        app.update();
+        // The ground and the ball's wireframes, and the sensor's in its own color
+        assert!(segment_count(&app, DebugLayer::Colliders) > 12);
+        assert_eq!(segment_count(&app, DebugLayer::Sensors), 12);
+        // A cross and the ray itself
+        assert_eq!(segment_count(&app, DebugLayer::GroundProbeHit), 4);
+        assert_eq!(segment_count(&app, DebugLayer::GroundProbeMiss), 0);
+        // The rolling ball touches the ground, and it's the only dynamic body
+        assert!(segment_count(&app, DebugLayer::Contacts) > 0);
+        assert_eq!(segment_count(&app, DebugLayer::Velocities), 1);
+
+        app.world
+            .get_resource_mut::<PhysicsDebugOverlay>()
+            .unwrap()
+            .enabled = false;
+        app.update();
+        assert_eq!(segment_count(&app, DebugLayer::Colliders), 0);
+    }
+}
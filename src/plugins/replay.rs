@@ -0,0 +1,273 @@
+use crate::replay::{
+    apply_replay_intents, capture_player_actions, inject_replay_actions, record_player_intents,
+    report_replay_result, resume_replay_after_pause, run_extra_physics_steps, save_recording,
+    start_recording, start_replay, step_physics_while_recording, PhysicsSteps,
+};
+use crate::states::GameLevel;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin records every run of [`GameLevel::Main`](crate::states::GameLevel). It needs an
+/// [`InputRecorder`](crate::replay::InputRecorder) resource.
+///
+/// Every frame it records the [`Movement`](crate::components::Movement) and [`Lookaround`](crate::components::Lookaround)
+/// intents of every [`FirstPersonSubject`](crate::components::FirstPersonSubject), and whether the players
+/// pressed jump or pause. Physics steps on a fixed timestep, as many times a frame as the
+/// [`GameClock`](crate::resources::GameClock) says, see [`PhysicsSteps`](crate::replay::PhysicsSteps),
+/// and that count is recorded too. The recording is saved when the level exits.
+pub struct InputRecorderPlugin;
+
+impl Plugin for InputRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsSteps>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                capture_player_actions.after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::Update,
+                run_extra_physics_steps.exclusive_system().at_end(),
+            )
+            .add_system_set(SystemSet::on_enter(GameLevel::Main).with_system(start_recording))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(step_physics_while_recording.label("step-recorded-physics"))
+                    .with_system(
+                        record_player_intents
+                            .after("first-person-intents")
+                            .after("step-recorded-physics"),
+                    ),
+            )
+            .add_system_set(SystemSet::on_exit(GameLevel::Main).with_system(save_recording));
+    }
+}
+
+/// TL;DR: This plugin plays a recording back the next time [`GameLevel::Main`](crate::states::GameLevel)
+/// starts. It needs a [`ReplayPlayback`](crate::replay::ReplayPlayback) resource.
+///
+/// The recorded intents replace the ones read from the players' devices, the recorded jumps and
+/// pauses replace the players' key presses, and physics takes the recorded number of steps. When the replay ends, the players are handed control
+/// back and the result is logged, see [`ReplayPlayback::divergence`](crate::replay::ReplayPlayback::divergence).
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsSteps>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                inject_replay_actions.after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::Update,
+                run_extra_physics_steps.exclusive_system().at_end(),
+            )
+            .add_system_set(SystemSet::on_enter(GameLevel::Main).with_system(start_replay))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main).with_system(
+                    apply_replay_intents
+                        .after("first-person-intents")
+                        .before("player-body"),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameLevel::PauseMenu).with_system(resume_replay_after_pause),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, report_replay_result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonSubject, Lookaround, Movement};
+    use crate::plugins::test_app::{headless_physics_app, send_key};
+    use crate::replay::{InputRecorder, Recording, ReplayPlayback};
+    use crate::resources::{GameClock, GameConfig, GameRng, GameSettings, PauseReason};
+    use crate::states::FirstPersonControlSettings;
+    use crate::systems::player::{jump_player_body, move_player_body, rotate_player_body};
+    use crate::systems::{first_person_lookaround, first_person_movement, SubjectDiagnostic};
+    use bevy::input::{ElementState, InputPlugin};
+    use bevy_rapier3d::na::Point3;
+    use bevy_rapier3d::prelude::*;
+    use std::time::Duration;
+
+    // Slower than the physics timestep, so some frames step physics twice
+    fn advance_game_clock_fixed(mut game_clock: ResMut<GameClock>) {
+        game_clock.advance(Duration::from_secs_f32(1f32 / 45f32));
+    }
+
+    fn spawn_level(mut commands: Commands, game_config: Res<GameConfig>) {
+        commands.spawn_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(100.0, 0.1, 100.0).into(),
+            ..Default::default()
+        });
+        let player_config = game_config.player();
+        let halfheight =
+            (player_config.capsule_height() - 2f32 * player_config.capsule_radius()) / 2f32;
+        commands
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Movement::default())
+            .insert(Lookaround::default())
+            .insert(Transform::default())
+            .insert(GlobalTransform::default())
+            .insert(RigidBodyPositionSync::Discrete)
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::capsule(
+                    Point3::new(0f32, -halfheight, 0f32),
+                    Point3::new(0f32, halfheight, 0f32),
+                    player_config.capsule_radius(),
+                )
+                .into(),
+                ..Default::default()
+            })
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0f32, player_config.capsule_height() / 2f32 + 0.2, 0f32).into(),
+                mass_properties: (RigidBodyMassPropsFlags::ROTATION_LOCKED_X
+                    | RigidBodyMassPropsFlags::ROTATION_LOCKED_Z)
+                    .into(),
+                ..Default::default()
+            });
+    }
+
+    // Wire the player up the same way MainGameLevel and FirstPersonControlPlugin do, without a window
+    fn setup_app() -> App {
+        let mut app = headless_physics_app();
+        app.add_plugin(InputPlugin)
+            .insert_resource(GameConfig::default())
+            .insert_resource(GameSettings::default())
+            .insert_resource(GameRng::from_seed(1))
+            .insert_resource(PauseReason::default())
+            .insert_resource(GameClock::default())
+            .add_system_to_stage(CoreStage::PreUpdate, advance_game_clock_fixed)
+            .add_event::<SubjectDiagnostic>()
+            .add_state(FirstPersonControlSettings::Enabled)
+            .add_state(GameLevel::Main)
+            .add_startup_system(spawn_level)
+            .add_system(first_person_movement.label("first-person-intents"))
+            .add_system(first_person_lookaround.label("first-person-intents"))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(
+                        rotate_player_body
+                            .label("player-body")
+                            .after("first-person-intents"),
+                    )
+                    .with_system(
+                        move_player_body
+                            .label("move-player-body")
+                            .label("player-body")
+                            .after("first-person-intents"),
+                    )
+                    .with_system(
+                        jump_player_body
+                            .label("player-body")
+                            .after("move-player-body"),
+                    ),
+            );
+        // The player lands after jumping
+        app.world
+            .get_resource_mut::<RapierConfiguration>()
+            .unwrap()
+            .gravity = Vector::y() * -9.81;
+        app
+    }
+
+    fn subject_translation(app: &mut App) -> Vec3 {
+        app.world
+            .query_filtered::<&Transform, With<FirstPersonSubject>>()
+            .iter(&app.world)
+            .next()
+            .unwrap()
+            .translation
+    }
+
+    fn record_run() -> Recording {
+        let mut app = setup_app();
+        app.insert_resource(InputRecorder::in_memory())
+            .add_plugin(InputRecorderPlugin);
+        // Settle, walk forward, jump while walking, then stop
+        for _ in 0..10 {
+            app.update();
+        }
+        send_key(&mut app, KeyCode::W, ElementState::Pressed);
+        for frame in 0..60 {
+            if frame == 20 {
+                send_key(&mut app, KeyCode::Space, ElementState::Pressed);
+            }
+            if frame == 21 {
+                send_key(&mut app, KeyCode::Space, ElementState::Released);
+            }
+            app.update();
+        }
+        send_key(&mut app, KeyCode::W, ElementState::Released);
+        for _ in 0..30 {
+            app.update();
+        }
+        // Leaving the level saves the recording
+        app.world
+            .get_resource_mut::<State<GameLevel>>()
+            .unwrap()
+            .set(GameLevel::MainMenu)
+            .unwrap();
+        app.update();
+
+        let recording = app
+            .world
+            .get_resource::<InputRecorder>()
+            .unwrap()
+            .recording()
+            .cloned()
+            .expect("Nothing was recorded!");
+        // The run started over from a fresh seed, not the one the app started with
+        assert_ne!(recording.seed, 1);
+        assert_eq!(
+            app.world.get_resource::<GameRng>().unwrap().seed(),
+            recording.seed
+        );
+        assert!(!recording.frames.is_empty());
+        assert!(recording.frames.iter().any(|frame| frame.steps == 2));
+        assert_eq!(recording.final_positions.len(), 1);
+        recording
+    }
+
+    #[test]
+    fn test_replay_reproduces_final_position() {
+        let recording = record_run();
+        let start = Vec3::new(
+            0f32,
+            GameConfig::default().player().capsule_height() / 2f32,
+            0f32,
+        );
+        // The player walked forward (-Z)
+        assert!(recording.final_positions[0].z < start.z - 1f32);
+
+        // Replay from the file format, so it's covered too
+        let recording = Recording::decode(&recording.encode()).unwrap();
+        let final_position = recording.final_positions[0];
+        let mut app = setup_app();
+        app.insert_resource(ReplayPlayback::new(recording))
+            .add_plugin(ReplayPlugin);
+        for _ in 0..200 {
+            app.update();
+            if app
+                .world
+                .get_resource::<ReplayPlayback>()
+                .unwrap()
+                .divergence()
+                .is_some()
+            {
+                break;
+            }
+        }
+
+        let divergence = app
+            .world
+            .get_resource::<ReplayPlayback>()
+            .unwrap()
+            .divergence()
+            .expect("The replay never finished!");
+        assert!(divergence < 0.0001);
+        assert!((subject_translation(&mut app) - final_position).length() < 0.0001);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::save::{
+    apply_loaded_players, begin_save, clear_snapshot, finish_load,
+    quick_save_or_load_on_key_pressed, read_requested_save, restore_snapshot, write_save,
+    LoadGameRequested, RestoreSnapshotRequested, SaveGameRequested, SaveManager, SaveSlots,
+    SnapshotRequested,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin saves and loads the game. It adds a default [`SaveSlots`](crate::save::SaveSlots)
+/// resource, which keeps saves in the `saves` directory.
+///
+/// A save is made by sending a [`SaveGameRequested`](crate::save::SaveGameRequested) event, and loaded by
+/// sending a [`LoadGameRequested`](crate::save::LoadGameRequested) event, which starts the saved level
+/// first if it isn't running. During [`GameLevel::Main`](crate::states::GameLevel), F5 quick-saves and F9
+/// quick-loads.
+///
+/// A [`SnapshotRequested`](crate::save::SnapshotRequested) event keeps a save in memory instead, until
+/// the level exits, and a [`RestoreSnapshotRequested`](crate::save::RestoreSnapshotRequested) event loads it.
+///
+/// Note: Components on level objects are only saved if they're registered with
+/// [`RegisterSaveable::register_saveable`](crate::save::RegisterSaveable::register_saveable).
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveSlots::default())
+            .insert_resource(SaveManager::default())
+            .add_event::<SaveGameRequested>()
+            .add_event::<LoadGameRequested>()
+            .add_event::<SnapshotRequested>()
+            .add_event::<RestoreSnapshotRequested>()
+            .add_system(read_requested_save.label("read-save"))
+            .add_system(restore_snapshot.label("read-save"))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(
+                        quick_save_or_load_on_key_pressed
+                            .label("quick-save-or-load")
+                            .before("read-save"),
+                    )
+                    .with_system(begin_save.label("begin-save").after("quick-save-or-load"))
+                    .with_system(
+                        apply_loaded_players
+                            .label("apply-loaded-players")
+                            .after("read-save"),
+                    ),
+            )
+            .add_system_set(SystemSet::on_exit(GameLevel::Main).with_system(clear_snapshot))
+            .add_system(write_save.label("write-save").after("begin-save"))
+            .add_system(
+                finish_load
+                    .label("finish-load")
+                    .after("apply-loaded-players"),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonHead, FirstPersonSubject, LevelObject};
+    use crate::plugins::test_app::send;
+    use crate::save::{RegisterSaveable, SaveId, Saveable};
+    use bevy_rapier3d::na::Vector3;
+    use bevy_rapier3d::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct SwitchState {
+        on: bool,
+    }
+
+    impl Saveable for SwitchState {
+        const SAVE_KEY: &'static str = "switch_state";
+    }
+
+    fn setup_app(directory: &str) -> (App, Entity, Entity, Entity) {
+        let directory = std::env::temp_dir().join(format!(
+            "bevy-fp-template-{}-{}",
+            directory,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Input::<KeyCode>::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(SavePlugin)
+            .insert_resource(SaveSlots::new(directory))
+            .register_saveable::<SwitchState>();
+        let player = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(1f32, 2f32, 3f32).into(),
+                ..Default::default()
+            })
+            .id();
+        let head = app
+            .world
+            .spawn()
+            .insert(FirstPersonHead)
+            .insert(Transform::from_rotation(Quat::from_rotation_x(-0.5)))
+            .insert(Parent(player))
+            .id();
+        let switch = app
+            .world
+            .spawn()
+            .insert(LevelObject)
+            .insert(SaveId::new("switch"))
+            .insert(SwitchState { on: true })
+            .id();
+        (app, player, head, switch)
+    }
+
+    #[test]
+    fn test_save_then_load() {
+        let (mut app, player, head, switch) = setup_app("save-then-load");
+        send(
+            &mut app,
+            SaveGameRequested {
+                slot: String::from("slot"),
+            },
+        );
+        app.update();
+        let saves = app.world.get_resource::<SaveSlots>().unwrap().list();
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].level, "main");
+
+        // Change everything that was saved
+        app.world
+            .get_mut::<RigidBodyPositionComponent>(player)
+            .unwrap()
+            .position = Vec3::new(9f32, 9f32, 9f32).into();
+        app.world
+            .get_mut::<RigidBodyVelocityComponent>(player)
+            .unwrap()
+            .linvel = Vector3::new(0f32, 5f32, 0f32);
+        app.world.get_mut::<Transform>(head).unwrap().rotation = Quat::IDENTITY;
+        app.world.get_mut::<SwitchState>(switch).unwrap().on = false;
+
+        send(
+            &mut app,
+            LoadGameRequested {
+                slot: String::from("slot"),
+            },
+        );
+        app.update();
+
+        let position = app
+            .world
+            .get::<RigidBodyPositionComponent>(player)
+            .unwrap()
+            .position
+            .translation
+            .vector;
+        assert!((position - Vector3::new(1f32, 2f32, 3f32)).magnitude() < 0.0001);
+        let velocity = app
+            .world
+            .get::<RigidBodyVelocityComponent>(player)
+            .unwrap()
+            .linvel;
+        assert!(velocity.magnitude() < 0.0001);
+        let (head_pitch, _, _) = app
+            .world
+            .get::<Transform>(head)
+            .unwrap()
+            .rotation
+            .to_euler(EulerRot::XYZ);
+        assert!((head_pitch + 0.5).abs() < 0.0001);
+        assert_eq!(
+            app.world.get::<SwitchState>(switch),
+            Some(&SwitchState { on: true })
+        );
+        // The load is finished, so nothing is applied again
+        assert!(app
+            .world
+            .get_resource::<SaveManager>()
+            .unwrap()
+            .loaded_save()
+            .is_none());
+    }
+}
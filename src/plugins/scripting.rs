@@ -0,0 +1,261 @@
+use crate::scripting::{
+    add_script_shape_meshes, change_level_on_action, detect_trigger_occupants,
+    enable_platforms_on_action, move_platforms, play_sounds_on_action, reset_script_state,
+    run_interaction_actions, run_trigger_actions, set_flags_on_action, show_messages_on_action,
+    spawn_entities_on_action, tick_script_message, toggle_lights_on_action, ActionTriggered,
+    ScriptFlags, ScriptMessage, TriggerEntered, TriggerExited, TriggerStayed,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+
+/// TL;DR: This plugin runs the triggers and moving platforms of [`GameLevel::Main`](crate::states::GameLevel),
+/// ie the ones spawned from its [`LevelScript`](crate::scripting::LevelScript).
+///
+/// Every [`TriggerVolume`](crate::scripting::TriggerVolume) sends [`TriggerEntered`](crate::scripting::TriggerEntered),
+/// [`TriggerStayed`](crate::scripting::TriggerStayed) and [`TriggerExited`](crate::scripting::TriggerExited) events,
+/// and the ones with [`TriggerActions`](crate::scripting::TriggerActions) run them. The flags and message set by
+/// actions are kept in the [`ScriptFlags`](crate::scripting::ScriptFlags) and [`ScriptMessage`](crate::scripting::ScriptMessage)
+/// resources until the level exits. Scripted interactables run their
+/// [`InteractionActions`](crate::scripting::InteractionActions) when they're used.
+///
+/// Note: Triggers are sensors, so [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too,
+/// and interactables are used through the [`InteractionPlugin`](crate::plugins::InteractionPlugin)'s
+/// [`Interacted`](crate::interaction::Interacted) events.
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScriptFlags::default())
+            .insert_resource(ScriptMessage::default())
+            .add_event::<TriggerEntered>()
+            .add_event::<TriggerStayed>()
+            .add_event::<TriggerExited>()
+            .add_event::<ActionTriggered>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(detect_trigger_occupants.label("detect-triggers"))
+                    .with_system(
+                        run_trigger_actions
+                            .label("run-trigger-actions")
+                            .after("detect-triggers"),
+                    )
+                    .with_system(
+                        run_interaction_actions
+                            .label("run-trigger-actions")
+                            .after("interact"),
+                    )
+                    .with_system(spawn_entities_on_action.after("run-trigger-actions"))
+                    .with_system(play_sounds_on_action.after("run-trigger-actions"))
+                    .with_system(change_level_on_action.after("run-trigger-actions"))
+                    .with_system(
+                        enable_platforms_on_action
+                            .label("enable-platforms")
+                            .after("run-trigger-actions"),
+                    )
+                    .with_system(
+                        show_messages_on_action
+                            .label("show-messages")
+                            .after("run-trigger-actions"),
+                    )
+                    .with_system(set_flags_on_action.after("run-trigger-actions"))
+                    .with_system(toggle_lights_on_action.after("run-trigger-actions"))
+                    .with_system(move_platforms.after("enable-platforms"))
+                    .with_system(tick_script_message.before("show-messages"))
+                    .with_system(add_script_shape_meshes),
+            )
+            .add_system_set(SystemSet::on_exit(GameLevel::Main).with_system(reset_script_state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Tags;
+    use crate::interaction::Interacted;
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::GameClock;
+    use crate::scripting::{InteractionActions, LevelScript, MovingPlatform, ScriptShape};
+    use crate::systems::tick_game_clock;
+    use bevy_rapier3d::prelude::*;
+
+    const LEVEL_SCRIPT: &str = r#"
+        [[platforms]]
+        tags = ["lift"]
+        from = [20.0, 0.0, 0.0]
+        to = [20.0, 10.0, 0.0]
+        half_extents = [1.0, 0.2, 1.0]
+        speed = 2.0
+
+        [[triggers]]
+        position = [0.0, 0.0, 0.0]
+        half_extents = [2.0, 2.0, 2.0]
+        tags = ["player"]
+        once = true
+        actions = [
+            { type = "enable_platform", tag = "lift" },
+            { type = "show_message", text = "Going up!" },
+            { type = "set_flag", flag = "entered" },
+            { type = "spawn_entity", shape = { kind = "ball", radius = 0.5 }, position = [-20.0, 0.0, 0.0], tags = ["spawned"] },
+        ]
+
+        [[triggers]]
+        position = [0.0, 0.0, 0.0]
+        half_extents = [2.0, 2.0, 2.0]
+        tags = ["player"]
+        on = "exit"
+        actions = [{ type = "set_flag", flag = "left" }]
+
+        [[interactables]]
+        position = [-20.0, 0.0, 10.0]
+        half_extents = [0.5, 1.0, 0.5]
+        prompt = "flip the light switch"
+        actions = [{ type = "toggle_lights" }, { type = "set_flag", flag = "flipped" }]
+    "#;
+
+    fn spawn_level_script(mut commands: Commands) {
+        LevelScript::from_toml(LEVEL_SCRIPT)
+            .unwrap()
+            .spawn(&mut commands);
+    }
+
+    fn setup_app() -> (App, Entity) {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameClock::default())
+            .insert_resource(AmbientLight {
+                color: Color::WHITE,
+                brightness: 1f32,
+            })
+            .add_event::<Interacted>()
+            .add_state(GameLevel::Main)
+            .add_plugin(ScriptingPlugin)
+            .add_startup_system(spawn_level_script)
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+        // Something without the player tag sits in the triggers the whole time
+        app.world
+            .spawn()
+            .insert(Tags::new(["crate"]))
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0f32, 1.5f32, 0f32).into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            });
+        let player = app
+            .world
+            .spawn()
+            .insert(Tags::new(["player"]))
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0f32, 0f32, 10f32).into(),
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            })
+            .id();
+        (app, player)
+    }
+
+    fn move_player(app: &mut App, player: Entity, translation: Vec3) {
+        let mut position = app
+            .world
+            .get_mut::<RigidBodyPositionComponent>(player)
+            .unwrap();
+        position.position = translation.into();
+        position.next_position = translation.into();
+    }
+
+    fn lift_enabled(app: &mut App) -> bool {
+        app.world
+            .query::<&MovingPlatform>()
+            .iter(&app.world)
+            .all(MovingPlatform::is_enabled)
+    }
+
+    #[test]
+    fn test_trigger_runs_actions() {
+        let (mut app, player) = setup_app();
+        for _ in 0..5 {
+            app.update();
+        }
+        // The crate doesn't set the triggers off
+        assert!(!lift_enabled(&mut app));
+        assert!(!app
+            .world
+            .get_resource::<ScriptFlags>()
+            .unwrap()
+            .is_set("entered"));
+
+        move_player(&mut app, player, Vec3::ZERO);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(lift_enabled(&mut app));
+        let flags = app.world.get_resource::<ScriptFlags>().unwrap();
+        assert!(flags.is_set("entered"));
+        assert!(!flags.is_set("left"));
+        assert_eq!(
+            app.world.get_resource::<ScriptMessage>().unwrap().text(),
+            Some("Going up!")
+        );
+        let spawned = app
+            .world
+            .query::<(&Tags, &ScriptShape)>()
+            .iter(&app.world)
+            .filter(|(tags, _)| tags.contains("spawned"))
+            .count();
+        assert_eq!(spawned, 1);
+
+        move_player(&mut app, player, Vec3::new(0f32, 0f32, 10f32));
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app
+            .world
+            .get_resource::<ScriptFlags>()
+            .unwrap()
+            .is_set("left"));
+
+        // The first trigger only goes off once
+        move_player(&mut app, player, Vec3::ZERO);
+        for _ in 0..5 {
+            app.update();
+        }
+        let spawned = app
+            .world
+            .query::<(&Tags, &ScriptShape)>()
+            .iter(&app.world)
+            .filter(|(tags, _)| tags.contains("spawned"))
+            .count();
+        assert_eq!(spawned, 1);
+    }
+
+    #[test]
+    fn test_interactable_runs_actions() {
+        let (mut app, player) = setup_app();
+        app.update();
+        let switch = app
+            .world
+            .query_filtered::<Entity, With<InteractionActions>>()
+            .iter(&app.world)
+            .next()
+            .unwrap();
+        app.world
+            .get_resource_mut::<Events<Interacted>>()
+            .unwrap()
+            .send(Interacted {
+                subject: player,
+                target: switch,
+            });
+        app.update();
+        assert!(app
+            .world
+            .get_resource::<ScriptFlags>()
+            .unwrap()
+            .is_set("flipped"));
+        // The lights were on, so they're dimmed
+        assert!(app.world.get_resource::<AmbientLight>().unwrap().brightness < 0.5);
+    }
+}
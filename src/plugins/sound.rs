@@ -0,0 +1,217 @@
+use crate::sound::{
+    load_sound_bank, mix_sounds, play_footstep_sounds, play_impact_sounds, play_level_tracks,
+    AudioBackend, PlaySound, RodioAudioBackend, SoundBank, SoundDefinitions, Soundtrack,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+/// TL;DR: This plugin plays positional sound effects, and music and ambience for every
+/// [`GameLevel`](crate::states::GameLevel), through the [`AudioBackend`](crate::sound::AudioBackend) `B`.
+///
+/// The sounds are loaded from the [`SoundDefinitions`](crate::sound::SoundDefinitions) at startup.
+/// Footsteps and impacts only play in [`GameLevel::Main`](crate::states::GameLevel), and anything can
+/// send a [`PlaySound`](crate::sound::PlaySound) event. How loud everything is is set in the
+/// [`GameSettings`](crate::resources::GameSettings).
+///
+/// Note: Sounds are [`AudioSource`](bevy::audio::AudioSource) assets, so Bevy's `AudioPlugin` (in
+/// the `DefaultPlugins`) must be added too. Footsteps come from the
+/// [`PerceptionPlugin`](crate::plugins::PerceptionPlugin)'s noises, and impacts from Rapier. What
+/// colliders sound like is set by the material of their [`Surface`](crate::components::Surface).
+pub struct SoundPlugin<B: AudioBackend = RodioAudioBackend> {
+    backend: PhantomData<fn() -> B>,
+}
+
+impl<B: AudioBackend> Default for SoundPlugin<B> {
+    fn default() -> Self {
+        SoundPlugin {
+            backend: PhantomData,
+        }
+    }
+}
+
+impl<B: AudioBackend> Plugin for SoundPlugin<B> {
+    fn build(&self, app: &mut App) {
+        if app.world.get_non_send_resource::<B>().is_none() {
+            app.insert_non_send_resource(B::default());
+        }
+        app.add_event::<PlaySound>()
+            .init_resource::<SoundDefinitions>()
+            .init_resource::<SoundBank>()
+            .init_resource::<Soundtrack>()
+            .add_startup_system(load_sound_bank)
+            .add_system(play_level_tracks.label("play-level-tracks"))
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(
+                        play_footstep_sounds
+                            .label("play-sounds")
+                            .after("emit-noises"),
+                    )
+                    .with_system(play_impact_sounds.label("play-sounds")),
+            )
+            .add_system(
+                mix_sounds::<B>
+                    .after("play-sounds")
+                    .after("play-level-tracks"),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonHead, Surface};
+    use crate::perception::{NoiseEvent, NoiseKind};
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::{GameConfig, GameSettings};
+    use crate::sound::{NullAudioBackend, SoundChannel};
+    use bevy::asset::AssetPlugin;
+    use bevy_rapier3d::prelude::*;
+    use std::sync::Arc;
+
+    const SOUNDS: &str = r#"
+        crossfade_seconds = 0.0
+
+        [surfaces.default]
+        footsteps = ["step.ogg"]
+
+        [surfaces.metal]
+        footsteps = ["clang.ogg"]
+
+        [music]
+        main_menu = "menu.ogg"
+        main = "level.ogg"
+    "#;
+
+    /// A listener at the origin looking down -Z, and every sound loaded
+    fn setup_app() -> App {
+        let mut app = headless_physics_app();
+        app.add_plugin(AssetPlugin)
+            .add_asset::<AudioSource>()
+            .insert_resource(GameConfig::default())
+            .insert_resource(GameSettings::default())
+            .insert_resource(SoundDefinitions::from_toml(SOUNDS).unwrap())
+            .add_state(GameLevel::Main)
+            .add_event::<NoiseEvent>()
+            .add_plugin(SoundPlugin::<NullAudioBackend>::default());
+        for path in ["step.ogg", "clang.ogg", "menu.ogg", "level.ogg"] {
+            let handle = app
+                .world
+                .get_resource_mut::<Assets<AudioSource>>()
+                .unwrap()
+                .add(AudioSource {
+                    bytes: Arc::from(Vec::new()),
+                });
+            app.world
+                .get_resource_mut::<SoundBank>()
+                .unwrap()
+                .insert(path, handle);
+        }
+        app.world
+            .spawn()
+            .insert(FirstPersonHead)
+            .insert(GlobalTransform::identity());
+        app
+    }
+
+    fn backend(app: &App) -> &NullAudioBackend {
+        app.world
+            .get_non_send_resource::<NullAudioBackend>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_positional_sounds() {
+        let mut app = setup_app();
+        app.update();
+        let play = |app: &mut App| {
+            app.world
+                .get_resource_mut::<Events<PlaySound>>()
+                .unwrap()
+                .send(PlaySound {
+                    path: String::from("step.ogg"),
+                    position: Some(Vec3::new(5.0, 0.0, 0.0)),
+                    volume: 1f32,
+                });
+            app.update();
+        };
+        play(&mut app);
+        let played = backend(&app).played()[0].clone();
+        assert_eq!(played.path, "step.ogg");
+        assert!((played.pan - 1f32).abs() < 1e-4);
+        assert!(played.volume > 0f32 && played.volume < 0.5);
+
+        app.world
+            .get_resource_mut::<GameSettings>()
+            .unwrap()
+            .set_master_volume(50);
+        play(&mut app);
+        let quieter = backend(&app).played()[1].volume;
+        assert!((quieter - played.volume / 2f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_footsteps_sound_like_the_ground() {
+        let mut app = setup_app();
+        let ground = app.world.spawn().insert(Surface::new("metal")).id();
+        app.update();
+        for (ground, loudness) in [(Some(ground), 12f32), (None, 6f32)] {
+            app.world
+                .get_resource_mut::<Events<NoiseEvent>>()
+                .unwrap()
+                .send(NoiseEvent {
+                    source: Entity::from_raw(1000),
+                    position: Vec3::new(0.0, -1.0, 0.0),
+                    kind: NoiseKind::Footstep,
+                    loudness,
+                    ground,
+                });
+            app.update();
+        }
+        let played = backend(&app).played();
+        assert_eq!(played.len(), 2);
+        assert_eq!(played[0].path, "clang.ogg");
+        assert_eq!(played[1].path, "step.ogg");
+        assert!(played[1].volume < played[0].volume);
+    }
+
+    #[test]
+    fn test_music_crossfades_between_levels() {
+        let mut app = setup_app();
+        app.update();
+        app.update();
+        let tracks = backend(&app).tracks().clone();
+        assert_eq!(tracks.len(), 1);
+        let music_volume = SoundChannel::Music.volume(&GameSettings::default());
+        assert!(tracks
+            .values()
+            .all(|volume| (volume - music_volume).abs() < 1e-4));
+
+        app.world
+            .get_resource_mut::<State<GameLevel>>()
+            .unwrap()
+            .set(GameLevel::MainMenu)
+            .unwrap();
+        for _ in 0..3 {
+            app.update();
+        }
+        let soundtrack = app.world.get_resource::<Soundtrack>().unwrap();
+        assert_eq!(soundtrack.music.current(), Some("menu.ogg"));
+        let new_tracks = backend(&app).tracks();
+        assert_eq!(new_tracks.len(), 1);
+        assert!(tracks.keys().all(|id| !new_tracks.contains_key(id)));
+
+        // The inventory has no music of its own
+        app.world
+            .get_resource_mut::<State<GameLevel>>()
+            .unwrap()
+            .set(GameLevel::Inventory)
+            .unwrap();
+        for _ in 0..3 {
+            app.update();
+        }
+        let soundtrack = app.world.get_resource::<Soundtrack>().unwrap();
+        assert_eq!(soundtrack.music.current(), Some("menu.ogg"));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::split_screen::{
+    activate_split_screen_cameras, extract_split_screen_cameras, fit_split_screen_projections,
+    match_msaa_to_split_screen, SplitScreenPassNode,
+};
+use bevy::core_pipeline::node::MAIN_PASS_DRIVER;
+use bevy::prelude::*;
+use bevy::render::render_graph::RenderGraph;
+use bevy::render::{RenderApp, RenderStage};
+
+/// The UI's render graph node, which split-screen views are drawn before so the HUD is on top
+const UI_PASS_DRIVER: &str = "ui_pass_driver";
+
+/// TL;DR: This plugin draws every local player's camera into its own
+/// [`SplitScreenViewport`](crate::components::SplitScreenViewport) in split-screen.
+///
+/// The cameras must be named with [`split_screen_camera_name`](crate::split_screen::split_screen_camera_name),
+/// like [`add_player`](crate::systems::player::add_player) names them when there's more than one player.
+///
+/// Note: This plugin must be added after the `DefaultPlugins`, since it adds its node to their
+/// render graph. Without a renderer, ie in tests, it only fits the cameras' projections.
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(activate_split_screen_cameras)
+            .add_system_to_stage(CoreStage::Last, fit_split_screen_projections)
+            .add_system_to_stage(CoreStage::Last, match_msaa_to_split_screen);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app.add_system_to_stage(RenderStage::Extract, extract_split_screen_cameras);
+        let split_screen_node = SplitScreenPassNode::new(&mut render_app.world);
+        let mut render_graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        render_graph.add_node(SplitScreenPassNode::NAME, split_screen_node);
+        render_graph
+            .add_node_edge(MAIN_PASS_DRIVER, SplitScreenPassNode::NAME)
+            .unwrap();
+        if render_graph
+            .add_node_edge(SplitScreenPassNode::NAME, UI_PASS_DRIVER)
+            .is_err()
+        {
+            warn!("There's no UI pass, so split-screen views might be drawn over the UI");
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::states::GameLevel;
+use crate::systems::{apply_surface_materials, update_footing};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin makes colliders out of the named
+/// [`SurfaceMaterial`](crate::resources::SurfaceMaterial)s of their [`Surface`](crate::components::Surface),
+/// and finds what players stand on.
+///
+/// The materials are in the [`GameConfig`](crate::resources::GameConfig), and they're applied again
+/// whenever it changes. In [`GameLevel::Main`](crate::states::GameLevel), every player's
+/// [`Footing`](crate::components::Footing) is updated under the "update-footing" label, before the
+/// player's body is moved.
+///
+/// Note: Footing is found with Rapier ray casts, so
+/// [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be added too.
+pub struct SurfacePlugin;
+
+impl Plugin for SurfacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_surface_materials).add_system_set(
+            SystemSet::on_update(GameLevel::Main)
+                .with_system(update_footing.label("update-footing").before("player-body")),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FirstPersonSubject, Footing, Surface};
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::GameConfig;
+    use bevy_rapier3d::prelude::*;
+
+    fn setup_app() -> App {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameConfig::default())
+            .add_state(GameLevel::Main)
+            .add_plugin(SurfacePlugin);
+        app
+    }
+
+    /// A 2m square of ground centered on `x`, with its top at 0
+    fn spawn_ground(app: &mut App, x: f32, material: Option<&str>) -> Entity {
+        let mut ground = app.world.spawn();
+        ground.insert_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(1.0, 0.1, 1.0).into(),
+            position: Vec3::new(x, -0.1, 0.0).into(),
+            ..Default::default()
+        });
+        if let Some(material) = material {
+            ground.insert(Surface::new(material));
+        }
+        ground.id()
+    }
+
+    #[test]
+    fn test_colliders_are_made_of_their_material() {
+        let mut app = setup_app();
+        let ice = spawn_ground(&mut app, 0.0, Some("ice"));
+        let plain = spawn_ground(&mut app, 5.0, None);
+        app.update();
+        let material = |app: &App, entity: Entity| {
+            app.world
+                .get::<ColliderMaterialComponent>(entity)
+                .unwrap()
+                .0
+        };
+        assert_eq!(material(&app, ice).friction, 0.02);
+        assert_eq!(
+            material(&app, plain).friction,
+            ColliderMaterial::default().friction
+        );
+
+        app.world.entity_mut(ice).insert(Surface::new("bouncy"));
+        app.update();
+        assert_eq!(material(&app, ice).restitution, 0.7);
+    }
+
+    #[test]
+    fn test_footing_slows_players_in_mud() {
+        let mut app = setup_app();
+        let mud = spawn_ground(&mut app, 0.0, Some("mud"));
+        let plain = spawn_ground(&mut app, 5.0, None);
+        // Just above the ground, close enough to jump, with the default 8m capsule
+        let half_height = GameConfig::default().player().capsule_height() / 2f32;
+        let subject = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(Footing::default())
+            .insert_bundle(RigidBodyBundle {
+                position: Vec3::new(0.0, half_height + 0.005, 0.0).into(),
+                ..Default::default()
+            })
+            .id();
+        app.update();
+        app.update();
+        let footing = *app.world.get::<Footing>(subject).unwrap();
+        assert_eq!(footing.ground(), Some(mud));
+        assert_eq!(footing.speed_modifier(), 0.5);
+
+        let move_to = |app: &mut App, translation: Vec3| {
+            let mut position = app
+                .world
+                .get_mut::<RigidBodyPositionComponent>(subject)
+                .unwrap();
+            position.position = translation.into();
+            position.next_position = translation.into();
+            app.update();
+            app.update();
+            *app.world.get::<Footing>(subject).unwrap()
+        };
+        let footing = move_to(&mut app, Vec3::new(5.0, half_height + 0.005, 0.0));
+        assert_eq!(footing.ground(), Some(plain));
+        assert_eq!(footing.speed_modifier(), 1f32);
+        assert_eq!(
+            move_to(&mut app, Vec3::new(5.0, half_height + 3.0, 0.0)),
+            Footing::default()
+        );
+        // Too high to jump off the ground is too high to stand on it
+        assert_eq!(
+            move_to(&mut app, Vec3::new(5.0, half_height + 0.1, 0.0)),
+            Footing::default()
+        );
+
+        // A trigger under the subject's feet isn't ground
+        app.world.spawn().insert_bundle(ColliderBundle {
+            collider_type: ColliderType::Sensor.into(),
+            shape: ColliderShape::cuboid(1.0, 1.0, 1.0).into(),
+            position: Vec3::new(5.0, half_height + 3.0, 0.0).into(),
+            ..Default::default()
+        });
+        assert_eq!(
+            move_to(&mut app, Vec3::new(5.0, half_height + 3.0, 0.0)),
+            Footing::default()
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! What the tests share: a headless app with physics, and ways to poke at it
+use crate::save::SaveSlots;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ElementState;
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use bevy_rapier3d::physics::TimestepMode;
+use bevy_rapier3d::prelude::*;
+
+/// An app without a window, where Rapier steps once every update however long it took. Nothing
+/// falls unless the test turns gravity on.
+pub fn headless_physics_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .insert_resource(RapierConfiguration {
+            gravity: Vector::zeros(),
+            scale: 1.0,
+            physics_pipeline_active: true,
+            query_pipeline_active: true,
+            timestep_mode: TimestepMode::FixedTimestep,
+        })
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default());
+    app
+}
+
+pub fn send<T: Send + Sync + 'static>(app: &mut App, event: T) {
+    app.world
+        .get_resource_mut::<Events<T>>()
+        .unwrap()
+        .send(event);
+}
+
+/// The app needs the `InputPlugin` to read the key
+pub fn send_key(app: &mut App, key_code: KeyCode, state: ElementState) {
+    send(
+        app,
+        KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key_code),
+            state,
+        },
+    );
+}
+
+/// Save slots for a test that doesn't save. Nothing is written to them, but they're somewhere
+/// they couldn't end up in the game's saves anyway.
+pub fn unused_save_slots(test: &str) -> SaveSlots {
+    SaveSlots::new(std::env::temp_dir().join(format!(
+        "bevy-fp-template-{}-{}",
+        test,
+        std::process::id()
+    )))
+}
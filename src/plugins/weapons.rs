@@ -0,0 +1,213 @@
+use crate::states::GameLevel;
+use crate::weapons::{
+    damage_on_impact, expire_projectiles, fire_weapons, hit_with_projectiles, WeaponDefinitions,
+    WeaponImpact,
+};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin lets players fire the weapons in their head's [`Armory`](crate::weapons::Armory)
+/// in [`GameLevel::Main`](crate::states::GameLevel).
+///
+/// Weapons are fired, reloaded and switched following the subject's [`WeaponIntents`](crate::components::WeaponIntents),
+/// which the [`FirstPersonControlPlugin`](crate::plugins::FirstPersonControlPlugin) reads from the player's
+/// devices. Hits send a [`WeaponImpact`](crate::weapons::WeaponImpact) event and hurt what they hit. Unless
+/// [`WeaponDefinitions`](crate::weapons::WeaponDefinitions) were inserted already, players are armed with the
+/// weapons in `assets/weapons.toml`.
+///
+/// Note: Hurting what's hit needs the [`HealthPlugin`](crate::plugins::HealthPlugin), and shots are cast and
+/// launched through Rapier, so [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) must be
+/// added too.
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeaponDefinitions>()
+            .add_event::<WeaponImpact>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    // Recoil turns the head after the player did
+                    .with_system(
+                        fire_weapons
+                            .label("fire-weapons")
+                            .after("first-person-intents")
+                            .after("player-body"),
+                    )
+                    .with_system(hit_with_projectiles.label("fire-weapons"))
+                    .with_system(expire_projectiles.after("fire-weapons"))
+                    .with_system(damage_on_impact.label("deal-damage").after("fire-weapons")),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{
+        DamageType, FirstPersonHead, FirstPersonSubject, WeaponIntents, WeaponSwitch,
+    };
+    use crate::plugins::test_app::headless_physics_app;
+    use crate::resources::{GameClock, GameConfig, GameRng};
+    use crate::systems::{tick_game_clock, DamageEvent, SubjectDiagnostic};
+    use crate::weapons::{Armory, Projectile, Weapon};
+    use bevy_rapier3d::prelude::*;
+
+    const WEAPONS: &str = r#"
+        [[weapons]]
+        name = "Pistol"
+        kind = "hitscan"
+        range = 50.0
+        damage = 25.0
+        fire_rate = 4.0
+        recoil = 10.0
+        magazine = 12
+        reload_seconds = 1.0
+
+        [[weapons]]
+        name = "Launcher"
+        kind = "projectile"
+        speed = 20.0
+        damage = 40.0
+        radius = 0.1
+        fire_rate = 1.0
+        magazine = 1
+        reload_seconds = 1.0
+    "#;
+
+    #[derive(Default)]
+    struct DamageLog(Vec<DamageEvent>);
+
+    fn log_damage(mut events: EventReader<DamageEvent>, mut log: ResMut<DamageLog>) {
+        log.0.extend(events.iter().cloned());
+    }
+
+    struct TestApp {
+        app: App,
+        subject: Entity,
+        head: Entity,
+        target: Entity,
+    }
+
+    fn setup_app() -> TestApp {
+        let mut app = headless_physics_app();
+        app.insert_resource(GameConfig::default())
+            .insert_resource(GameClock::default())
+            .insert_resource(GameRng::from_seed(5))
+            .insert_resource(WeaponDefinitions::from_toml(WEAPONS).unwrap())
+            .insert_resource(DamageLog::default())
+            .add_state(GameLevel::Main)
+            .add_event::<SubjectDiagnostic>()
+            .add_event::<DamageEvent>()
+            .add_plugin(WeaponPlugin)
+            .add_system(log_damage.after("deal-damage"))
+            .add_system_to_stage(CoreStage::PreUpdate, tick_game_clock);
+
+        let armory =
+            Armory::from_definitions(app.world.get_resource::<WeaponDefinitions>().unwrap());
+        // The subject's own body is in the line of fire
+        let subject = app
+            .world
+            .spawn()
+            .insert(FirstPersonSubject)
+            .insert(WeaponIntents::default())
+            .insert(Transform::default())
+            .insert(GlobalTransform::default())
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.5).into(),
+                ..Default::default()
+            })
+            .id();
+        let head = app
+            .world
+            .spawn()
+            .insert(FirstPersonHead)
+            .insert(armory)
+            .insert(Transform::default())
+            .insert(GlobalTransform::default())
+            .id();
+        app.world.entity_mut(subject).push_children(&[head]);
+        // A wall straight ahead
+        let target = app
+            .world
+            .spawn()
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::cuboid(2f32, 2f32, 0.5).into(),
+                position: Vec3::new(0f32, 0f32, -8f32).into(),
+                ..Default::default()
+            })
+            .id();
+        app.update();
+        TestApp {
+            app,
+            subject,
+            head,
+            target,
+        }
+    }
+
+    fn fire(test: &mut TestApp, switch: Option<WeaponSwitch>) {
+        let mut intents = WeaponIntents::default();
+        intents.set_fire(true, true);
+        intents.set_switch(switch);
+        *test
+            .app
+            .world
+            .get_mut::<WeaponIntents>(test.subject)
+            .unwrap() = intents;
+        test.app.update();
+        *test
+            .app
+            .world
+            .get_mut::<WeaponIntents>(test.subject)
+            .unwrap() = WeaponIntents::default();
+    }
+
+    fn damage_log(test: &TestApp) -> &Vec<DamageEvent> {
+        &test.app.world.get_resource::<DamageLog>().unwrap().0
+    }
+
+    #[test]
+    fn test_hitscan_hits_and_kicks_the_head_up() {
+        let mut test = setup_app();
+        fire(&mut test, None);
+        test.app.update();
+        assert_eq!(
+            damage_log(&test),
+            &vec![DamageEvent {
+                subject: test.target,
+                amount: 25f32,
+                damage_type: DamageType::Ballistic,
+                source: Some(test.subject),
+            }]
+        );
+        let head_transform = test.app.world.get::<Transform>(test.head).unwrap();
+        let (pitch, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
+        assert!((pitch - 10f32.to_radians()).abs() < 1e-3);
+        let armory = test.app.world.get::<Armory>(test.head).unwrap();
+        assert_eq!(armory.selected().map(Weapon::rounds), Some(11));
+    }
+
+    #[test]
+    fn test_switch_to_projectile_and_hit() {
+        let mut test = setup_app();
+        fire(&mut test, Some(WeaponSwitch::Slot(1)));
+        let armory = test.app.world.get::<Armory>(test.head).unwrap();
+        assert_eq!(armory.selected_index(), 1);
+        // Its only round is in flight, so it's reloading
+        assert!(armory.selected().unwrap().is_reloading());
+        let mut projectiles = test.app.world.query::<&Projectile>();
+        assert_eq!(projectiles.iter(&test.app.world).count(), 1);
+        for _ in 0..60 {
+            test.app.update();
+        }
+        assert_eq!(
+            damage_log(&test),
+            &vec![DamageEvent {
+                subject: test.target,
+                amount: 40f32,
+                damage_type: DamageType::Ballistic,
+                source: Some(test.subject),
+            }]
+        );
+        assert_eq!(projectiles.iter(&test.app.world).count(), 0);
+    }
+}
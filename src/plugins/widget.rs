@@ -0,0 +1,59 @@
+use crate::resources::UiTheme;
+use crate::systems::spawn_ui_camera;
+use crate::widgets::{
+    activate_widgets, adjust_focused_slider, drag_slider, flip_toggle_on_activated,
+    navigate_menu_focus, select_dropdown_option_on_activated, send_menu_back,
+    style_buttons_on_interaction, update_dropdown_display, update_slider_display,
+    update_toggle_display, MenuBack, MenuFocus, WidgetActivated,
+};
+use bevy::prelude::*;
+
+/// TL;DR: This plugin makes the widgets in [`widgets`](crate::widgets) interactive.
+///
+/// It adds a default [`UiTheme`](crate::resources::UiTheme) resource and the systems that style
+/// buttons, move sliders, flip toggles and open dropdowns. The systems run in every level, since
+/// they only do work when widgets exist.
+///
+/// It also moves the [`MenuFocus`](crate::widgets::MenuFocus) with the keyboard and gamepads, and
+/// sends the [`WidgetActivated`](crate::widgets::WidgetActivated) and [`MenuBack`](crate::widgets::MenuBack)
+/// events that the levels react to.
+///
+/// Note: It spawns the one UI camera at startup, so screens and overlays only spawn UI nodes.
+pub struct WidgetPlugin;
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UiTheme::default())
+            .insert_resource(MenuFocus::default())
+            .add_startup_system(spawn_ui_camera)
+            .add_event::<WidgetActivated>()
+            .add_event::<MenuBack>()
+            .add_system(navigate_menu_focus.label("navigate-menu-focus"))
+            .add_system(style_buttons_on_interaction.after("navigate-menu-focus"))
+            .add_system(
+                activate_widgets
+                    .label("activate-widgets")
+                    .after("navigate-menu-focus"),
+            )
+            .add_system(send_menu_back)
+            .add_system(drag_slider.label("update-widget-values"))
+            .add_system(
+                adjust_focused_slider
+                    .label("update-widget-values")
+                    .after("navigate-menu-focus"),
+            )
+            .add_system(
+                flip_toggle_on_activated
+                    .label("update-widget-values")
+                    .after("activate-widgets"),
+            )
+            .add_system(
+                select_dropdown_option_on_activated
+                    .label("update-widget-values")
+                    .after("activate-widgets"),
+            )
+            .add_system(update_slider_display.after("update-widget-values"))
+            .add_system(update_toggle_display.after("update-widget-values"))
+            .add_system(update_dropdown_display.after("update-widget-values"));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::components::{Lookaround, LookaroundDirection, Movement, MovementDirection};
+use bevy::math::Vec3;
+use std::fs;
+use std::path::Path;
+
+/// Every replay file starts with these bytes
+const REPLAY_MAGIC: [u8; 4] = *b"BFPR";
+
+/// Bumped whenever the layout of a replay changes, so old replays are turned away
+pub const REPLAY_VERSION: u8 = 2;
+
+const FLAG_PAUSE: u8 = 1;
+
+/// What one [`FirstPersonSubject`](crate::components::FirstPersonSubject) was told to do during one frame.
+/// Directions are stored signed: right, forward and up are positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubjectFrame {
+    pub left_right: f32,
+    pub forward_back: f32,
+    pub look_left_right: f32,
+    pub look_up_down: f32,
+    pub jump: bool,
+}
+
+impl SubjectFrame {
+    pub fn from_intents(movement: &Movement, lookaround: &Lookaround, jump: bool) -> Self {
+        SubjectFrame {
+            left_right: match movement.left_right() {
+                MovementDirection::Left(magnitude) => -magnitude,
+                MovementDirection::Right(magnitude) => magnitude,
+                _ => panic!("Movement left_right() was neither Left nor Right!"),
+            },
+            forward_back: match movement.forward_back() {
+                MovementDirection::Back(magnitude) => -magnitude,
+                MovementDirection::Forward(magnitude) => magnitude,
+                _ => panic!("Movement forward_back() was neither Forward nor Back!"),
+            },
+            look_left_right: match lookaround.left_right() {
+                LookaroundDirection::Left(magnitude) => -magnitude,
+                LookaroundDirection::Right(magnitude) => magnitude,
+                _ => panic!("Lookaround left_right() was neither Left nor Right!"),
+            },
+            look_up_down: match lookaround.up_down() {
+                LookaroundDirection::Down(magnitude) => -magnitude,
+                LookaroundDirection::Up(magnitude) => magnitude,
+                _ => panic!("Lookaround up_down() was neither Up nor Down!"),
+            },
+            jump,
+        }
+    }
+
+    // The sign of `-0.0` is kept, so a `Left(0.0)` comes back as a `Left(0.0)`
+    pub fn movement(&self) -> Movement {
+        Movement::from_components(
+            if self.left_right.is_sign_negative() {
+                MovementDirection::Left(-self.left_right)
+            } else {
+                MovementDirection::Right(self.left_right)
+            },
+            if self.forward_back.is_sign_negative() {
+                MovementDirection::Back(-self.forward_back)
+            } else {
+                MovementDirection::Forward(self.forward_back)
+            },
+        )
+    }
+
+    pub fn lookaround(&self) -> Lookaround {
+        Lookaround::from_components(
+            if self.look_left_right.is_sign_negative() {
+                LookaroundDirection::Left(-self.look_left_right)
+            } else {
+                LookaroundDirection::Right(self.look_left_right)
+            },
+            if self.look_up_down.is_sign_negative() {
+                LookaroundDirection::Down(-self.look_up_down)
+            } else {
+                LookaroundDirection::Up(self.look_up_down)
+            },
+        )
+    }
+}
+
+/// Everything the players did during one frame of [`GameLevel::Main`](crate::states::GameLevel).
+/// Subjects are ordered by their [`LocalPlayer`](crate::components::LocalPlayer) index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedFrame {
+    pub subjects: Vec<SubjectFrame>,
+    pub pause: bool,
+    /// How many times physics stepped by the timestep this frame
+    pub steps: u8,
+}
+
+/// A recorded run of the main level, along with what's needed to play it back the same way
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// The [`GameRng`](crate::resources::GameRng) seed the run started from
+    pub seed: u64,
+    /// The [`GameConfig::content_hash`](crate::resources::GameConfig::content_hash) of the run
+    pub config_hash: u64,
+    /// The fixed physics step, in seconds. Every frame steps physics a whole number of these.
+    pub timestep: f32,
+    pub frames: Vec<RecordedFrame>,
+    /// Where every subject ended up, so a replay can tell whether it reproduced the run
+    pub final_positions: Vec<Vec3>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Io(String),
+    NotAReplay,
+    UnknownVersion(u8),
+    Truncated,
+}
+
+impl Recording {
+    pub fn new(seed: u64, config_hash: u64, timestep: f32) -> Self {
+        Recording {
+            seed,
+            config_hash,
+            timestep,
+            frames: Vec::new(),
+            final_positions: Vec::new(),
+        }
+    }
+
+    /// Frames are run length encoded, since players usually hold the same input for many frames
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.push(REPLAY_VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.config_hash.to_le_bytes());
+        bytes.extend_from_slice(&self.timestep.to_le_bytes());
+
+        let mut runs: Vec<(&RecordedFrame, u16)> = Vec::new();
+        for frame in self.frames.iter() {
+            match runs.last_mut() {
+                Some((run_frame, count)) if *run_frame == frame && *count < u16::MAX => *count += 1,
+                _ => runs.push((frame, 1)),
+            }
+        }
+        bytes.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+        for (frame, count) in runs {
+            bytes.extend_from_slice(&count.to_le_bytes());
+            bytes.push(if frame.pause { FLAG_PAUSE } else { 0 });
+            bytes.push(frame.steps);
+            bytes.push(frame.subjects.len() as u8);
+            for subject in frame.subjects.iter() {
+                for value in [
+                    subject.left_right,
+                    subject.forward_back,
+                    subject.look_left_right,
+                    subject.look_up_down,
+                ] {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                bytes.push(subject.jump as u8);
+            }
+        }
+
+        bytes.push(self.final_positions.len() as u8);
+        for position in self.final_positions.iter() {
+            for value in position.to_array() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader { bytes };
+        if reader.take(REPLAY_MAGIC.len()) != Ok(&REPLAY_MAGIC[..]) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = reader.u8()?;
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnknownVersion(version));
+        }
+        let mut recording = Recording::new(reader.u64()?, reader.u64()?, reader.f32()?);
+
+        let run_count = reader.u32()?;
+        for _ in 0..run_count {
+            let count = reader.u16()?;
+            let pause = reader.u8()? & FLAG_PAUSE != 0;
+            let steps = reader.u8()?;
+            let subject_count = reader.u8()?;
+            let mut subjects = Vec::with_capacity(subject_count as usize);
+            for _ in 0..subject_count {
+                subjects.push(SubjectFrame {
+                    left_right: reader.f32()?,
+                    forward_back: reader.f32()?,
+                    look_left_right: reader.f32()?,
+                    look_up_down: reader.f32()?,
+                    jump: reader.u8()? != 0,
+                });
+            }
+            let frame = RecordedFrame {
+                subjects,
+                pause,
+                steps,
+            };
+            for _ in 0..count {
+                recording.frames.push(frame.clone());
+            }
+        }
+
+        let position_count = reader.u8()?;
+        for _ in 0..position_count {
+            recording
+                .final_positions
+                .push(Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?));
+        }
+        Ok(recording)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, self.encode()).map_err(|io_err| ReplayError::Io(io_err.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path).map_err(|io_err| ReplayError::Io(io_err.to_string()))?;
+        Recording::decode(&bytes)
+    }
+}
+
+/// Numbers are little endian
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.bytes.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        let bytes = self.take(2)?.try_into().expect("Took 2 bytes for a u16!");
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        let bytes = self.take(4)?.try_into().expect("Took 4 bytes for a u32!");
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        let bytes = self.take(8)?.try_into().expect("Took 8 bytes for a u64!");
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        let bytes = self.take(4)?.try_into().expect("Took 4 bytes for an f32!");
+        Ok(f32::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walking_frame(jump: bool) -> RecordedFrame {
+        RecordedFrame {
+            subjects: vec![SubjectFrame {
+                left_right: -0f32,
+                forward_back: 1f32,
+                look_left_right: 3.5,
+                look_up_down: -1.25,
+                jump,
+            }],
+            pause: false,
+            steps: 1,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut recording = Recording::new(42, 7, 1f32 / 60f32);
+        for _ in 0..100 {
+            recording.frames.push(walking_frame(false));
+        }
+        recording.frames.push(walking_frame(true));
+        recording.frames.push(RecordedFrame {
+            subjects: Vec::new(),
+            pause: true,
+            steps: 0,
+        });
+        recording.final_positions.push(Vec3::new(1f32, 2f32, 3f32));
+
+        let encoded = recording.encode();
+        // The 100 identical frames are stored once
+        assert!(encoded.len() < 100);
+        let decoded = Recording::decode(&encoded).unwrap();
+        assert_eq!(decoded, recording);
+        // -0.0 and 0.0 compare equal, so check the sign made it through
+        assert!(decoded.frames[0].subjects[0].left_right.is_sign_negative());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(Recording::decode(b"nope"), Err(ReplayError::NotAReplay));
+        let mut encoded = Recording::new(1, 2, 0.5).encode();
+        encoded[REPLAY_MAGIC.len()] = REPLAY_VERSION + 1;
+        assert_eq!(
+            Recording::decode(&encoded),
+            Err(ReplayError::UnknownVersion(REPLAY_VERSION + 1))
+        );
+        let mut encoded = Recording::new(1, 2, 0.5).encode();
+        encoded.pop();
+        assert_eq!(Recording::decode(&encoded), Err(ReplayError::Truncated));
+    }
+
+    #[test]
+    fn test_subject_frame_keeps_directions() {
+        let movement =
+            Movement::from_components(MovementDirection::Left(0f32), MovementDirection::Back(0.5));
+        let lookaround = Lookaround::from_components(
+            LookaroundDirection::Right(2f32),
+            LookaroundDirection::Down(0.25),
+        );
+        let frame = SubjectFrame::from_intents(&movement, &lookaround, false);
+        assert_eq!(frame.movement(), movement);
+        assert_eq!(frame.lookaround(), lookaround);
+        assert_eq!(frame.movement().left_right(), MovementDirection::Left(0f32));
+    }
+}
@@ -0,0 +1,16 @@
+//! Deterministic input recording and playback.
+//!
+//! A [`Recording`](crate::replay::Recording) holds the intents and actions of every frame of a run
+//! of the main level, along with the [`GameRng`](crate::resources::GameRng) seed, a hash of the
+//! [`GameConfig`](crate::resources::GameConfig) and the physics timestep. Every frame steps physics a whole
+//! number of those timesteps, and the recording notes how many, so a replay reproduces the run at
+//! any frame rate, which makes replays useful for reproducing physics bugs and as regression tests.
+mod format;
+mod playback;
+mod recorder;
+mod steps;
+
+pub use self::format::*;
+pub use self::playback::*;
+pub use self::recorder::*;
+pub use self::steps::*;
@@ -0,0 +1,194 @@
+use crate::components::{FirstPersonSubject, LocalPlayer, Lookaround, Movement};
+use crate::replay::{subject_order, PhysicsSteps, RecordedFrame, Recording, ReplayError};
+use crate::resources::{GameConfig, GameRng, PauseReason};
+use crate::states::{FirstPersonControlSettings, GameLevel};
+use crate::systems::pausing::resume_game;
+use bevy::prelude::*;
+use bevy_rapier3d::physics::TimestepMode;
+use bevy_rapier3d::prelude::*;
+use std::path::Path;
+
+/// Plays a [`Recording`](crate::replay::Recording) back the next time the main level starts.
+/// Insert this resource along with the [`ReplayPlugin`](crate::plugins::ReplayPlugin).
+pub struct ReplayPlayback {
+    recording: Recording,
+    next_frame: usize,
+    divergence: Option<f32>,
+}
+
+impl ReplayPlayback {
+    pub fn new(recording: Recording) -> Self {
+        ReplayPlayback {
+            recording,
+            next_frame: 0,
+            divergence: None,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Ok(ReplayPlayback::new(Recording::load(path)?))
+    }
+
+    /// The frame being played back this frame, if there are any left
+    pub fn current_frame(&self) -> Option<&RecordedFrame> {
+        self.recording.frames.get(self.next_frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.recording.frames.len()
+    }
+
+    /// Once the replay has finished, how far the subject that ended up furthest from where
+    /// it was recorded ended up from there, in meters. `0.0` means the run was reproduced.
+    pub fn divergence(&self) -> Option<f32> {
+        self.divergence
+    }
+}
+
+/// Start the replay over, with the seed and timestep it was recorded with
+pub fn start_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    mut rng: ResMut<GameRng>,
+    game_config: Res<GameConfig>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if playback.recording.config_hash != game_config.content_hash() {
+        warn!("This replay was recorded with a different config, so it might not play back the same way");
+    }
+    rng.reseed(playback.recording.seed);
+    integration_parameters.dt = playback.recording.timestep;
+    rapier_config.timestep_mode = TimestepMode::FixedTimestep;
+    playback.next_frame = 0;
+    playback.divergence = None;
+    info!("Replaying {} frames", playback.recording.frames.len());
+}
+
+/// Replace the players' input with the jumps and pauses of the frame being played back. This runs
+/// right after the input is read, so the rest of the game sees the recorded presses instead.
+pub fn inject_replay_actions(
+    playback: Res<ReplayPlayback>,
+    game_level: Res<State<GameLevel>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    query: Query<Option<&LocalPlayer>, With<FirstPersonSubject>>,
+) {
+    if game_level.current() != &GameLevel::Main {
+        return;
+    }
+    let frame = match playback.current_frame() {
+        Some(frame) => frame,
+        None => return,
+    };
+    let pressed_keys: Vec<KeyCode> = keyboard_input.get_pressed().cloned().collect();
+    for key in pressed_keys {
+        keyboard_input.reset(key);
+    }
+    let pressed_buttons: Vec<GamepadButton> = gamepad_buttons.get_pressed().cloned().collect();
+    for button in pressed_buttons {
+        gamepad_buttons.reset(button);
+    }
+
+    let mut subjects: Vec<Option<&LocalPlayer>> = query.iter().collect();
+    subjects.sort_by_key(|local_player| subject_order(*local_player));
+    for (local_player, subject_frame) in subjects.into_iter().zip(frame.subjects.iter()) {
+        if !subject_frame.jump {
+            continue;
+        }
+        match local_player.and_then(|local_player| local_player.input().gamepad()) {
+            Some(gamepad) => {
+                gamepad_buttons.press(GamepadButton(gamepad, GamepadButtonType::South))
+            }
+            None => keyboard_input.press(KeyCode::Space),
+        }
+    }
+    if frame.pause {
+        keyboard_input.press(KeyCode::Escape);
+    }
+}
+
+/// Replace the intents the first person systems read from the players' devices with the
+/// recorded ones, and step physics as many times as it stepped while recording. Then move on
+/// to the next frame.
+pub fn apply_replay_intents(
+    mut playback: ResMut<ReplayPlayback>,
+    mut physics_steps: ResMut<PhysicsSteps>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut query: Query<
+        (&mut Movement, &mut Lookaround, Option<&LocalPlayer>),
+        With<FirstPersonSubject>,
+    >,
+) {
+    let frame = match playback.current_frame() {
+        Some(frame) => frame,
+        None => return,
+    };
+    let mut subjects: Vec<_> = query.iter_mut().collect();
+    if subjects.len() != frame.subjects.len() {
+        warn!(
+            "This replay was recorded with {} subjects, but there are {}",
+            frame.subjects.len(),
+            subjects.len()
+        );
+    }
+    subjects.sort_by_key(|(_, _, local_player)| subject_order(*local_player));
+    for ((mut movement, mut lookaround, _), subject_frame) in
+        subjects.into_iter().zip(frame.subjects.iter())
+    {
+        *movement = subject_frame.movement();
+        *lookaround = subject_frame.lookaround();
+    }
+    physics_steps.set(frame.steps, &mut rapier_config);
+    playback.next_frame += 1;
+}
+
+/// The recording has no frames for the time the game spent paused, so a replay resumes
+/// straight away instead of waiting in the pause menu.
+pub fn resume_replay_after_pause(
+    playback: Res<ReplayPlayback>,
+    mut pause_reason: ResMut<PauseReason>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    if playback.is_finished() || game_level.current() != &GameLevel::PauseMenu {
+        return;
+    }
+    resume_game(&mut pause_reason, &mut fp_control_settings, &mut game_level);
+}
+
+/// Once the last frame has been played back and physics has stepped, compare where the subjects
+/// ended up with where they were recorded to end up, and hand physics back to its usual timestep.
+pub fn report_replay_result(
+    mut playback: ResMut<ReplayPlayback>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    game_level: Res<State<GameLevel>>,
+    query: Query<(&Transform, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+) {
+    if !playback.is_finished() || playback.divergence.is_some() {
+        return;
+    }
+    let mut subjects: Vec<(&Transform, Option<&LocalPlayer>)> = query.iter().collect();
+    subjects.sort_by_key(|(_, local_player)| subject_order(*local_player));
+    let divergence = subjects
+        .iter()
+        .zip(playback.recording.final_positions.iter())
+        .map(|((transform, _), recorded)| transform.translation.distance(*recorded))
+        .fold(0f32, f32::max);
+    if subjects.len() != playback.recording.final_positions.len() {
+        warn!("The replay ended with a different number of subjects than it was recorded with");
+    }
+    if divergence > 0.001 {
+        warn!(
+            "The replay diverged from the recording by {} meters",
+            divergence
+        );
+    } else {
+        info!("The replay reproduced the recording");
+    }
+    playback.divergence = Some(divergence);
+    integration_parameters.dt = playback.recording.timestep;
+    rapier_config.timestep_mode = TimestepMode::VariableTimestep;
+    // The last frame might have paused, or not stepped at all
+    rapier_config.physics_pipeline_active = game_level.current() == &GameLevel::Main;
+}
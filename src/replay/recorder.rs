@@ -0,0 +1,194 @@
+use crate::components::{FirstPersonSubject, LocalPlayer, Lookaround, Movement};
+use crate::replay::{PhysicsSteps, RecordedFrame, Recording, SubjectFrame};
+use crate::resources::{GameClock, GameConfig, GameRng};
+use crate::systems::pausing::pause_just_pressed;
+use crate::systems::player::jump_just_pressed;
+use bevy::prelude::*;
+use bevy_rapier3d::physics::TimestepMode;
+use bevy_rapier3d::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A long frame steps physics at most this many times, the rest of its time is dropped so a
+/// hitch doesn't snowball into ever longer frames
+pub const MAX_STEPS_PER_FRAME: u8 = 4;
+
+/// Records every run of the main level. Insert this resource along with the
+/// [`InputRecorderPlugin`](crate::plugins::InputRecorderPlugin) to record.
+pub struct InputRecorder {
+    path: Option<PathBuf>,
+    recording: Option<Recording>,
+    jumps: Vec<bool>,
+    pause: bool,
+    accumulator: Duration,
+    steps: u8,
+}
+
+impl InputRecorder {
+    /// Save every run to `path` when the main level exits. Each run overwrites the last.
+    pub fn to_file(path: impl Into<PathBuf>) -> Self {
+        InputRecorder {
+            path: Some(path.into()),
+            ..InputRecorder::in_memory()
+        }
+    }
+
+    /// Keep the recording in memory only, ie to replay it in a test
+    pub fn in_memory() -> Self {
+        InputRecorder {
+            path: None,
+            recording: None,
+            jumps: Vec::new(),
+            pause: false,
+            accumulator: Duration::ZERO,
+            steps: 0,
+        }
+    }
+
+    /// The current run, or the last one once the main level has exited
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+}
+
+/// Subjects are recorded and replayed in the order of their [`LocalPlayer`](crate::components::LocalPlayer) index
+pub fn subject_order(local_player: Option<&LocalPlayer>) -> usize {
+    local_player
+        .map(|local_player| local_player.index())
+        .unwrap_or(0)
+}
+
+/// Start a new recording. Physics steps on Rapier's default timestep while recording, see
+/// [`step_physics_while_recording`], whatever the timestep was before. The
+/// [`GameRng`](crate::resources::GameRng) starts over from a fresh seed, since earlier runs
+/// have drawn from it, and that seed is the one recorded.
+pub fn start_recording(
+    mut recorder: ResMut<InputRecorder>,
+    mut rng: ResMut<GameRng>,
+    game_config: Res<GameConfig>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    rapier_config.timestep_mode = TimestepMode::FixedTimestep;
+    recorder.accumulator = Duration::ZERO;
+    recorder.steps = 0;
+    let seed = rng.next_u64();
+    rng.reseed(seed);
+    recorder.recording = Some(Recording::new(
+        seed,
+        game_config.content_hash(),
+        IntegrationParameters::default().dt,
+    ));
+    info!("Recording started");
+}
+
+/// Step physics by however many fixed steps of game time passed this frame, carrying the rest
+/// over to the next frame, so the game runs at the same speed at any frame rate. The step count
+/// is recorded with the frame so a replay steps the same way, see
+/// [`PhysicsSteps`](crate::replay::PhysicsSteps).
+pub fn step_physics_while_recording(
+    mut recorder: ResMut<InputRecorder>,
+    game_clock: Res<GameClock>,
+    mut physics_steps: ResMut<PhysicsSteps>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    let timestep = match recorder.recording.as_ref() {
+        Some(recording) => recording.timestep,
+        None => return,
+    };
+    let fixed_step = Duration::from_secs_f32(timestep);
+    recorder.accumulator += game_clock.delta();
+    let steps = (recorder.accumulator.as_nanos() / fixed_step.as_nanos()) as u32;
+    let steps = if steps > MAX_STEPS_PER_FRAME as u32 {
+        recorder.accumulator = Duration::ZERO;
+        MAX_STEPS_PER_FRAME as u32
+    } else {
+        recorder.accumulator -= fixed_step * steps;
+        steps
+    };
+    recorder.steps = steps as u8;
+    integration_parameters.dt = timestep;
+    physics_steps.set(recorder.steps, &mut rapier_config);
+}
+
+/// Note which players pressed jump or pause this frame, before the systems that act on them reset them
+pub fn capture_player_actions(
+    mut recorder: ResMut<InputRecorder>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    query: Query<Option<&LocalPlayer>, With<FirstPersonSubject>>,
+) {
+    let mut subjects: Vec<Option<&LocalPlayer>> = query.iter().collect();
+    subjects.sort_by_key(|local_player| subject_order(*local_player));
+    recorder.jumps = subjects
+        .into_iter()
+        .map(|local_player| {
+            jump_just_pressed(
+                local_player.map(|local_player| local_player.input()),
+                &keyboard_input,
+                &gamepads,
+                &gamepad_buttons,
+            )
+        })
+        .collect();
+    recorder.pause = pause_just_pressed(&keyboard_input, &gamepads, &gamepad_buttons);
+}
+
+/// Add this frame's intents, actions and physics steps to the recording
+pub fn record_player_intents(
+    mut recorder: ResMut<InputRecorder>,
+    query: Query<(&Movement, &Lookaround, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+) {
+    let mut subjects: Vec<(&Movement, &Lookaround, Option<&LocalPlayer>)> = query.iter().collect();
+    subjects.sort_by_key(|(_, _, local_player)| subject_order(*local_player));
+    let frame = RecordedFrame {
+        subjects: subjects
+            .into_iter()
+            .enumerate()
+            .map(|(index, (movement, lookaround, _))| {
+                let jump = recorder.jumps.get(index).cloned().unwrap_or(false);
+                SubjectFrame::from_intents(movement, lookaround, jump)
+            })
+            .collect(),
+        pause: recorder.pause,
+        steps: recorder.steps,
+    };
+    if let Some(recording) = recorder.recording.as_mut() {
+        recording.frames.push(frame);
+    }
+}
+
+/// Note where every subject ended up, and save the recording if it has a file. Physics goes
+/// back to its usual timestep.
+pub fn save_recording(
+    mut recorder: ResMut<InputRecorder>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    query: Query<(&Transform, Option<&LocalPlayer>), With<FirstPersonSubject>>,
+) {
+    let mut subjects: Vec<(&Transform, Option<&LocalPlayer>)> = query.iter().collect();
+    subjects.sort_by_key(|(_, local_player)| subject_order(*local_player));
+    let path = recorder.path.clone();
+    let recording = match recorder.recording.as_mut() {
+        Some(recording) => recording,
+        None => return,
+    };
+    integration_parameters.dt = recording.timestep;
+    rapier_config.timestep_mode = TimestepMode::VariableTimestep;
+    recording.final_positions = subjects
+        .into_iter()
+        .map(|(transform, _)| transform.translation)
+        .collect();
+    info!("Recorded {} frames", recording.frames.len());
+    if let Some(path) = path {
+        match recording.save(&path) {
+            Ok(()) => info!("Saved the recording to {}", path.display()),
+            Err(save_err) => error!(
+                "Could not save the recording to {}: {:?}",
+                path.display(),
+                save_err
+            ),
+        }
+    }
+}
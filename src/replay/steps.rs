@@ -0,0 +1,51 @@
+use crate::systems::player::move_player_body;
+use bevy::prelude::*;
+use bevy_rapier3d::physics::step_world_system;
+use bevy_rapier3d::prelude::*;
+
+/// How many times physics steps by the fixed timestep this frame, while a run is recorded or
+/// replayed. The [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) takes the first
+/// step, [`run_extra_physics_steps`] the rest.
+///
+/// Note: Rapier resets the forces on a body after every step, so the players' movement forces are
+/// applied again before each extra step. A jump only pushes on the first one, so it's the same
+/// jump however many steps the frame takes.
+pub struct PhysicsSteps {
+    steps: u8,
+    extra_step: SystemStage,
+}
+
+impl Default for PhysicsSteps {
+    fn default() -> Self {
+        PhysicsSteps {
+            steps: 0,
+            extra_step: SystemStage::single_threaded()
+                .with_system(move_player_body.label("move-player-body"))
+                .with_system(step_world_system::<NoUserData>.after("move-player-body")),
+        }
+    }
+}
+
+impl PhysicsSteps {
+    pub fn steps(&self) -> u8 {
+        self.steps
+    }
+
+    /// Step `steps` times this frame, or not at all if it's 0
+    pub fn set(&mut self, steps: u8, rapier_config: &mut RapierConfiguration) {
+        self.steps = steps;
+        rapier_config.physics_pipeline_active = steps > 0;
+    }
+}
+
+/// Step physics the rest of the [`PhysicsSteps`](crate::replay::PhysicsSteps) this frame takes,
+/// after the [`RapierPhysicsPlugin`](bevy_rapier3d::prelude::RapierPhysicsPlugin) has taken the first
+pub fn run_extra_physics_steps(world: &mut World) {
+    world.resource_scope(|world, mut physics_steps: Mut<PhysicsSteps>| {
+        for _ in 1..physics_steps.steps {
+            physics_steps.extra_step.run(world);
+        }
+        // Both the recorder and the replay add this system, only step once
+        physics_steps.steps = 0;
+    });
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+/// This resource records the last [`Checkpoint`](crate::components::Checkpoint) reached in the
+/// current level, so walking back through it doesn't take another snapshot.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckpointProgress {
+    last_reached: Option<Entity>,
+}
+
+impl CheckpointProgress {
+    /// Record that a checkpoint was reached. Returns `false` if it was already the last one.
+    pub fn reach(&mut self, checkpoint: Entity) -> bool {
+        if self.last_reached == Some(checkpoint) {
+            return false;
+        }
+        self.last_reached = Some(checkpoint);
+        true
+    }
+
+    pub fn last_reached(&self) -> Option<Entity> {
+        self.last_reached
+    }
+
+    pub fn clear(&mut self) {
+        self.last_reached = None;
+    }
+}
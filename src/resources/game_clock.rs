@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+/// The virtual clock that gameplay runs on. Unlike [`Time`](bevy::core::Time), this clock
+/// stops while the game is paused and can be slowed down or sped up with a time scale.
+///
+/// Gameplay systems (timers, animations, cooldowns, etc) should read their delta
+/// from this resource instead of [`Time`](bevy::core::Time).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameClock {
+    delta: Duration,
+    elapsed: Duration,
+    time_scale: f32,
+    paused: bool,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            time_scale: 1f32,
+            paused: false,
+        }
+    }
+}
+
+impl GameClock {
+    /// Advance the clock by a real (wall clock) delta. The delta is scaled by the
+    /// time scale, and ignored entirely if the clock is paused.
+    pub fn advance(&mut self, real_delta: Duration) {
+        if self.paused {
+            self.delta = Duration::ZERO;
+            return;
+        }
+        self.delta = real_delta.mul_f32(self.time_scale);
+        self.elapsed += self.delta;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.delta = Duration::ZERO;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Set the rate at which game time passes relative to real time. `1.0` is real time,
+    /// values below `1.0` are slow motion. Negative and non-finite values are clamped to `0.0`.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = if time_scale.is_finite() {
+            time_scale.max(0f32)
+        } else {
+            0f32
+        };
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// The amount of game time that passed during the last frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// The total amount of game time that has passed
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default() {
+        let clock = GameClock::default();
+        assert_eq!(clock.delta(), Duration::ZERO);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+        assert_eq!(clock.time_scale(), 1f32);
+        assert!(!clock.is_paused());
+    }
+
+    #[test]
+    fn advance() {
+        let mut clock = GameClock::default();
+        clock.advance(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(clock.delta(), Duration::from_millis(50));
+        assert_eq!(clock.elapsed(), Duration::from_millis(150));
+    }
+
+    #[test]
+    fn advance_while_paused() {
+        let mut clock = GameClock::default();
+        clock.advance(Duration::from_millis(100));
+        clock.pause();
+        assert_eq!(clock.delta(), Duration::ZERO);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.delta(), Duration::ZERO);
+        assert_eq!(clock.elapsed(), Duration::from_millis(100));
+        clock.resume();
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.elapsed(), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn set_time_scale() {
+        let mut clock = GameClock::default();
+        clock.set_time_scale(0.5);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.delta(), Duration::from_millis(50));
+        clock.set_time_scale(-2f32);
+        assert_eq!(clock.time_scale(), 0f32);
+        clock.set_time_scale(f32::NAN);
+        assert_eq!(clock.time_scale(), 0f32);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.elapsed(), Duration::from_millis(50));
+    }
+}
@@ -1,7 +1,14 @@
 use bevy::utils::tracing::Level as LogLevel;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Clone)]
+/// Where the game reads its config from, relative to the working directory
+pub const GAME_CONFIG_PATH: &str = "assets/config.toml";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlayerConfig {
     /// The height of the physics capsule for the player
     capsule_height: f32,
@@ -16,6 +23,82 @@ pub struct PlayerConfig {
     /// The max speed of the FirstPersonSubject.
     /// Unsure of units but likely in meters per second
     max_speed: f32,
+    /// How far from the FirstPersonHead the player can reach
+    /// an Interactable, in meters
+    #[serde(default = "default_interaction_reach")]
+    interaction_reach: f32,
+    /// The heaviest Grabbable the player can pick up, in kilograms
+    #[serde(default = "default_max_carry_mass")]
+    max_carry_mass: f32,
+    /// The Health the FirstPersonSubject starts with
+    #[serde(default = "default_max_health")]
+    max_health: f32,
+    /// How long the FirstPersonSubject can't be hurt again
+    /// after being hurt, in seconds. Fall damage still hurts
+    #[serde(default = "default_invulnerability_seconds")]
+    invulnerability_seconds: f32,
+    /// How long after being hurt the FirstPersonSubject starts
+    /// regenerating Health, in seconds
+    #[serde(default = "default_regeneration_delay_seconds")]
+    regeneration_delay_seconds: f32,
+    /// How much Health the FirstPersonSubject regenerates per second
+    #[serde(default = "default_regeneration_per_second")]
+    regeneration_per_second: f32,
+    /// The vertical impact speed the FirstPersonSubject can land at
+    /// without being hurt, in meters per second
+    #[serde(default = "default_fall_damage_min_speed")]
+    fall_damage_min_speed: f32,
+    /// The damage for every meter per second of vertical impact speed
+    /// over the minimum
+    #[serde(default = "default_fall_damage_per_speed")]
+    fall_damage_per_speed: f32,
+    /// How many stacks of items the FirstPersonSubject's Inventory holds
+    #[serde(default = "default_inventory_slots")]
+    inventory_slots: usize,
+    /// The most the items in the FirstPersonSubject's Inventory
+    /// can weigh, in kilograms
+    #[serde(default = "default_inventory_max_weight")]
+    inventory_max_weight: f32,
+}
+
+fn default_interaction_reach() -> f32 {
+    4f32
+}
+
+fn default_max_carry_mass() -> f32 {
+    30f32
+}
+
+fn default_max_health() -> f32 {
+    100f32
+}
+
+fn default_invulnerability_seconds() -> f32 {
+    0.5
+}
+
+fn default_regeneration_delay_seconds() -> f32 {
+    5f32
+}
+
+fn default_regeneration_per_second() -> f32 {
+    5f32
+}
+
+fn default_fall_damage_min_speed() -> f32 {
+    15f32
+}
+
+fn default_fall_damage_per_speed() -> f32 {
+    8f32
+}
+
+fn default_inventory_slots() -> usize {
+    12
+}
+
+fn default_inventory_max_weight() -> f32 {
+    25f32
 }
 
 impl Default for PlayerConfig {
@@ -26,6 +109,16 @@ impl Default for PlayerConfig {
             movement_force: 1000f32,
             jump_force: 10000f32,
             max_speed: 5f32,
+            interaction_reach: default_interaction_reach(),
+            max_carry_mass: default_max_carry_mass(),
+            max_health: default_max_health(),
+            invulnerability_seconds: default_invulnerability_seconds(),
+            regeneration_delay_seconds: default_regeneration_delay_seconds(),
+            regeneration_per_second: default_regeneration_per_second(),
+            fall_damage_min_speed: default_fall_damage_min_speed(),
+            fall_damage_per_speed: default_fall_damage_per_speed(),
+            inventory_slots: default_inventory_slots(),
+            inventory_max_weight: default_inventory_max_weight(),
         }
     }
 }
@@ -50,18 +143,367 @@ impl PlayerConfig {
     pub fn max_speed(&self) -> f32 {
         self.max_speed
     }
+
+    pub fn interaction_reach(&self) -> f32 {
+        self.interaction_reach
+    }
+
+    pub fn max_carry_mass(&self) -> f32 {
+        self.max_carry_mass
+    }
+
+    pub fn max_health(&self) -> f32 {
+        self.max_health
+    }
+
+    pub fn invulnerability(&self) -> Duration {
+        Duration::from_secs_f32(self.invulnerability_seconds.max(0f32))
+    }
+
+    pub fn regeneration_delay(&self) -> Duration {
+        Duration::from_secs_f32(self.regeneration_delay_seconds.max(0f32))
+    }
+
+    pub fn regeneration_per_second(&self) -> f32 {
+        self.regeneration_per_second
+    }
+
+    /// The damage for landing at `impact_speed`, in meters per second
+    pub fn fall_damage(&self, impact_speed: f32) -> f32 {
+        (impact_speed - self.fall_damage_min_speed).max(0f32) * self.fall_damage_per_speed
+    }
+
+    pub fn inventory_slots(&self) -> usize {
+        self.inventory_slots
+    }
+
+    pub fn inventory_max_weight(&self) -> f32 {
+        self.inventory_max_weight
+    }
+}
+
+/// How NPCs are built and how they get around
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NpcConfig {
+    /// The height of the physics capsule for an NPC
+    #[serde(default = "default_npc_capsule_height")]
+    capsule_height: f32,
+    /// The radius of the physics capsule for an NPC
+    #[serde(default = "default_npc_capsule_radius")]
+    capsule_radius: f32,
+    /// The most force that steers an NPC, like the FirstPersonSubject's movement force
+    #[serde(default = "default_npc_movement_force")]
+    movement_force: f32,
+    /// How fast an NPC walks while patrolling, in meters per second
+    #[serde(default = "default_npc_walk_speed")]
+    walk_speed: f32,
+    /// How fast an NPC runs while chasing, in meters per second
+    #[serde(default = "default_npc_chase_speed")]
+    chase_speed: f32,
+    /// How far an NPC can see the FirstPersonSubject from, in meters
+    #[serde(default = "default_npc_sight_distance")]
+    sight_distance: f32,
+    /// How wide an NPC's view cone is, from one edge to the other, in degrees
+    #[serde(default = "default_npc_view_angle_degrees")]
+    view_angle_degrees: f32,
+    /// How long an NPC remembers where it last saw or heard the FirstPersonSubject, in seconds
+    #[serde(default = "default_npc_memory_seconds")]
+    memory_seconds: f32,
+    /// How long an NPC waits at each patrol waypoint, in seconds
+    #[serde(default = "default_npc_patrol_wait_seconds")]
+    patrol_wait_seconds: f32,
+    /// The size of a navigation grid cell, in meters
+    #[serde(default = "default_nav_cell_size")]
+    nav_cell_size: f32,
+    /// The highest ledge an NPC can step up or down, in meters
+    #[serde(default = "default_nav_max_step")]
+    nav_max_step: f32,
+    /// The steepest ground an NPC can walk on, in degrees
+    #[serde(default = "default_nav_max_slope_degrees")]
+    nav_max_slope_degrees: f32,
+}
+
+fn default_npc_capsule_height() -> f32 {
+    2f32
+}
+
+fn default_npc_capsule_radius() -> f32 {
+    0.4
+}
+
+fn default_npc_movement_force() -> f32 {
+    40f32
+}
+
+fn default_npc_walk_speed() -> f32 {
+    2f32
+}
+
+fn default_npc_chase_speed() -> f32 {
+    4.5
+}
+
+fn default_npc_sight_distance() -> f32 {
+    20f32
+}
+
+fn default_npc_view_angle_degrees() -> f32 {
+    120f32
+}
+
+fn default_npc_memory_seconds() -> f32 {
+    10f32
+}
+
+fn default_npc_patrol_wait_seconds() -> f32 {
+    2f32
+}
+
+fn default_nav_cell_size() -> f32 {
+    0.5
+}
+
+fn default_nav_max_step() -> f32 {
+    0.35
+}
+
+fn default_nav_max_slope_degrees() -> f32 {
+    40f32
+}
+
+impl Default for NpcConfig {
+    fn default() -> Self {
+        NpcConfig {
+            capsule_height: default_npc_capsule_height(),
+            capsule_radius: default_npc_capsule_radius(),
+            movement_force: default_npc_movement_force(),
+            walk_speed: default_npc_walk_speed(),
+            chase_speed: default_npc_chase_speed(),
+            sight_distance: default_npc_sight_distance(),
+            view_angle_degrees: default_npc_view_angle_degrees(),
+            memory_seconds: default_npc_memory_seconds(),
+            patrol_wait_seconds: default_npc_patrol_wait_seconds(),
+            nav_cell_size: default_nav_cell_size(),
+            nav_max_step: default_nav_max_step(),
+            nav_max_slope_degrees: default_nav_max_slope_degrees(),
+        }
+    }
+}
+
+impl NpcConfig {
+    pub fn capsule_height(&self) -> f32 {
+        self.capsule_height
+    }
+
+    pub fn capsule_radius(&self) -> f32 {
+        self.capsule_radius
+    }
+
+    pub fn movement_force(&self) -> f32 {
+        self.movement_force
+    }
+
+    pub fn walk_speed(&self) -> f32 {
+        self.walk_speed
+    }
+
+    pub fn chase_speed(&self) -> f32 {
+        self.chase_speed
+    }
+
+    pub fn sight_distance(&self) -> f32 {
+        self.sight_distance
+    }
+
+    /// How wide an NPC's view cone is, from one edge to the other, in radians
+    pub fn view_angle(&self) -> f32 {
+        self.view_angle_degrees.to_radians()
+    }
+
+    pub fn memory(&self) -> Duration {
+        Duration::from_secs_f32(self.memory_seconds.max(0f32))
+    }
+
+    pub fn patrol_wait(&self) -> Duration {
+        Duration::from_secs_f32(self.patrol_wait_seconds.max(0f32))
+    }
+
+    pub fn nav_cell_size(&self) -> f32 {
+        self.nav_cell_size
+    }
+
+    pub fn nav_max_step(&self) -> f32 {
+        self.nav_max_step
+    }
+
+    /// The steepest ground an NPC can walk on, in radians
+    pub fn nav_max_slope(&self) -> f32 {
+        self.nav_max_slope_degrees.to_radians()
+    }
+}
+
+/// How the coefficients of two touching colliders are combined, like Rapier's
+/// `CoefficientCombineRule`. When the colliders' rules differ, the one furthest down the list wins.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CombineRule {
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl Default for CombineRule {
+    fn default() -> Self {
+        CombineRule::Average
+    }
+}
+
+/// A named physics material colliders can be made of, see
+/// [`Surface`](crate::components::Surface)
+///
+/// ```toml
+/// [materials.ice]
+/// friction = 0.02
+/// footsteps = "ice"
+/// impacts = "ice"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SurfaceMaterial {
+    #[serde(default = "default_material_friction")]
+    friction: f32,
+    #[serde(default)]
+    friction_combine: CombineRule,
+    #[serde(default)]
+    restitution: f32,
+    #[serde(default)]
+    restitution_combine: CombineRule,
+    /// The sounds of walking on it, by their surface in `assets/sounds.toml`
+    #[serde(default)]
+    footsteps: Option<String>,
+    /// The sounds of something hitting it, by their surface in `assets/sounds.toml`
+    #[serde(default)]
+    impacts: Option<String>,
+    /// How fast the FirstPersonSubject moves while standing on it,
+    /// compared to its max speed and movement force
+    #[serde(default = "default_material_speed_modifier")]
+    speed_modifier: f32,
+}
+
+fn default_material_friction() -> f32 {
+    0.5
+}
+
+fn default_material_speed_modifier() -> f32 {
+    1f32
+}
+
+impl Default for SurfaceMaterial {
+    fn default() -> Self {
+        SurfaceMaterial {
+            friction: default_material_friction(),
+            friction_combine: CombineRule::default(),
+            restitution: 0f32,
+            restitution_combine: CombineRule::default(),
+            footsteps: None,
+            impacts: None,
+            speed_modifier: default_material_speed_modifier(),
+        }
+    }
+}
+
+impl SurfaceMaterial {
+    pub fn friction(&self) -> f32 {
+        self.friction
+    }
+
+    pub fn friction_combine(&self) -> CombineRule {
+        self.friction_combine
+    }
+
+    pub fn restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    pub fn restitution_combine(&self) -> CombineRule {
+        self.restitution_combine
+    }
+
+    pub fn footsteps(&self) -> Option<&str> {
+        self.footsteps.as_deref()
+    }
+
+    pub fn impacts(&self) -> Option<&str> {
+        self.impacts.as_deref()
+    }
+
+    pub fn speed_modifier(&self) -> f32 {
+        self.speed_modifier.max(0f32)
+    }
+}
+
+/// A material that sounds like the surface of the same name
+fn sounding_material(name: &str, friction: f32, speed_modifier: f32) -> SurfaceMaterial {
+    SurfaceMaterial {
+        friction,
+        footsteps: Some(String::from(name)),
+        impacts: Some(String::from(name)),
+        speed_modifier,
+        ..Default::default()
+    }
+}
+
+fn default_materials() -> BTreeMap<String, SurfaceMaterial> {
+    BTreeMap::from([
+        // The player multiplies, so it grips the ground as well as it slips on ice.
+        // On anything that's left at Rapier's default friction of 0.5, it's 2.
+        (
+            String::from("player"),
+            SurfaceMaterial {
+                friction: 4f32,
+                friction_combine: CombineRule::Multiply,
+                restitution: 0.15,
+                ..Default::default()
+            },
+        ),
+        // Steering alone decides how fast NPCs go
+        (
+            String::from("npc"),
+            SurfaceMaterial {
+                friction: 0f32,
+                friction_combine: CombineRule::Min,
+                ..Default::default()
+            },
+        ),
+        (String::from("ice"), sounding_material("ice", 0.02, 1f32)),
+        (String::from("mud"), sounding_material("mud", 1.5, 0.5)),
+        (String::from("metal"), sounding_material("metal", 0.4, 1f32)),
+        (String::from("wood"), sounding_material("wood", 0.6, 1f32)),
+        (
+            String::from("bouncy"),
+            SurfaceMaterial {
+                restitution: 0.7,
+                ..sounding_material("bouncy", 0.5, 1f32)
+            },
+        ),
+    ])
 }
 
 /// The global runtime configuration of the game. This value
 /// is loaded at runtime instead of build time and cannot be edited
 /// by the player
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameConfig {
     name: String,
     window_title: String,
     log_level: String,
     log_filter: String,
     player: PlayerConfig,
+    #[serde(default)]
+    npc: NpcConfig,
+    /// The physics materials, by name. Listing any replaces all the default ones.
+    #[serde(default = "default_materials")]
+    materials: BTreeMap<String, SurfaceMaterial>,
 }
 
 impl Default for GameConfig {
@@ -72,12 +514,24 @@ impl Default for GameConfig {
             log_level: String::from("error"),
             log_filter: String::from("none=warn"),
             player: PlayerConfig::default(),
+            npc: NpcConfig::default(),
+            materials: default_materials(),
         }
     }
 }
 
+fn parse_log_level(log_level: &str) -> Option<LogLevel> {
+    match log_level {
+        "trace" => Some(LogLevel::TRACE),
+        "debug" => Some(LogLevel::DEBUG),
+        "info" => Some(LogLevel::INFO),
+        "warn" => Some(LogLevel::WARN),
+        "error" => Some(LogLevel::ERROR),
+        _ => None,
+    }
+}
+
 impl GameConfig {
-    #[allow(dead_code)]
     pub fn try_from_toml(toml_str: String) -> Result<Self, String> {
         match toml::from_str::<GameConfig>(toml_str.as_str()) {
             Ok(config) => Ok(config),
@@ -85,6 +539,78 @@ impl GameConfig {
         }
     }
 
+    /// Read and validate the config in a TOML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let toml_str = fs::read_to_string(path)
+            .map_err(|io_err| format!("Could not read {}: {}", path.display(), io_err))?;
+        let game_config = GameConfig::try_from_toml(toml_str)?;
+        game_config.validate()?;
+        Ok(game_config)
+    }
+
+    /// Check the values that parse but make no sense, ie a negative capsule radius or an unknown
+    /// log level
+    pub fn validate(&self) -> Result<(), String> {
+        if parse_log_level(&self.log_level).is_none() {
+            return Err(format!(
+                "log_level must be \"trace\", \"debug\", \"info\", \"warn\" or \"error\", not \"{}\"",
+                self.log_level
+            ));
+        }
+        let (player, npc) = (&self.player, &self.npc);
+        let positive = [
+            ("player.capsule_height", player.capsule_height),
+            ("player.capsule_radius", player.capsule_radius),
+            ("player.max_speed", player.max_speed),
+            ("player.max_health", player.max_health),
+            ("npc.capsule_height", npc.capsule_height),
+            ("npc.capsule_radius", npc.capsule_radius),
+            ("npc.nav_cell_size", npc.nav_cell_size),
+        ];
+        for (name, value) in positive {
+            if !value.is_finite() || value <= 0f32 {
+                return Err(format!("{} must be more than 0, not {}", name, value));
+            }
+        }
+        let not_negative = [
+            ("player.movement_force", player.movement_force),
+            ("player.jump_force", player.jump_force),
+            ("player.interaction_reach", player.interaction_reach),
+            ("player.max_carry_mass", player.max_carry_mass),
+            ("player.inventory_max_weight", player.inventory_max_weight),
+            ("npc.movement_force", npc.movement_force),
+            ("npc.walk_speed", npc.walk_speed),
+            ("npc.chase_speed", npc.chase_speed),
+            ("npc.sight_distance", npc.sight_distance),
+        ];
+        for (name, value) in not_negative {
+            if !value.is_finite() || value < 0f32 {
+                return Err(format!("{} can't be negative, not {}", name, value));
+            }
+        }
+        for (prefix, capsule_height, capsule_radius) in [
+            ("player", player.capsule_height, player.capsule_radius),
+            ("npc", npc.capsule_height, npc.capsule_radius),
+        ] {
+            if capsule_height < 2f32 * capsule_radius {
+                return Err(format!(
+                    "{0}.capsule_height must be at least twice {0}.capsule_radius",
+                    prefix
+                ));
+            }
+        }
+        for (name, material) in self.materials.iter() {
+            if !material.friction.is_finite() || material.friction < 0f32 {
+                return Err(format!("materials.{}.friction can't be negative", name));
+            }
+            if !material.restitution.is_finite() || material.restitution < 0f32 {
+                return Err(format!("materials.{}.restitution can't be negative", name));
+            }
+        }
+        Ok(())
+    }
+
     pub fn name(&self) -> &String {
         &self.name
     }
@@ -101,16 +627,9 @@ impl GameConfig {
     }
 
     pub fn log_level(&self) -> LogLevel {
-        match self.log_level.as_str() {
-            "trace" => LogLevel::TRACE,
-            "debug" => LogLevel::DEBUG,
-            "info" => LogLevel::INFO,
-            "warn" => LogLevel::WARN,
-            "error" => LogLevel::ERROR,
-            _ => {
-                panic!("Unrecognized log level found! Must be \"trace\", \"debug\", \"info\", \"warn\", or \"error\"");
-            }
-        }
+        parse_log_level(&self.log_level).unwrap_or_else(|| {
+            panic!("Unrecognized log level found! Must be \"trace\", \"debug\", \"info\", \"warn\", or \"error\"");
+        })
     }
 
     pub fn log_filter(&self) -> &String {
@@ -120,6 +639,24 @@ impl GameConfig {
     pub fn player(&self) -> &PlayerConfig {
         &self.player
     }
+
+    pub fn npc(&self) -> &NpcConfig {
+        &self.npc
+    }
+
+    pub fn material(&self, name: &str) -> Option<&SurfaceMaterial> {
+        self.materials.get(name)
+    }
+
+    /// A hash of every value in the config, using FNV-1a so it's the same on every
+    /// machine and build. Replays store it to tell when they were recorded with a different config.
+    pub fn content_hash(&self) -> u64 {
+        format!("{:?}", self)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +676,86 @@ mod tests {
         assert_eq!(default_config.log_filter(), "none=warn");
     }
 
+    #[test]
+    fn content_hash() {
+        assert_eq!(
+            GameConfig::default().content_hash(),
+            GameConfig::default().content_hash()
+        );
+        let mut changed_config = GameConfig::default();
+        changed_config.player.jump_force += 1f32;
+        assert_ne!(
+            changed_config.content_hash(),
+            GameConfig::default().content_hash()
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(GameConfig::default().validate(), Ok(()));
+        assert_eq!(
+            GameConfig::load(GAME_CONFIG_PATH).unwrap().validate(),
+            Ok(())
+        );
+
+        let mut bad_config = GameConfig::default();
+        bad_config.player.capsule_radius = -1f32;
+        assert_eq!(
+            bad_config.validate(),
+            Err(String::from(
+                "player.capsule_radius must be more than 0, not -1"
+            ))
+        );
+        let mut bad_config = GameConfig::default();
+        bad_config.npc.capsule_height = 0.5;
+        assert_eq!(
+            bad_config.validate(),
+            Err(String::from(
+                "npc.capsule_height must be at least twice npc.capsule_radius"
+            ))
+        );
+        let mut bad_config = GameConfig::default();
+        bad_config.player.jump_force = f32::NAN;
+        assert!(bad_config.validate().is_err());
+        let mut bad_config = GameConfig::default();
+        bad_config.log_level = String::from("loud");
+        assert!(bad_config.validate().is_err());
+    }
+
+    #[test]
+    fn materials_from_toml() {
+        let config = GameConfig::try_from_toml(String::from(
+            "
+        name = \"some name\"
+        window_title = \"some title\"
+        log_level = \"trace\"
+        log_filter = \"some=trace\"
+        [player]
+        capsule_height = 8
+        capsule_radius = 1
+        movement_force = 1000
+        jump_force = 10000
+        max_speed = 5
+        [materials.carpet]
+        friction = 0.9
+        friction_combine = \"max\"
+        footsteps = \"cloth\"
+        speed_modifier = 0.8
+        ",
+        ))
+        .unwrap();
+        let carpet = config.material("carpet").unwrap();
+        assert_eq!(carpet.friction(), 0.9);
+        assert_eq!(carpet.friction_combine(), CombineRule::Max);
+        assert_eq!(carpet.restitution(), 0f32);
+        assert_eq!(carpet.restitution_combine(), CombineRule::Average);
+        assert_eq!(carpet.footsteps(), Some("cloth"));
+        assert_eq!(carpet.impacts(), None);
+        assert_eq!(carpet.speed_modifier(), 0.8);
+        // Listing materials replaces the defaults
+        assert!(config.material("ice").is_none());
+    }
+
     #[test]
     fn try_from_toml() {
         // Test normal conditions
@@ -167,6 +784,31 @@ mod tests {
         assert_eq!(good_config.player().movement_force(), 1000f32);
         assert_eq!(good_config.player().jump_force(), 10000f32);
         assert_eq!(good_config.player().max_speed(), 5f32);
+        assert_eq!(good_config.player().interaction_reach(), 4f32);
+        assert_eq!(good_config.player().max_carry_mass(), 30f32);
+        assert_eq!(good_config.player().max_health(), 100f32);
+        assert_eq!(
+            good_config.player().invulnerability(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            good_config.player().regeneration_delay(),
+            Duration::from_secs(5)
+        );
+        assert_eq!(good_config.player().regeneration_per_second(), 5f32);
+        assert_eq!(good_config.player().fall_damage(10f32), 0f32);
+        assert_eq!(good_config.player().fall_damage(20f32), 40f32);
+        assert_eq!(good_config.player().inventory_slots(), 12);
+        assert_eq!(good_config.player().inventory_max_weight(), 25f32);
+        assert_eq!(good_config.npc().capsule_height(), 2f32);
+        assert_eq!(good_config.npc().nav_cell_size(), 0.5);
+        assert_eq!(good_config.npc().patrol_wait(), Duration::from_secs(2));
+        assert_eq!(good_config.npc().memory(), Duration::from_secs(10));
+        assert_eq!(
+            good_config.material("ice").unwrap().footsteps(),
+            Some("ice")
+        );
+        assert_eq!(good_config.material("mud").unwrap().speed_modifier(), 0.5);
 
         // Test bad configs
 
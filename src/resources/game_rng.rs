@@ -0,0 +1,92 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The random number generator gameplay should draw from. It's seeded once per run, and the
+/// seed is saved in replays so a replay sees the same random numbers as the run it recorded.
+///
+/// Note: Anything random that affects gameplay should use this resource rather than another
+/// source of randomness, or replays won't reproduce it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for GameRng {
+    /// Seed from the system clock, so every run is different
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_nanos() as u64)
+            .unwrap_or_default();
+        GameRng::from_seed(seed)
+    }
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng { seed, state: seed }
+    }
+
+    /// The seed the generator started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Start over from `seed`, ie when a replay starts
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng::from_seed(seed);
+    }
+
+    /// The next number, using SplitMix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// A number from `0.0` (inclusive) to `1.0` (exclusive)
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fit exactly in an f32's mantissa
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut first = GameRng::from_seed(42);
+        let mut second = GameRng::from_seed(42);
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+        assert_ne!(
+            GameRng::from_seed(1).next_u64(),
+            GameRng::from_seed(2).next_u64()
+        );
+    }
+
+    #[test]
+    fn reseed() {
+        let mut rng = GameRng::from_seed(7);
+        let first = rng.next_u64();
+        rng.next_u64();
+        rng.reseed(7);
+        assert_eq!(rng.seed(), 7);
+        assert_eq!(rng.next_u64(), first);
+    }
+
+    #[test]
+    fn next_f32_in_range() {
+        let mut rng = GameRng::from_seed(3);
+        for _ in 0..1000 {
+            let value = rng.next_f32();
+            assert!((0f32..1f32).contains(&value));
+        }
+    }
+}
@@ -1,11 +1,36 @@
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the game keeps the player's settings, relative to the working directory
+pub const GAME_SETTINGS_PATH: &str = "settings.toml";
+
+/// The loudest a volume can be, in percent. Louder volumes are clamped to it.
+pub const MAX_VOLUME: u8 = 100;
+
+/// Read a volume, clamping it like the setters do. Anything that isn't a whole number from 0 up
+/// is an error.
+fn deserialize_volume<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let volume = u64::deserialize(deserializer)?;
+    Ok(volume.min(MAX_VOLUME as u64) as u8)
+}
 
 // The global player-editable game configuration.
-/// These settings can be edited at runtime
-#[derive(Serialize, Debug, PartialEq, Eq)]
+/// These settings can be edited at runtime. Settings missing from a file keep their default.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct GameSettings {
     horizontal_sensitivity: u8,
     vertical_sensitivity: u8,
+    /// Every sound's volume, in percent
+    #[serde(deserialize_with = "deserialize_volume")]
+    master_volume: u8,
+    /// In percent of the master volume
+    #[serde(deserialize_with = "deserialize_volume")]
+    music_volume: u8,
+    /// Sound effects and ambience, in percent of the master volume
+    #[serde(deserialize_with = "deserialize_volume")]
+    sfx_volume: u8,
 }
 
 impl Default for GameSettings {
@@ -13,6 +38,9 @@ impl Default for GameSettings {
         GameSettings {
             horizontal_sensitivity: 5,
             vertical_sensitivity: 5,
+            master_volume: 100,
+            music_volume: 80,
+            sfx_volume: 100,
         }
     }
 }
@@ -38,7 +66,30 @@ impl GameSettings {
         self.vertical_sensitivity = sensitivity;
     }
 
-    #[allow(dead_code)]
+    pub fn master_volume(&self) -> u8 {
+        self.master_volume
+    }
+
+    pub fn music_volume(&self) -> u8 {
+        self.music_volume
+    }
+
+    pub fn sfx_volume(&self) -> u8 {
+        self.sfx_volume
+    }
+
+    pub fn set_master_volume(&mut self, volume: u8) {
+        self.master_volume = volume.min(MAX_VOLUME);
+    }
+
+    pub fn set_music_volume(&mut self, volume: u8) {
+        self.music_volume = volume.min(MAX_VOLUME);
+    }
+
+    pub fn set_sfx_volume(&mut self, volume: u8) {
+        self.sfx_volume = volume.min(MAX_VOLUME);
+    }
+
     pub fn try_to_toml(&self) -> Result<String, String> {
         match toml::to_string(&self) {
             Ok(settings_string) => Ok(settings_string),
@@ -50,6 +101,36 @@ impl GameSettings {
     pub fn to_toml(&self) -> String {
         self.try_to_toml().unwrap()
     }
+
+    /// Volumes over [`MAX_VOLUME`](crate::resources::MAX_VOLUME) are clamped like they are when
+    /// they're set
+    pub fn try_from_toml(toml_str: &str) -> Result<Self, String> {
+        toml::from_str(toml_str).map_err(|toml_de_err| toml_de_err.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let toml_str = fs::read_to_string(path)
+            .map_err(|io_err| format!("Could not read {}: {}", path.display(), io_err))?;
+        GameSettings::try_from_toml(&toml_str)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.try_to_toml()?)
+            .map_err(|io_err| format!("Could not write {}: {}", path.display(), io_err))
+    }
+}
+
+/// This resource is the file the [`GameSettings`](crate::resources::GameSettings) are loaded from and
+/// saved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameSettingsFile(pub PathBuf);
+
+impl Default for GameSettingsFile {
+    fn default() -> Self {
+        GameSettingsFile(PathBuf::from(GAME_SETTINGS_PATH))
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +143,20 @@ mod tests {
         let default_settings = GameSettings::default();
         assert_eq!(default_settings.horizontal_sensitivity(), 5);
         assert_eq!(default_settings.vertical_sensitivity(), 5);
+        assert_eq!(default_settings.master_volume(), 100);
+        assert_eq!(default_settings.music_volume(), 80);
+        assert_eq!(default_settings.sfx_volume(), 100);
+    }
+
+    #[test]
+    fn set_volumes() {
+        let mut settings = GameSettings::default();
+        settings.set_master_volume(40);
+        settings.set_music_volume(0);
+        settings.set_sfx_volume(250);
+        assert_eq!(settings.master_volume(), 40);
+        assert_eq!(settings.music_volume(), 0);
+        assert_eq!(settings.sfx_volume(), 100);
     }
 
     #[test]
@@ -87,16 +182,45 @@ mod tests {
         let settings = GameSettings::default();
         assert_eq!(
             settings.try_to_toml().unwrap(),
-            "horizontal_sensitivity = 5\nvertical_sensitivity = 5\n"
+            "horizontal_sensitivity = 5\nvertical_sensitivity = 5\nmaster_volume = 100\nmusic_volume = 80\nsfx_volume = 100\n"
         );
     }
 
+    #[test]
+    fn try_from_toml() {
+        let settings = GameSettings::try_from_toml(
+            "master_volume = 40\nmusic_volume = 250\nsfx_volume = 1000\n",
+        )
+        .unwrap();
+        assert_eq!(settings.master_volume(), 40);
+        assert_eq!(settings.music_volume(), 100);
+        assert_eq!(settings.sfx_volume(), 100);
+        // Missing settings keep their default
+        assert_eq!(settings.horizontal_sensitivity(), 5);
+        assert!(GameSettings::try_from_toml("master_volume = \"loud\"").is_err());
+        assert!(GameSettings::try_from_toml("master_volume = -1").is_err());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "bevy-fp-template-settings-{}.toml",
+            std::process::id()
+        ));
+        let mut settings = GameSettings::default();
+        settings.set_sfx_volume(30);
+        settings.save(&path).unwrap();
+        assert_eq!(GameSettings::load(&path).unwrap(), settings);
+        let _ = fs::remove_file(&path);
+        assert!(GameSettings::load(&path).is_err());
+    }
+
     #[test]
     fn to_toml() {
         let settings = GameSettings::default();
         assert_eq!(
             settings.to_toml(),
-            "horizontal_sensitivity = 5\nvertical_sensitivity = 5\n"
+            "horizontal_sensitivity = 5\nvertical_sensitivity = 5\nmaster_volume = 100\nmusic_volume = 80\nsfx_volume = 100\n"
         );
     }
 }
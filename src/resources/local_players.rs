@@ -0,0 +1,114 @@
+use crate::components::InputSource;
+
+/// The most players that can share the screen
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+
+/// The players sharing this machine, in the order they joined, and the device each one uses.
+/// Whichever device joins first is player 1's, so a game can be played with only gamepads.
+///
+/// Until anyone joins there's a single player, who can use any device.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LocalPlayers {
+    inputs: Vec<InputSource>,
+}
+
+impl LocalPlayers {
+    /// Add a player using `input`. Returns the new player's index, or `None` if the device
+    /// is already used by a player or the screen is full.
+    pub fn join(&mut self, input: InputSource) -> Option<usize> {
+        if self.inputs.contains(&input) || self.inputs.len() >= MAX_LOCAL_PLAYERS {
+            return None;
+        }
+        self.inputs.push(input);
+        Some(self.inputs.len() - 1)
+    }
+
+    /// Remove the player using `input`, if any. Later players move up to fill the gap.
+    pub fn leave(&mut self, input: InputSource) {
+        self.inputs.retain(|joined| *joined != input);
+    }
+
+    pub fn is_joined(&self, input: InputSource) -> bool {
+        self.inputs.contains(&input)
+    }
+
+    /// How many players there are, which is one before anyone joins
+    pub fn count(&self) -> usize {
+        self.inputs.len().max(1)
+    }
+
+    /// Whether the screen is split. A single player can use any device.
+    pub fn is_split_screen(&self) -> bool {
+        self.inputs.len() > 1
+    }
+
+    /// The device player `index` joined with, if they joined
+    pub fn input(&self, index: usize) -> Option<InputSource> {
+        self.inputs.get(index).copied()
+    }
+
+    /// The devices that joined, in player order
+    pub fn inputs(&self) -> impl Iterator<Item = &InputSource> {
+        self.inputs.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use bevy::input::gamepad::Gamepad;
+
+    #[test]
+    fn default() {
+        let local_players = LocalPlayers::default();
+        assert_eq!(local_players.count(), 1);
+        assert!(!local_players.is_split_screen());
+        assert_eq!(local_players.input(0), None);
+    }
+
+    #[test]
+    fn join() {
+        let mut local_players = LocalPlayers::default();
+        // A gamepad joining first is player 1
+        assert_eq!(
+            local_players.join(InputSource::Gamepad(Gamepad(0))),
+            Some(0)
+        );
+        assert_eq!(local_players.count(), 1);
+        assert!(!local_players.is_split_screen());
+        assert_eq!(local_players.join(InputSource::KeyboardMouse), Some(1));
+        assert!(local_players.is_split_screen());
+        // The same device can't join twice
+        assert_eq!(local_players.join(InputSource::Gamepad(Gamepad(0))), None);
+        assert_eq!(
+            local_players.join(InputSource::Gamepad(Gamepad(3))),
+            Some(2)
+        );
+        assert_eq!(
+            local_players.join(InputSource::Gamepad(Gamepad(1))),
+            Some(3)
+        );
+        // The screen is full
+        assert_eq!(local_players.join(InputSource::Gamepad(Gamepad(2))), None);
+        assert!(!local_players.is_joined(InputSource::Gamepad(Gamepad(2))));
+    }
+
+    #[test]
+    fn leave() {
+        let mut local_players = LocalPlayers::default();
+        local_players.join(InputSource::KeyboardMouse);
+        local_players.join(InputSource::Gamepad(Gamepad(0)));
+        local_players.join(InputSource::Gamepad(Gamepad(1)));
+        local_players.leave(InputSource::KeyboardMouse);
+        assert!(!local_players.is_joined(InputSource::KeyboardMouse));
+        // The first gamepad is player 1 now
+        assert_eq!(
+            local_players.inputs().collect::<Vec<&InputSource>>(),
+            vec![
+                &InputSource::Gamepad(Gamepad(0)),
+                &InputSource::Gamepad(Gamepad(1))
+            ]
+        );
+    }
+}
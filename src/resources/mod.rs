@@ -1,5 +1,23 @@
+mod checkpoint_progress;
+mod game_clock;
 mod game_config;
+mod game_rng;
 mod game_settings;
+mod local_players;
+mod pause_reason;
+mod respawn_fade;
+mod ui_theme;
+mod unsaved_progress;
+mod weapon_bindings;
 
+pub use self::checkpoint_progress::*;
+pub use self::game_clock::*;
 pub use self::game_config::*;
+pub use self::game_rng::*;
 pub use self::game_settings::*;
+pub use self::local_players::*;
+pub use self::pause_reason::*;
+pub use self::respawn_fade::*;
+pub use self::ui_theme::*;
+pub use self::unsaved_progress::*;
+pub use self::weapon_bindings::*;
@@ -0,0 +1,72 @@
+use bevy::input::gamepad::Gamepad;
+
+/// This enum records why the game was most recently paused, so the pause menu can
+/// tell the player what happened and decide whether they're allowed to resume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The game isn't paused
+    None,
+    /// The player paused the game themselves
+    Manual,
+    /// The game window lost focus
+    FocusLost,
+    /// A gamepad disconnected while the game was running. The player can't resume until
+    /// it's reconnected or they switch to the keyboard.
+    GamepadDisconnected(Gamepad),
+}
+
+impl Default for PauseReason {
+    fn default() -> Self {
+        PauseReason::None
+    }
+}
+
+impl PauseReason {
+    /// Whether the player must do something (like reconnect a controller) before
+    /// the game can be resumed.
+    pub fn blocks_resume(&self) -> bool {
+        matches!(self, PauseReason::GamepadDisconnected(_))
+    }
+
+    /// A message to show the player in the pause menu, if there's anything to tell them.
+    pub fn notice(&self) -> Option<String> {
+        match self {
+            PauseReason::GamepadDisconnected(Gamepad(id)) => Some(format!(
+                "Controller {} disconnected. Reconnect it or press any key to use the keyboard.",
+                id + 1
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default() {
+        assert_eq!(PauseReason::default(), PauseReason::None);
+    }
+
+    #[test]
+    fn blocks_resume() {
+        assert!(!PauseReason::None.blocks_resume());
+        assert!(!PauseReason::Manual.blocks_resume());
+        assert!(!PauseReason::FocusLost.blocks_resume());
+        assert!(PauseReason::GamepadDisconnected(Gamepad(0)).blocks_resume());
+    }
+
+    #[test]
+    fn notice() {
+        assert_eq!(PauseReason::Manual.notice(), None);
+        assert_eq!(PauseReason::FocusLost.notice(), None);
+        assert_eq!(
+            PauseReason::GamepadDisconnected(Gamepad(1)).notice(),
+            Some(String::from(
+                "Controller 2 disconnected. Reconnect it or press any key to use the keyboard."
+            ))
+        );
+    }
+}
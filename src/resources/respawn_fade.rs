@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+/// How long the screen takes to fade out when a player dies, and to fade back in once
+/// they've respawned
+pub const RESPAWN_FADE_DURATION: Duration = Duration::from_millis(600);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FadePhase {
+    Idle,
+    FadingOut(Duration),
+    FadingIn(Duration),
+}
+
+/// This resource tracks the fade to black between a player dying and respawning at the
+/// last checkpoint. It's ticked with the [`GameClock`](crate::resources::GameClock), so it stops
+/// while the game is paused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RespawnFade {
+    phase: FadePhase,
+    duration: Duration,
+}
+
+impl Default for RespawnFade {
+    fn default() -> Self {
+        RespawnFade::new(RESPAWN_FADE_DURATION)
+    }
+}
+
+impl RespawnFade {
+    pub fn new(duration: Duration) -> Self {
+        RespawnFade {
+            phase: FadePhase::Idle,
+            duration,
+        }
+    }
+
+    /// Start fading out. Does nothing if a respawn is already underway.
+    pub fn start(&mut self) {
+        if self.phase == FadePhase::Idle {
+            self.phase = FadePhase::FadingOut(Duration::ZERO);
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.phase != FadePhase::Idle
+    }
+
+    /// Advance the fade. Returns `true` on the tick the screen goes fully black, which is
+    /// when the players should be respawned.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        match self.phase {
+            FadePhase::Idle => false,
+            FadePhase::FadingOut(elapsed) => {
+                let elapsed = elapsed + delta;
+                if elapsed >= self.duration {
+                    self.phase = FadePhase::FadingIn(Duration::ZERO);
+                    true
+                } else {
+                    self.phase = FadePhase::FadingOut(elapsed);
+                    false
+                }
+            }
+            FadePhase::FadingIn(elapsed) => {
+                let elapsed = elapsed + delta;
+                self.phase = if elapsed >= self.duration {
+                    FadePhase::Idle
+                } else {
+                    FadePhase::FadingIn(elapsed)
+                };
+                false
+            }
+        }
+    }
+
+    /// How opaque the black overlay is, from `0.0` to `1.0`
+    pub fn alpha(&self) -> f32 {
+        let progress = |elapsed: Duration| {
+            if self.duration.is_zero() {
+                1f32
+            } else {
+                (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1f32)
+            }
+        };
+        match self.phase {
+            FadePhase::Idle => 0f32,
+            FadePhase::FadingOut(elapsed) => progress(elapsed),
+            FadePhase::FadingIn(elapsed) => 1f32 - progress(elapsed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_out_then_in() {
+        let mut fade = RespawnFade::new(Duration::from_millis(500));
+        assert_eq!(fade.alpha(), 0f32);
+        assert!(!fade.tick(Duration::from_millis(100)));
+
+        fade.start();
+        assert!(fade.is_active());
+        assert!(!fade.tick(Duration::from_millis(250)));
+        assert!((fade.alpha() - 0.5f32).abs() < 1e-5);
+        assert!(fade.tick(Duration::from_millis(250)));
+        assert_eq!(fade.alpha(), 1f32);
+
+        assert!(!fade.tick(Duration::from_millis(400)));
+        assert!((fade.alpha() - 0.2f32).abs() < 1e-5);
+        assert!(!fade.tick(Duration::from_millis(100)));
+        assert!(!fade.is_active());
+        assert_eq!(fade.alpha(), 0f32);
+    }
+
+    #[test]
+    fn test_start_is_ignored_while_respawning() {
+        let mut fade = RespawnFade::new(Duration::from_millis(500));
+        fade.start();
+        fade.tick(Duration::from_millis(400));
+        fade.start();
+        assert!(fade.tick(Duration::from_millis(100)));
+
+        fade.start();
+        assert!((fade.alpha() - 1f32).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::render::color::Color;
+use bevy::ui::Interaction;
+
+/// The colors, font and text sizes used by every widget in the menu levels.
+/// Changing this resource before a menu is set up restyles the whole menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiTheme {
+    font_path: String,
+    title_font_size: f32,
+    text_font_size: f32,
+    small_font_size: f32,
+    title_color: Color,
+    text_color: Color,
+    warning_color: Color,
+    background_color: Color,
+    column_color: Color,
+    overlay_color: Color,
+    panel_color: Color,
+    button_color: Color,
+    button_hovered_color: Color,
+    button_pressed_color: Color,
+    button_focused_color: Color,
+    slider_fill_color: Color,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            font_path: String::from("fonts/FiraSans-Bold.ttf"),
+            title_font_size: 40f32,
+            text_font_size: 40f32,
+            small_font_size: 20f32,
+            title_color: Color::WHITE,
+            text_color: Color::rgb(0.9, 0.9, 0.9),
+            warning_color: Color::YELLOW,
+            background_color: Color::BLUE,
+            column_color: Color::RED,
+            overlay_color: Color::rgba(0f32, 0f32, 0f32, 0.75),
+            panel_color: Color::rgb(0.1, 0.1, 0.1),
+            button_color: Color::rgb(0.15, 0.15, 0.15),
+            button_hovered_color: Color::rgb(0.25, 0.25, 0.25),
+            button_pressed_color: Color::rgb(0.35, 0.75, 0.35),
+            button_focused_color: Color::rgb(0.25, 0.25, 0.55),
+            slider_fill_color: Color::rgb(0.35, 0.75, 0.35),
+        }
+    }
+}
+
+impl UiTheme {
+    pub fn font_path(&self) -> &String {
+        &self.font_path
+    }
+
+    pub fn title_font_size(&self) -> f32 {
+        self.title_font_size
+    }
+
+    pub fn text_font_size(&self) -> f32 {
+        self.text_font_size
+    }
+
+    pub fn small_font_size(&self) -> f32 {
+        self.small_font_size
+    }
+
+    pub fn title_color(&self) -> Color {
+        self.title_color
+    }
+
+    pub fn text_color(&self) -> Color {
+        self.text_color
+    }
+
+    pub fn warning_color(&self) -> Color {
+        self.warning_color
+    }
+
+    pub fn background_color(&self) -> Color {
+        self.background_color
+    }
+
+    pub fn column_color(&self) -> Color {
+        self.column_color
+    }
+
+    pub fn overlay_color(&self) -> Color {
+        self.overlay_color
+    }
+
+    pub fn panel_color(&self) -> Color {
+        self.panel_color
+    }
+
+    pub fn button_color(&self) -> Color {
+        self.button_color
+    }
+
+    pub fn button_hovered_color(&self) -> Color {
+        self.button_hovered_color
+    }
+
+    pub fn button_pressed_color(&self) -> Color {
+        self.button_pressed_color
+    }
+
+    pub fn button_focused_color(&self) -> Color {
+        self.button_focused_color
+    }
+
+    pub fn slider_fill_color(&self) -> Color {
+        self.slider_fill_color
+    }
+
+    /// The color of a button given how the player is interacting with it
+    pub fn button_color_for(&self, interaction: &Interaction) -> Color {
+        match interaction {
+            Interaction::Clicked => self.button_pressed_color,
+            Interaction::Hovered => self.button_hovered_color,
+            Interaction::None => self.button_color,
+        }
+    }
+
+    /// Like [`button_color_for`](crate::resources::UiTheme::button_color_for), but a focused
+    /// button is highlighted unless the mouse is on it
+    pub fn button_color_with_focus(&self, interaction: &Interaction, focused: bool) -> Color {
+        match interaction {
+            Interaction::None if focused => self.button_focused_color,
+            _ => self.button_color_for(interaction),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn button_color_for() {
+        let theme = UiTheme::default();
+        assert_eq!(
+            theme.button_color_for(&Interaction::None),
+            theme.button_color()
+        );
+        assert_eq!(
+            theme.button_color_for(&Interaction::Hovered),
+            theme.button_hovered_color()
+        );
+        assert_eq!(
+            theme.button_color_for(&Interaction::Clicked),
+            theme.button_pressed_color()
+        );
+    }
+
+    #[test]
+    fn button_color_with_focus() {
+        let theme = UiTheme::default();
+        assert_eq!(
+            theme.button_color_with_focus(&Interaction::None, true),
+            theme.button_focused_color()
+        );
+        assert_eq!(
+            theme.button_color_with_focus(&Interaction::None, false),
+            theme.button_color()
+        );
+        assert_eq!(
+            theme.button_color_with_focus(&Interaction::Clicked, true),
+            theme.button_pressed_color()
+        );
+    }
+}
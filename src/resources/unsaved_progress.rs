@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+/// Gameplay code registers anything the player would lose by quitting here.
+/// Before the game quits, the player is warned about every registered entry.
+///
+/// Entries are keyed by a source name so the code that registered
+/// one can clear it again once the progress has been saved.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UnsavedProgress {
+    warnings: BTreeMap<String, String>,
+}
+
+impl UnsavedProgress {
+    /// Register (or replace) a warning about progress that hasn't been saved yet
+    pub fn mark(&mut self, source: impl Into<String>, warning: impl Into<String>) {
+        self.warnings.insert(source.into(), warning.into());
+    }
+
+    /// Clear the warning registered by a source, ie once its progress has been saved
+    pub fn clear(&mut self, source: &str) {
+        self.warnings.remove(source);
+    }
+
+    pub fn clear_all(&mut self) {
+        self.warnings.clear();
+    }
+
+    pub fn has_unsaved_progress(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &String> {
+        self.warnings.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default() {
+        let unsaved_progress = UnsavedProgress::default();
+        assert!(!unsaved_progress.has_unsaved_progress());
+        assert_eq!(unsaved_progress.warnings().count(), 0);
+    }
+
+    #[test]
+    fn mark() {
+        let mut unsaved_progress = UnsavedProgress::default();
+        unsaved_progress.mark("level", "Level progress since the last checkpoint");
+        unsaved_progress.mark("inventory", "Items picked up");
+        assert!(unsaved_progress.has_unsaved_progress());
+        assert_eq!(
+            unsaved_progress.warnings().collect::<Vec<&String>>(),
+            vec![
+                "Items picked up",
+                "Level progress since the last checkpoint"
+            ]
+        );
+
+        // Marking the same source again replaces its warning
+        unsaved_progress.mark("level", "Everything");
+        assert_eq!(unsaved_progress.warnings().count(), 2);
+    }
+
+    #[test]
+    fn clear() {
+        let mut unsaved_progress = UnsavedProgress::default();
+        unsaved_progress.mark("level", "Level progress since the last checkpoint");
+        unsaved_progress.mark("inventory", "Items picked up");
+        unsaved_progress.clear("level");
+        assert_eq!(
+            unsaved_progress.warnings().collect::<Vec<&String>>(),
+            vec!["Items picked up"]
+        );
+        unsaved_progress.clear_all();
+        assert!(!unsaved_progress.has_unsaved_progress());
+    }
+}
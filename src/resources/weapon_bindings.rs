@@ -0,0 +1,155 @@
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::mouse::MouseButton;
+
+/// Something a player does with their weapons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponAction {
+    Fire,
+    Reload,
+    NextWeapon,
+    PreviousWeapon,
+    /// Pick the weapon in this slot, counting from 0
+    Slot(usize),
+}
+
+/// An input a [`WeaponAction`](crate::resources::WeaponAction) can be bound to. The keyboard, mouse
+/// and scroll wheel are only read for players on the keyboard and mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+    ScrollUp,
+    ScrollDown,
+}
+
+/// This resource maps every [`WeaponAction`](crate::resources::WeaponAction) to the inputs that do it.
+/// An action can have any number of inputs, and one input can do several actions.
+///
+/// By default the left mouse button or the right trigger fires, R or the east face button reloads, the
+/// number keys pick a slot, and the mouse wheel or the north face button switch weapons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponBindings {
+    bindings: Vec<(WeaponAction, WeaponBinding)>,
+}
+
+/// The keys that pick a weapon slot by default, in order
+const SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+impl Default for WeaponBindings {
+    fn default() -> Self {
+        let mut bindings = WeaponBindings::empty()
+            .with(WeaponAction::Fire, WeaponBinding::Mouse(MouseButton::Left))
+            .with(
+                WeaponAction::Fire,
+                WeaponBinding::Gamepad(GamepadButtonType::RightTrigger2),
+            )
+            .with(WeaponAction::Reload, WeaponBinding::Key(KeyCode::R))
+            .with(
+                WeaponAction::Reload,
+                WeaponBinding::Gamepad(GamepadButtonType::East),
+            )
+            .with(WeaponAction::PreviousWeapon, WeaponBinding::ScrollUp)
+            .with(WeaponAction::NextWeapon, WeaponBinding::ScrollDown)
+            .with(
+                WeaponAction::NextWeapon,
+                WeaponBinding::Gamepad(GamepadButtonType::North),
+            );
+        for (slot, key) in SLOT_KEYS.iter().enumerate() {
+            bindings.bind(WeaponAction::Slot(slot), WeaponBinding::Key(*key));
+        }
+        bindings
+    }
+}
+
+impl WeaponBindings {
+    /// No action is bound to anything
+    pub fn empty() -> Self {
+        WeaponBindings {
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, action: WeaponAction, binding: WeaponBinding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Add an input to an action, keeping the ones it already has
+    pub fn bind(&mut self, action: WeaponAction, binding: WeaponBinding) {
+        if !self.bindings.contains(&(action, binding)) {
+            self.bindings.push((action, binding));
+        }
+    }
+
+    /// Remove every input from an action
+    pub fn unbind(&mut self, action: WeaponAction) {
+        self.bindings
+            .retain(|(bound_action, _)| *bound_action != action);
+    }
+
+    /// The inputs that do an action
+    pub fn bindings(&self, action: WeaponAction) -> impl Iterator<Item = WeaponBinding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(bound_action, _)| *bound_action == action)
+            .map(|(_, binding)| *binding)
+    }
+
+    /// The slots that have an input, in order
+    pub fn slots(&self) -> Vec<usize> {
+        let mut slots: Vec<usize> = self
+            .bindings
+            .iter()
+            .filter_map(|(action, _)| match action {
+                WeaponAction::Slot(slot) => Some(*slot),
+                _ => None,
+            })
+            .collect();
+        slots.sort_unstable();
+        slots.dedup();
+        slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let bindings = WeaponBindings::default();
+        assert_eq!(
+            bindings.bindings(WeaponAction::Reload).collect::<Vec<_>>(),
+            vec![
+                WeaponBinding::Key(KeyCode::R),
+                WeaponBinding::Gamepad(GamepadButtonType::East)
+            ]
+        );
+        assert_eq!(bindings.slots(), (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rebind() {
+        let mut bindings = WeaponBindings::default();
+        bindings.unbind(WeaponAction::Fire);
+        bindings.bind(WeaponAction::Fire, WeaponBinding::Key(KeyCode::Space));
+        bindings.bind(WeaponAction::Fire, WeaponBinding::Key(KeyCode::Space));
+        assert_eq!(
+            bindings.bindings(WeaponAction::Fire).collect::<Vec<_>>(),
+            vec![WeaponBinding::Key(KeyCode::Space)]
+        );
+        assert_eq!(bindings.bindings(WeaponAction::Slot(9)).count(), 0);
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Bumped whenever the layout of a save changes. Add a [`Migration`](crate::save::Migration)
+/// from the old version to [`MIGRATIONS`](crate::save::MIGRATIONS) at the same time, so old saves still load.
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// Upgrades a save from `from_version` to `from_version + 1`, before it's deserialized
+pub struct Migration {
+    pub from_version: u32,
+    pub migrate: fn(&mut toml::value::Table) -> Result<(), String>,
+}
+
+/// Every migration, oldest first. Empty until the format changes for the first time.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Where one player was and what they were doing when the game was saved.
+/// Rotations are `[x, y, z, w]` quaternions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSave {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub linear_velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    /// How far the player's head was tilted up, in radians
+    pub head_pitch: f32,
+}
+
+/// Everything a save file holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    /// The [`GameLevel::save_id`](crate::states::GameLevel::save_id) of the level that was saved
+    pub level: String,
+    /// When the game was saved, in seconds since the Unix epoch
+    pub saved_at: u64,
+    /// Ordered by [`LocalPlayer`](crate::components::LocalPlayer) index
+    pub players: Vec<PlayerSave>,
+    /// The [`Saveable`](crate::save::Saveable) components of every level object, by
+    /// [`SaveId`](crate::save::SaveId) and then by [`Saveable::SAVE_KEY`](crate::save::Saveable::SAVE_KEY)
+    #[serde(default)]
+    pub objects: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    Io(String),
+    Parse(String),
+    InvalidSlot(String),
+    /// The save was made by a newer version of the game
+    NewerVersion(u32),
+    /// There's no migration from this version
+    NoMigration(u32),
+    Migration {
+        from_version: u32,
+        message: String,
+    },
+}
+
+impl SaveGame {
+    pub fn new(level: impl Into<String>, saved_at: u64) -> Self {
+        SaveGame {
+            version: SAVE_FORMAT_VERSION,
+            level: level.into(),
+            saved_at,
+            players: Vec::new(),
+            objects: BTreeMap::new(),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, SaveError> {
+        // Going through a Value puts plain values before tables, which TOML requires
+        let value = toml::Value::try_from(self)
+            .map_err(|serialization_err| SaveError::Parse(serialization_err.to_string()))?;
+        toml::to_string(&value)
+            .map_err(|serialization_err| SaveError::Parse(serialization_err.to_string()))
+    }
+
+    /// Parse a save, migrating it to the current format version first
+    pub fn from_toml(toml_str: &str) -> Result<Self, SaveError> {
+        let value = toml::from_str::<toml::Value>(toml_str)
+            .map_err(|toml_de_err| SaveError::Parse(toml_de_err.to_string()))?;
+        migrate(value, MIGRATIONS)?
+            .try_into::<SaveGame>()
+            .map_err(|toml_de_err| SaveError::Parse(toml_de_err.to_string()))
+    }
+}
+
+/// Run `migrations` on a save until it's at [`SAVE_FORMAT_VERSION`](crate::save::SAVE_FORMAT_VERSION)
+pub fn migrate(mut value: toml::Value, migrations: &[Migration]) -> Result<toml::Value, SaveError> {
+    let table = value
+        .as_table_mut()
+        .ok_or_else(|| SaveError::Parse(String::from("A save must be a table")))?;
+    loop {
+        let version = table
+            .get("version")
+            .and_then(|version| version.as_integer())
+            .ok_or_else(|| SaveError::Parse(String::from("missing field `version`")))?
+            as u32;
+        if version == SAVE_FORMAT_VERSION {
+            break;
+        }
+        if version > SAVE_FORMAT_VERSION {
+            return Err(SaveError::NewerVersion(version));
+        }
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from_version == version)
+            .ok_or(SaveError::NoMigration(version))?;
+        (migration.migrate)(table).map_err(|message| SaveError::Migration {
+            from_version: version,
+            message,
+        })?;
+        table.insert(
+            String::from("version"),
+            toml::Value::Integer(version as i64 + 1),
+        );
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> PlayerSave {
+        PlayerSave {
+            translation: [1f32, 4.5, -3f32],
+            rotation: [0f32, 0.5, 0f32, 0.75],
+            linear_velocity: [0f32, -1f32, 0f32],
+            angular_velocity: [0f32, 0f32, 0f32],
+            head_pitch: -0.25,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut save = SaveGame::new("main", 1_600_000_000);
+        save.players.push(player());
+        let mut counter = BTreeMap::new();
+        counter.insert(String::from("counter"), toml::Value::Integer(3));
+        save.objects.insert(String::from("ball"), counter);
+
+        let toml_str = save.to_toml().unwrap();
+        assert_eq!(SaveGame::from_toml(&toml_str), Ok(save));
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let mut save = SaveGame::new("main", 0);
+        save.version = SAVE_FORMAT_VERSION + 1;
+        let toml_str = save.to_toml().unwrap();
+        assert_eq!(
+            SaveGame::from_toml(&toml_str),
+            Err(SaveError::NewerVersion(SAVE_FORMAT_VERSION + 1))
+        );
+    }
+
+    fn rename_level_field(table: &mut toml::value::Table) -> Result<(), String> {
+        let level = table
+            .remove("map")
+            .ok_or_else(|| String::from("missing field `map`"))?;
+        table.insert(String::from("level"), level);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate() {
+        // A save from before `map` was renamed to `level`
+        let old_save = toml::from_str::<toml::Value>(&format!(
+            "version = {}\nmap = \"main\"\nsaved_at = 5\nplayers = []\n",
+            SAVE_FORMAT_VERSION - 1
+        ))
+        .unwrap();
+        let migrations = [Migration {
+            from_version: SAVE_FORMAT_VERSION - 1,
+            migrate: rename_level_field,
+        }];
+        let migrated = migrate(old_save.clone(), &migrations)
+            .unwrap()
+            .try_into::<SaveGame>()
+            .unwrap();
+        assert_eq!(migrated, SaveGame::new("main", 5));
+
+        // Without the migration it can't be loaded
+        assert_eq!(
+            migrate(old_save, &[]),
+            Err(SaveError::NoMigration(SAVE_FORMAT_VERSION - 1))
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Saving and loading the game.
+//!
+//! A [`SaveGame`](crate::save::SaveGame) holds the level, the players' bodies and heads, and the
+//! [`Saveable`](crate::save::Saveable) components of every level object with a [`SaveId`](crate::save::SaveId).
+//! Saves are TOML files in [`SaveSlots`](crate::save::SaveSlots), and carry a format version so older
+//! saves can be migrated when the format changes.
+mod format;
+mod saveable;
+mod slots;
+mod systems;
+
+pub use self::format::*;
+pub use self::saveable::*;
+pub use self::slots::*;
+pub use self::systems::*;
@@ -0,0 +1,108 @@
+use crate::components::LevelObject;
+use crate::save::SaveManager;
+use bevy::ecs::component::Component;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A component that's saved with the level objects it's on. Register it with
+/// [`RegisterSaveable::register_saveable`](crate::save::RegisterSaveable::register_saveable)
+/// and give the entity a [`SaveId`](crate::save::SaveId).
+pub trait Saveable: Component + Serialize + DeserializeOwned {
+    /// The name the component is saved under. It shouldn't change once saves exist, or
+    /// they'll need a [`Migration`](crate::save::Migration).
+    const SAVE_KEY: &'static str;
+}
+
+/// This component names a [`LevelObject`](crate::components::LevelObject) whose [`Saveable`](crate::save::Saveable)
+/// components are saved. The level has to spawn the entity with the same id every time, so
+/// the components can be put back on it when a save is loaded.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SaveId(String);
+
+impl SaveId {
+    pub fn new(id: impl Into<String>) -> Self {
+        SaveId(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+pub trait RegisterSaveable {
+    /// Save and load `T` along with the rest of the game. This needs the
+    /// [`SavePlugin`](crate::plugins::SavePlugin).
+    fn register_saveable<T: Saveable>(&mut self) -> &mut Self;
+}
+
+impl RegisterSaveable for App {
+    fn register_saveable<T: Saveable>(&mut self) -> &mut Self {
+        self.add_system(
+            collect_saveable::<T>
+                .after("begin-save")
+                .before("write-save"),
+        )
+        .add_system(
+            apply_saveable::<T>
+                .after("apply-loaded-players")
+                .before("finish-load"),
+        )
+    }
+}
+
+fn collect_saveable<T: Saveable>(
+    mut manager: ResMut<SaveManager>,
+    query: Query<(&SaveId, &T), With<LevelObject>>,
+) {
+    for save in manager.pending_saves_mut() {
+        for (save_id, component) in query.iter() {
+            match toml::Value::try_from(component) {
+                Ok(value) => {
+                    save.objects
+                        .entry(save_id.as_str().to_string())
+                        .or_default()
+                        .insert(T::SAVE_KEY.to_string(), value);
+                }
+                Err(serialization_err) => error!(
+                    "Could not save {} on {}: {}",
+                    T::SAVE_KEY,
+                    save_id.as_str(),
+                    serialization_err
+                ),
+            }
+        }
+    }
+}
+
+fn apply_saveable<T: Saveable>(
+    mut commands: Commands,
+    manager: Res<SaveManager>,
+    query: Query<(Entity, &SaveId), With<LevelObject>>,
+) {
+    let save = match manager.loaded_save() {
+        Some(save) => save,
+        None => return,
+    };
+    for (entity, save_id) in query.iter() {
+        let value = match save
+            .objects
+            .get(save_id.as_str())
+            .and_then(|components| components.get(T::SAVE_KEY))
+        {
+            Some(value) => value.clone(),
+            None => continue,
+        };
+        match value.try_into::<T>() {
+            Ok(component) => {
+                commands.entity(entity).insert(component);
+            }
+            Err(toml_de_err) => error!(
+                "Could not load {} on {}: {}",
+                T::SAVE_KEY,
+                save_id.as_str(),
+                toml_de_err
+            ),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::save::{SaveError, SaveGame};
+use std::fs;
+use std::path::PathBuf;
+
+/// The slot quick-save and quick-load use
+pub const QUICK_SAVE_SLOT: &str = "quicksave";
+
+const SAVE_EXTENSION: &str = "toml";
+
+/// What the load-game list shows about a slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlotInfo {
+    pub slot: String,
+    pub level: String,
+    pub saved_at: u64,
+}
+
+impl SaveSlotInfo {
+    /// How long ago the slot was saved, ie "5 minutes ago"
+    pub fn age(&self, now: u64) -> String {
+        let seconds = now.saturating_sub(self.saved_at);
+        let (count, unit) = match seconds {
+            0..=59 => return String::from("just now"),
+            60..=3599 => (seconds / 60, "minute"),
+            3600..=86399 => (seconds / 3600, "hour"),
+            _ => (seconds / 86400, "day"),
+        };
+        format!(
+            "{} {}{} ago",
+            count,
+            unit,
+            if count == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// The directory save slots are kept in. Every slot is one file named after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlots {
+    directory: PathBuf,
+}
+
+impl Default for SaveSlots {
+    fn default() -> Self {
+        SaveSlots::new("saves")
+    }
+}
+
+impl SaveSlots {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        SaveSlots {
+            directory: directory.into(),
+        }
+    }
+
+    /// Slot names become file names, so they're kept to letters, digits, `-` and `_`
+    fn slot_path(&self, slot: &str) -> Result<PathBuf, SaveError> {
+        let valid = !slot.is_empty()
+            && slot
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(SaveError::InvalidSlot(String::from(slot)));
+        }
+        Ok(self.directory.join(format!("{}.{}", slot, SAVE_EXTENSION)))
+    }
+
+    pub fn write(&self, slot: &str, save: &SaveGame) -> Result<(), SaveError> {
+        let path = self.slot_path(slot)?;
+        let toml_str = save.to_toml()?;
+        fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(path, toml_str))
+            .map_err(|io_err| SaveError::Io(io_err.to_string()))
+    }
+
+    pub fn read(&self, slot: &str) -> Result<SaveGame, SaveError> {
+        let path = self.slot_path(slot)?;
+        let toml_str =
+            fs::read_to_string(path).map_err(|io_err| SaveError::Io(io_err.to_string()))?;
+        SaveGame::from_toml(&toml_str)
+    }
+
+    /// Every slot that can be loaded, most recently saved first. Slots that can't be read are left out.
+    pub fn list(&self) -> Vec<SaveSlotInfo> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            // No saves yet
+            Err(_) => return Vec::new(),
+        };
+        let mut slots: Vec<SaveSlotInfo> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == SAVE_EXTENSION))
+            .filter_map(|path| {
+                let slot = path.file_stem()?.to_str()?.to_string();
+                let save = self.read(&slot).ok()?;
+                Some(SaveSlotInfo {
+                    slot,
+                    level: save.level,
+                    saved_at: save.saved_at,
+                })
+            })
+            .collect();
+        slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then(a.slot.cmp(&b.slot)));
+        slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_slots(name: &str) -> SaveSlots {
+        let directory = std::env::temp_dir().join(format!(
+            "bevy-fp-template-saves-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        SaveSlots::new(directory)
+    }
+
+    #[test]
+    fn test_write_read_list() {
+        let slots = temp_slots("write-read-list");
+        assert!(slots.list().is_empty());
+        slots.write("older", &SaveGame::new("main", 100)).unwrap();
+        slots
+            .write(QUICK_SAVE_SLOT, &SaveGame::new("main", 200))
+            .unwrap();
+        fs::write(slots.directory.join("broken.toml"), "not a save").unwrap();
+
+        assert_eq!(slots.read("older"), Ok(SaveGame::new("main", 100)));
+        assert_eq!(
+            slots
+                .list()
+                .into_iter()
+                .map(|info| info.slot)
+                .collect::<Vec<String>>(),
+            vec![String::from(QUICK_SAVE_SLOT), String::from("older")]
+        );
+        let _ = fs::remove_dir_all(&slots.directory);
+    }
+
+    #[test]
+    fn test_invalid_slot() {
+        let slots = temp_slots("invalid-slot");
+        assert_eq!(
+            slots.write("../escape", &SaveGame::new("main", 0)),
+            Err(SaveError::InvalidSlot(String::from("../escape")))
+        );
+        assert!(matches!(slots.read("missing"), Err(SaveError::Io(_))));
+    }
+
+    #[test]
+    fn test_age() {
+        let info = SaveSlotInfo {
+            slot: String::from(QUICK_SAVE_SLOT),
+            level: String::from("main"),
+            saved_at: 1000,
+        };
+        assert_eq!(info.age(1030), "just now");
+        assert_eq!(info.age(1060), "1 minute ago");
+        assert_eq!(info.age(1000 + 3 * 3600), "3 hours ago");
+        assert_eq!(info.age(1000 + 2 * 86400), "2 days ago");
+        // A save from the future (ie the clock changed) is just now
+        assert_eq!(info.age(0), "just now");
+    }
+}
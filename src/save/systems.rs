@@ -0,0 +1,318 @@
+use crate::components::{FirstPersonHead, FirstPersonSubject, LocalPlayer};
+use crate::save::{PlayerSave, SaveGame, SaveSlots, QUICK_SAVE_SLOT};
+use crate::states::GameLevel;
+use crate::systems::physics::body_position;
+use bevy::prelude::*;
+use bevy_rapier3d::na::Vector3;
+use bevy_rapier3d::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Save the game to a slot at the end of this frame
+pub struct SaveGameRequested {
+    pub slot: String,
+}
+
+/// Load a slot, starting its level first if it isn't running
+pub struct LoadGameRequested {
+    pub slot: String,
+}
+
+/// Keep a snapshot of the level in memory, ie when a checkpoint is reached. Every player is
+/// put at `respawn` and at rest in the snapshot, instead of where they are.
+pub struct SnapshotRequested {
+    pub respawn: Transform,
+}
+
+/// Put the level back the way the last snapshot has it
+pub struct RestoreSnapshotRequested;
+
+#[derive(Debug)]
+enum SaveDestination {
+    Slot(String),
+    Snapshot,
+}
+
+/// The saves being written or loaded. A save is gathered over one frame by the
+/// [`SavePlugin`](crate::plugins::SavePlugin) systems, and a load is applied once the level it's for has
+/// spawned its players.
+#[derive(Debug, Default)]
+pub struct SaveManager {
+    pending_saves: Vec<(SaveDestination, SaveGame)>,
+    pending_load: Option<SaveGame>,
+    players_loaded: bool,
+    snapshot: Option<SaveGame>,
+}
+
+impl SaveManager {
+    /// The saves being gathered this frame
+    pub fn pending_saves_mut(&mut self) -> impl Iterator<Item = &mut SaveGame> {
+        self.pending_saves.iter_mut().map(|(_, save)| save)
+    }
+
+    /// The last snapshot taken in this level, if any
+    pub fn snapshot(&self) -> Option<&SaveGame> {
+        self.snapshot.as_ref()
+    }
+
+    /// The save being loaded, once the players have been put back where it says
+    pub fn loaded_save(&self) -> Option<&SaveGame> {
+        self.pending_load.as_ref().filter(|_| self.players_loaded)
+    }
+}
+
+pub fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default()
+}
+
+/// F5 quick-saves, F9 quick-loads
+pub fn quick_save_or_load_on_key_pressed(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut save_requests: EventWriter<SaveGameRequested>,
+    mut load_requests: EventWriter<LoadGameRequested>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        save_requests.send(SaveGameRequested {
+            slot: String::from(QUICK_SAVE_SLOT),
+        });
+    }
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        load_requests.send(LoadGameRequested {
+            slot: String::from(QUICK_SAVE_SLOT),
+        });
+    }
+}
+
+/// Start gathering the requested saves and snapshots with the level and the players' bodies and heads
+#[allow(clippy::too_many_arguments)]
+pub fn begin_save(
+    mut save_requests: EventReader<SaveGameRequested>,
+    mut snapshot_requests: EventReader<SnapshotRequested>,
+    mut manager: ResMut<SaveManager>,
+    game_level: Res<State<GameLevel>>,
+    player_query: Query<
+        (
+            Entity,
+            &RigidBodyPositionComponent,
+            &RigidBodyVelocityComponent,
+            Option<&LocalPlayer>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    head_query: Query<(&Transform, &Parent), With<FirstPersonHead>>,
+) {
+    let slot = save_requests
+        .iter()
+        .last()
+        .map(|request| request.slot.clone());
+    let respawn = snapshot_requests
+        .iter()
+        .last()
+        .map(|request| request.respawn);
+    if slot.is_none() && respawn.is_none() {
+        return;
+    }
+    let level = match game_level.current().save_id() {
+        Some(level) => level,
+        None => {
+            warn!("Nothing to save in {:?}", game_level.current());
+            return;
+        }
+    };
+    let mut players: Vec<_> = player_query.iter().collect();
+    players.sort_by_key(|(_, _, _, local_player)| {
+        local_player.map(|local_player| local_player.index())
+    });
+
+    if let Some(slot) = slot {
+        let mut save = SaveGame::new(level, unix_time_now());
+        for (entity, position, velocity, _) in players.iter() {
+            let head_pitch = head_query
+                .iter()
+                .find(|(_, parent)| parent.0 == *entity)
+                .map(|(head_transform, _)| head_transform.rotation.to_euler(EulerRot::XYZ).0)
+                .unwrap_or(0f32);
+            let translation = position.position.translation.vector;
+            let rotation = position.position.rotation;
+            save.players.push(PlayerSave {
+                translation: [translation.x, translation.y, translation.z],
+                rotation: [rotation.i, rotation.j, rotation.k, rotation.w],
+                linear_velocity: [velocity.linvel.x, velocity.linvel.y, velocity.linvel.z],
+                angular_velocity: [velocity.angvel.x, velocity.angvel.y, velocity.angvel.z],
+                head_pitch,
+            });
+        }
+        manager
+            .pending_saves
+            .push((SaveDestination::Slot(slot), save));
+    }
+
+    if let Some(respawn) = respawn {
+        let mut snapshot = SaveGame::new(level, unix_time_now());
+        // Players respawn side by side, the way the level spawns them
+        for index in 0..players.len() {
+            let translation =
+                respawn.translation + respawn.rotation * Vec3::X * 2f32 * index as f32;
+            snapshot.players.push(PlayerSave {
+                translation: translation.to_array(),
+                rotation: respawn.rotation.to_array(),
+                linear_velocity: [0f32; 3],
+                angular_velocity: [0f32; 3],
+                head_pitch: 0f32,
+            });
+        }
+        manager
+            .pending_saves
+            .push((SaveDestination::Snapshot, snapshot));
+    }
+}
+
+/// Write the gathered saves to their slots, and keep the snapshot
+pub fn write_save(mut manager: ResMut<SaveManager>, slots: Res<SaveSlots>) {
+    let pending_saves: Vec<(SaveDestination, SaveGame)> = manager.pending_saves.drain(..).collect();
+    for (destination, save) in pending_saves {
+        match destination {
+            SaveDestination::Slot(slot) => match slots.write(&slot, &save) {
+                Ok(()) => info!("Saved the game to {}", slot),
+                Err(save_err) => error!("Could not save the game to {}: {:?}", slot, save_err),
+            },
+            SaveDestination::Snapshot => {
+                debug!("Took a snapshot of the level");
+                manager.snapshot = Some(save);
+            }
+        }
+    }
+}
+
+/// Start loading the last snapshot
+pub fn restore_snapshot(
+    mut restore_requests: EventReader<RestoreSnapshotRequested>,
+    mut manager: ResMut<SaveManager>,
+) {
+    if restore_requests.iter().last().is_none() {
+        return;
+    }
+    match manager.snapshot.clone() {
+        Some(snapshot) => {
+            manager.pending_load = Some(snapshot);
+            manager.players_loaded = false;
+        }
+        None => warn!("There's no snapshot to restore"),
+    }
+}
+
+/// Snapshots only make sense in the level they were taken in
+pub fn clear_snapshot(mut manager: ResMut<SaveManager>) {
+    manager.snapshot = None;
+}
+
+/// Read the requested slot, and start its level if it isn't the one running
+pub fn read_requested_save(
+    mut load_requests: EventReader<LoadGameRequested>,
+    mut manager: ResMut<SaveManager>,
+    slots: Res<SaveSlots>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let slot = match load_requests.iter().last() {
+        Some(request) => request.slot.clone(),
+        None => return,
+    };
+    let save = match slots.read(&slot) {
+        Ok(save) => save,
+        Err(load_err) => {
+            error!("Could not load {}: {:?}", slot, load_err);
+            return;
+        }
+    };
+    let level = match GameLevel::from_save_id(&save.level) {
+        Some(level) => level,
+        None => {
+            error!(
+                "Could not load {}, it's for an unknown level {}",
+                slot, save.level
+            );
+            return;
+        }
+    };
+    if game_level.current() != &level && game_level.set(level).is_err() {
+        warn!(
+            "Could not load {}, a level transition is already queued",
+            slot
+        );
+        return;
+    }
+    info!("Loading {}", slot);
+    manager.pending_load = Some(save);
+    manager.players_loaded = false;
+}
+
+/// Put the players back where the save being loaded says, once the level has spawned them
+pub fn apply_loaded_players(
+    mut manager: ResMut<SaveManager>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            Option<&LocalPlayer>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    mut head_query: Query<(&mut Transform, &Parent), With<FirstPersonHead>>,
+) {
+    if manager.players_loaded {
+        return;
+    }
+    let save = match manager.pending_load.as_ref() {
+        Some(save) => save,
+        None => return,
+    };
+    let mut players: Vec<_> = player_query.iter_mut().collect();
+    if players.is_empty() {
+        // The level hasn't spawned them yet
+        return;
+    }
+    if players.len() != save.players.len() {
+        warn!(
+            "The save has {} players, but there are {}",
+            save.players.len(),
+            players.len()
+        );
+    }
+    players.sort_by_key(|(_, _, _, local_player)| {
+        local_player.map(|local_player| local_player.index())
+    });
+    for ((entity, mut position, mut velocity, _), player_save) in
+        players.into_iter().zip(save.players.iter())
+    {
+        let [i, j, k, w] = player_save.rotation;
+        let isometry = body_position(&Transform {
+            translation: player_save.translation.into(),
+            rotation: Quat::from_xyzw(i, j, k, w),
+            ..Default::default()
+        });
+        position.position = isometry;
+        position.next_position = isometry;
+        let [x, y, z] = player_save.linear_velocity;
+        velocity.linvel = Vector3::new(x, y, z);
+        let [x, y, z] = player_save.angular_velocity;
+        velocity.angvel = Vector3::new(x, y, z);
+        for (mut head_transform, parent) in head_query.iter_mut() {
+            if parent.0 == entity {
+                head_transform.rotation = Quat::from_rotation_x(player_save.head_pitch);
+            }
+        }
+    }
+    manager.players_loaded = true;
+}
+
+/// The load is done once everything has been applied for a frame
+pub fn finish_load(mut manager: ResMut<SaveManager>) {
+    if manager.players_loaded {
+        manager.pending_load = None;
+        manager.players_loaded = false;
+        info!("Loaded the game");
+    }
+}
@@ -0,0 +1,480 @@
+use crate::components::{LevelObject, Tags};
+use crate::interaction::Interacted;
+use crate::resources::GameClock;
+use crate::scripting::{
+    MovingPlatform, TriggerEntered, TriggerExited, TriggerPhase, TriggerStayed,
+};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The shape of an entity spawned by a level script. It's kept on the entity so a mesh can be
+/// added for it when there's a renderer.
+#[derive(Component, Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScriptShape {
+    Ball {
+        radius: f32,
+    },
+    Cuboid {
+        half_extents: [f32; 3],
+    },
+    /// Upright, `half_height` being half the height of its cylinder
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+}
+
+impl ScriptShape {
+    pub fn collider(&self) -> ColliderShape {
+        match self {
+            ScriptShape::Ball { radius } => ColliderShape::ball(*radius),
+            ScriptShape::Cuboid {
+                half_extents: [x, y, z],
+            } => ColliderShape::cuboid(*x, *y, *z),
+            ScriptShape::Capsule {
+                half_height,
+                radius,
+            } => ColliderShape::capsule(
+                point![0f32, -half_height, 0f32],
+                point![0f32, *half_height, 0f32],
+                *radius,
+            ),
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        match self {
+            ScriptShape::Ball { radius } => Mesh::from(bevy::prelude::shape::UVSphere {
+                radius: *radius,
+                ..Default::default()
+            }),
+            ScriptShape::Cuboid {
+                half_extents: [x, y, z],
+            } => Mesh::from(bevy::prelude::shape::Box::new(2f32 * x, 2f32 * y, 2f32 * z)),
+            ScriptShape::Capsule {
+                half_height,
+                radius,
+            } => Mesh::from(bevy::prelude::shape::Capsule {
+                radius: *radius,
+                depth: 2f32 * half_height,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// The color of a scripted entity's mesh, if it isn't gold
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ScriptColor(pub Color);
+
+fn default_message_seconds() -> f32 {
+    3f32
+}
+
+fn default_flag_value() -> bool {
+    true
+}
+
+/// Something a level script does when a trigger goes off. In a level file, the kind of action
+/// is its `type`, ie `{ type = "show_message", text = "Hello" }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// Spawn a physics object. It's dynamic unless it's `fixed`.
+    SpawnEntity {
+        shape: ScriptShape,
+        position: [f32; 3],
+        #[serde(default)]
+        fixed: bool,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// Play a sound from the assets folder
+    PlaySound { path: String },
+    /// Switch to another level, by its save id or `main_menu`
+    ChangeLevel { level: String },
+    /// Start every [`MovingPlatform`](crate::scripting::MovingPlatform) with this tag
+    EnablePlatform { tag: String },
+    /// Open every unlocked [`Door`](crate::mechanisms::Door) with this tag
+    OpenDoor { tag: String },
+    /// Close every unlocked [`Door`](crate::mechanisms::Door) with this tag
+    CloseDoor { tag: String },
+    /// Show the player a message for a few seconds
+    ShowMessage {
+        text: String,
+        #[serde(default = "default_message_seconds")]
+        seconds: f32,
+    },
+    /// Set one of the level's [`ScriptFlags`](crate::scripting::ScriptFlags)
+    SetFlag {
+        flag: String,
+        #[serde(default = "default_flag_value")]
+        value: bool,
+    },
+    /// Dim the level's ambient light, or turn it back up if it's dimmed
+    ToggleLights,
+}
+
+/// This component gives a [`TriggerVolume`](crate::scripting::TriggerVolume) actions to run when it
+/// goes off. If it's `once`, the actions only run the first time.
+#[derive(Component, Debug, Clone)]
+pub struct TriggerActions {
+    on: TriggerPhase,
+    once: bool,
+    fired: bool,
+    actions: Vec<TriggerAction>,
+}
+
+impl TriggerActions {
+    pub fn new(on: TriggerPhase, once: bool, actions: Vec<TriggerAction>) -> Self {
+        TriggerActions {
+            on,
+            once,
+            fired: false,
+            actions,
+        }
+    }
+}
+
+/// This component gives an [`Interactable`](crate::interaction::Interactable) actions to run every
+/// time a player uses it
+#[derive(Component, Debug, Clone)]
+pub struct InteractionActions {
+    actions: Vec<TriggerAction>,
+}
+
+impl InteractionActions {
+    pub fn new(actions: Vec<TriggerAction>) -> Self {
+        InteractionActions { actions }
+    }
+}
+
+/// This event is sent for every action a trigger runs. Each kind of action is carried out by
+/// its own system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionTriggered {
+    pub trigger: Entity,
+    pub action: TriggerAction,
+}
+
+/// Flags set by level scripts. They're cleared when the level exits.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScriptFlags {
+    flags: BTreeMap<String, bool>,
+}
+
+impl ScriptFlags {
+    pub fn set(&mut self, flag: impl Into<String>, value: bool) {
+        self.flags.insert(flag.into(), value);
+    }
+
+    /// Whether a flag is set. Flags that were never set aren't.
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.flags.get(flag).copied().unwrap_or(false)
+    }
+
+    pub fn clear(&mut self) {
+        self.flags.clear();
+    }
+}
+
+/// The message a level script is showing the player, if any
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScriptMessage {
+    text: Option<String>,
+    remaining: Duration,
+}
+
+impl ScriptMessage {
+    /// Show a message, replacing the one on screen
+    pub fn show(&mut self, text: impl Into<String>, seconds: f32) {
+        self.text = Some(text.into());
+        self.remaining = Duration::from_secs_f32(seconds.max(0f32));
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        if self.text.is_none() {
+            return;
+        }
+        self.remaining = self.remaining.saturating_sub(delta);
+        if self.remaining.is_zero() {
+            self.text = None;
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    pub fn clear(&mut self) {
+        self.text = None;
+    }
+}
+
+/// Send the actions of every trigger that went off this frame
+pub fn run_trigger_actions(
+    mut entered_events: EventReader<TriggerEntered>,
+    mut stayed_events: EventReader<TriggerStayed>,
+    mut exited_events: EventReader<TriggerExited>,
+    mut actions_query: Query<&mut TriggerActions>,
+    mut action_events: EventWriter<ActionTriggered>,
+) {
+    let went_off = entered_events
+        .iter()
+        .map(|event| (event.trigger, TriggerPhase::Enter))
+        .chain(
+            stayed_events
+                .iter()
+                .map(|event| (event.trigger, TriggerPhase::Stay)),
+        )
+        .chain(
+            exited_events
+                .iter()
+                .map(|event| (event.trigger, TriggerPhase::Exit)),
+        );
+    for (trigger, phase) in went_off {
+        let mut trigger_actions = match actions_query.get_mut(trigger) {
+            Ok(trigger_actions) => trigger_actions,
+            Err(_) => continue,
+        };
+        if trigger_actions.on != phase || (trigger_actions.once && trigger_actions.fired) {
+            continue;
+        }
+        trigger_actions.fired = true;
+        for action in trigger_actions.actions.iter() {
+            action_events.send(ActionTriggered {
+                trigger,
+                action: action.clone(),
+            });
+        }
+    }
+}
+
+/// Send the actions of every interactable that was used this frame. They're sent as if a trigger
+/// ran them, with the interactable as the trigger.
+pub fn run_interaction_actions(
+    mut interacted_events: EventReader<Interacted>,
+    actions_query: Query<&InteractionActions>,
+    mut action_events: EventWriter<ActionTriggered>,
+) {
+    for interacted in interacted_events.iter() {
+        let interaction_actions = match actions_query.get(interacted.target) {
+            Ok(interaction_actions) => interaction_actions,
+            Err(_) => continue,
+        };
+        for action in interaction_actions.actions.iter() {
+            action_events.send(ActionTriggered {
+                trigger: interacted.target,
+                action: action.clone(),
+            });
+        }
+    }
+}
+
+pub fn spawn_entities_on_action(
+    mut commands: Commands,
+    mut action_events: EventReader<ActionTriggered>,
+) {
+    for event in action_events.iter() {
+        if let TriggerAction::SpawnEntity {
+            shape,
+            position,
+            fixed,
+            tags,
+        } = &event.action
+        {
+            let position = Vec3::from(*position);
+            let body_type = if *fixed {
+                RigidBodyType::Static
+            } else {
+                RigidBodyType::Dynamic
+            };
+            commands
+                .spawn()
+                .insert(LevelObject)
+                .insert(Tags::new(tags.iter().cloned()))
+                .insert(shape.clone())
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default())
+                .insert(RigidBodyPositionSync::Discrete)
+                .insert_bundle(RigidBodyBundle {
+                    body_type: body_type.into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    ..Default::default()
+                });
+        }
+    }
+}
+
+/// Give scripted entities a mesh, if there's a renderer to draw it
+pub fn add_script_shape_meshes(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    query: Query<(Entity, &ScriptShape, &Transform, Option<&ScriptColor>), Added<ScriptShape>>,
+) {
+    let (mut meshes, mut materials) = match (meshes, materials) {
+        (Some(meshes), Some(materials)) => (meshes, materials),
+        _ => return,
+    };
+    for (entity, shape, transform, color) in query.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: meshes.add(shape.mesh()),
+            material: materials.add(StandardMaterial {
+                base_color: color.map_or(Color::GOLD, |color| color.0),
+                perceptual_roughness: 1f32,
+                ..Default::default()
+            }),
+            transform: *transform,
+            ..Default::default()
+        });
+    }
+}
+
+pub fn play_sounds_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    asset_server: Option<Res<AssetServer>>,
+    audio: Option<Res<Audio>>,
+) {
+    for event in action_events.iter() {
+        if let TriggerAction::PlaySound { path } = &event.action {
+            match (&asset_server, &audio) {
+                (Some(asset_server), Some(audio)) => {
+                    audio.play(asset_server.load::<AudioSource, _>(path.as_str()));
+                }
+                _ => debug!("Can't play {}, there's no audio", path),
+            }
+        }
+    }
+}
+
+/// The level a level script or the console names, ie "main" or "main_menu"
+pub fn level_named(name: &str) -> Option<GameLevel> {
+    match name {
+        "main_menu" => Some(GameLevel::MainMenu),
+        _ => GameLevel::from_save_id(name),
+    }
+}
+
+pub fn change_level_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let level = action_events
+        .iter()
+        .filter_map(|event| match &event.action {
+            TriggerAction::ChangeLevel { level } => Some(level),
+            _ => None,
+        })
+        .last();
+    if let Some(level) = level {
+        match level_named(level) {
+            Some(next_level) => {
+                if let Err(state_err) = game_level.set(next_level) {
+                    warn!("Could not change the level to {}: {:?}", level, state_err);
+                }
+            }
+            None => warn!("There's no level called {}", level),
+        }
+    }
+}
+
+pub fn enable_platforms_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    mut platform_query: Query<(&Tags, &mut MovingPlatform)>,
+) {
+    for event in action_events.iter() {
+        if let TriggerAction::EnablePlatform { tag } = &event.action {
+            for (tags, mut platform) in platform_query.iter_mut() {
+                if tags.contains(tag) {
+                    platform.set_enabled(true);
+                }
+            }
+        }
+    }
+}
+
+pub fn show_messages_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    mut message: ResMut<ScriptMessage>,
+) {
+    for event in action_events.iter() {
+        if let TriggerAction::ShowMessage { text, seconds } = &event.action {
+            message.show(text.clone(), *seconds);
+        }
+    }
+}
+
+pub fn set_flags_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    mut flags: ResMut<ScriptFlags>,
+) {
+    for event in action_events.iter() {
+        if let TriggerAction::SetFlag { flag, value } = &event.action {
+            flags.set(flag.clone(), *value);
+        }
+    }
+}
+
+pub fn toggle_lights_on_action(
+    mut action_events: EventReader<ActionTriggered>,
+    ambient_light: Option<ResMut<AmbientLight>>,
+) {
+    let toggles = action_events
+        .iter()
+        .filter(|event| event.action == TriggerAction::ToggleLights)
+        .count();
+    let mut ambient_light = match ambient_light {
+        Some(ambient_light) => ambient_light,
+        None => return,
+    };
+    if toggles % 2 == 1 {
+        ambient_light.brightness = if ambient_light.brightness > 0.5 {
+            0.2
+        } else {
+            1f32
+        };
+    }
+}
+
+pub fn tick_script_message(game_clock: Res<GameClock>, mut message: ResMut<ScriptMessage>) {
+    message.tick(game_clock.delta());
+}
+
+/// Flags and messages only make sense in the level that set them
+pub fn reset_script_state(mut flags: ResMut<ScriptFlags>, mut message: ResMut<ScriptMessage>) {
+    flags.clear();
+    message.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_disappears() {
+        let mut message = ScriptMessage::default();
+        assert_eq!(message.text(), None);
+        message.show("Hello", 1f32);
+        message.tick(Duration::from_millis(600));
+        assert_eq!(message.text(), Some("Hello"));
+        message.tick(Duration::from_millis(600));
+        assert_eq!(message.text(), None);
+    }
+
+    #[test]
+    fn test_level_named() {
+        assert_eq!(level_named("main"), Some(GameLevel::Main));
+        assert_eq!(level_named("main_menu"), Some(GameLevel::MainMenu));
+        assert_eq!(level_named("pause_menu"), None);
+    }
+}
@@ -0,0 +1,878 @@
+use crate::components::{DamageType, HazardVolume, LevelObject, Surface, Tags};
+use crate::interaction::{Grabbable, Interactable};
+use crate::inventory::{ItemPickup, ItemPickupState};
+use crate::mechanisms::{
+    Door, DoorKind, DoorState, KeyItem, KeyItemState, Switch, SwitchKind, SwitchState,
+};
+use crate::npc::{Npc, NpcBehavior};
+use crate::save::SaveId;
+use crate::scripting::{
+    InteractionActions, MovingPlatform, ScriptColor, ScriptShape, TriggerAction, TriggerActions,
+    TriggerPhase, TriggerVolume,
+};
+use crate::states::GameLevel;
+use crate::systems::physics::body_position;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A box-shaped [`TriggerVolume`](crate::scripting::TriggerVolume) and the actions it runs
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TriggerDefinition {
+    pub position: [f32; 3],
+    pub half_extents: [f32; 3],
+    /// Only entities with one of these tags set the trigger off. Any entity does if it's empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub on: TriggerPhase,
+    #[serde(default)]
+    pub once: bool,
+    #[serde(default)]
+    pub actions: Vec<TriggerAction>,
+}
+
+/// A box-shaped [`MovingPlatform`](crate::scripting::MovingPlatform)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlatformDefinition {
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub half_extents: [f32; 3],
+    /// In meters per second
+    pub speed: f32,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The [`SurfaceMaterial`](crate::resources::SurfaceMaterial) it's made of
+    #[serde(default)]
+    pub material: Option<String>,
+}
+
+fn default_door_seconds() -> f32 {
+    1f32
+}
+
+fn default_interactable() -> bool {
+    true
+}
+
+/// A box-shaped [`Door`](crate::mechanisms::Door). Its `id` is the [`SaveId`](crate::save::SaveId)
+/// its state is saved under.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DoorDefinition {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: DoorKind,
+    pub position: [f32; 3],
+    /// The door's turn about the vertical axis when it's closed, in degrees
+    #[serde(default)]
+    pub yaw: f32,
+    pub half_extents: [f32; 3],
+    /// How long opening or closing takes
+    #[serde(default = "default_door_seconds")]
+    pub seconds: f32,
+    /// The key that unlocks the door. It starts locked if it has one.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Whether players can open the door themselves, rather than only with switches and triggers
+    #[serde(default = "default_interactable")]
+    pub interactable: bool,
+    #[serde(default)]
+    pub open: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+    /// The [`SurfaceMaterial`](crate::resources::SurfaceMaterial) it's made of
+    #[serde(default)]
+    pub material: Option<String>,
+}
+
+/// A fixed box made of a [`SurfaceMaterial`](crate::resources::SurfaceMaterial), ie a patch of ice
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FloorDefinition {
+    pub position: [f32; 3],
+    pub half_extents: [f32; 3],
+    pub material: String,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+/// A fixed box, ie a wall
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WallDefinition {
+    pub position: [f32; 3],
+    pub half_extents: [f32; 3],
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+fn default_grabbable() -> bool {
+    true
+}
+
+/// A loose physics object, ie a crate. Players can carry it unless it isn't `grabbable`. Its `id`,
+/// if it has one, is its [`SaveId`](crate::save::SaveId), which a server replicates it under.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PropDefinition {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub shape: ScriptShape,
+    pub position: [f32; 3],
+    #[serde(default = "default_grabbable")]
+    pub grabbable: bool,
+    /// The [`SurfaceMaterial`](crate::resources::SurfaceMaterial) it's made of
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+/// A fixed box players can use, which runs its actions every time they do
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct InteractableDefinition {
+    pub position: [f32; 3],
+    pub half_extents: [f32; 3],
+    /// What using it does, ie "flip the light switch"
+    pub prompt: String,
+    /// How long the use action has to be held, in seconds. It's used straight away if there's none.
+    #[serde(default)]
+    pub hold: Option<f32>,
+    #[serde(default)]
+    pub actions: Vec<TriggerAction>,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+/// A [`Switch`](crate::mechanisms::Switch) that signals the entities with the `targets` tags
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SwitchDefinition {
+    pub id: String,
+    pub kind: SwitchKind,
+    pub position: [f32; 3],
+    pub targets: Vec<String>,
+    #[serde(default)]
+    pub on: bool,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+/// A [`KeyItem`](crate::mechanisms::KeyItem) lying in the level
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct KeyDefinition {
+    pub id: String,
+    pub key: String,
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+fn default_hazard_interval() -> f32 {
+    0.5
+}
+
+/// A box-shaped [`HazardVolume`](crate::components::HazardVolume), ie a pool of lava
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HazardDefinition {
+    pub position: [f32; 3],
+    pub half_extents: [f32; 3],
+    pub damage: f32,
+    /// How often the damage is dealt, in seconds
+    #[serde(default = "default_hazard_interval")]
+    pub interval: f32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// Only entities with one of these tags are hurt. Any entity is if it's empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+fn default_pickup_count() -> u32 {
+    1
+}
+
+/// An [`ItemPickup`](crate::inventory::ItemPickup) lying in the level
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PickupDefinition {
+    pub id: String,
+    /// The item's id in `assets/items.toml`
+    pub item: String,
+    #[serde(default = "default_pickup_count")]
+    pub count: u32,
+    pub position: [f32; 3],
+    /// Whether players pick it up by walking into it, rather than by using it
+    #[serde(default)]
+    pub on_touch: bool,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+/// An [`Npc`](crate::npc::Npc) that patrols between its waypoints, or stands idle if it has none
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NpcDefinition {
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub patrol: Vec<[f32; 3]>,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+}
+
+fn script_color(color: Option<[f32; 3]>, default: Color) -> ScriptColor {
+    ScriptColor(color.map_or(default, |[r, g, b]| Color::rgb(r, g, b)))
+}
+
+/// This component is on every entity a [`LevelScript`](crate::scripting::LevelScript) spawned, so
+/// they can be despawned without the rest of the level, ie to spawn a changed script again
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptObject;
+
+/// The triggers, platforms, floors, walls, props, interactables, doors, switches, keys, hazards, item
+/// pickups and NPCs of a level, as declared in its TOML file, ie
+///
+/// ```toml
+/// [[platforms]]
+/// tags = ["lift"]
+/// from = [0.0, 0.0, -30.0]
+/// to = [0.0, 10.0, -30.0]
+/// half_extents = [2.0, 0.2, 2.0]
+/// speed = 2.0
+/// material = "metal"
+///
+/// [[floors]]
+/// position = [-8.0, 0.1, 0.0]
+/// half_extents = [3.0, 0.05, 3.0]
+/// material = "ice"
+///
+/// [[walls]]
+/// position = [0.0, 1.5, -6.0]
+/// half_extents = [4.0, 1.5, 0.1]
+///
+/// [[props]]
+/// shape = { kind = "cuboid", half_extents = [0.5, 0.5, 0.5] }
+/// position = [4.0, 0.6, 2.0]
+/// material = "wood"
+///
+/// [[interactables]]
+/// position = [-6.0, 1.0, 2.0]
+/// half_extents = [0.5, 1.0, 0.5]
+/// prompt = "flip the light switch"
+/// hold = 0.5
+/// actions = [{ type = "toggle_lights" }]
+///
+/// [[triggers]]
+/// position = [0.0, 1.0, -25.0]
+/// half_extents = [2.0, 1.0, 2.0]
+/// tags = ["player"]
+/// once = true
+/// actions = [
+///     { type = "enable_platform", tag = "lift" },
+///     { type = "show_message", text = "Going up!" },
+/// ]
+///
+/// [[doors]]
+/// id = "vault_door"
+/// kind = "hinged"
+/// hinge = [-1.0, 0.0, 0.0]
+/// position = [0.0, 1.3, -20.0]
+/// half_extents = [1.0, 1.2, 0.1]
+/// key = "brass key"
+///
+/// [[switches]]
+/// id = "lift_lever"
+/// kind = "lever"
+/// position = [3.0, 0.6, -25.0]
+/// targets = ["lift"]
+///
+/// [[keys]]
+/// id = "brass_key"
+/// key = "brass key"
+/// position = [5.0, 0.5, 0.0]
+///
+/// [[hazards]]
+/// position = [10.0, 0.2, -10.0]
+/// half_extents = [2.0, 0.3, 2.0]
+/// damage = 15.0
+/// damage_type = "fire"
+///
+/// [[pickups]]
+/// id = "first_medkit"
+/// item = "medkit"
+/// position = [2.0, 0.5, 4.0]
+/// on_touch = true
+///
+/// [[npcs]]
+/// position = [0.0, 1.5, 10.0]
+/// patrol = [[0.0, 0.0, 10.0], [8.0, 0.0, 10.0]]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct LevelScript {
+    #[serde(default)]
+    pub triggers: Vec<TriggerDefinition>,
+    #[serde(default)]
+    pub platforms: Vec<PlatformDefinition>,
+    #[serde(default)]
+    pub floors: Vec<FloorDefinition>,
+    #[serde(default)]
+    pub walls: Vec<WallDefinition>,
+    #[serde(default)]
+    pub props: Vec<PropDefinition>,
+    #[serde(default)]
+    pub interactables: Vec<InteractableDefinition>,
+    #[serde(default)]
+    pub doors: Vec<DoorDefinition>,
+    #[serde(default)]
+    pub switches: Vec<SwitchDefinition>,
+    #[serde(default)]
+    pub keys: Vec<KeyDefinition>,
+    #[serde(default)]
+    pub hazards: Vec<HazardDefinition>,
+    #[serde(default)]
+    pub pickups: Vec<PickupDefinition>,
+    #[serde(default)]
+    pub npcs: Vec<NpcDefinition>,
+}
+
+impl LevelScript {
+    pub fn from_toml(toml_str: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_str)
+    }
+
+    /// Spawn the level's triggers, platforms, floors, walls, props, interactables, doors, switches, keys,
+    /// hazards, item pickups and NPCs, as [`LevelObject`](crate::components::LevelObject)s and [`ScriptObject`](crate::scripting::ScriptObject)s
+    pub fn spawn(&self, commands: &mut Commands) {
+        for trigger in self.triggers.iter() {
+            let [x, y, z] = trigger.half_extents;
+            commands
+                .spawn_bundle(ColliderBundle {
+                    collider_type: ColliderType::Sensor.into(),
+                    shape: ColliderShape::cuboid(x, y, z).into(),
+                    position: Vec3::from(trigger.position).into(),
+                    flags: ActiveEvents::INTERSECTION_EVENTS.into(),
+                    ..Default::default()
+                })
+                .insert(TriggerVolume::with_tags(trigger.tags.iter().cloned()))
+                .insert(TriggerActions::new(
+                    trigger.on,
+                    trigger.once,
+                    trigger.actions.clone(),
+                ))
+                .insert(LevelObject)
+                .insert(ScriptObject);
+        }
+        for platform in self.platforms.iter() {
+            let from = Vec3::from(platform.from);
+            let mut moving_platform = MovingPlatform::new(from, platform.to.into(), platform.speed);
+            moving_platform.set_enabled(platform.enabled);
+            let shape = ScriptShape::Cuboid {
+                half_extents: platform.half_extents,
+            };
+            let mut platform_commands = commands.spawn_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::KinematicPositionBased.into(),
+                position: from.into(),
+                ..Default::default()
+            });
+            platform_commands
+                .insert_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(moving_platform)
+                .insert(Tags::new(platform.tags.iter().cloned()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(from))
+                .insert(GlobalTransform::default())
+                .insert(RigidBodyPositionSync::Discrete);
+            if let Some(material) = &platform.material {
+                platform_commands.insert(Surface::new(material.clone()));
+            }
+        }
+        for floor in self.floors.iter() {
+            let shape = ScriptShape::Cuboid {
+                half_extents: floor.half_extents,
+            };
+            let position = Vec3::from(floor.position);
+            commands
+                .spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(floor.color, Color::GRAY))
+                .insert(Surface::new(floor.material.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for wall in self.walls.iter() {
+            let shape = ScriptShape::Cuboid {
+                half_extents: wall.half_extents,
+            };
+            let position = Vec3::from(wall.position);
+            commands
+                .spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(wall.color, Color::rgb(0.7, 0.7, 0.65)))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for prop in self.props.iter() {
+            let position = Vec3::from(prop.position);
+            let mut prop_commands = commands.spawn_bundle(RigidBodyBundle {
+                position: position.into(),
+                ..Default::default()
+            });
+            prop_commands
+                .insert_bundle(ColliderBundle {
+                    shape: prop.shape.collider().into(),
+                    // For impact sounds
+                    flags: ActiveEvents::CONTACT_EVENTS.into(),
+                    ..Default::default()
+                })
+                .insert(prop.shape.clone())
+                .insert(script_color(prop.color, Color::rgb(0.6, 0.4, 0.2)))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default())
+                .insert(RigidBodyPositionSync::Discrete);
+            if prop.grabbable {
+                prop_commands.insert(Grabbable);
+            }
+            if let Some(material) = &prop.material {
+                prop_commands.insert(Surface::new(material.clone()));
+            }
+            if let Some(id) = &prop.id {
+                prop_commands.insert(SaveId::new(id.clone()));
+            }
+        }
+        for interactable in self.interactables.iter() {
+            let shape = ScriptShape::Cuboid {
+                half_extents: interactable.half_extents,
+            };
+            let position = Vec3::from(interactable.position);
+            let mut interactable_component = Interactable::new(interactable.prompt.clone());
+            if let Some(hold) = interactable.hold {
+                interactable_component = interactable_component.with_hold(hold);
+            }
+            commands
+                .spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(interactable.color, Color::YELLOW))
+                .insert(interactable_component)
+                .insert(InteractionActions::new(interactable.actions.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for door in self.doors.iter() {
+            let closed = Transform::from_translation(door.position.into())
+                .with_rotation(Quat::from_rotation_y(door.yaw.to_radians()));
+            let isometry = body_position(&closed);
+            let mut door_component = Door::new(door.kind.clone(), closed, door.seconds);
+            if let Some(key) = &door.key {
+                door_component = door_component.with_key(key.clone());
+            }
+            let shape = ScriptShape::Cuboid {
+                half_extents: door.half_extents,
+            };
+            let mut door_commands = commands.spawn_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::KinematicPositionBased.into(),
+                position: RigidBodyPosition {
+                    position: isometry,
+                    next_position: isometry,
+                }
+                .into(),
+                ..Default::default()
+            });
+            door_commands
+                .insert_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(door.color, Color::rgb(0.45, 0.3, 0.2)))
+                .insert(door_component)
+                .insert(DoorState {
+                    open: door.open,
+                    locked: door.key.is_some(),
+                })
+                .insert(Tags::new(door.tags.iter().cloned()))
+                .insert(SaveId::new(door.id.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(closed)
+                .insert(GlobalTransform::default())
+                .insert(RigidBodyPositionSync::Discrete);
+            if door.interactable {
+                door_commands.insert(Interactable::new("open the door"));
+            }
+            if let Some(material) = &door.material {
+                door_commands.insert(Surface::new(material.clone()));
+            }
+        }
+        for switch in self.switches.iter() {
+            let switch_component = Switch::new(switch.kind, switch.targets.iter().cloned());
+            let shape = match switch.kind {
+                SwitchKind::Button => ScriptShape::Cuboid {
+                    half_extents: [0.25, 0.25, 0.1],
+                },
+                SwitchKind::Lever => ScriptShape::Cuboid {
+                    half_extents: [0.08, 0.5, 0.08],
+                },
+            };
+            let position = Vec3::from(switch.position);
+            commands
+                .spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(switch.color, Color::CRIMSON))
+                .insert(Interactable::new(switch_component.action()))
+                .insert(switch_component)
+                .insert(SwitchState { on: switch.on })
+                .insert(SaveId::new(switch.id.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for key in self.keys.iter() {
+            let shape = ScriptShape::Ball { radius: 0.25 };
+            let position = Vec3::from(key.position);
+            commands
+                .spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(key.color, Color::GOLD))
+                .insert(Interactable::new(format!("pick up the {}", key.key)))
+                .insert(KeyItem::new(key.key.clone()))
+                .insert(KeyItemState::default())
+                .insert(SaveId::new(key.id.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for hazard in self.hazards.iter() {
+            let shape = ScriptShape::Cuboid {
+                half_extents: hazard.half_extents,
+            };
+            let position = Vec3::from(hazard.position);
+            commands
+                .spawn_bundle(ColliderBundle {
+                    collider_type: ColliderType::Sensor.into(),
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    flags: ActiveEvents::INTERSECTION_EVENTS.into(),
+                    ..Default::default()
+                })
+                .insert(shape)
+                .insert(script_color(hazard.color, Color::ORANGE_RED))
+                .insert(TriggerVolume::with_tags(hazard.tags.iter().cloned()))
+                .insert(HazardVolume::new(
+                    hazard.damage,
+                    hazard.damage_type,
+                    hazard.interval,
+                ))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for pickup in self.pickups.iter() {
+            let shape = ScriptShape::Ball { radius: 0.25 };
+            let position = Vec3::from(pickup.position);
+            let mut entity = if pickup.on_touch {
+                let mut entity = commands.spawn_bundle(ColliderBundle {
+                    collider_type: ColliderType::Sensor.into(),
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    flags: ActiveEvents::INTERSECTION_EVENTS.into(),
+                    ..Default::default()
+                });
+                entity.insert(TriggerVolume::with_tags(["player"]));
+                entity
+            } else {
+                let mut entity = commands.spawn_bundle(ColliderBundle {
+                    shape: shape.collider().into(),
+                    position: position.into(),
+                    ..Default::default()
+                });
+                entity.insert(Interactable::new(format!("pick up the {}", pickup.item)));
+                entity
+            };
+            entity
+                .insert(shape)
+                .insert(script_color(pickup.color, Color::LIME_GREEN))
+                .insert(ItemPickup::new(pickup.item.clone()))
+                .insert(ItemPickupState {
+                    count: pickup.count,
+                })
+                .insert(SaveId::new(pickup.id.clone()))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(position))
+                .insert(GlobalTransform::default());
+        }
+        for npc in self.npcs.iter() {
+            let patrol = npc.patrol.iter().map(|waypoint| Vec3::from(*waypoint));
+            commands
+                .spawn()
+                .insert(Npc)
+                .insert(NpcBehavior::new(patrol.collect()))
+                .insert(Tags::new(["npc"]))
+                .insert(script_color(npc.color, Color::ORANGE_RED))
+                .insert(LevelObject)
+                .insert(ScriptObject)
+                .insert(Transform::from_translation(Vec3::from(npc.position)))
+                .insert(GlobalTransform::default());
+        }
+    }
+}
+
+/// This resource is the [`LevelScript`](crate::scripting::LevelScript) of every level that has one.
+/// A level spawns its script when it's set up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelScripts(HashMap<GameLevel, LevelScript>);
+
+impl LevelScripts {
+    pub fn get(&self, level: &GameLevel) -> Option<&LevelScript> {
+        self.0.get(level)
+    }
+
+    pub fn insert(&mut self, level: GameLevel, level_script: LevelScript) {
+        self.0.insert(level, level_script);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level_script() {
+        let script = LevelScript::from_toml(
+            r#"
+            [[triggers]]
+            position = [0.0, 1.0, 0.0]
+            half_extents = [1.0, 1.0, 1.0]
+            on = "exit"
+            actions = [
+                { type = "spawn_entity", shape = { kind = "ball", radius = 0.5 }, position = [0.0, 5.0, 0.0] },
+                { type = "play_sound", path = "sounds/chime.ogg" },
+                { type = "change_level", level = "main_menu" },
+                { type = "set_flag", flag = "left" },
+                { type = "show_message", text = "Bye", seconds = 1.0 },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(script.platforms.is_empty());
+        let trigger = &script.triggers[0];
+        assert_eq!(trigger.on, TriggerPhase::Exit);
+        assert!(trigger.tags.is_empty());
+        assert!(!trigger.once);
+        assert_eq!(
+            trigger.actions[0],
+            TriggerAction::SpawnEntity {
+                shape: ScriptShape::Ball { radius: 0.5 },
+                position: [0.0, 5.0, 0.0],
+                fixed: false,
+                tags: Vec::new(),
+            }
+        );
+        assert_eq!(
+            trigger.actions[3],
+            TriggerAction::SetFlag {
+                flag: String::from("left"),
+                value: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_mechanisms() {
+        let script = LevelScript::from_toml(
+            r#"
+            [[doors]]
+            id = "gate"
+            kind = "sliding"
+            offset = [2.0, 0.0, 0.0]
+            position = [0.0, 1.0, 0.0]
+            half_extents = [1.0, 1.0, 0.1]
+            interactable = false
+            tags = ["gate"]
+            material = "metal"
+
+            [[doors]]
+            id = "vault"
+            kind = "hinged"
+            hinge = [-1.0, 0.0, 0.0]
+            position = [0.0, 1.0, 5.0]
+            yaw = 90.0
+            half_extents = [1.0, 1.0, 0.1]
+            key = "brass key"
+
+            [[switches]]
+            id = "gate_lever"
+            kind = "lever"
+            position = [3.0, 0.5, 0.0]
+            targets = ["gate"]
+
+            [[keys]]
+            id = "brass_key"
+            key = "brass key"
+            position = [0.0, 0.5, 3.0]
+
+            [[triggers]]
+            position = [0.0, 1.0, 0.0]
+            half_extents = [1.0, 1.0, 1.0]
+            actions = [{ type = "close_door", tag = "gate" }]
+
+            [[hazards]]
+            position = [0.0, 0.0, 10.0]
+            half_extents = [2.0, 0.3, 2.0]
+            damage = 15.0
+            damage_type = "fire"
+
+            [[pickups]]
+            id = "coins"
+            item = "coin"
+            count = 5
+            position = [0.0, 0.5, -3.0]
+            on_touch = true
+
+            [[npcs]]
+            position = [0.0, 1.5, 8.0]
+            patrol = [[0.0, 0.0, 8.0], [4.0, 0.0, 8.0]]
+
+            [[npcs]]
+            position = [2.0, 1.5, 8.0]
+
+            [[floors]]
+            position = [0.0, 0.1, -8.0]
+            half_extents = [2.0, 0.05, 2.0]
+            material = "mud"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            script.doors[0].kind,
+            DoorKind::Sliding {
+                offset: [2f32, 0f32, 0f32]
+            }
+        );
+        assert!(!script.doors[0].interactable);
+        assert_eq!(script.doors[0].material.as_deref(), Some("metal"));
+        assert_eq!(script.doors[1].material, None);
+        assert_eq!(
+            script.doors[1].kind,
+            DoorKind::Hinged {
+                hinge: [-1f32, 0f32, 0f32],
+                angle: 90f32,
+            }
+        );
+        assert_eq!(script.doors[1].key.as_deref(), Some("brass key"));
+        assert_eq!(script.doors[1].seconds, 1f32);
+        assert_eq!(script.switches[0].kind, SwitchKind::Lever);
+        assert!(!script.switches[0].on);
+        assert_eq!(script.keys[0].key, "brass key");
+        assert_eq!(
+            script.triggers[0].actions[0],
+            TriggerAction::CloseDoor {
+                tag: String::from("gate")
+            }
+        );
+        assert_eq!(script.hazards[0].damage_type, DamageType::Fire);
+        assert_eq!(script.hazards[0].interval, 0.5);
+        assert_eq!(script.pickups[0].count, 5);
+        assert!(script.pickups[0].on_touch);
+        assert_eq!(script.npcs[0].patrol.len(), 2);
+        assert!(script.npcs[1].patrol.is_empty());
+        assert_eq!(script.floors[0].material, "mud");
+    }
+
+    #[test]
+    fn test_parse_walls_props_and_interactables() {
+        let script = LevelScript::from_toml(
+            r#"
+            [[walls]]
+            position = [0.0, 1.5, -6.0]
+            half_extents = [4.0, 1.5, 0.1]
+
+            [[props]]
+            id = "ball"
+            shape = { kind = "ball", radius = 0.5 }
+            position = [0.0, 10.0, 0.0]
+            grabbable = false
+            material = "bouncy"
+
+            [[props]]
+            shape = { kind = "cuboid", half_extents = [0.5, 0.5, 0.5] }
+            position = [4.0, 0.6, 2.0]
+
+            [[interactables]]
+            position = [-6.0, 3.0, 2.0]
+            half_extents = [0.5, 1.0, 0.5]
+            prompt = "flip the light switch"
+            hold = 0.5
+            actions = [{ type = "toggle_lights" }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(script.walls[0].color, None);
+        assert_eq!(script.props[0].id.as_deref(), Some("ball"));
+        assert!(!script.props[0].grabbable);
+        assert_eq!(script.props[0].material.as_deref(), Some("bouncy"));
+        assert_eq!(script.props[1].id, None);
+        assert!(script.props[1].grabbable);
+        assert_eq!(
+            script.props[1].shape,
+            ScriptShape::Cuboid {
+                half_extents: [0.5, 0.5, 0.5]
+            }
+        );
+        assert_eq!(script.interactables[0].hold, Some(0.5));
+        assert_eq!(
+            script.interactables[0].actions,
+            vec![TriggerAction::ToggleLights]
+        );
+    }
+
+    #[test]
+    fn test_unknown_action_is_an_error() {
+        let script = LevelScript::from_toml(
+            r#"
+            [[triggers]]
+            position = [0.0, 1.0, 0.0]
+            half_extents = [1.0, 1.0, 1.0]
+            actions = [{ type = "explode" }]
+            "#,
+        );
+        assert!(script.is_err());
+    }
+}
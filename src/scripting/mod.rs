@@ -0,0 +1,17 @@
+//! Scripting levels without code.
+//!
+//! A [`TriggerVolume`](crate::scripting::TriggerVolume) is a sensor that sends enter, stay and exit
+//! events for the entities whose [`Tags`](crate::components::Tags) it accepts. With
+//! [`TriggerActions`](crate::scripting::TriggerActions), it runs a list of
+//! [`TriggerAction`](crate::scripting::TriggerAction)s when that happens. Triggers, their actions and
+//! the moving platforms they can enable are declared in a level's [`LevelScript`](crate::scripting::LevelScript)
+//! file.
+mod actions;
+mod level_script;
+mod platform;
+mod trigger;
+
+pub use self::actions::*;
+pub use self::level_script::*;
+pub use self::platform::*;
+pub use self::trigger::*;
@@ -0,0 +1,99 @@
+use crate::resources::GameClock;
+use bevy::prelude::*;
+use bevy_rapier3d::na::Vector3;
+use bevy_rapier3d::prelude::*;
+
+/// This component moves a kinematic, position based rigid body back and forth between two
+/// points once it's enabled, ie by an [`EnablePlatform`](crate::scripting::TriggerAction::EnablePlatform) action.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct MovingPlatform {
+    from: Vec3,
+    to: Vec3,
+    /// In meters per second
+    speed: f32,
+    enabled: bool,
+    /// How far along the path from `from` to `to` the platform is, from `0.0` to `1.0`
+    progress: f32,
+    forward: bool,
+}
+
+impl MovingPlatform {
+    pub fn new(from: Vec3, to: Vec3, speed: f32) -> Self {
+        MovingPlatform {
+            from,
+            to,
+            speed,
+            enabled: false,
+            progress: 0f32,
+            forward: true,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Move along the path for `delta_seconds`, turning around at either end, and return
+    /// where the platform is now
+    pub fn advance(&mut self, delta_seconds: f32) -> Vec3 {
+        let length = self.from.distance(self.to);
+        if self.enabled && length > f32::EPSILON {
+            let mut step = self.speed * delta_seconds / length;
+            // Turn around as many times as needed, in case the step is longer than the path
+            while step > 0f32 {
+                let remaining = if self.forward {
+                    1f32 - self.progress
+                } else {
+                    self.progress
+                };
+                if step < remaining {
+                    self.progress += if self.forward { step } else { -step };
+                    break;
+                }
+                step -= remaining;
+                self.progress = if self.forward { 1f32 } else { 0f32 };
+                self.forward = !self.forward;
+            }
+        }
+        self.from.lerp(self.to, self.progress)
+    }
+}
+
+pub fn move_platforms(
+    game_clock: Res<GameClock>,
+    mut platform_query: Query<(&mut MovingPlatform, &mut RigidBodyPositionComponent)>,
+) {
+    for (mut platform, mut position) in platform_query.iter_mut() {
+        if !platform.is_enabled() {
+            continue;
+        }
+        let translation = platform.advance(game_clock.delta_seconds());
+        position.next_position.translation.vector =
+            Vector3::new(translation.x, translation.y, translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_platform_stays_put() {
+        let mut platform = MovingPlatform::new(Vec3::ZERO, Vec3::X * 4f32, 1f32);
+        assert_eq!(platform.advance(1f32), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_platform_turns_around_at_the_ends() {
+        let mut platform = MovingPlatform::new(Vec3::ZERO, Vec3::X * 4f32, 1f32);
+        platform.set_enabled(true);
+        assert!((platform.advance(1f32) - Vec3::X).length() < 1e-5);
+        assert!((platform.advance(4f32) - Vec3::X * 3f32).length() < 1e-5);
+        // A step longer than two lengths of the path
+        assert!((platform.advance(9f32) - Vec3::X * 2f32).length() < 1e-5);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::components::Tags;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+/// This component turns a sensor collider into a trigger. It should be on an entity with a
+/// [`ColliderType::Sensor`] collider that has [`ActiveEvents::INTERSECTION_EVENTS`].
+///
+/// Only entities with one of the trigger's tags set it off. A trigger without tags is set
+/// off by any entity.
+#[derive(Component, Debug, Clone, Default)]
+pub struct TriggerVolume {
+    tags: Vec<String>,
+    occupants: Vec<Entity>,
+}
+
+impl TriggerVolume {
+    pub fn with_tags<I, S>(tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        TriggerVolume {
+            tags: tags.into_iter().map(Into::into).collect(),
+            occupants: Vec::new(),
+        }
+    }
+
+    /// Whether an entity with these tags sets the trigger off
+    pub fn accepts(&self, tags: Option<&Tags>) -> bool {
+        self.tags.is_empty()
+            || tags
+                .map(|tags| self.tags.iter().any(|tag| tags.contains(tag)))
+                .unwrap_or(false)
+    }
+
+    /// The entities inside the trigger
+    pub fn occupants(&self) -> &[Entity] {
+        &self.occupants
+    }
+}
+
+/// When a [`TriggerVolume`](crate::scripting::TriggerVolume) reacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerPhase {
+    /// An entity came in
+    Enter,
+    /// An entity is still inside, sent every frame after the one it came in
+    Stay,
+    /// An entity left, or was despawned while inside
+    Exit,
+}
+
+impl Default for TriggerPhase {
+    fn default() -> Self {
+        TriggerPhase::Enter
+    }
+}
+
+/// This event is sent when an entity enters a [`TriggerVolume`](crate::scripting::TriggerVolume)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// This event is sent every frame an entity stays in a [`TriggerVolume`](crate::scripting::TriggerVolume)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerStayed {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// This event is sent when an entity leaves a [`TriggerVolume`](crate::scripting::TriggerVolume)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Keep track of what's inside every trigger, and send its enter, stay and exit events
+pub fn detect_trigger_occupants(
+    mut intersection_events: EventReader<IntersectionEvent>,
+    mut trigger_query: Query<(Entity, &mut TriggerVolume)>,
+    tags_query: Query<Option<&Tags>>,
+    mut entered_events: EventWriter<TriggerEntered>,
+    mut stayed_events: EventWriter<TriggerStayed>,
+    mut exited_events: EventWriter<TriggerExited>,
+) {
+    let mut entered_now: Vec<(Entity, Entity)> = Vec::new();
+    for event in intersection_events.iter() {
+        let (entity1, entity2) = (event.collider1.entity(), event.collider2.entity());
+        let (trigger, entity) = if trigger_query.get(entity1).is_ok() {
+            (entity1, entity2)
+        } else if trigger_query.get(entity2).is_ok() {
+            (entity2, entity1)
+        } else {
+            continue;
+        };
+        let (_, mut volume) = trigger_query.get_mut(trigger).unwrap();
+        let inside = volume.occupants.contains(&entity);
+        if event.intersecting && !inside {
+            let tags = tags_query.get(entity).ok().flatten();
+            if volume.accepts(tags) {
+                volume.occupants.push(entity);
+                entered_events.send(TriggerEntered { trigger, entity });
+                entered_now.push((trigger, entity));
+            }
+        } else if !event.intersecting && inside {
+            volume.occupants.retain(|occupant| *occupant != entity);
+            exited_events.send(TriggerExited { trigger, entity });
+        }
+    }
+
+    for (trigger, mut volume) in trigger_query.iter_mut() {
+        // Despawned entities don't send an intersection event when they go
+        let (remaining, despawned): (Vec<Entity>, Vec<Entity>) = volume
+            .occupants
+            .iter()
+            .partition(|occupant| tags_query.get(**occupant).is_ok());
+        for entity in despawned {
+            exited_events.send(TriggerExited { trigger, entity });
+        }
+        for entity in remaining.iter().copied() {
+            if !entered_now.contains(&(trigger, entity)) {
+                stayed_events.send(TriggerStayed { trigger, entity });
+            }
+        }
+        if remaining.len() != volume.occupants.len() {
+            volume.occupants = remaining;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_accepts_tags() {
+        let any = TriggerVolume::default();
+        assert!(any.accepts(None));
+        assert!(any.accepts(Some(&Tags::new(["crate"]))));
+
+        let players_only = TriggerVolume::with_tags(["player"]);
+        assert!(!players_only.accepts(None));
+        assert!(!players_only.accepts(Some(&Tags::new(["crate"]))));
+        assert!(players_only.accepts(Some(&Tags::new(["crate", "player"]))));
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// What actually plays the sounds the [`mix_sounds`](crate::sound::mix_sounds) system mixes. Volumes
+/// go from 0 to 1, with settings and distance already applied.
+pub trait AudioBackend: Default + 'static {
+    /// Play a sound once, panned from -1 for all the way left to 1 for all the way right
+    fn play(&mut self, path: &str, source: &AudioSource, volume: f32, pan: f32);
+    /// Start looping a music or ambience track
+    fn start_track(&mut self, id: u64, source: &AudioSource, volume: f32);
+    fn set_track_volume(&mut self, id: u64, volume: f32);
+    fn stop_track(&mut self, id: u64);
+}
+
+/// Plays sounds on the default output device with rodio. If there isn't one, nothing is played.
+pub struct RodioAudioBackend {
+    /// The stream only plays while it's kept around
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    tracks: HashMap<u64, Sink>,
+}
+
+impl Default for RodioAudioBackend {
+    fn default() -> Self {
+        let output = match OutputStream::try_default() {
+            Ok(output) => Some(output),
+            Err(stream_err) => {
+                warn!("No audio output, the game will be silent: {}", stream_err);
+                None
+            }
+        };
+        RodioAudioBackend {
+            output,
+            tracks: HashMap::new(),
+        }
+    }
+}
+
+impl AudioBackend for RodioAudioBackend {
+    fn play(&mut self, path: &str, source: &AudioSource, volume: f32, pan: f32) {
+        let handle = match self.output.as_ref() {
+            Some((_, handle)) => handle,
+            None => return,
+        };
+        let decoder = match Decoder::new(Cursor::new(source.bytes.clone())) {
+            Ok(decoder) => decoder,
+            Err(decode_err) => {
+                warn!("Could not play {}: {}", path, decode_err);
+                return;
+            }
+        };
+        // Equal-power panning, so a sound in the middle isn't louder than one to the side. The
+        // channels are added together first, so a stereo sound isn't louder than a mono one.
+        let angle = (pan.clamp(-1f32, 1f32) + 1f32) * std::f32::consts::FRAC_PI_4;
+        let volume = volume / decoder.channels().max(1) as f32;
+        let panned = rodio::source::ChannelVolume::new(
+            decoder.convert_samples::<f32>(),
+            vec![volume * angle.cos(), volume * angle.sin()],
+        );
+        if let Err(play_err) = handle.play_raw(panned) {
+            warn!("Could not play {}: {}", path, play_err);
+        }
+    }
+
+    fn start_track(&mut self, id: u64, source: &AudioSource, volume: f32) {
+        let handle = match self.output.as_ref() {
+            Some((_, handle)) => handle,
+            None => return,
+        };
+        let sink = match Sink::try_new(handle) {
+            Ok(sink) => sink,
+            Err(play_err) => {
+                warn!("Could not start a track: {}", play_err);
+                return;
+            }
+        };
+        match Decoder::new_looped(Cursor::new(source.bytes.clone())) {
+            Ok(decoder) => sink.append(decoder),
+            Err(decode_err) => {
+                warn!("Could not start a track: {}", decode_err);
+                return;
+            }
+        }
+        sink.set_volume(volume);
+        self.tracks.insert(id, sink);
+    }
+
+    fn set_track_volume(&mut self, id: u64, volume: f32) {
+        if let Some(sink) = self.tracks.get(&id) {
+            sink.set_volume(volume);
+        }
+    }
+
+    fn stop_track(&mut self, id: u64) {
+        if let Some(sink) = self.tracks.remove(&id) {
+            sink.stop();
+        }
+    }
+}
+
+/// A sound the [`NullAudioBackend`](crate::sound::NullAudioBackend) was asked to play
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayedSound {
+    pub path: String,
+    pub volume: f32,
+    pub pan: f32,
+}
+
+/// Plays nothing, but remembers what it was asked to, for testing the mix without an audio device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NullAudioBackend {
+    played: Vec<PlayedSound>,
+    /// The volume of every track that's playing, by id
+    tracks: HashMap<u64, f32>,
+}
+
+impl NullAudioBackend {
+    pub fn played(&self) -> &[PlayedSound] {
+        &self.played
+    }
+
+    pub fn tracks(&self) -> &HashMap<u64, f32> {
+        &self.tracks
+    }
+}
+
+impl AudioBackend for NullAudioBackend {
+    fn play(&mut self, path: &str, _source: &AudioSource, volume: f32, pan: f32) {
+        self.played.push(PlayedSound {
+            path: path.to_string(),
+            volume,
+            pan,
+        });
+    }
+
+    fn start_track(&mut self, id: u64, _source: &AudioSource, volume: f32) {
+        self.tracks.insert(id, volume);
+    }
+
+    fn set_track_volume(&mut self, id: u64, volume: f32) {
+        if let Some(track) = self.tracks.get_mut(&id) {
+            *track = volume;
+        }
+    }
+
+    fn stop_track(&mut self, id: u64) {
+        self.tracks.remove(&id);
+    }
+}
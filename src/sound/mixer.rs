@@ -0,0 +1,244 @@
+use crate::resources::GameSettings;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Sounds closer than this to the listener play at full volume, in meters
+const REFERENCE_DISTANCE: f32 = 1f32;
+/// Sounds further than this from the listener can't be heard, in meters
+const MAX_DISTANCE: f32 = 50f32;
+
+/// What a sound is mixed as, which decides which volume settings it's under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundChannel {
+    Music,
+    Ambience,
+    Sfx,
+}
+
+impl SoundChannel {
+    /// How loud the channel is, from 0 to 1, with the master volume applied
+    pub fn volume(&self, settings: &GameSettings) -> f32 {
+        let channel = match self {
+            SoundChannel::Music => settings.music_volume(),
+            SoundChannel::Ambience | SoundChannel::Sfx => settings.sfx_volume(),
+        };
+        (settings.master_volume() as f32 / 100f32) * (channel as f32 / 100f32)
+    }
+}
+
+/// How a sound at a position is heard by a listener
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spatial {
+    /// From 0 to 1
+    pub gain: f32,
+    /// From -1 for all the way left to 1 for all the way right
+    pub pan: f32,
+}
+
+impl Spatial {
+    /// A sound that isn't anywhere, ie a menu click
+    pub const CENTERED: Spatial = Spatial {
+        gain: 1f32,
+        pan: 0f32,
+    };
+
+    /// Hear a sound at `position` from `listener`. It gets quieter with distance, fading out
+    /// completely at the edge of hearing, and it's panned to the side of the listener it's on.
+    pub fn from_listener(listener: &GlobalTransform, position: Vec3) -> Self {
+        let offset = position - listener.translation;
+        let distance = offset.length();
+        if distance >= MAX_DISTANCE {
+            return Spatial {
+                gain: 0f32,
+                pan: 0f32,
+            };
+        }
+        let rolloff = REFERENCE_DISTANCE / distance.max(REFERENCE_DISTANCE);
+        let fade = 1f32 - distance / MAX_DISTANCE;
+        let right = listener.rotation * Vec3::X;
+        Spatial {
+            gain: rolloff * fade,
+            pan: right.dot(offset.normalize_or_zero()).clamp(-1f32, 1f32),
+        }
+    }
+}
+
+/// A looping track of a [`TrackMix`](crate::sound::TrackMix)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: u64,
+    pub sound: String,
+    /// How far it's faded in, from 0 to 1
+    pub weight: f32,
+    /// Whether it's fading in, or out to be stopped
+    fading_in: bool,
+    /// Whether the backend has started playing it yet
+    pub started: bool,
+}
+
+/// The looping tracks of a music or ambience channel. Playing a new track fades it in while the
+/// others fade out, so there's only ever one left once the crossfade is done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackMix {
+    channel: SoundChannel,
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+
+impl TrackMix {
+    pub fn new(channel: SoundChannel) -> Self {
+        TrackMix {
+            channel,
+            tracks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn channel(&self) -> SoundChannel {
+        self.channel
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn tracks_mut(&mut self) -> &mut [Track] {
+        &mut self.tracks
+    }
+
+    /// The track that's playing, or fading in
+    pub fn current(&self) -> Option<&str> {
+        self.tracks
+            .iter()
+            .rev()
+            .find(|track| track.fading_in)
+            .map(|track| track.sound.as_str())
+    }
+
+    /// Crossfade to `sound`, or fade out to silence if it's `None`
+    pub fn play(&mut self, sound: Option<&str>) {
+        if self.current() == sound {
+            return;
+        }
+        for track in self.tracks.iter_mut() {
+            track.fading_in = false;
+        }
+        if let Some(sound) = sound {
+            // Fade a track that's on its way out back in, rather than starting it over
+            match self.tracks.iter_mut().find(|track| track.sound == sound) {
+                Some(track) => track.fading_in = true,
+                None => {
+                    self.next_id += 1;
+                    self.tracks.push(Track {
+                        id: self.next_id,
+                        sound: sound.to_string(),
+                        weight: 0f32,
+                        fading_in: true,
+                        started: false,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Fade the tracks for `delta`, when a whole crossfade takes `fade`, and return the ids of the
+    /// tracks that have faded out and need stopping
+    pub fn update(&mut self, delta: Duration, fade: Duration) -> Vec<u64> {
+        let step = if fade.is_zero() {
+            1f32
+        } else {
+            delta.as_secs_f32() / fade.as_secs_f32()
+        };
+        for track in self.tracks.iter_mut() {
+            track.weight = if track.fading_in {
+                (track.weight + step).min(1f32)
+            } else {
+                (track.weight - step).max(0f32)
+            };
+        }
+        let (stopped, playing) = std::mem::take(&mut self.tracks)
+            .into_iter()
+            .partition(|track| !track.fading_in && track.weight <= 0f32);
+        self.tracks = playing;
+        stopped
+            .into_iter()
+            .filter(|track: &Track| track.started)
+            .map(|track| track.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_rolloff_and_pan() {
+        // Looking down -Z, so +X is to the right
+        let listener = GlobalTransform::identity();
+        let close = Spatial::from_listener(&listener, Vec3::new(0.0, 0.0, -0.5));
+        assert_eq!(close.pan, 0f32);
+        assert!(close.gain > 0.98);
+        let right = Spatial::from_listener(&listener, Vec3::new(10.0, 0.0, 0.0));
+        assert!((right.pan - 1f32).abs() < 1e-4);
+        assert!(right.gain < close.gain);
+        let left = Spatial::from_listener(&listener, Vec3::new(-10.0, 0.0, 0.0));
+        assert!((left.pan + 1f32).abs() < 1e-4);
+        assert_eq!(
+            Spatial::from_listener(&listener, Vec3::new(0.0, 0.0, 60.0)).gain,
+            0f32
+        );
+
+        // Turned around, the right is on the left
+        let turned = GlobalTransform::from_rotation(Quat::from_rotation_y(std::f32::consts::PI));
+        let pan = Spatial::from_listener(&turned, Vec3::new(10.0, 0.0, 0.0)).pan;
+        assert!((pan + 1f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_channel_volumes() {
+        let mut settings = GameSettings::default();
+        settings.set_master_volume(50);
+        settings.set_music_volume(50);
+        settings.set_sfx_volume(100);
+        assert!((SoundChannel::Music.volume(&settings) - 0.25).abs() < 1e-4);
+        assert!((SoundChannel::Sfx.volume(&settings) - 0.5).abs() < 1e-4);
+        settings.set_master_volume(0);
+        assert_eq!(SoundChannel::Ambience.volume(&settings), 0f32);
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let fade = Duration::from_secs(2);
+        let second = Duration::from_secs(1);
+        let mut mix = TrackMix::new(SoundChannel::Music);
+        mix.play(Some("menu.ogg"));
+        for track in mix.tracks_mut() {
+            track.started = true;
+        }
+        assert!(mix.update(fade, fade).is_empty());
+        assert_eq!(mix.tracks()[0].weight, 1f32);
+
+        mix.play(Some("level.ogg"));
+        assert_eq!(mix.current(), Some("level.ogg"));
+        assert!(mix.update(second, fade).is_empty());
+        let weights: Vec<f32> = mix.tracks().iter().map(|track| track.weight).collect();
+        assert_eq!(weights, vec![0.5, 0.5]);
+        // Playing the same track again doesn't start it over
+        mix.play(Some("level.ogg"));
+        assert_eq!(mix.tracks().len(), 2);
+        let menu_id = mix.tracks()[0].id;
+        assert_eq!(mix.update(second, fade), vec![menu_id]);
+        assert_eq!(mix.tracks().len(), 1);
+        assert_eq!(mix.tracks()[0].weight, 1f32);
+
+        // Fading out to nothing, then back in halfway
+        mix.play(None);
+        assert_eq!(mix.current(), None);
+        mix.update(second, fade);
+        mix.play(Some("level.ogg"));
+        assert_eq!(mix.tracks().len(), 1);
+        mix.update(second, fade);
+        assert_eq!(mix.tracks()[0].weight, 1f32);
+    }
+}
@@ -0,0 +1,22 @@
+//! Positional sound effects, music and ambience.
+//!
+//! Sound effects are sent as [`PlaySound`](crate::sound::PlaySound) events: footsteps and landings from
+//! the players' [`NoiseEvent`](crate::perception::NoiseEvent)s, picked by the material of the
+//! [`Surface`](crate::components::Surface) underfoot, and impacts from Rapier contact events, as
+//! loud as the impulse between the colliders. Music and ambience loop per
+//! [`GameLevel`](crate::states::GameLevel), crossfading when it changes. Which sounds there are is
+//! set in `assets/sounds.toml`.
+//!
+//! Everything is mixed against the closest [`FirstPersonHead`](crate::components::FirstPersonHead)
+//! and the volumes in the [`GameSettings`](crate::resources::GameSettings), then handed to an
+//! [`AudioBackend`](crate::sound::AudioBackend). The game plays through rodio, and tests use the
+//! [`NullAudioBackend`](crate::sound::NullAudioBackend) to check the mix without an audio device.
+mod backend;
+mod mixer;
+mod sounds;
+mod systems;
+
+pub use self::backend::*;
+pub use self::mixer::*;
+pub use self::sounds::*;
+pub use self::systems::*;
@@ -0,0 +1,180 @@
+use crate::states::GameLevel;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The sounds there are, in `assets/sounds.toml`
+const SOUNDS: &str = include_str!("../../assets/sounds.toml");
+
+/// The surface whose sounds are played for colliders whose
+/// [`SurfaceMaterial`](crate::resources::SurfaceMaterial) doesn't name any
+pub const DEFAULT_SURFACE: &str = "default";
+
+fn default_crossfade_seconds() -> f32 {
+    2f32
+}
+
+/// The sounds of walking on and hitting a surface
+///
+/// ```toml
+/// [surfaces.metal]
+/// footsteps = ["sounds/footsteps/metal_1.ogg", "sounds/footsteps/metal_2.ogg"]
+/// impacts = ["sounds/impacts/metal.ogg"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SurfaceSounds {
+    #[serde(default)]
+    footsteps: Vec<String>,
+    #[serde(default)]
+    impacts: Vec<String>,
+}
+
+impl SurfaceSounds {
+    pub fn footsteps(&self) -> &[String] {
+        &self.footsteps
+    }
+
+    pub fn impacts(&self) -> &[String] {
+        &self.impacts
+    }
+}
+
+/// This resource holds every sound the game plays. By default, they're the ones in
+/// `assets/sounds.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SoundDefinitions {
+    /// How long music and ambience take to crossfade, in seconds
+    #[serde(default = "default_crossfade_seconds")]
+    crossfade_seconds: f32,
+    #[serde(default)]
+    surfaces: HashMap<String, SurfaceSounds>,
+    /// By [`level_key`](crate::sound::level_key)
+    #[serde(default)]
+    music: HashMap<String, String>,
+    /// By [`level_key`](crate::sound::level_key)
+    #[serde(default)]
+    ambience: HashMap<String, String>,
+}
+
+impl Default for SoundDefinitions {
+    fn default() -> Self {
+        SoundDefinitions::from_toml(SOUNDS)
+            .unwrap_or_else(|parse_err| panic!("Could not parse the sounds: {}", parse_err))
+    }
+}
+
+impl SoundDefinitions {
+    pub fn from_toml(toml_str: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_str)
+    }
+
+    pub fn crossfade(&self) -> Duration {
+        Duration::from_secs_f32(self.crossfade_seconds.max(0f32))
+    }
+
+    /// The sounds of `surface`, or of the default surface if it doesn't have any
+    pub fn surface(&self, surface: Option<&str>) -> Option<&SurfaceSounds> {
+        surface
+            .and_then(|surface| self.surfaces.get(surface))
+            .or_else(|| self.surfaces.get(DEFAULT_SURFACE))
+    }
+
+    pub fn music(&self, level: &GameLevel) -> Option<&str> {
+        self.music.get(level_key(level)).map(String::as_str)
+    }
+
+    pub fn ambience(&self, level: &GameLevel) -> Option<&str> {
+        self.ambience.get(level_key(level)).map(String::as_str)
+    }
+
+    /// Every sound file there is
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.surfaces
+            .values()
+            .flat_map(|surface| surface.footsteps.iter().chain(surface.impacts.iter()))
+            .chain(self.music.values())
+            .chain(self.ambience.values())
+            .map(String::as_str)
+    }
+}
+
+/// What `assets/sounds.toml` calls a level
+pub fn level_key(level: &GameLevel) -> &'static str {
+    match level {
+        GameLevel::MainMenu => "main_menu",
+        GameLevel::Main => "main",
+        GameLevel::PauseMenu => "pause_menu",
+        GameLevel::Inventory => "inventory",
+    }
+}
+
+/// This resource holds the loaded sound files, by their path
+#[derive(Debug, Clone, Default)]
+pub struct SoundBank {
+    sounds: HashMap<String, Handle<AudioSource>>,
+}
+
+impl SoundBank {
+    pub fn get(&self, path: &str) -> Option<&Handle<AudioSource>> {
+        self.sounds.get(path)
+    }
+
+    pub fn insert(&mut self, path: impl Into<String>, handle: Handle<AudioSource>) {
+        self.sounds.insert(path.into(), handle);
+    }
+}
+
+/// Start loading every sound in the [`SoundDefinitions`](crate::sound::SoundDefinitions)
+pub fn load_sound_bank(
+    asset_server: Res<AssetServer>,
+    sound_definitions: Res<SoundDefinitions>,
+    mut sound_bank: ResMut<SoundBank>,
+) {
+    for path in sound_definitions.paths() {
+        if sound_bank.get(path).is_none() {
+            sound_bank.insert(path, asset_server.load(path));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sounds() {
+        let sounds = SoundDefinitions::default();
+        assert!(sounds.surface(None).is_some());
+        assert_eq!(sounds.surface(Some("unicorn")), sounds.surface(None));
+
+        let sounds = SoundDefinitions::from_toml(
+            r#"
+            crossfade_seconds = 0.5
+
+            [surfaces.default]
+            footsteps = ["step.ogg"]
+
+            [surfaces.metal]
+            footsteps = ["clang.ogg"]
+            impacts = ["bonk.ogg"]
+
+            [music]
+            main = "level.ogg"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(sounds.crossfade(), Duration::from_millis(500));
+        assert_eq!(
+            sounds.surface(Some("metal")).unwrap().impacts(),
+            ["bonk.ogg"]
+        );
+        assert_eq!(sounds.surface(None).unwrap().footsteps(), ["step.ogg"]);
+        assert_eq!(sounds.music(&GameLevel::Main), Some("level.ogg"));
+        assert_eq!(sounds.music(&GameLevel::MainMenu), None);
+        assert_eq!(sounds.ambience(&GameLevel::Main), None);
+        let mut paths: Vec<&str> = sounds.paths().collect();
+        paths.sort_unstable();
+        assert_eq!(paths, ["bonk.ogg", "clang.ogg", "level.ogg", "step.ogg"]);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::components::{FirstPersonHead, Surface};
+use crate::perception::{NoiseEvent, NoiseKind};
+use crate::resources::{GameConfig, GameSettings};
+use crate::sound::{AudioBackend, SoundBank, SoundChannel, SoundDefinitions, Spatial, TrackMix};
+use crate::states::GameLevel;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How loud a [`NoiseEvent`](crate::perception::NoiseEvent) has to be for its sound to play at full
+/// volume, in meters it can be heard from
+const FULL_VOLUME_LOUDNESS: f32 = 12f32;
+/// How hard two colliders have to hit each other for the impact to play at full volume, in newton
+/// seconds
+const FULL_VOLUME_IMPULSE: f32 = 20f32;
+/// Softer impacts than this don't make a sound, in newton seconds
+const MIN_IMPACT_IMPULSE: f32 = 0.5;
+
+/// Send this event to play a sound effect once
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaySound {
+    /// The sound's path in the [`SoundBank`](crate::sound::SoundBank)
+    pub path: String,
+    /// Where the sound is, or `None` if it isn't anywhere in particular
+    pub position: Option<Vec3>,
+    /// From 0 to 1, before the settings and distance are applied
+    pub volume: f32,
+}
+
+/// This resource is the music and ambience being played
+#[derive(Debug, Clone, PartialEq)]
+pub struct Soundtrack {
+    pub music: TrackMix,
+    pub ambience: TrackMix,
+}
+
+impl Default for Soundtrack {
+    fn default() -> Self {
+        Soundtrack {
+            music: TrackMix::new(SoundChannel::Music),
+            ambience: TrackMix::new(SoundChannel::Ambience),
+        }
+    }
+}
+
+/// Crossfade to the music and ambience of the [`GameLevel`](crate::states::GameLevel) that's active
+/// now. A level without any keeps what's playing, so the pause menu doesn't cut the music.
+pub fn play_level_tracks(
+    game_level: Res<State<GameLevel>>,
+    sound_definitions: Res<SoundDefinitions>,
+    mut soundtrack: ResMut<Soundtrack>,
+) {
+    if !game_level.is_changed() {
+        return;
+    }
+    let level = game_level.current();
+    if let Some(music) = sound_definitions.music(level) {
+        soundtrack.music.play(Some(music));
+    }
+    if let Some(ambience) = sound_definitions.ambience(level) {
+        soundtrack.ambience.play(Some(ambience));
+    }
+}
+
+/// Pick the next of `sounds`, taking turns
+fn next_sound<'a>(sounds: &'a [String], turn: &mut usize) -> Option<&'a String> {
+    if sounds.is_empty() {
+        return None;
+    }
+    *turn = turn.wrapping_add(1);
+    sounds.get(*turn % sounds.len())
+}
+
+/// Play the footsteps and landings of the [`NoiseEvent`](crate::perception::NoiseEvent)s, from the
+/// footstep sounds of the [`Surface`](crate::components::Surface) they were on. They're as loud as
+/// the noise is, so running is louder than sneaking.
+pub fn play_footstep_sounds(
+    mut noise_events: EventReader<NoiseEvent>,
+    surface_query: Query<&Surface>,
+    game_config: Res<GameConfig>,
+    sound_definitions: Res<SoundDefinitions>,
+    mut turn: Local<usize>,
+    mut play_sounds: EventWriter<PlaySound>,
+) {
+    for noise in noise_events.iter() {
+        if !matches!(noise.kind, NoiseKind::Footstep | NoiseKind::Landing) {
+            continue;
+        }
+        let surface = noise
+            .ground
+            .and_then(|ground| surface_query.get(ground).ok())
+            .and_then(|surface| game_config.material(surface.material()))
+            .and_then(|material| material.footsteps());
+        let footsteps = match sound_definitions.surface(surface) {
+            Some(sounds) => sounds.footsteps(),
+            None => continue,
+        };
+        if let Some(path) = next_sound(footsteps, &mut turn) {
+            play_sounds.send(PlaySound {
+                path: path.clone(),
+                position: Some(noise.position),
+                volume: (noise.loudness / FULL_VOLUME_LOUDNESS).min(1f32),
+            });
+        }
+    }
+}
+
+/// Play an impact sound when colliders start touching, as loud as the impulse between them. It's
+/// the impact sound of the first of them whose [`Surface`](crate::components::Surface) has one.
+///
+/// Note: Only colliders with [`ActiveEvents::CONTACT_EVENTS`](bevy_rapier3d::prelude::ActiveEvents)
+/// make a sound.
+#[allow(clippy::too_many_arguments)]
+pub fn play_impact_sounds(
+    mut contact_events: EventReader<ContactEvent>,
+    narrow_phase: Res<NarrowPhase>,
+    collider_position_query: Query<&ColliderPositionComponent>,
+    surface_query: Query<&Surface>,
+    game_config: Res<GameConfig>,
+    sound_definitions: Res<SoundDefinitions>,
+    mut turn: Local<usize>,
+    mut play_sounds: EventWriter<PlaySound>,
+) {
+    for event in contact_events.iter() {
+        let (collider1, collider2) = match event {
+            ContactEvent::Started(collider1, collider2) => (*collider1, *collider2),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let contact_pair = match narrow_phase.contact_pair(collider1, collider2) {
+            Some(contact_pair) => contact_pair,
+            None => continue,
+        };
+        let impulse: f32 = contact_pair
+            .manifolds
+            .iter()
+            .flat_map(|manifold| manifold.points.iter())
+            .map(|point| point.data.impulse)
+            .sum();
+        if impulse < MIN_IMPACT_IMPULSE {
+            continue;
+        }
+        let position = contact_pair
+            .manifolds
+            .iter()
+            .flat_map(|manifold| manifold.data.solver_contacts.iter())
+            .map(|contact| Vec3::new(contact.point.x, contact.point.y, contact.point.z))
+            .next()
+            .or_else(|| {
+                let position = collider_position_query.get(collider1.entity()).ok()?;
+                let translation = position.0 .0.translation.vector;
+                Some(Vec3::new(translation.x, translation.y, translation.z))
+            });
+        let surface = [collider1, collider2]
+            .iter()
+            .filter_map(|collider| surface_query.get(collider.entity()).ok())
+            .filter_map(|surface| game_config.material(surface.material()))
+            .find_map(|material| material.impacts());
+        let impacts = match sound_definitions.surface(surface) {
+            Some(sounds) => sounds.impacts(),
+            None => continue,
+        };
+        if let Some(path) = next_sound(impacts, &mut turn) {
+            play_sounds.send(PlaySound {
+                path: path.clone(),
+                position,
+                volume: (impulse / FULL_VOLUME_IMPULSE).min(1f32),
+            });
+        }
+    }
+}
+
+/// Fade the tracks of `mix` and keep their volume in step with the settings
+fn mix_tracks<B: AudioBackend>(
+    backend: &mut B,
+    mix: &mut TrackMix,
+    time: &Time,
+    sound_definitions: &SoundDefinitions,
+    sound_bank: &SoundBank,
+    audio_sources: &Assets<AudioSource>,
+    settings: &GameSettings,
+) {
+    for stopped in mix.update(time.delta(), sound_definitions.crossfade()) {
+        backend.stop_track(stopped);
+    }
+    let channel_volume = mix.channel().volume(settings);
+    for track in mix.tracks_mut() {
+        let volume = track.weight * channel_volume;
+        if track.started {
+            backend.set_track_volume(track.id, volume);
+            continue;
+        }
+        // Tracks start once they've loaded
+        let source = sound_bank
+            .get(&track.sound)
+            .and_then(|handle| audio_sources.get(handle));
+        if let Some(source) = source {
+            backend.start_track(track.id, source, volume);
+            track.started = true;
+        }
+    }
+}
+
+/// Mix the sounds there are to play, and hand them to the [`AudioBackend`](crate::sound::AudioBackend).
+/// A sound effect with a position is heard by the closest
+/// [`FirstPersonHead`](crate::components::FirstPersonHead), quieter the further away it is and
+/// panned to its side. Sounds that haven't loaded yet are skipped, since they'd be late.
+#[allow(clippy::too_many_arguments)]
+pub fn mix_sounds<B: AudioBackend>(
+    mut backend: NonSendMut<B>,
+    mut play_sounds: EventReader<PlaySound>,
+    listener_query: Query<&GlobalTransform, With<FirstPersonHead>>,
+    mut soundtrack: ResMut<Soundtrack>,
+    sound_definitions: Res<SoundDefinitions>,
+    sound_bank: Res<SoundBank>,
+    audio_sources: Res<Assets<AudioSource>>,
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+) {
+    let sfx_volume = SoundChannel::Sfx.volume(&settings);
+    for sound in play_sounds.iter() {
+        let source = match sound_bank
+            .get(&sound.path)
+            .and_then(|handle| audio_sources.get(handle))
+        {
+            Some(source) => source,
+            None => continue,
+        };
+        let spatial = match sound.position {
+            Some(position) => listener_query
+                .iter()
+                .min_by(|a, b| {
+                    a.translation
+                        .distance(position)
+                        .partial_cmp(&b.translation.distance(position))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map_or(Spatial::CENTERED, |listener| {
+                    Spatial::from_listener(listener, position)
+                }),
+            None => Spatial::CENTERED,
+        };
+        let volume = sound.volume * sfx_volume * spatial.gain;
+        if volume > 0f32 {
+            backend.play(&sound.path, source, volume, spatial.pan);
+        }
+    }
+
+    let soundtrack = &mut *soundtrack;
+    for mix in [&mut soundtrack.music, &mut soundtrack.ambience] {
+        mix_tracks(
+            &mut *backend,
+            mix,
+            &time,
+            &sound_definitions,
+            &sound_bank,
+            &audio_sources,
+            &settings,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sounds_take_turns() {
+        let sounds = vec![String::from("a.ogg"), String::from("b.ogg")];
+        let mut turn = 0;
+        let picked: Vec<&String> = (0..4)
+            .filter_map(|_| next_sound(&sounds, &mut turn))
+            .collect();
+        assert_eq!(picked, [&sounds[1], &sounds[0], &sounds[1], &sounds[0]]);
+        assert_eq!(next_sound(&[], &mut turn), None);
+    }
+}
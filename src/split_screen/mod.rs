@@ -0,0 +1,18 @@
+//! Drawing every local player's camera into its own part of the window.
+//!
+//! Bevy 0.6 draws the active 3D camera to the whole window and has no camera viewports, so in
+//! split-screen every player's camera is named after its player and added to the
+//! [`ActiveCameras`](bevy::render::camera::ActiveCameras) instead. The cameras are extracted with
+//! their own render phases and depth textures like the 3D camera is, and the
+//! [`SplitScreenPassNode`](crate::split_screen::SplitScreenPassNode) draws each of them with the
+//! render pass's viewport set to the camera's
+//! [`SplitScreenViewport`](crate::components::SplitScreenViewport), after the main pass and before
+//! the UI.
+//!
+//! Each camera's projection is fitted to its viewport's aspect ratio, and multisampling is turned
+//! off while the screen is split, since every view is resolved into the whole window.
+mod node;
+mod systems;
+
+pub use self::node::*;
+pub use self::systems::*;
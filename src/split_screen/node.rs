@@ -0,0 +1,153 @@
+use crate::components::SplitScreenViewport;
+use crate::resources::MAX_LOCAL_PLAYERS;
+use crate::split_screen::split_screen_camera_name;
+use bevy::core_pipeline::{AlphaMask3d, Opaque3d, Transparent3d};
+use bevy::ecs::query::QueryState;
+use bevy::prelude::*;
+use bevy::render::camera::ActiveCameras;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraphContext};
+use bevy::render::render_phase::{DrawFunctions, PhaseItem, RenderPhase, TrackedRenderPass};
+use bevy::render::render_resource::{
+    LoadOp, Operations, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+};
+use bevy::render::renderer::RenderContext;
+use bevy::render::view::{ExtractedView, ViewDepthTexture, ViewTarget};
+
+/// Give every active split-screen camera the render phases a 3D camera has, and its viewport, so
+/// meshes are queued and a depth texture is made for it
+pub fn extract_split_screen_cameras(
+    mut commands: Commands,
+    active_cameras: Res<ActiveCameras>,
+    viewport_query: Query<&SplitScreenViewport>,
+) {
+    for index in 0..MAX_LOCAL_PLAYERS {
+        let entity = match active_cameras
+            .get(&split_screen_camera_name(index))
+            .and_then(|active_camera| active_camera.entity)
+        {
+            Some(entity) => entity,
+            None => continue,
+        };
+        if let Ok(viewport) = viewport_query.get(entity) {
+            commands.get_or_spawn(entity).insert_bundle((
+                *viewport,
+                RenderPhase::<Opaque3d>::default(),
+                RenderPhase::<AlphaMask3d>::default(),
+                RenderPhase::<Transparent3d>::default(),
+            ));
+        }
+    }
+}
+
+/// A split-screen camera's view, and the part of the window it's drawn in
+struct SplitScreenView<'a> {
+    entity: Entity,
+    target: &'a ViewTarget,
+    depth: &'a ViewDepthTexture,
+    viewport: [f32; 4],
+}
+
+impl<'a> SplitScreenView<'a> {
+    /// Draw one of the view's phases, on top of what's been drawn already
+    fn draw_phase<P: PhaseItem>(
+        &self,
+        render_context: &mut RenderContext,
+        world: &World,
+        label: &str,
+        phase: &RenderPhase<P>,
+    ) {
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[self.target.get_color_attachment(Operations {
+                load: LoadOp::Load,
+                store: true,
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.depth.view,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        };
+        let draw_functions = world.get_resource::<DrawFunctions<P>>().unwrap();
+        let mut render_pass = render_context
+            .command_encoder
+            .begin_render_pass(&pass_descriptor);
+        let [x, y, width, height] = self.viewport;
+        render_pass.set_viewport(x, y, width, height, 0f32, 1f32);
+        let mut draw_functions = draw_functions.write();
+        let mut tracked_pass = TrackedRenderPass::new(render_pass);
+        for item in &phase.items {
+            let draw_function = draw_functions.get_mut(item.draw_function()).unwrap();
+            draw_function.draw(world, &mut tracked_pass, self.entity, item);
+        }
+    }
+}
+
+/// This render graph node draws every split-screen camera into its
+/// [`SplitScreenViewport`](crate::components::SplitScreenViewport), like the main 3D pass draws the
+/// active 3D camera into the whole window
+pub struct SplitScreenPassNode {
+    query: QueryState<(
+        Entity,
+        &'static SplitScreenViewport,
+        &'static ExtractedView,
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static RenderPhase<Opaque3d>,
+        &'static RenderPhase<AlphaMask3d>,
+        &'static RenderPhase<Transparent3d>,
+    )>,
+}
+
+impl SplitScreenPassNode {
+    pub const NAME: &'static str = "split_screen_pass";
+
+    pub fn new(world: &mut World) -> Self {
+        SplitScreenPassNode {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for SplitScreenPassNode {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        for (entity, viewport, view, target, depth, opaque, alpha_mask, transparent) in
+            self.query.iter_manual(world)
+        {
+            let view = SplitScreenView {
+                entity,
+                target,
+                depth,
+                viewport: viewport.in_pixels(view.width, view.height),
+            };
+            // Opaque front to back, then alpha masked, then transparent back to front, like the
+            // main pass
+            view.draw_phase(render_context, world, "split_screen_opaque_pass", opaque);
+            view.draw_phase(
+                render_context,
+                world,
+                "split_screen_alpha_mask_pass",
+                alpha_mask,
+            );
+            view.draw_phase(
+                render_context,
+                world,
+                "split_screen_transparent_pass",
+                transparent,
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::components::SplitScreenViewport;
+use crate::resources::MAX_LOCAL_PLAYERS;
+use bevy::prelude::*;
+use bevy::render::camera::{ActiveCameras, Camera, CameraProjection, PerspectiveProjection};
+
+/// The name of player `index`'s camera in split-screen
+pub fn split_screen_camera_name(index: usize) -> String {
+    format!("player_{}_camera", index + 1)
+}
+
+/// Add every split-screen camera's name to the [`ActiveCameras`](bevy::render::camera::ActiveCameras),
+/// if there's a renderer, so they're extracted like the 3D camera
+pub fn activate_split_screen_cameras(active_cameras: Option<ResMut<ActiveCameras>>) {
+    if let Some(mut active_cameras) = active_cameras {
+        for index in 0..MAX_LOCAL_PLAYERS {
+            active_cameras.add(&split_screen_camera_name(index));
+        }
+    }
+}
+
+/// Fit every camera's projection to its [`SplitScreenViewport`](crate::components::SplitScreenViewport)
+/// instead of the whole window. It runs after Bevy's camera systems, which fit them to the window
+/// when it's resized.
+pub fn fit_split_screen_projections(
+    windows: Res<Windows>,
+    mut camera_query: Query<(
+        &SplitScreenViewport,
+        &mut Camera,
+        &mut PerspectiveProjection,
+    )>,
+) {
+    for (viewport, mut camera, mut projection) in camera_query.iter_mut() {
+        let window = match windows.get(camera.window) {
+            Some(window) => window,
+            None => continue,
+        };
+        let aspect_ratio = viewport.aspect_ratio(window.width(), window.height());
+        if (projection.aspect_ratio - aspect_ratio).abs() < f32::EPSILON {
+            continue;
+        }
+        projection.aspect_ratio = aspect_ratio;
+        camera.projection_matrix = projection.get_projection_matrix();
+    }
+}
+
+/// Turn multisampling off while the screen is split, and back to what it was once it isn't.
+/// Every player's view is resolved into the whole window, which would paint over the other
+/// players' viewports.
+pub fn match_msaa_to_split_screen(
+    viewport_query: Query<&SplitScreenViewport>,
+    msaa: Option<ResMut<Msaa>>,
+    mut single_screen_samples: Local<Option<u32>>,
+) {
+    let mut msaa = match msaa {
+        Some(msaa) => msaa,
+        None => return,
+    };
+    let split_screen = viewport_query.iter().nth(1).is_some();
+    if split_screen {
+        if msaa.samples != 1 {
+            *single_screen_samples = Some(msaa.samples);
+            msaa.samples = 1;
+        }
+    } else if let Some(samples) = single_screen_samples.take() {
+        msaa.samples = samples;
+    }
+}
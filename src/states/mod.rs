@@ -15,4 +15,23 @@ pub enum GameLevel {
     MainMenu,
     Main,
     PauseMenu,
+    /// The inventory screen, on top of [`GameLevel::Main`]
+    Inventory,
+}
+
+impl GameLevel {
+    /// The id a save records for this level, or `None` if the level can't be saved
+    pub fn save_id(&self) -> Option<&'static str> {
+        match self {
+            GameLevel::Main => Some("main"),
+            GameLevel::MainMenu | GameLevel::PauseMenu | GameLevel::Inventory => None,
+        }
+    }
+
+    pub fn from_save_id(save_id: &str) -> Option<Self> {
+        match save_id {
+            "main" => Some(GameLevel::Main),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,115 @@
+use crate::components::{Checkpoint, FirstPersonSubject, LevelObject, RespawnOverlay};
+use crate::resources::{CheckpointProgress, GameClock, RespawnFade};
+use crate::save::{RestoreSnapshotRequested, SnapshotRequested};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use bevy_rapier3d::prelude::*;
+
+/// Players that fall below this height die
+pub const KILL_PLANE_HEIGHT: f32 = -20f32;
+
+/// This event is sent when a player dies. The screen fades out, and every player is put back
+/// at the last [`Checkpoint`](crate::components::Checkpoint) reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerDied {
+    pub subject: Entity,
+}
+
+/// Take a snapshot of the level when a subject walks into a checkpoint it hasn't reached yet
+pub fn reach_checkpoints(
+    mut intersection_events: EventReader<IntersectionEvent>,
+    subject_query: Query<(), With<FirstPersonSubject>>,
+    checkpoint_query: Query<&Checkpoint>,
+    mut progress: ResMut<CheckpointProgress>,
+    mut snapshot_requests: EventWriter<SnapshotRequested>,
+) {
+    for event in intersection_events
+        .iter()
+        .filter(|event| event.intersecting)
+    {
+        let (entity1, entity2) = (event.collider1.entity(), event.collider2.entity());
+        let checkpoint_entity = if subject_query.get(entity1).is_ok() {
+            entity2
+        } else if subject_query.get(entity2).is_ok() {
+            entity1
+        } else {
+            continue;
+        };
+        if let Ok(checkpoint) = checkpoint_query.get(checkpoint_entity) {
+            if progress.reach(checkpoint_entity) {
+                info!("Reached a checkpoint");
+                snapshot_requests.send(SnapshotRequested {
+                    respawn: checkpoint.respawn(),
+                });
+            }
+        }
+    }
+}
+
+/// Subjects that fall off the level die
+pub fn kill_subjects_below_kill_plane(
+    subject_query: Query<(Entity, &RigidBodyPositionComponent), With<FirstPersonSubject>>,
+    fade: Res<RespawnFade>,
+    mut died_events: EventWriter<PlayerDied>,
+) {
+    // They're already being respawned
+    if fade.is_active() {
+        return;
+    }
+    for (subject, position) in subject_query.iter() {
+        if position.position.translation.y < KILL_PLANE_HEIGHT {
+            died_events.send(PlayerDied { subject });
+        }
+    }
+}
+
+pub fn start_respawn_on_player_died(
+    mut died_events: EventReader<PlayerDied>,
+    mut fade: ResMut<RespawnFade>,
+) {
+    if let Some(died) = died_events.iter().last() {
+        info!("{:?} died", died.subject);
+        fade.start();
+    }
+}
+
+/// Fade the screen out, restore the last checkpoint's snapshot while it's black, then fade back in
+pub fn advance_respawn_fade(
+    game_clock: Res<GameClock>,
+    mut fade: ResMut<RespawnFade>,
+    mut restore_requests: EventWriter<RestoreSnapshotRequested>,
+    mut overlay_query: Query<&mut UiColor, With<RespawnOverlay>>,
+) {
+    if !fade.is_active() {
+        return;
+    }
+    if fade.tick(game_clock.delta()) {
+        restore_requests.send(RestoreSnapshotRequested);
+    }
+    for mut color in overlay_query.iter_mut() {
+        color.0 = Color::rgba(0f32, 0f32, 0f32, fade.alpha());
+    }
+}
+
+/// Add the overlay the respawn fade is drawn on. It doesn't block the mouse.
+pub fn add_respawn_overlay(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100f32), Val::Percent(100f32)),
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..Default::default()
+        })
+        .insert(RespawnOverlay)
+        .insert(LevelObject);
+}
+
+/// Checkpoints only make sense in the level they were reached in
+pub fn reset_checkpoints(mut progress: ResMut<CheckpointProgress>, mut fade: ResMut<RespawnFade>) {
+    progress.clear();
+    *fade = RespawnFade::new(fade.duration());
+}
@@ -1,4 +1,5 @@
 use crate::components::*;
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
 use bevy::input::mouse::MouseMotion;
 use bevy::prelude::*;
 
@@ -7,65 +8,90 @@ use bevy::prelude::*;
 /// component.
 ///
 /// The function listens for mouse movement, and it listens for right stick events
-/// on a gamepad.
+/// on a gamepad. A subject with a [`LocalPlayer`](crate::components::LocalPlayer) component only
+/// listens to that player's device.
 ///
-/// Note: This function will panic if there is either _no_ entity
-/// with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component or if there are more than one
-/// entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component.
+/// Note: This function does nothing if there is _no_ entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// component. If there are several without a [`LocalPlayer`](crate::components::LocalPlayer) component, only the
+/// one with an [`ActiveSubject`](crate::components::ActiveSubject) component is turned.
 pub fn first_person_lookaround(
     mut mouse_motion_events: EventReader<MouseMotion>,
     gamepads: Res<Gamepads>,
     axes: Res<Axis<GamepadAxis>>,
-    mut query: Query<&mut Lookaround, With<FirstPersonSubject>>,
+    mut query: Query<
+        (
+            &mut Lookaround,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
 ) {
-    let mut left_right = LookaroundDirection::Right(0f32);
-    let mut up_down = LookaroundDirection::Up(0f32);
+    let mut mouse_left_right = LookaroundDirection::Right(0f32);
+    let mut mouse_up_down = LookaroundDirection::Up(0f32);
 
     for mouse_motion_event in mouse_motion_events.iter() {
         let delta_x = mouse_motion_event.delta.x;
         let delta_y = mouse_motion_event.delta.y;
-        left_right = if delta_x > 0f32 {
+        mouse_left_right = if delta_x > 0f32 {
             LookaroundDirection::Right(delta_x)
         } else if delta_x < 0f32 {
             LookaroundDirection::Left(delta_x.abs())
         } else {
-            left_right
+            mouse_left_right
         };
-        up_down = if delta_y > 0f32 {
+        mouse_up_down = if delta_y > 0f32 {
             LookaroundDirection::Down(delta_y)
         } else if delta_y < 0f32 {
             LookaroundDirection::Up(delta_y.abs())
         } else {
-            up_down
+            mouse_up_down
         };
     }
 
-    // Process gamepad input because they have precedence
-    // over keyboard input
-    for gamepad in gamepads.iter().cloned() {
-        if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::RightStickX)) {
-            if magnitude != 0f32 {
-                left_right = if magnitude > 0f32 {
-                    LookaroundDirection::Right(magnitude * 11f32)
-                } else {
-                    LookaroundDirection::Left(magnitude.abs() * 11f32)
-                };
+    let subjects = query.iter_mut().map(|(lookaround, local_player, active)| {
+        (lookaround, local_player.cloned(), active.is_some())
+    });
+    for (mut lookaround, input) in
+        select_controlled_subjects("first_person_lookaround", subjects, &mut diagnostics)
+    {
+        let (mut left_right, mut up_down) = match input {
+            Some(input) if !input.uses_keyboard_mouse() => (
+                LookaroundDirection::Right(0f32),
+                LookaroundDirection::Up(0f32),
+            ),
+            _ => (mouse_left_right, mouse_up_down),
+        };
+        let subject_gamepads: Vec<Gamepad> = match input {
+            None => gamepads.iter().cloned().collect(),
+            Some(input) => input.gamepad().into_iter().collect(),
+        };
+
+        // Process gamepad input because they have precedence
+        // over keyboard input
+        for gamepad in subject_gamepads {
+            if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::RightStickX)) {
+                if magnitude != 0f32 {
+                    left_right = if magnitude > 0f32 {
+                        LookaroundDirection::Right(magnitude * 11f32)
+                    } else {
+                        LookaroundDirection::Left(magnitude.abs() * 11f32)
+                    };
+                }
             }
-        }
-        if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::RightStickY)) {
-            if magnitude != 0f32 {
-                up_down = if magnitude > 0f32 {
-                    LookaroundDirection::Up(magnitude * 6.5)
-                } else {
-                    LookaroundDirection::Down(magnitude.abs() * 6.5)
-                };
+            if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::RightStickY)) {
+                if magnitude != 0f32 {
+                    up_down = if magnitude > 0f32 {
+                        LookaroundDirection::Up(magnitude * 6.5)
+                    } else {
+                        LookaroundDirection::Down(magnitude.abs() * 6.5)
+                    };
+                }
             }
         }
-    }
 
-    let mut lookaround = query
-        .get_single_mut()
-        .expect("Could not find a player when querying using Lookaround component!");
-    lookaround.set_left_right(left_right);
-    lookaround.set_up_down(up_down);
+        lookaround.set_left_right(left_right);
+        lookaround.set_up_down(up_down);
+    }
 }
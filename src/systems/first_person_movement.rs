@@ -1,43 +1,85 @@
 use crate::components::*;
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
 use bevy::prelude::*;
 
+/// How hard a keyboard player pushes while holding the sneak key, compared to not holding it
+const SNEAK_MAGNITUDE: f32 = 0.5;
+
 /// This function listens for keyboard and gamepad events and
 /// updates the [`Movement`](crate::components::Movement) component of an entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
 /// component.
 ///
-/// The function listens for WASD and arrow key presses on the keyboard, and
-/// it listens for left stick events on a gamepad.
+/// The function listens for WASD and arrow key presses on the keyboard, with left control to
+/// sneak, and it listens for left stick events on a gamepad. A subject with a [`LocalPlayer`](crate::components::LocalPlayer)
+/// component only listens to that player's device.
 ///
-/// Note: This function will panic if there is either _no_ entity
-/// with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component or if there are more than one
-/// entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component.
+/// Note: This function does nothing if there is _no_ entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// component. If there are several without a [`LocalPlayer`](crate::components::LocalPlayer) component, only the
+/// one with an [`ActiveSubject`](crate::components::ActiveSubject) component is moved.
 pub fn first_person_movement(
     keyboard_input: Res<Input<KeyCode>>,
     gamepads: Res<Gamepads>,
     axes: Res<Axis<GamepadAxis>>,
-    mut query: Query<&mut Movement, With<FirstPersonSubject>>,
+    mut query: Query<
+        (&mut Movement, Option<&LocalPlayer>, Option<&ActiveSubject>),
+        With<FirstPersonSubject>,
+    >,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
 ) {
+    let subjects = query.iter_mut().map(|(movement, local_player, active)| {
+        (movement, local_player.cloned(), active.is_some())
+    });
+    for (mut movement, input) in
+        select_controlled_subjects("first_person_movement", subjects, &mut diagnostics)
+    {
+        let (left_right, forward_back) = match input {
+            None => read_movement(Some(&keyboard_input), gamepads.iter().cloned(), &axes),
+            Some(input) => read_movement(
+                input.uses_keyboard_mouse().then(|| &*keyboard_input),
+                input.gamepad().into_iter(),
+                &axes,
+            ),
+        };
+        movement.set_left_right(left_right);
+        movement.set_forward_back(forward_back);
+    }
+}
+
+/// Read the movement the player wants from the keyboard, if given, and from `gamepads`
+fn read_movement(
+    keyboard_input: Option<&Input<KeyCode>>,
+    gamepads: impl Iterator<Item = Gamepad>,
+    axes: &Axis<GamepadAxis>,
+) -> (MovementDirection, MovementDirection) {
     // Set defaults
     let mut left_right = MovementDirection::Right(0f32);
     let mut forward_back = MovementDirection::Forward(0f32);
 
     // Process Keyboard input
-    if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
-        left_right = MovementDirection::Left(1f32);
-    }
-    if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
-        left_right = MovementDirection::Right(1f32);
-    }
-    if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
-        forward_back = MovementDirection::Back(1f32);
-    }
-    if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
-        forward_back = MovementDirection::Forward(1f32);
+    if let Some(keyboard_input) = keyboard_input {
+        // Sneaking is like only tilting a gamepad's stick part of the way
+        let magnitude = if keyboard_input.pressed(KeyCode::LControl) {
+            SNEAK_MAGNITUDE
+        } else {
+            1f32
+        };
+        if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
+            left_right = MovementDirection::Left(magnitude);
+        }
+        if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
+            left_right = MovementDirection::Right(magnitude);
+        }
+        if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
+            forward_back = MovementDirection::Back(magnitude);
+        }
+        if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
+            forward_back = MovementDirection::Forward(magnitude);
+        }
     }
 
     // Process gamepad input because they have precedence
     // over keyboard input
-    for gamepad in gamepads.iter().cloned() {
+    for gamepad in gamepads {
         if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::LeftStickX)) {
             if magnitude != 0f32 {
                 left_right = if magnitude > 0f32 {
@@ -57,12 +99,7 @@ pub fn first_person_movement(
             }
         }
     }
-
-    let mut movement = query
-        .get_single_mut()
-        .expect("Could not find a player when querying using Movement component!");
-    movement.set_left_right(left_right);
-    movement.set_forward_back(forward_back);
+    (left_right, forward_back)
 }
 
 #[cfg(test)]
@@ -91,6 +128,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         // Add the system
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         // Step
@@ -126,6 +164,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -158,6 +197,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -190,6 +230,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -222,6 +263,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -235,6 +277,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_player_movement_w_sneaking() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let player_entity = world
+            .spawn()
+            .insert(Movement::default())
+            .insert(FirstPersonSubject)
+            .id();
+        let mut query = world.query::<(&mut Movement, With<FirstPersonSubject>)>();
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        // Press W while holding left control
+        keyboard_input.press(KeyCode::W);
+        keyboard_input.press(KeyCode::LControl);
+        let gamepads = Gamepads::default();
+        let axes: Axis<GamepadAxis> = Axis::default();
+        world.insert_resource(keyboard_input);
+        world.insert_resource(gamepads);
+        world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_movement.label("first"));
+        schedule.run_once(&mut world);
+
+        let (movement, _) = query.get_mut(&mut world, player_entity).unwrap();
+        assert_eq!(
+            movement.into_inner(),
+            &mut Movement::from_components(
+                MovementDirection::Right(0f32),
+                MovementDirection::Forward(SNEAK_MAGNITUDE)
+            )
+        );
+    }
+
     #[test]
     fn test_player_movement_wd() {
         let mut world = World::new();
@@ -255,6 +331,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -288,6 +365,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -320,6 +398,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -367,6 +446,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -399,6 +479,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -431,6 +512,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -463,6 +545,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -496,6 +579,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -529,6 +613,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -561,6 +646,7 @@ mod tests {
         world.insert_resource(keyboard_input);
         world.insert_resource(gamepads);
         world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
         schedule.add_system_to_stage("update", first_person_movement.label("first"));
         schedule.run_once(&mut world);
 
@@ -588,4 +674,159 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_player_movement_no_subject() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::W);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(Axis::<GamepadAxis>::default());
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_movement.label("first"));
+        // The player was despawned, so there's nothing to move and nothing to report
+        schedule.run_once(&mut world);
+
+        let mut diagnostics = world
+            .get_resource_mut::<Events<SubjectDiagnostic>>()
+            .unwrap();
+        assert_eq!(diagnostics.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_player_movement_two_subjects_one_active() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let inactive_entity = world
+            .spawn()
+            .insert(Movement::default())
+            .insert(FirstPersonSubject)
+            .id();
+        let active_entity = world
+            .spawn()
+            .insert(Movement::default())
+            .insert(FirstPersonSubject)
+            .insert(ActiveSubject)
+            .id();
+        let mut query = world.query::<(&mut Movement, With<FirstPersonSubject>)>();
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::W);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(Axis::<GamepadAxis>::default());
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_movement.label("first"));
+        schedule.run_once(&mut world);
+
+        // Only the active subject moves
+        let (movement, _) = query.get_mut(&mut world, active_entity).unwrap();
+        assert_eq!(
+            movement.into_inner(),
+            &mut Movement::from_components(
+                MovementDirection::Right(0f32),
+                MovementDirection::Forward(1f32)
+            )
+        );
+        let (movement, _) = query.get_mut(&mut world, inactive_entity).unwrap();
+        assert_eq!(movement.into_inner(), &mut Movement::default());
+        let mut diagnostics = world
+            .get_resource_mut::<Events<SubjectDiagnostic>>()
+            .unwrap();
+        assert_eq!(diagnostics.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_player_movement_two_subjects_none_active() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let player_entities = [
+            world
+                .spawn()
+                .insert(Movement::default())
+                .insert(FirstPersonSubject)
+                .id(),
+            world
+                .spawn()
+                .insert(Movement::default())
+                .insert(FirstPersonSubject)
+                .id(),
+        ];
+        let mut query = world.query::<(&mut Movement, With<FirstPersonSubject>)>();
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::W);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(Axis::<GamepadAxis>::default());
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_movement.label("first"));
+        schedule.run_once(&mut world);
+
+        // Neither subject moves, and the misconfiguration is reported
+        for player_entity in player_entities {
+            let (movement, _) = query.get_mut(&mut world, player_entity).unwrap();
+            assert_eq!(movement.into_inner(), &mut Movement::default());
+        }
+        let mut diagnostics = world
+            .get_resource_mut::<Events<SubjectDiagnostic>>()
+            .unwrap();
+        assert_eq!(
+            diagnostics.drain().collect::<Vec<SubjectDiagnostic>>(),
+            vec![SubjectDiagnostic::NoActiveSubject {
+                system: "first_person_movement",
+                subjects: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_player_movement_split_screen_reads_own_device() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let keyboard_player = world
+            .spawn()
+            .insert(Movement::default())
+            .insert(FirstPersonSubject)
+            .insert(LocalPlayer::new(0, InputSource::KeyboardMouse))
+            .id();
+        let gamepad_player = world
+            .spawn()
+            .insert(Movement::default())
+            .insert(FirstPersonSubject)
+            .insert(LocalPlayer::new(1, InputSource::Gamepad(Gamepad(0))))
+            .id();
+        let mut query = world.query::<(&mut Movement, With<FirstPersonSubject>)>();
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::D);
+        let mut axes: Axis<GamepadAxis> = Axis::default();
+        axes.set(GamepadAxis(Gamepad(0), GamepadAxisType::LeftStickY), 1f32);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(axes);
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_movement.label("first"));
+        schedule.run_once(&mut world);
+
+        let (movement, _) = query.get_mut(&mut world, keyboard_player).unwrap();
+        assert_eq!(
+            movement.into_inner(),
+            &mut Movement::from_components(
+                MovementDirection::Right(1f32),
+                MovementDirection::Forward(0f32)
+            )
+        );
+        let (movement, _) = query.get_mut(&mut world, gamepad_player).unwrap();
+        assert_eq!(
+            movement.into_inner(),
+            &mut Movement::from_components(
+                MovementDirection::Right(0f32),
+                MovementDirection::Forward(1f32)
+            )
+        );
+    }
 }
@@ -0,0 +1,155 @@
+use crate::components::{InputSource, LocalPlayer};
+use bevy::prelude::*;
+
+/// This event is sent by the first person systems when they can't tell which entity to control.
+/// The systems skip the frame instead of panicking, so this is how misconfiguration shows up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubjectDiagnostic {
+    /// Several entities have a [`FirstPersonSubject`](crate::components::FirstPersonSubject) component,
+    /// but none of them has an [`ActiveSubject`](crate::components::ActiveSubject) component
+    NoActiveSubject {
+        system: &'static str,
+        subjects: usize,
+    },
+    /// More than one entity has an [`ActiveSubject`](crate::components::ActiveSubject) component
+    SeveralActiveSubjects {
+        system: &'static str,
+        active_subjects: usize,
+    },
+    /// The subject being controlled has no [`FirstPersonHead`](crate::components::FirstPersonHead) child
+    MissingHead {
+        system: &'static str,
+        subject: Entity,
+    },
+}
+
+/// Pick the subject a first person system should control. `subjects` yields every subject
+/// with whether it has an [`ActiveSubject`](crate::components::ActiveSubject) component.
+///
+/// If there's no subject, `None` is returned, ie the player was despawned. If there's exactly
+/// one, it's returned. If there are several, the one marked active is returned. Otherwise a
+/// [`SubjectDiagnostic`](crate::systems::SubjectDiagnostic) is sent and `None` is returned.
+pub fn select_subject<T>(
+    system: &'static str,
+    subjects: impl Iterator<Item = (T, bool)>,
+    diagnostics: &mut EventWriter<SubjectDiagnostic>,
+) -> Option<T> {
+    match pick_subject(system, subjects) {
+        Ok(subject) => subject,
+        Err(diagnostic) => {
+            diagnostics.send(diagnostic);
+            None
+        }
+    }
+}
+
+/// Pick every subject a first person system should control: each subject with a
+/// [`LocalPlayer`](crate::components::LocalPlayer) component, along with the device its player uses,
+/// and the subject [`select_subject`](crate::systems::select_subject) picks from the rest.
+/// The latter is returned with no device, since it's controlled by every device.
+pub fn select_controlled_subjects<T>(
+    system: &'static str,
+    subjects: impl Iterator<Item = (T, Option<LocalPlayer>, bool)>,
+    diagnostics: &mut EventWriter<SubjectDiagnostic>,
+) -> Vec<(T, Option<InputSource>)> {
+    let mut controlled = Vec::new();
+    let mut shared = Vec::new();
+    for (subject, local_player, active) in subjects {
+        match local_player {
+            Some(local_player) => controlled.push((subject, Some(local_player.input()))),
+            None => shared.push((subject, active)),
+        }
+    }
+    if let Some(subject) = select_subject(system, shared.into_iter(), diagnostics) {
+        controlled.push((subject, None));
+    }
+    controlled
+}
+
+fn pick_subject<T>(
+    system: &'static str,
+    subjects: impl Iterator<Item = (T, bool)>,
+) -> Result<Option<T>, SubjectDiagnostic> {
+    let mut subjects: Vec<(T, bool)> = subjects.collect();
+    if subjects.len() <= 1 {
+        return Ok(subjects.pop().map(|(subject, _)| subject));
+    }
+    let subject_count = subjects.len();
+    let mut active_subjects: Vec<T> = subjects
+        .into_iter()
+        .filter(|(_, active)| *active)
+        .map(|(subject, _)| subject)
+        .collect();
+    match active_subjects.len() {
+        1 => Ok(active_subjects.pop()),
+        0 => Err(SubjectDiagnostic::NoActiveSubject {
+            system,
+            subjects: subject_count,
+        }),
+        active_subject_count => Err(SubjectDiagnostic::SeveralActiveSubjects {
+            system,
+            active_subjects: active_subject_count,
+        }),
+    }
+}
+
+/// Log [`SubjectDiagnostic`](crate::systems::SubjectDiagnostic) events. The same problem is
+/// reported every frame until it's fixed, so it's only logged when it first appears.
+pub fn log_subject_diagnostics(
+    mut diagnostics: EventReader<SubjectDiagnostic>,
+    mut logged: Local<Vec<SubjectDiagnostic>>,
+) {
+    let diagnostics: Vec<SubjectDiagnostic> = diagnostics.iter().cloned().collect();
+    for diagnostic in diagnostics.iter() {
+        if !logged.contains(diagnostic) {
+            warn!("First person controls are misconfigured: {:?}", diagnostic);
+        }
+    }
+    *logged = diagnostics;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_subject_none() {
+        let subjects: Vec<(u32, bool)> = vec![];
+        assert_eq!(pick_subject("test", subjects.into_iter()), Ok(None));
+    }
+
+    #[test]
+    fn test_pick_subject_single() {
+        // A single subject doesn't need to be marked active
+        assert_eq!(
+            pick_subject("test", vec![(1, false)].into_iter()),
+            Ok(Some(1))
+        );
+        assert_eq!(
+            pick_subject("test", vec![(1, true)].into_iter()),
+            Ok(Some(1))
+        );
+    }
+
+    #[test]
+    fn test_pick_subject_several() {
+        assert_eq!(
+            pick_subject("test", vec![(1, false), (2, true), (3, false)].into_iter()),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            pick_subject("test", vec![(1, false), (2, false)].into_iter()),
+            Err(SubjectDiagnostic::NoActiveSubject {
+                system: "test",
+                subjects: 2
+            })
+        );
+        assert_eq!(
+            pick_subject("test", vec![(1, true), (2, true), (3, false)].into_iter()),
+            Err(SubjectDiagnostic::SeveralActiveSubjects {
+                system: "test",
+                active_subjects: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::components::*;
+use crate::resources::{WeaponAction, WeaponBinding, WeaponBindings};
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+
+/// This function listens for keyboard, mouse and gamepad events and
+/// updates the [`WeaponIntents`](crate::components::WeaponIntents) component of an entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// component.
+///
+/// The inputs that fire, reload and switch weapons come from the [`WeaponBindings`](crate::resources::WeaponBindings)
+/// resource. A subject with a [`LocalPlayer`](crate::components::LocalPlayer) component only listens to
+/// that player's device.
+///
+/// Note: This function does nothing if there is _no_ entity with a [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// component. If there are several without a [`LocalPlayer`](crate::components::LocalPlayer) component, only the
+/// one with an [`ActiveSubject`](crate::components::ActiveSubject) component is armed.
+#[allow(clippy::too_many_arguments)]
+pub fn first_person_weapons(
+    weapon_bindings: Res<WeaponBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut query: Query<
+        (
+            &mut WeaponIntents,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
+) {
+    let scrolled: f32 = mouse_wheel_events.iter().map(|event| event.y).sum();
+    let subjects = query
+        .iter_mut()
+        .map(|(intents, local_player, active)| (intents, local_player.cloned(), active.is_some()));
+    for (mut intents, input) in
+        select_controlled_subjects("first_person_weapons", subjects, &mut diagnostics)
+    {
+        let (uses_keyboard_mouse, subject_gamepads): (bool, Vec<Gamepad>) = match input {
+            None => (true, gamepads.iter().cloned().collect()),
+            Some(input) => (
+                input.uses_keyboard_mouse(),
+                input.gamepad().into_iter().collect(),
+            ),
+        };
+        let pressed = |binding: WeaponBinding, just: bool| match binding {
+            WeaponBinding::Key(key) if uses_keyboard_mouse => {
+                if just {
+                    keyboard_input.just_pressed(key)
+                } else {
+                    keyboard_input.pressed(key)
+                }
+            }
+            WeaponBinding::Mouse(button) if uses_keyboard_mouse => {
+                if just {
+                    mouse_buttons.just_pressed(button)
+                } else {
+                    mouse_buttons.pressed(button)
+                }
+            }
+            WeaponBinding::Gamepad(button_type) => subject_gamepads.iter().any(|gamepad| {
+                let button = GamepadButton(*gamepad, button_type);
+                if just {
+                    gamepad_buttons.just_pressed(button)
+                } else {
+                    gamepad_buttons.pressed(button)
+                }
+            }),
+            // Every scroll is a press
+            WeaponBinding::ScrollUp => uses_keyboard_mouse && scrolled > 0f32,
+            WeaponBinding::ScrollDown => uses_keyboard_mouse && scrolled < 0f32,
+            _ => false,
+        };
+        let action_held = |action: WeaponAction| {
+            weapon_bindings
+                .bindings(action)
+                .any(|binding| pressed(binding, false))
+        };
+        let action_pressed = |action: WeaponAction| {
+            weapon_bindings
+                .bindings(action)
+                .any(|binding| pressed(binding, true))
+        };
+
+        let fire_held = action_held(WeaponAction::Fire);
+        let fire_pressed = action_pressed(WeaponAction::Fire);
+        let reload = action_pressed(WeaponAction::Reload);
+
+        let switch = weapon_bindings
+            .slots()
+            .into_iter()
+            .find(|slot| action_pressed(WeaponAction::Slot(*slot)))
+            .map(WeaponSwitch::Slot)
+            .or_else(|| {
+                if action_pressed(WeaponAction::NextWeapon) {
+                    Some(WeaponSwitch::Next)
+                } else if action_pressed(WeaponAction::PreviousWeapon) {
+                    Some(WeaponSwitch::Previous)
+                } else {
+                    None
+                }
+            });
+
+        intents.set_fire(fire_held, fire_pressed);
+        intents.set_reload(reload);
+        intents.set_switch(switch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with(
+        keyboard_input: Input<KeyCode>,
+        mouse_buttons: Input<MouseButton>,
+    ) -> WeaponIntents {
+        run_with_bindings(WeaponBindings::default(), keyboard_input, mouse_buttons)
+    }
+
+    fn run_with_bindings(
+        weapon_bindings: WeaponBindings,
+        keyboard_input: Input<KeyCode>,
+        mouse_buttons: Input<MouseButton>,
+    ) -> WeaponIntents {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        let player_entity = world
+            .spawn()
+            .insert(WeaponIntents::default())
+            .insert(FirstPersonSubject)
+            .id();
+        world.insert_resource(weapon_bindings);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(mouse_buttons);
+        world.insert_resource(Events::<MouseWheel>::default());
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(Input::<GamepadButton>::default());
+        world.insert_resource(Events::<SubjectDiagnostic>::default());
+        schedule.add_system_to_stage("update", first_person_weapons.label("first"));
+        schedule.run_once(&mut world);
+        *world.get::<WeaponIntents>(player_entity).unwrap()
+    }
+
+    #[test]
+    fn test_weapons_idle() {
+        let intents = run_with(Input::default(), Input::default());
+        assert_eq!(intents, WeaponIntents::default());
+    }
+
+    #[test]
+    fn test_weapons_fire_and_switch() {
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        // Press 3
+        keyboard_input.press(KeyCode::Key3);
+        let mut mouse_buttons: Input<MouseButton> = Input::default();
+        mouse_buttons.press(MouseButton::Left);
+        let intents = run_with(keyboard_input, mouse_buttons);
+        assert!(intents.fire_held());
+        assert!(intents.fire_pressed());
+        assert!(!intents.reload());
+        assert_eq!(intents.switch(), Some(WeaponSwitch::Slot(2)));
+    }
+
+    #[test]
+    fn test_weapons_follow_bindings() {
+        let mut weapon_bindings = WeaponBindings::default();
+        weapon_bindings.unbind(WeaponAction::Fire);
+        weapon_bindings.bind(WeaponAction::Fire, WeaponBinding::Key(KeyCode::F));
+        weapon_bindings.bind(WeaponAction::NextWeapon, WeaponBinding::Key(KeyCode::Tab));
+        let mut mouse_buttons: Input<MouseButton> = Input::default();
+        mouse_buttons.press(MouseButton::Left);
+        let intents = run_with_bindings(weapon_bindings.clone(), Input::default(), mouse_buttons);
+        assert!(!intents.fire_held());
+
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::F);
+        keyboard_input.press(KeyCode::Tab);
+        let intents = run_with_bindings(weapon_bindings, keyboard_input, Input::default());
+        assert!(intents.fire_held());
+        assert!(intents.fire_pressed());
+        assert_eq!(intents.switch(), Some(WeaponSwitch::Next));
+    }
+}
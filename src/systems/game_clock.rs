@@ -0,0 +1,206 @@
+use crate::resources::GameClock;
+use bevy::prelude::*;
+use bevy_rapier3d::physics::TimestepMode;
+use bevy_rapier3d::prelude::*;
+
+/// Advance the [`GameClock`](crate::resources::GameClock) by the real time that passed
+/// since the last frame. This should run before any gameplay system reads the clock.
+pub fn tick_game_clock(time: Res<Time>, mut game_clock: ResMut<GameClock>) {
+    game_clock.advance(time.delta());
+}
+
+/// Step physics by the [`GameClock`](crate::resources::GameClock)'s time scale too, by switching a
+/// variable timestep to a fixed one that's scaled every frame. It's switched back once the time scale
+/// is 1 again. `switched` is whether this system switched it, so a fixed timestep set up for
+/// something else, ie a replay, is left alone.
+pub fn scale_physics_timestep(
+    time: Res<Time>,
+    game_clock: Res<GameClock>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut switched: Local<bool>,
+) {
+    let is_scaled = game_clock.time_scale() != 1f32;
+    if is_scaled && matches!(rapier_config.timestep_mode, TimestepMode::VariableTimestep) {
+        rapier_config.timestep_mode = TimestepMode::FixedTimestep;
+        *switched = true;
+    }
+    if !*switched {
+        return;
+    }
+    if is_scaled {
+        integration_parameters.dt = time.delta_seconds() * game_clock.time_scale();
+    } else {
+        rapier_config.timestep_mode = TimestepMode::VariableTimestep;
+        *switched = false;
+    }
+}
+
+/// Stop the [`GameClock`](crate::resources::GameClock) so gameplay timers stop advancing.
+pub fn pause_game_clock(mut game_clock: ResMut<GameClock>) {
+    game_clock.pause();
+}
+
+/// Restart the [`GameClock`](crate::resources::GameClock) after it's been paused.
+pub fn resume_game_clock(mut game_clock: ResMut<GameClock>) {
+    game_clock.resume();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::states::GameLevel;
+    use std::time::Duration;
+
+    struct LevelTimer(Timer);
+
+    fn advance_game_clock_fixed(mut game_clock: ResMut<GameClock>) {
+        game_clock.advance(Duration::from_millis(100));
+    }
+
+    fn tick_level_timer(game_clock: Res<GameClock>, mut level_timer: ResMut<LevelTimer>) {
+        level_timer.0.tick(game_clock.delta());
+    }
+
+    // Wire the clock up the same way MainGameLevel does, but with a fixed
+    // real time delta so the results are deterministic
+    fn setup_app() -> App {
+        let mut app = App::new();
+        app.add_state(GameLevel::Main)
+            .insert_resource(GameClock::default())
+            .insert_resource(LevelTimer(Timer::from_seconds(10f32, false)))
+            .add_system_set(SystemSet::on_pause(GameLevel::Main).with_system(pause_game_clock))
+            .add_system_set(SystemSet::on_resume(GameLevel::Main).with_system(resume_game_clock))
+            .add_system_to_stage(CoreStage::PreUpdate, advance_game_clock_fixed)
+            .add_system_to_stage(CoreStage::PostUpdate, tick_level_timer);
+        app
+    }
+
+    fn push_level(app: &mut App, level: GameLevel) {
+        app.world
+            .get_resource_mut::<State<GameLevel>>()
+            .unwrap()
+            .push(level)
+            .unwrap();
+    }
+
+    fn pop_level(app: &mut App) {
+        app.world
+            .get_resource_mut::<State<GameLevel>>()
+            .unwrap()
+            .pop()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_time_scale_slows_physics_down() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(GameClock::default())
+            .insert_resource(RapierConfiguration {
+                gravity: Vector::zeros(),
+                scale: 1.0,
+                physics_pipeline_active: true,
+                query_pipeline_active: true,
+                timestep_mode: TimestepMode::VariableTimestep,
+            })
+            .insert_resource(IntegrationParameters::default())
+            .add_system(scale_physics_timestep);
+        let is_variable = |app: &App| {
+            matches!(
+                app.world
+                    .get_resource::<RapierConfiguration>()
+                    .unwrap()
+                    .timestep_mode,
+                TimestepMode::VariableTimestep
+            )
+        };
+        app.update();
+        assert!(is_variable(&app));
+
+        app.world
+            .get_resource_mut::<GameClock>()
+            .unwrap()
+            .set_time_scale(0.5);
+        std::thread::sleep(Duration::from_millis(10));
+        app.update();
+        assert!(!is_variable(&app));
+        let time_delta = app.world.get_resource::<Time>().unwrap().delta_seconds();
+        let dt = app
+            .world
+            .get_resource::<IntegrationParameters>()
+            .unwrap()
+            .dt;
+        assert!((dt - time_delta * 0.5).abs() < 1e-6);
+
+        app.world
+            .get_resource_mut::<GameClock>()
+            .unwrap()
+            .set_time_scale(1f32);
+        app.update();
+        assert!(is_variable(&app));
+    }
+
+    #[test]
+    fn test_game_clock_advances_in_main_level() {
+        let mut app = setup_app();
+        app.update();
+        app.update();
+
+        let game_clock = app.world.get_resource::<GameClock>().unwrap();
+        assert_eq!(game_clock.elapsed(), Duration::from_millis(200));
+        let level_timer = app.world.get_resource::<LevelTimer>().unwrap();
+        assert_eq!(level_timer.0.elapsed(), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_timers_dont_advance_in_pause_menu() {
+        let mut app = setup_app();
+        app.update();
+        push_level(&mut app, GameLevel::PauseMenu);
+        // The pause takes effect during this frame
+        app.update();
+        assert_eq!(
+            app.world
+                .get_resource::<State<GameLevel>>()
+                .unwrap()
+                .current(),
+            &GameLevel::PauseMenu
+        );
+        let paused_elapsed = app.world.get_resource::<GameClock>().unwrap().elapsed();
+        let paused_timer_elapsed = app.world.get_resource::<LevelTimer>().unwrap().0.elapsed();
+
+        app.update();
+        app.update();
+        app.update();
+
+        let game_clock = app.world.get_resource::<GameClock>().unwrap();
+        assert!(game_clock.is_paused());
+        assert_eq!(game_clock.delta(), Duration::ZERO);
+        assert_eq!(game_clock.elapsed(), paused_elapsed);
+        let level_timer = app.world.get_resource::<LevelTimer>().unwrap();
+        assert_eq!(level_timer.0.elapsed(), paused_timer_elapsed);
+    }
+
+    #[test]
+    fn test_timers_advance_after_resume() {
+        let mut app = setup_app();
+        app.update();
+        push_level(&mut app, GameLevel::PauseMenu);
+        app.update();
+        app.update();
+        let paused_elapsed = app.world.get_resource::<GameClock>().unwrap().elapsed();
+
+        pop_level(&mut app);
+        // The resume takes effect during this frame
+        app.update();
+        app.update();
+
+        let game_clock = app.world.get_resource::<GameClock>().unwrap();
+        assert!(!game_clock.is_paused());
+        assert_eq!(
+            game_clock.elapsed(),
+            paused_elapsed + Duration::from_millis(100)
+        );
+    }
+}
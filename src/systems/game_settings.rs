@@ -0,0 +1,24 @@
+use crate::resources::{GameSettings, GameSettingsFile};
+use bevy::prelude::*;
+
+/// Write the [`GameSettings`](crate::resources::GameSettings) to their file whenever they change.
+/// `saved` is what was last written, so settings that were only borrowed mutably aren't written again.
+pub fn save_changed_game_settings(
+    settings: Res<GameSettings>,
+    settings_file: Res<GameSettingsFile>,
+    mut saved: Local<Option<GameSettings>>,
+) {
+    if !settings.is_changed() || saved.as_ref() == Some(&*settings) {
+        return;
+    }
+    if settings.is_added() {
+        // They were just loaded, or are the defaults
+        *saved = Some(settings.clone());
+        return;
+    }
+    match settings.save(&settings_file.0) {
+        Ok(()) => debug!("Saved the settings to {}", settings_file.0.display()),
+        Err(save_err) => error!("Could not save the settings: {}", save_err),
+    }
+    *saved = Some(settings.clone());
+}
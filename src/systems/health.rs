@@ -0,0 +1,140 @@
+use crate::components::{DamageType, FallTracker, FirstPersonSubject, HazardVolume, Health};
+use crate::resources::{GameClock, GameConfig};
+use crate::save::RestoreSnapshotRequested;
+use crate::scripting::TriggerVolume;
+use crate::systems::PlayerDied;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashSet;
+
+/// Send this event to hurt an entity with [`Health`](crate::components::Health)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DamageEvent {
+    pub subject: Entity,
+    pub amount: f32,
+    pub damage_type: DamageType,
+    /// What dealt the damage, if it was something in the level
+    pub source: Option<Entity>,
+}
+
+/// Hurt the entities damage is dealt to. A [`FirstPersonSubject`](crate::components::FirstPersonSubject)
+/// that runs out of health dies.
+pub fn apply_damage(
+    game_config: Res<GameConfig>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut health_query: Query<(&mut Health, Option<&FirstPersonSubject>)>,
+    mut died_events: EventWriter<PlayerDied>,
+) {
+    let invulnerability = game_config.player().invulnerability();
+    for event in damage_events.iter() {
+        let (mut health, subject) = match health_query.get_mut(event.subject) {
+            Ok(health) => health,
+            Err(_) => continue,
+        };
+        let lost = health.damage(event.amount, event.damage_type, invulnerability);
+        if lost <= 0f32 {
+            continue;
+        }
+        debug!(
+            "{:?} took {} {:?} damage",
+            event.subject, lost, event.damage_type
+        );
+        if health.is_dead() && subject.is_some() {
+            died_events.send(PlayerDied {
+                subject: event.subject,
+            });
+        }
+    }
+}
+
+pub fn regenerate_health(
+    game_clock: Res<GameClock>,
+    game_config: Res<GameConfig>,
+    mut health_query: Query<&mut Health>,
+) {
+    let player_config = game_config.player();
+    for mut health in health_query.iter_mut() {
+        health.tick(
+            game_clock.delta(),
+            player_config.regeneration_delay(),
+            player_config.regeneration_per_second(),
+        );
+    }
+}
+
+/// Hurt bodies that land too hard. A contact starting this frame is an impact, and how much it
+/// slowed the body's fall since last frame is the speed it landed at.
+pub fn deal_fall_damage(
+    game_config: Res<GameConfig>,
+    mut contact_events: EventReader<ContactEvent>,
+    mut body_query: Query<(Entity, &mut FallTracker, &RigidBodyVelocityComponent)>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let impacted: HashSet<Entity> = contact_events
+        .iter()
+        .filter_map(|event| match event {
+            ContactEvent::Started(collider1, collider2) => Some([collider1, collider2]),
+            ContactEvent::Stopped(_, _) => None,
+        })
+        .flatten()
+        .map(|collider| collider.entity())
+        .collect();
+    for (entity, mut tracker, velocity) in body_query.iter_mut() {
+        let slowed_by = tracker.update(velocity.linvel.y);
+        if !impacted.contains(&entity) {
+            continue;
+        }
+        let amount = game_config.player().fall_damage(slowed_by);
+        if amount > 0f32 {
+            damage_events.send(DamageEvent {
+                subject: entity,
+                amount,
+                damage_type: DamageType::Fall,
+                source: None,
+            });
+        }
+    }
+}
+
+pub fn damage_in_hazards(
+    game_clock: Res<GameClock>,
+    mut hazard_query: Query<(Entity, &mut HazardVolume, &TriggerVolume)>,
+    health_query: Query<(), With<Health>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (hazard_entity, mut hazard, trigger) in hazard_query.iter_mut() {
+        let victims: Vec<Entity> = trigger
+            .occupants()
+            .iter()
+            .copied()
+            .filter(|occupant| health_query.get(*occupant).is_ok())
+            .collect();
+        if !hazard.tick(game_clock.delta(), !victims.is_empty()) {
+            continue;
+        }
+        for victim in victims {
+            damage_events.send(DamageEvent {
+                subject: victim,
+                amount: hazard.damage(),
+                damage_type: hazard.damage_type(),
+                source: Some(hazard_entity),
+            });
+        }
+    }
+}
+
+/// Bring dead players back when everyone is put back at the last checkpoint. If the checkpoint's
+/// snapshot has their health, it's loaded over this.
+pub fn revive_players_on_respawn(
+    mut restore_requests: EventReader<RestoreSnapshotRequested>,
+    mut health_query: Query<&mut Health, With<FirstPersonSubject>>,
+) {
+    if restore_requests.iter().last().is_none() {
+        return;
+    }
+    for mut health in health_query.iter_mut() {
+        if health.is_dead() {
+            health.revive();
+        }
+    }
+}
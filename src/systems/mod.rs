@@ -1,13 +1,30 @@
 mod activate_physics;
+mod checkpoint;
 mod deactivate_physics;
 mod first_person_lookaround;
 mod first_person_movement;
+mod first_person_subject;
+mod first_person_weapons;
+mod game_clock;
+mod game_settings;
+mod health;
 pub mod pausing;
+pub mod physics;
 pub mod player;
+mod surface;
 mod teardown_game_level;
+mod ui_camera;
 
 pub use self::activate_physics::*;
+pub use self::checkpoint::*;
 pub use self::deactivate_physics::*;
 pub use self::first_person_lookaround::*;
 pub use self::first_person_movement::*;
+pub use self::first_person_subject::*;
+pub use self::first_person_weapons::*;
+pub use self::game_clock::*;
+pub use self::game_settings::*;
+pub use self::health::*;
+pub use self::surface::*;
 pub use self::teardown_game_level::*;
+pub use self::ui_camera::*;
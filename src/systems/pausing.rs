@@ -1,35 +1,340 @@
+use crate::resources::PauseReason;
 use crate::states::{FirstPersonControlSettings, GameLevel};
+use bevy::input::gamepad::{GamepadEvent, GamepadEventType};
 use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+/// Push the pause menu and disable first person controls. If a level transition is
+/// already queued this frame (ie another system already paused the game), this does nothing.
+fn request_pause(
+    reason: PauseReason,
+    pause_reason: &mut PauseReason,
+    fp_control_settings: &mut State<FirstPersonControlSettings>,
+    game_level: &mut State<GameLevel>,
+) {
+    if game_level.push(GameLevel::PauseMenu).is_err() {
+        debug!(
+            "Ignoring pause request ({:?}), a level transition is already queued",
+            reason
+        );
+        return;
+    }
+    fp_control_settings
+        .set(FirstPersonControlSettings::Disabled)
+        .expect("Could not disable First Person Controls while pausing the game!");
+    *pause_reason = reason;
+}
+
+/// Whether the player pressed pause this frame, on the keyboard or any gamepad
+pub fn pause_just_pressed(
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> bool {
+    keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton(*gamepad, GamepadButtonType::Start))
+        })
+}
 
 pub fn pause_game(
     mut keyboard_input: ResMut<Input<KeyCode>>,
     gamepads: Res<Gamepads>,
     gamepad_buttons: Res<Input<GamepadButton>>,
+    mut pause_reason: ResMut<PauseReason>,
     mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
     mut game_level: ResMut<State<GameLevel>>,
 ) {
-    let mut should_pause = false;
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        should_pause = true;
+    if pause_just_pressed(&keyboard_input, &gamepads, &gamepad_buttons) {
         keyboard_input.reset(KeyCode::Escape);
+        request_pause(
+            PauseReason::Manual,
+            &mut pause_reason,
+            &mut fp_control_settings,
+            &mut game_level,
+        );
     }
-    for gamepad in gamepads.iter().cloned() {
-        if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::Start)) {
-            should_pause = true;
-        }
+}
+
+/// Whether the player pressed the inventory key this frame, Tab or I on the keyboard or Select
+/// on any gamepad
+pub fn inventory_just_pressed(
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> bool {
+    keyboard_input.just_pressed(KeyCode::Tab)
+        || keyboard_input.just_pressed(KeyCode::I)
+        || gamepads.iter().any(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton(*gamepad, GamepadButtonType::Select))
+        })
+}
+
+/// Push the inventory screen and disable first person controls, pausing the game the same
+/// way the pause menu does
+pub fn open_inventory(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    if !inventory_just_pressed(&keyboard_input, &gamepads, &gamepad_buttons) {
+        return;
     }
-    if should_pause {
-        fp_control_settings
-            .set(FirstPersonControlSettings::Disabled)
-            .expect("Could not disable First Person Controls while pausing the game!");
-        game_level
-            .push(GameLevel::PauseMenu)
-            .expect("Error occurred while trying to pause the game!");
+    // The inventory screen closes with the same keys
+    keyboard_input.reset(KeyCode::Tab);
+    keyboard_input.reset(KeyCode::I);
+    if game_level.push(GameLevel::Inventory).is_err() {
+        debug!("Ignoring inventory request, a level transition is already queued");
+        return;
     }
+    fp_control_settings
+        .set(FirstPersonControlSettings::Disabled)
+        .expect("Could not disable First Person Controls while opening the inventory!");
 }
 
-pub fn resume_game(mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>) {
+/// Pause the game when the window loses focus, so the player doesn't keep running
+/// and the cursor doesn't stay locked while they're in another window.
+pub fn pause_game_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut pause_reason: ResMut<PauseReason>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let focus_lost = focus_events.iter().any(|event| !event.focused);
+    if focus_lost {
+        request_pause(
+            PauseReason::FocusLost,
+            &mut pause_reason,
+            &mut fp_control_settings,
+            &mut game_level,
+        );
+    }
+}
+
+/// Pause the game when a gamepad disconnects mid-game.
+pub fn pause_game_on_gamepad_disconnected(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut pause_reason: ResMut<PauseReason>,
+    mut fp_control_settings: ResMut<State<FirstPersonControlSettings>>,
+    mut game_level: ResMut<State<GameLevel>>,
+) {
+    let disconnected_gamepad = gamepad_events
+        .iter()
+        .filter(|GamepadEvent(_, event_type)| *event_type == GamepadEventType::Disconnected)
+        .map(|GamepadEvent(gamepad, _)| *gamepad)
+        .last();
+    if let Some(gamepad) = disconnected_gamepad {
+        request_pause(
+            PauseReason::GamepadDisconnected(gamepad),
+            &mut pause_reason,
+            &mut fp_control_settings,
+            &mut game_level,
+        );
+    }
+}
+
+/// While the game is paused because a gamepad disconnected, let the player resume
+/// once that gamepad reconnects or they press a key to switch to the keyboard.
+pub fn allow_resume_on_input_restored(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut pause_reason: ResMut<PauseReason>,
+) {
+    if let PauseReason::GamepadDisconnected(lost_gamepad) = *pause_reason {
+        let reconnected = gamepad_events
+            .iter()
+            .any(|GamepadEvent(gamepad, event_type)| {
+                *gamepad == lost_gamepad && *event_type == GamepadEventType::Connected
+            });
+        let switched_to_keyboard = keyboard_input.get_just_pressed().next().is_some();
+        if reconnected || switched_to_keyboard {
+            *pause_reason = PauseReason::Manual;
+        }
+    }
+}
+
+/// Pop the pause menu and enable first person controls again.
+///
+/// This is called from the pause menu rather than when [`GameLevel::Main`](crate::states::GameLevel)
+/// resumes, because the main level also resumes briefly while it's being exited (ie when
+/// quitting to the main menu), and first person controls shouldn't be enabled then.
+pub fn resume_game(
+    pause_reason: &mut PauseReason,
+    fp_control_settings: &mut State<FirstPersonControlSettings>,
+    game_level: &mut State<GameLevel>,
+) {
+    game_level
+        .pop()
+        .expect("Error occurred while popping GameLevel from the pause menu!");
     fp_control_settings
         .set(FirstPersonControlSettings::Enabled)
         .expect("Could not enable First Person Controls while resuming the game!");
+    *pause_reason = PauseReason::None;
+}
+
+/// Pop the inventory screen and enable first person controls again, like
+/// [`resume_game`](crate::systems::pausing::resume_game) does for the pause menu
+pub fn close_inventory(
+    fp_control_settings: &mut State<FirstPersonControlSettings>,
+    game_level: &mut State<GameLevel>,
+) {
+    game_level
+        .pop()
+        .expect("Error occurred while popping GameLevel from the inventory!");
+    fp_control_settings
+        .set(FirstPersonControlSettings::Enabled)
+        .expect("Could not enable First Person Controls while closing the inventory!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::test_app::send;
+    use bevy::window::WindowId;
+
+    // Wire the systems up the same way PauseManagerPlugin does
+    fn setup_app() -> App {
+        let mut app = App::new();
+        app.add_state(GameLevel::Main)
+            .add_state(FirstPersonControlSettings::Enabled)
+            .insert_resource(PauseReason::default())
+            .insert_resource(Input::<KeyCode>::default())
+            .add_event::<WindowFocused>()
+            .add_event::<GamepadEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameLevel::Main)
+                    .with_system(pause_game_on_focus_lost)
+                    .with_system(pause_game_on_gamepad_disconnected),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameLevel::PauseMenu)
+                    .with_system(allow_resume_on_input_restored),
+            );
+        app.update();
+        app
+    }
+
+    fn game_level(app: &App) -> GameLevel {
+        app.world
+            .get_resource::<State<GameLevel>>()
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    fn pause_reason(app: &App) -> PauseReason {
+        *app.world.get_resource::<PauseReason>().unwrap()
+    }
+
+    fn fp_control_settings(app: &App) -> FirstPersonControlSettings {
+        app.world
+            .get_resource::<State<FirstPersonControlSettings>>()
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    fn send_focused(app: &mut App, focused: bool) {
+        send(
+            app,
+            WindowFocused {
+                id: WindowId::primary(),
+                focused,
+            },
+        );
+    }
+
+    #[test]
+    fn test_pause_on_focus_lost() {
+        let mut app = setup_app();
+        send_focused(&mut app, true);
+        app.update();
+        assert_eq!(game_level(&app), GameLevel::Main);
+        assert_eq!(pause_reason(&app), PauseReason::None);
+
+        send_focused(&mut app, false);
+        app.update();
+        assert_eq!(game_level(&app), GameLevel::PauseMenu);
+        assert_eq!(pause_reason(&app), PauseReason::FocusLost);
+        assert_eq!(
+            fp_control_settings(&app),
+            FirstPersonControlSettings::Disabled
+        );
+    }
+
+    #[test]
+    fn test_pause_on_gamepad_disconnected() {
+        let mut app = setup_app();
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(0), GamepadEventType::Connected),
+        );
+        app.update();
+        assert_eq!(game_level(&app), GameLevel::Main);
+
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(1), GamepadEventType::Disconnected),
+        );
+        app.update();
+        assert_eq!(game_level(&app), GameLevel::PauseMenu);
+        assert_eq!(
+            pause_reason(&app),
+            PauseReason::GamepadDisconnected(Gamepad(1))
+        );
+        assert_eq!(
+            fp_control_settings(&app),
+            FirstPersonControlSettings::Disabled
+        );
+    }
+
+    #[test]
+    fn test_resume_blocked_until_gamepad_reconnects() {
+        let mut app = setup_app();
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(1), GamepadEventType::Disconnected),
+        );
+        app.update();
+        app.update();
+        assert!(pause_reason(&app).blocks_resume());
+
+        // Another gamepad doesn't stand in for the lost one
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(0), GamepadEventType::Connected),
+        );
+        app.update();
+        assert!(pause_reason(&app).blocks_resume());
+
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(1), GamepadEventType::Connected),
+        );
+        app.update();
+        assert_eq!(pause_reason(&app), PauseReason::Manual);
+        assert_eq!(game_level(&app), GameLevel::PauseMenu);
+    }
+
+    #[test]
+    fn test_resume_blocked_until_key_pressed() {
+        let mut app = setup_app();
+        send(
+            &mut app,
+            GamepadEvent(Gamepad(1), GamepadEventType::Disconnected),
+        );
+        app.update();
+        app.update();
+        assert!(pause_reason(&app).blocks_resume());
+
+        app.world
+            .get_resource_mut::<Input<KeyCode>>()
+            .unwrap()
+            .press(KeyCode::Space);
+        app.update();
+        assert_eq!(pause_reason(&app), PauseReason::Manual);
+        assert_eq!(game_level(&app), GameLevel::PauseMenu);
+    }
 }
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use bevy_rapier3d::na::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use bevy_rapier3d::prelude::*;
+
+/// The Rapier position of a body at `transform`, ie to move a kinematic body there or to put a
+/// body back where it was saved
+pub fn body_position(transform: &Transform) -> Isometry3<f32> {
+    let translation = transform.translation;
+    let rotation = transform.rotation;
+    Isometry3::from_parts(
+        Translation3::new(translation.x, translation.y, translation.z),
+        UnitQuaternion::from_quaternion(Quaternion::new(
+            rotation.w, rotation.x, rotation.y, rotation.z,
+        )),
+    )
+}
+
+/// Whether a ray cast should stop at a collider. Rays pass through sensors, ie triggers, and
+/// through the `ignored` entities, ie whoever cast them.
+pub fn blocks_rays(
+    collider_type_query: &Query<&ColliderTypeComponent>,
+    ignored: &[Entity],
+    handle: ColliderHandle,
+) -> bool {
+    let entity = handle.entity();
+    !ignored.contains(&entity)
+        && collider_type_query
+            .get(entity)
+            .map(|collider_type| collider_type.0 != ColliderType::Sensor)
+            .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_position() {
+        let transform = Transform {
+            translation: Vec3::new(1f32, 2f32, 3f32),
+            rotation: Quat::from_rotation_y(0.75),
+            ..Default::default()
+        };
+        let position = body_position(&transform);
+        assert_eq!(position.translation.vector.x, 1f32);
+        assert_eq!(position.translation.vector.z, 3f32);
+        assert!((position.rotation.angle() - 0.75).abs() < 0.0001);
+    }
+}
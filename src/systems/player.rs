@@ -1,148 +1,321 @@
 use crate::components::{
-    FirstPersonHead, FirstPersonSubject, LevelObject, Lookaround, LookaroundDirection, Movement,
-    MovementDirection,
+    ActiveSubject, FallTracker, FirstPersonHead, FirstPersonSubject, Footing, Health, InputSource,
+    LevelObject, LocalPlayer, Lookaround, LookaroundDirection, Movement, MovementDirection,
+    SplitScreenViewport, Surface, Tags, WeaponIntents,
 };
-use crate::resources::{GameConfig, GameSettings};
+use crate::interaction::{Carrier, Interactor, PLAYER_SOLVER_GROUP};
+use crate::inventory::Inventory;
+use crate::mechanisms::KeyRing;
+use crate::perception::NoiseMaker;
+use crate::resources::{GameConfig, GameSettings, LocalPlayers, PlayerConfig};
+use crate::save::SaveId;
+use crate::split_screen::split_screen_camera_name;
+use crate::systems::physics::blocks_rays;
+use crate::systems::{select_controlled_subjects, SubjectDiagnostic};
+use crate::weapons::{Armory, WeaponDefinitions};
 use bevy::prelude::*;
 use bevy_rapier3d::na::{Point3, Vector3};
 use bevy_rapier3d::prelude::*;
 
+/// Add a player for every one of the [`LocalPlayers`](crate::resources::LocalPlayers), side by side.
+///
+/// In split-screen, each player gets a [`LocalPlayer`](crate::components::LocalPlayer) component so they're
+/// only controlled by their own device, and each camera gets its [`SplitScreenViewport`](crate::components::SplitScreenViewport).
+/// Their cameras are named after their player, so the [`SplitScreenPlugin`](crate::plugins::SplitScreenPlugin) draws them.
+/// If there are [`WeaponDefinitions`](crate::weapons::WeaponDefinitions), every head is armed with them.
+/// Players are made of the "player" [`SurfaceMaterial`](crate::resources::SurfaceMaterial).
 pub fn add_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     game_config: Res<GameConfig>,
+    local_players: Res<LocalPlayers>,
+    weapon_definitions: Option<Res<WeaponDefinitions>>,
 ) {
     let player_config = game_config.player();
     // Add a player
-    let player_capsule_total_height = player_config.capsule_height();
-    let player_capsule_radius = player_config.capsule_radius();
-    let player_halfheight_raw =
-        (player_capsule_total_height - (2f32 * player_capsule_radius)) / 2f32;
-    let player_halfheight_physics = Point3::from(Vector3::y() * player_halfheight_raw);
-    commands
-        .spawn()
-        .insert(FirstPersonSubject)
-        .insert(LevelObject)
-        .insert(Movement::default())
-        .insert(Lookaround::default())
-        // The transform is auto-updated by the rigid body
-        .insert(Transform::default())
-        .insert(RigidBodyPositionSync::Discrete)
-        .insert_bundle(ColliderBundle {
-            shape: ColliderShape::capsule(
-                -player_halfheight_physics,
-                player_halfheight_physics,
-                player_capsule_radius,
-            )
-            .into(),
-            material: ColliderMaterial {
-                restitution: 0.15f32,
-                friction: 2f32,
-                friction_combine_rule: CoefficientCombineRule::Max,
-                ..Default::default()
-            }
-            .into(),
-            ..Default::default()
-        })
-        .insert_bundle(RigidBodyBundle {
-            position: Vec3::new(0.0, 7.0, 7.0).into(),
-            mass_properties: (RigidBodyMassPropsFlags::ROTATION_LOCKED_X
-                | RigidBodyMassPropsFlags::ROTATION_LOCKED_Z)
-                .into(),
-            ..Default::default()
-        })
-        .insert_bundle(PbrBundle {
-            mesh: meshes.add(Mesh::from(bevy::prelude::shape::Capsule {
-                radius: player_capsule_radius,
-                depth: 2f32 * player_halfheight_raw,
-                rings: 3,
-                latitudes: 4,
-                longitudes: 6,
-                uv_profile: bevy::prelude::shape::CapsuleUvProfile::Fixed,
-            })),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                perceptual_roughness: 1f32,
+    let player_mesh = meshes.add(player_capsule_mesh(player_config));
+    let player_material = materials.add(StandardMaterial {
+        base_color: Color::RED,
+        perceptual_roughness: 1f32,
+        ..Default::default()
+    });
+    let player_count = local_players.count();
+    for index in 0..player_count {
+        let mut player = commands.spawn();
+        player
+            .insert(FirstPersonSubject)
+            .insert(LevelObject)
+            // Players are saved in their own section, this only names them for their saveable components
+            .insert(SaveId::new(format!("player_{}", index + 1)))
+            .insert(Tags::new(["player"]))
+            .insert(Interactor::default())
+            .insert(Carrier::default())
+            .insert(KeyRing::default())
+            .insert(Inventory::new(
+                player_config.inventory_slots(),
+                player_config.inventory_max_weight(),
+            ))
+            .insert(Health::new(player_config.max_health()))
+            .insert(FallTracker::default())
+            .insert(NoiseMaker::default())
+            .insert(Footing::default())
+            .insert(Surface::new("player"))
+            .insert(Movement::default())
+            .insert(Lookaround::default())
+            .insert(WeaponIntents::default())
+            // The transform is auto-updated by the rigid body
+            .insert(Transform::default())
+            .insert(RigidBodyPositionSync::Discrete)
+            .insert_bundle(player_collider(player_config))
+            .insert_bundle(player_rigid_body(Vec3::new(2f32 * index as f32, 7.0, 7.0)))
+            .insert_bundle(PbrBundle {
+                mesh: player_mesh.clone(),
+                material: player_material.clone(),
                 ..Default::default()
-            }),
-            ..Default::default()
-        })
-        .with_children(|player_body| {
-            player_body
-                .spawn()
-                .insert(FirstPersonHead)
+            });
+        // A single player can use any device
+        let split_screen_input = local_players
+            .input(index)
+            .filter(|_| local_players.is_split_screen());
+        if let Some(input) = split_screen_input {
+            player.insert(LocalPlayer::new(index, input));
+        }
+        player.with_children(|player_body| {
+            // A single player's camera is the active 3D camera, covering the whole window
+            let camera = if split_screen_input.is_some() {
+                PerspectiveCameraBundle::with_name(&split_screen_camera_name(index))
+            } else {
+                PerspectiveCameraBundle::default()
+            };
+            let mut head = player_body.spawn();
+            head.insert(FirstPersonHead)
                 .insert(LevelObject)
+                .insert(SplitScreenViewport::for_player(index, player_count))
                 .insert(Transform::from_rotation(Quat::from_rotation_x(
                     -std::f32::consts::FRAC_PI_4,
                 )))
-                .insert_bundle(PerspectiveCameraBundle::default());
+                .insert_bundle(camera);
+            if let Some(weapon_definitions) = weapon_definitions.as_ref() {
+                head.insert(Armory::from_definitions(weapon_definitions));
+            }
         });
+    }
 }
 
-pub fn rotate_player_head(
-    body_query: Query<&Lookaround, With<FirstPersonSubject>>,
-    mut head_query: Query<&mut Transform, (With<FirstPersonHead>, Without<FirstPersonSubject>)>,
-    settings: Res<GameSettings>,
-) {
-    let lookaround = body_query.get_single().expect("Could not find a player with Lookaround component while querying during rotating the player head!");
-    let mut head_transform = head_query.get_single_mut().expect("Could not find a FirstPersonHead with a Transform component while querying for the player's head!");
-    match lookaround.up_down() {
-        LookaroundDirection::Up(magnitude) => {
-            let (angle, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
-            let new_quat = Quat::from_rotation_x(
-                (angle + magnitude * 0.005 * (settings.vertical_sensitivity() as f32 / 5 as f32))
-                    .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
-            );
-            head_transform.rotation = new_quat;
+/// Half the length of the segment between the centers of a player capsule's caps
+fn player_capsule_half_height(player_config: &PlayerConfig) -> f32 {
+    (player_config.capsule_height() - (2f32 * player_config.capsule_radius())) / 2f32
+}
+
+/// The collider of a player's body, a capsule standing up around its center
+pub fn player_capsule_shape(player_config: &PlayerConfig) -> ColliderShape {
+    let half_height = Point3::from(Vector3::y() * player_capsule_half_height(player_config));
+    ColliderShape::capsule(-half_height, half_height, player_config.capsule_radius())
+}
+
+/// The collider of a player's body. It reports contacts, for fall damage.
+pub fn player_collider(player_config: &PlayerConfig) -> ColliderBundle {
+    ColliderBundle {
+        shape: player_capsule_shape(player_config).into(),
+        flags: ColliderFlags {
+            solver_groups: InteractionGroups::new(PLAYER_SOLVER_GROUP, u32::MAX),
+            // For fall damage
+            active_events: ActiveEvents::CONTACT_EVENTS,
+            ..Default::default()
         }
-        LookaroundDirection::Down(magnitude) => {
-            let (angle, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
-            let new_quat = Quat::from_rotation_x(
-                (angle - magnitude * 0.005 * (settings.vertical_sensitivity() as f32 / 5 as f32))
-                    .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
-            );
-            head_transform.rotation = new_quat;
+        .into(),
+        ..Default::default()
+    }
+}
+
+/// The dynamic body of a player centered on `position`, which only turns around the vertical axis
+pub fn player_rigid_body(position: Vec3) -> RigidBodyBundle {
+    RigidBodyBundle {
+        position: position.into(),
+        mass_properties: (RigidBodyMassPropsFlags::ROTATION_LOCKED_X
+            | RigidBodyMassPropsFlags::ROTATION_LOCKED_Z)
+            .into(),
+        ..Default::default()
+    }
+}
+
+/// The mesh of a player's body, the same size as its collider
+pub fn player_capsule_mesh(player_config: &PlayerConfig) -> Mesh {
+    Mesh::from(bevy::prelude::shape::Capsule {
+        radius: player_config.capsule_radius(),
+        depth: 2f32 * player_capsule_half_height(player_config),
+        rings: 3,
+        latitudes: 4,
+        longitudes: 6,
+        uv_profile: bevy::prelude::shape::CapsuleUvProfile::Fixed,
+    })
+}
+
+/// Rebuild the body of every [`FirstPersonSubject`](crate::components::FirstPersonSubject) when the
+/// [`PlayerConfig`](crate::resources::PlayerConfig) sizes its capsule differently, ie after the
+/// config is reloaded. Its feet stay where they were, so a taller capsule doesn't sink into the
+/// ground.
+pub fn resize_player_capsules(
+    game_config: Res<GameConfig>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut subject_query: Query<
+        (
+            &mut ColliderShapeComponent,
+            &mut RigidBodyPositionComponent,
+            Option<&mut Handle<Mesh>>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+) {
+    if !game_config.is_changed() {
+        return;
+    }
+    let player_config = game_config.player();
+    let half_height = player_capsule_half_height(player_config);
+    let radius = player_config.capsule_radius();
+    // Every resized player shares a mesh, like the ones add_player spawns
+    let mut resized_mesh: Option<Handle<Mesh>> = None;
+    for (mut shape, mut position, mesh) in subject_query.iter_mut() {
+        let old_capsule = match shape.as_capsule() {
+            Some(old_capsule) => *old_capsule,
+            None => continue,
+        };
+        if (old_capsule.half_height() - half_height).abs() < 1e-4
+            && (old_capsule.radius - radius).abs() < 1e-4
+        {
+            continue;
         }
-        _ => {
-            panic!("Lookaround up_down() was neither Up nor Down!");
+        let lift = (half_height + radius) - (old_capsule.half_height() + old_capsule.radius);
+        position.position.translation.vector.y += lift;
+        position.next_position.translation.vector.y += lift;
+        shape.0 = player_capsule_shape(player_config);
+        if let (Some(meshes), Some(mut mesh)) = (meshes.as_mut(), mesh) {
+            *mesh = resized_mesh
+                .get_or_insert_with(|| meshes.add(player_capsule_mesh(player_config)))
+                .clone();
         }
-    };
+    }
+}
+
+pub fn rotate_player_head(
+    body_query: Query<
+        (
+            Entity,
+            &Lookaround,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    mut head_query: Query<
+        (&mut Transform, &Parent),
+        (With<FirstPersonHead>, Without<FirstPersonSubject>),
+    >,
+    settings: Res<GameSettings>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
+) {
+    let subjects = body_query
+        .iter()
+        .map(|(entity, lookaround, local_player, active)| {
+            (
+                (entity, lookaround),
+                local_player.cloned(),
+                active.is_some(),
+            )
+        });
+    for ((subject, lookaround), _) in
+        select_controlled_subjects("rotate_player_head", subjects, &mut diagnostics)
+    {
+        // Every subject has its own head, so find the one attached to this subject
+        let mut head_transform = match head_query
+            .iter_mut()
+            .find(|(_, parent)| parent.0 == subject)
+        {
+            Some((head_transform, _)) => head_transform,
+            None => {
+                diagnostics.send(SubjectDiagnostic::MissingHead {
+                    system: "rotate_player_head",
+                    subject,
+                });
+                continue;
+            }
+        };
+        match lookaround.up_down() {
+            LookaroundDirection::Up(magnitude) => {
+                let (angle, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
+                let new_quat = Quat::from_rotation_x(
+                    (angle
+                        + magnitude * 0.005 * (settings.vertical_sensitivity() as f32 / 5 as f32))
+                        .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
+                );
+                head_transform.rotation = new_quat;
+            }
+            LookaroundDirection::Down(magnitude) => {
+                let (angle, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
+                let new_quat = Quat::from_rotation_x(
+                    (angle
+                        - magnitude * 0.005 * (settings.vertical_sensitivity() as f32 / 5 as f32))
+                        .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
+                );
+                head_transform.rotation = new_quat;
+            }
+            _ => {
+                panic!("Lookaround up_down() was neither Up nor Down!");
+            }
+        };
+    }
+}
+
+/// How far a player's body turns for one unit of [`Lookaround`](crate::components::Lookaround), in
+/// radians, at the player's sensitivity
+pub fn body_turn_rate(settings: &GameSettings) -> f32 {
+    0.002 * (settings.horizontal_sensitivity() as f32 / 5 as f32)
 }
 
 pub fn rotate_player_body(
-    mut query: Query<(&Lookaround, &mut RigidBodyPositionComponent), With<FirstPersonSubject>>,
+    mut query: Query<
+        (
+            &Lookaround,
+            &mut RigidBodyPositionComponent,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
+        With<FirstPersonSubject>,
+    >,
     settings: Res<GameSettings>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
 ) {
-    let (lookaround, mut body) = query
-        .get_single_mut()
-        .expect("Could not find a player while querying during rotating the player body!");
-
-    let mut rotation = body.position.rotation;
-    match lookaround.left_right() {
-        LookaroundDirection::Left(magnitude) => {
-            rotation = rotation.append_axisangle_linearized(
-                &(Vector3::y()
-                    * magnitude
-                    * 0.002
-                    * (settings.horizontal_sensitivity() as f32 / 5 as f32)),
-            );
-        }
-        LookaroundDirection::Right(magnitude) => {
-            rotation = rotation.append_axisangle_linearized(
-                &(Vector3::y()
-                    * -magnitude
-                    * 0.002
-                    * (settings.horizontal_sensitivity() as f32 / 5 as f32)),
-            );
-        }
-        _ => {
-            panic!("Lookaround left_right() was neither Left nor Right!")
-        }
-    };
-    body.position.rotation = rotation;
+    let subjects = query
+        .iter_mut()
+        .map(|(lookaround, body, local_player, active)| {
+            ((lookaround, body), local_player.cloned(), active.is_some())
+        });
+    for ((lookaround, mut body), _) in
+        select_controlled_subjects("rotate_player_body", subjects, &mut diagnostics)
+    {
+        let mut rotation = body.position.rotation;
+        match lookaround.left_right() {
+            LookaroundDirection::Left(magnitude) => {
+                rotation = rotation.append_axisangle_linearized(
+                    &(Vector3::y() * magnitude * body_turn_rate(&settings)),
+                );
+            }
+            LookaroundDirection::Right(magnitude) => {
+                rotation = rotation.append_axisangle_linearized(
+                    &(Vector3::y() * -magnitude * body_turn_rate(&settings)),
+                );
+            }
+            _ => {
+                panic!("Lookaround left_right() was neither Left nor Right!")
+            }
+        };
+        body.position.rotation = rotation;
+    }
 }
 
+/// Push every controlled subject the way it's moving, up to its max speed. Both are scaled by the
+/// speed modifier of the [`Footing`](crate::components::Footing) it's standing on, if it has one.
 pub fn move_player_body(
     mut query: Query<
         (
@@ -150,29 +323,60 @@ pub fn move_player_body(
             &Transform,
             &mut RigidBodyForcesComponent,
             &RigidBodyVelocityComponent,
+            Option<&Footing>,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
         ),
         With<FirstPersonSubject>,
     >,
     game_config: Res<GameConfig>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
 ) {
     let player_config = game_config.player();
-    let (movement, subject_transform, mut body_force, body_velocity) = query
-        .get_single_mut()
-        .expect("Could not find a player while querying during moving the player body!");
-    if body_velocity.linvel.magnitude() < player_config.max_speed() {
+    let subjects = query.iter_mut().map(
+        |(
+            movement,
+            subject_transform,
+            body_force,
+            body_velocity,
+            footing,
+            local_player,
+            active,
+        )| {
+            (
+                (
+                    movement,
+                    subject_transform,
+                    body_force,
+                    body_velocity,
+                    footing,
+                ),
+                local_player.cloned(),
+                active.is_some(),
+            )
+        },
+    );
+    for ((movement, subject_transform, mut body_force, body_velocity, footing), _) in
+        select_controlled_subjects("move_player_body", subjects, &mut diagnostics)
+    {
+        let speed_modifier = footing.map_or(1f32, Footing::speed_modifier);
+        let movement_force = player_config.movement_force() * speed_modifier;
+        if body_velocity.linvel.magnitude() >= player_config.max_speed() * speed_modifier {
+            continue;
+        }
         let local_z = subject_transform.local_z();
         let forward = -Vec3::new(local_z.x, 0., local_z.z);
         let right = Vec3::new(local_z.z, 0., -local_z.x);
         let left_right_magnitude = match movement.left_right() {
-            MovementDirection::Left(magnitude) => -magnitude * player_config.movement_force(),
-            MovementDirection::Right(magnitude) => magnitude * player_config.movement_force(),
+            MovementDirection::Left(magnitude) => -magnitude * movement_force,
+            MovementDirection::Right(magnitude) => magnitude * movement_force,
             _ => {
                 panic!("Movement left_right() was neither Left nor Right!")
             }
         };
         let forward_back_magnitude = match movement.forward_back() {
-            MovementDirection::Forward(magnitude) => magnitude * player_config.movement_force(),
-            MovementDirection::Back(magnitude) => -magnitude * player_config.movement_force(),
+            MovementDirection::Forward(magnitude) => magnitude * movement_force,
+            MovementDirection::Back(magnitude) => -magnitude * movement_force,
             _ => {
                 panic!("Movement forward_back() was neither Forward nor Back!")
             }
@@ -181,47 +385,113 @@ pub fn move_player_body(
     }
 }
 
+/// How far below a subject's feet [`find_ground`](crate::systems::player::find_ground) looks for
+/// ground, in meters
+pub const GROUND_PROBE_LENGTH: f32 = 0.02;
+
+/// The ray [`find_ground`](crate::systems::player::find_ground) casts down from the feet of a
+/// subject centered on `center`
+pub fn ground_probe(center: Vec3, capsule_height: f32) -> Ray {
+    let feet = center - Vec3::Y * (capsule_height / 2f32 + 0.01);
+    Ray::new(feet.into(), Vec3::new(0.0, -1.0, 0.0).into())
+}
+
+/// What a subject centered on `center` is standing on, if anything: the first thing just under
+/// its capsule other than the subject itself or a sensor, ie a trigger. Jumping, footsteps and
+/// [`Footing`](crate::components::Footing) all go by this.
+pub fn find_ground(
+    query_pipeline: &QueryPipeline,
+    collider_set: &QueryPipelineColliderComponentsSet,
+    collider_type_query: &Query<&ColliderTypeComponent>,
+    subject: Entity,
+    center: Vec3,
+    capsule_height: f32,
+) -> Option<Entity> {
+    let ray = ground_probe(center, capsule_height);
+    let filter: &dyn Fn(ColliderHandle) -> bool =
+        &|handle: ColliderHandle| blocks_rays(collider_type_query, &[subject], handle);
+    query_pipeline
+        .cast_ray(
+            collider_set,
+            &ray,
+            GROUND_PROBE_LENGTH,
+            true,
+            InteractionGroups::all(),
+            Some(filter),
+        )
+        .map(|(handle, _)| handle.entity())
+}
+
+/// Whether the player controlling a subject pressed jump this frame. A single player jumps
+/// with any device (`input` is `None`), split-screen players with their own.
+pub fn jump_just_pressed(
+    input: Option<InputSource>,
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> bool {
+    let (uses_keyboard, subject_gamepads): (bool, Vec<Gamepad>) = match input {
+        None => (true, gamepads.iter().cloned().collect()),
+        Some(input) => (
+            input.uses_keyboard_mouse(),
+            input.gamepad().into_iter().collect(),
+        ),
+    };
+    (uses_keyboard && keyboard_input.just_pressed(KeyCode::Space))
+        || subject_gamepads.into_iter().any(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::South))
+        })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn jump_player_body(
     rapier_query_pipeline: Res<QueryPipeline>,
     collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
     mut player_query: Query<
-        (&GlobalTransform, &mut RigidBodyForcesComponent),
+        (
+            Entity,
+            &GlobalTransform,
+            &mut RigidBodyForcesComponent,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
         With<FirstPersonSubject>,
     >,
     keyboard_input: Res<Input<KeyCode>>,
     gamepads: Res<Gamepads>,
     gamepad_buttons: Res<Input<GamepadButton>>,
     game_config: Res<GameConfig>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
 ) {
     let player_config = game_config.player();
-    let (player_transform, mut body_forces) = player_query
-        .get_single_mut()
-        .expect("Could not find a player while querying during making the player body jump!");
-
-    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
-    let mut player_global_position = player_transform.translation;
-    player_global_position.y -= (player_config.capsule_height() / 2f32) + 0.01;
-    let ray = Ray::new(
-        player_global_position.into(),
-        Vec3::new(0.0, -1.0, 0.0).into(),
+    let subjects = player_query.iter_mut().map(
+        |(subject, player_transform, body_forces, local_player, active)| {
+            (
+                (subject, player_transform, body_forces),
+                local_player.cloned(),
+                active.is_some(),
+            )
+        },
     );
-    let max_toi = 0.02;
-    let solid = true;
-    let groups = InteractionGroups::all();
-    let filter = None;
-
-    if let Some(_) =
-        rapier_query_pipeline.cast_ray(&collider_set, &ray, max_toi, solid, groups, filter)
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for ((subject, player_transform, mut body_forces), input) in
+        select_controlled_subjects("jump_player_body", subjects, &mut diagnostics)
     {
-        let mut jump_vector = vector![0f32, 0f32, 0f32];
-        if keyboard_input.just_pressed(KeyCode::Space) {
-            jump_vector.y = player_config.jump_force();
-        }
-        for gamepad in gamepads.iter().cloned() {
-            if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::South)) {
+        let ground = find_ground(
+            &rapier_query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            player_transform.translation,
+            player_config.capsule_height(),
+        );
+        if ground.is_some() {
+            let mut jump_vector = vector![0f32, 0f32, 0f32];
+            if jump_just_pressed(input, &keyboard_input, &gamepads, &gamepad_buttons) {
                 jump_vector.y = player_config.jump_force();
             }
+            body_forces.force = (body_forces.force as Vector3<f32>) + jump_vector;
         }
-        body_forces.force = (body_forces.force as Vector3<f32>) + jump_vector;
     }
 }
@@ -0,0 +1,113 @@
+use crate::components::{FirstPersonSubject, Footing, Surface};
+use crate::resources::{CombineRule, GameConfig, SurfaceMaterial};
+use crate::systems::player::find_ground;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+impl From<CombineRule> for CoefficientCombineRule {
+    fn from(rule: CombineRule) -> Self {
+        match rule {
+            CombineRule::Average => CoefficientCombineRule::Average,
+            CombineRule::Min => CoefficientCombineRule::Min,
+            CombineRule::Multiply => CoefficientCombineRule::Multiply,
+            CombineRule::Max => CoefficientCombineRule::Max,
+        }
+    }
+}
+
+/// The Rapier material of a collider made of `material`
+pub fn collider_material(material: &SurfaceMaterial) -> ColliderMaterial {
+    ColliderMaterial {
+        friction: material.friction(),
+        restitution: material.restitution(),
+        friction_combine_rule: material.friction_combine().into(),
+        restitution_combine_rule: material.restitution_combine().into(),
+    }
+}
+
+/// Give colliders the friction and restitution of their [`Surface`](crate::components::Surface),
+/// when it's added or changed, or when the [`GameConfig`](crate::resources::GameConfig) is
+pub fn apply_surface_materials(
+    game_config: Res<GameConfig>,
+    mut query: Query<(
+        Entity,
+        &Surface,
+        ChangeTrackers<Surface>,
+        &mut ColliderMaterialComponent,
+    )>,
+) {
+    for (entity, surface, surface_tracker, mut material) in query.iter_mut() {
+        if !game_config.is_changed() && !surface_tracker.is_changed() {
+            continue;
+        }
+        match game_config.material(surface.material()) {
+            Some(surface_material) => material.0 = collider_material(surface_material),
+            None => warn!(
+                "{:?} is made of {}, but there's no such material",
+                entity,
+                surface.material()
+            ),
+        }
+    }
+}
+
+/// Find what every [`FirstPersonSubject`](crate::components::FirstPersonSubject) with a
+/// [`Footing`](crate::components::Footing) is standing on, see
+/// [`find_ground`](crate::systems::player::find_ground).
+#[allow(clippy::type_complexity)]
+pub fn update_footing(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    surface_query: Query<&Surface>,
+    mut subject_query: Query<
+        (Entity, &mut Footing, &RigidBodyPositionComponent),
+        With<FirstPersonSubject>,
+    >,
+    game_config: Res<GameConfig>,
+) {
+    let player_config = game_config.player();
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for (subject, mut footing, position) in subject_query.iter_mut() {
+        let translation = position.position.translation.vector;
+        let ground = find_ground(
+            &query_pipeline,
+            &collider_set,
+            &collider_type_query,
+            subject,
+            Vec3::new(translation.x, translation.y, translation.z),
+            player_config.capsule_height(),
+        );
+        let speed_modifier = ground
+            .and_then(|ground| surface_query.get(ground).ok())
+            .and_then(|surface| game_config.material(surface.material()))
+            .map_or(1f32, SurfaceMaterial::speed_modifier);
+        let new_footing = Footing::new(ground, speed_modifier);
+        if *footing != new_footing {
+            *footing = new_footing;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collider_material() {
+        let game_config = GameConfig::default();
+        let player = collider_material(game_config.material("player").unwrap());
+        assert_eq!(
+            player.friction_combine_rule,
+            CoefficientCombineRule::Multiply
+        );
+        // On ground left at Rapier's defaults, the player grips like it always has
+        assert_eq!(player.friction * ColliderMaterial::default().friction, 2f32);
+        let bouncy = collider_material(game_config.material("bouncy").unwrap());
+        assert_eq!(bouncy.restitution, 0.7);
+        assert_eq!(
+            bouncy.restitution_combine_rule,
+            CoefficientCombineRule::Average
+        );
+    }
+}
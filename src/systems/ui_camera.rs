@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+/// Spawn the one camera every screen, menu and overlay's UI is drawn with. It lives as long as
+/// the app, so levels only spawn and tear down UI nodes.
+pub fn spawn_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
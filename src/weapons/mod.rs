@@ -0,0 +1,17 @@
+//! Hitscan and projectile weapons.
+//!
+//! A [`FirstPersonHead`](crate::components::FirstPersonHead) is armed with an [`Armory`](crate::weapons::Armory)
+//! of [`Weapon`](crate::weapons::Weapon)s, defined in `assets/weapons.toml`. Its subject fires, reloads and
+//! switches them following its [`WeaponIntents`](crate::components::WeaponIntents), and every shot kicks the
+//! head up with the weapon's recoil.
+//!
+//! Hitscan shots are cast through Rapier's [`QueryPipeline`](bevy_rapier3d::prelude::QueryPipeline), and
+//! projectiles are rigid bodies. Either sends a [`WeaponImpact`](crate::weapons::WeaponImpact) event when it
+//! hits something, which hurts it if it has [`Health`](crate::components::Health).
+mod projectile;
+mod systems;
+mod weapon;
+
+pub use self::projectile::*;
+pub use self::systems::*;
+pub use self::weapon::*;
@@ -0,0 +1,45 @@
+use crate::components::DamageType;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// This component is on a rigid body fired by a [`Weapon`](crate::weapons::Weapon). It hits the
+/// first thing it touches, then disappears, or disappears when its lifetime runs out.
+///
+/// Note: Its collider needs [`ActiveEvents::CONTACT_EVENTS`](bevy_rapier3d::prelude::ActiveEvents) to hit anything.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Projectile {
+    shooter: Entity,
+    damage: f32,
+    damage_type: DamageType,
+    lifetime: Duration,
+}
+
+impl Projectile {
+    pub fn new(shooter: Entity, damage: f32, damage_type: DamageType, lifetime: Duration) -> Self {
+        Projectile {
+            shooter,
+            damage,
+            damage_type,
+            lifetime,
+        }
+    }
+
+    /// The subject that fired it
+    pub fn shooter(&self) -> Entity {
+        self.shooter
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+
+    /// Let `delta` pass, and return whether the projectile's lifetime has run out
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        self.lifetime = self.lifetime.saturating_sub(delta);
+        self.lifetime.is_zero()
+    }
+}
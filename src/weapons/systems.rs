@@ -0,0 +1,271 @@
+use crate::components::{
+    ActiveSubject, DamageType, FirstPersonHead, FirstPersonSubject, LevelObject, LocalPlayer,
+    WeaponIntents,
+};
+use crate::interaction::Carrier;
+use crate::resources::{GameClock, GameConfig, GameRng};
+use crate::scripting::{ScriptColor, ScriptShape};
+use crate::systems::physics::blocks_rays;
+use crate::systems::{select_controlled_subjects, DamageEvent, SubjectDiagnostic};
+use crate::weapons::{spread_direction, Armory, FireKind, Projectile};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// How far past the shooter's capsule projectiles are spawned, in meters
+const PROJECTILE_CLEARANCE: f32 = 0.05;
+
+/// This event is sent when a shot hits something, whether it's in a level or not
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeaponImpact {
+    /// The subject that fired the shot
+    pub shooter: Entity,
+    pub target: Entity,
+    pub point: Vec3,
+    /// The surface's normal for hitscan shots. Projectiles use the way they came from instead.
+    pub normal: Vec3,
+    pub damage: f32,
+    pub damage_type: DamageType,
+}
+
+type HeadQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Armory,
+        &'static mut Transform,
+        &'static GlobalTransform,
+        &'static Parent,
+    ),
+    With<FirstPersonHead>,
+>;
+
+/// Switch, reload and fire the weapons of every controlled subject's head following its
+/// [`WeaponIntents`](crate::components::WeaponIntents), and kick the head up with their recoil. A
+/// subject carrying something can't use its weapons.
+#[allow(clippy::too_many_arguments)]
+pub fn fire_weapons(
+    mut commands: Commands,
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    collider_type_query: Query<&ColliderTypeComponent>,
+    subject_query: Query<
+        (
+            Entity,
+            &WeaponIntents,
+            Option<&Carrier>,
+            Option<&LocalPlayer>,
+            Option<&ActiveSubject>,
+        ),
+        With<FirstPersonSubject>,
+    >,
+    mut head_query: HeadQuery,
+    game_config: Res<GameConfig>,
+    game_clock: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
+    mut impact_events: EventWriter<WeaponImpact>,
+    mut diagnostics: EventWriter<SubjectDiagnostic>,
+) {
+    for (mut armory, _, _, _) in head_query.iter_mut() {
+        armory.tick(game_clock.delta());
+    }
+    let subjects = subject_query
+        .iter()
+        .map(|(subject, intents, carrier, local_player, active)| {
+            (
+                (subject, intents, carrier),
+                local_player.cloned(),
+                active.is_some(),
+            )
+        });
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    for ((subject, intents, carrier), _) in
+        select_controlled_subjects("fire_weapons", subjects, &mut diagnostics)
+    {
+        if carrier.and_then(|carrier| carrier.held()).is_some() {
+            continue;
+        }
+        let (mut armory, mut head_transform, head_global, _) = match head_query
+            .iter_mut()
+            .find(|(_, _, _, parent)| parent.0 == subject)
+        {
+            Some(head) => head,
+            None => continue,
+        };
+        if let Some(switch) = intents.switch() {
+            armory.switch(switch);
+        }
+        let weapon = match armory.selected_mut() {
+            Some(weapon) => weapon,
+            None => continue,
+        };
+        if intents.reload() {
+            weapon.start_reload();
+        }
+        if !weapon.try_fire(intents.fire_held(), intents.fire_pressed()) {
+            continue;
+        }
+        let definition = weapon.definition().clone();
+        debug!("{:?} fired the {}", subject, definition.name());
+        for _ in 0..definition.pellets() {
+            let direction = spread_direction(head_global.rotation, definition.spread(), &mut rng);
+            match *definition.fire() {
+                FireKind::Hitscan { range, damage } => {
+                    let ray = Ray::new(head_global.translation.into(), direction.into());
+                    // Shots pass through the shooter and through sensors, ie triggers
+                    let filter: &dyn Fn(ColliderHandle) -> bool = &|handle: ColliderHandle| {
+                        blocks_rays(&collider_type_query, &[subject], handle)
+                    };
+                    if let Some((handle, intersection)) = query_pipeline.cast_ray_and_get_normal(
+                        &collider_set,
+                        &ray,
+                        range,
+                        true,
+                        InteractionGroups::all(),
+                        Some(filter),
+                    ) {
+                        let point = ray.point_at(intersection.toi);
+                        impact_events.send(WeaponImpact {
+                            shooter: subject,
+                            target: handle.entity(),
+                            point: Vec3::new(point.x, point.y, point.z),
+                            normal: Vec3::new(
+                                intersection.normal.x,
+                                intersection.normal.y,
+                                intersection.normal.z,
+                            ),
+                            damage,
+                            damage_type: definition.damage_type(),
+                        });
+                    }
+                }
+                FireKind::Projectile {
+                    speed,
+                    damage,
+                    radius,
+                    gravity_scale,
+                    lifetime_seconds,
+                } => {
+                    // Far enough from the head that it can't start inside the shooter's capsule
+                    let origin = head_global.translation
+                        + direction
+                            * (game_config.player().capsule_height() / 2f32
+                                + radius
+                                + PROJECTILE_CLEARANCE);
+                    commands
+                        .spawn()
+                        .insert(LevelObject)
+                        .insert(Projectile::new(
+                            subject,
+                            damage,
+                            definition.damage_type(),
+                            Duration::from_secs_f32(lifetime_seconds.max(0f32)),
+                        ))
+                        .insert(ScriptShape::Ball { radius })
+                        .insert(ScriptColor(Color::DARK_GRAY))
+                        .insert(Transform::from_translation(origin))
+                        .insert(RigidBodyPositionSync::Discrete)
+                        .insert_bundle(RigidBodyBundle {
+                            position: origin.into(),
+                            velocity: RigidBodyVelocity {
+                                linvel: (direction * speed).into(),
+                                ..Default::default()
+                            }
+                            .into(),
+                            forces: RigidBodyForces {
+                                gravity_scale,
+                                ..Default::default()
+                            }
+                            .into(),
+                            // They're small and fast, so they'd tunnel through walls
+                            ccd: RigidBodyCcd {
+                                ccd_enabled: true,
+                                ..Default::default()
+                            }
+                            .into(),
+                            ..Default::default()
+                        })
+                        .insert_bundle(ColliderBundle {
+                            shape: ColliderShape::ball(radius).into(),
+                            flags: ActiveEvents::CONTACT_EVENTS.into(),
+                            ..Default::default()
+                        });
+                }
+            }
+        }
+        let (pitch, _, _) = head_transform.rotation.to_euler(EulerRot::XYZ);
+        head_transform.rotation = Quat::from_rotation_x(
+            (pitch + definition.recoil())
+                .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
+        );
+    }
+}
+
+/// Projectiles hit the first thing they touch, then disappear
+pub fn hit_with_projectiles(
+    mut commands: Commands,
+    mut contact_events: EventReader<ContactEvent>,
+    projectile_query: Query<(
+        &Projectile,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+    )>,
+    mut impact_events: EventWriter<WeaponImpact>,
+) {
+    let mut spent = HashSet::new();
+    for event in contact_events.iter() {
+        let (collider1, collider2) = match event {
+            ContactEvent::Started(collider1, collider2) => (collider1.entity(), collider2.entity()),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        for (projectile_entity, target) in [(collider1, collider2), (collider2, collider1)] {
+            let (projectile, position, velocity) = match projectile_query.get(projectile_entity) {
+                Ok(projectile) => projectile,
+                Err(_) => continue,
+            };
+            if !spent.insert(projectile_entity) {
+                continue;
+            }
+            let translation = position.position.translation.vector;
+            let direction = Vec3::new(velocity.linvel.x, velocity.linvel.y, velocity.linvel.z);
+            impact_events.send(WeaponImpact {
+                shooter: projectile.shooter(),
+                target,
+                point: Vec3::new(translation.x, translation.y, translation.z),
+                normal: -direction.normalize_or_zero(),
+                damage: projectile.damage(),
+                damage_type: projectile.damage_type(),
+            });
+            commands.entity(projectile_entity).despawn_recursive();
+        }
+    }
+}
+
+/// Remove projectiles that haven't hit anything in their lifetime
+pub fn expire_projectiles(
+    mut commands: Commands,
+    game_clock: Res<GameClock>,
+    mut projectile_query: Query<(Entity, &mut Projectile)>,
+) {
+    for (entity, mut projectile) in projectile_query.iter_mut() {
+        if projectile.tick(game_clock.delta()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Hurt what shots hit, if it has [`Health`](crate::components::Health)
+pub fn damage_on_impact(
+    mut impact_events: EventReader<WeaponImpact>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for impact in impact_events.iter() {
+        damage_events.send(DamageEvent {
+            subject: impact.target,
+            amount: impact.damage,
+            damage_type: impact.damage_type,
+            source: Some(impact.shooter),
+        });
+    }
+}
@@ -0,0 +1,415 @@
+use crate::components::{DamageType, WeaponSwitch};
+use crate::resources::GameRng;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// The weapons every player is armed with, in slot order
+const WEAPONS: &str = include_str!("../../assets/weapons.toml");
+
+fn default_pellets() -> u32 {
+    1
+}
+
+fn default_damage_type() -> DamageType {
+    DamageType::Ballistic
+}
+
+fn default_gravity_scale() -> f32 {
+    1f32
+}
+
+fn default_lifetime_seconds() -> f32 {
+    5f32
+}
+
+/// How a weapon hits things. In a weapons file, it's the weapon's `kind`, ie
+/// `{ kind = "hitscan", range = 100.0, damage = 20.0 }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FireKind {
+    /// Hits the first thing within `range` meters straight away
+    Hitscan { range: f32, damage: f32 },
+    /// Launches a ball `radius` meters across at `speed` meters per second, which hits the first
+    /// thing it touches or disappears after `lifetime_seconds`
+    Projectile {
+        speed: f32,
+        damage: f32,
+        radius: f32,
+        #[serde(default = "default_gravity_scale")]
+        gravity_scale: f32,
+        #[serde(default = "default_lifetime_seconds")]
+        lifetime_seconds: f32,
+    },
+}
+
+/// How a weapon behaves, ie in `assets/weapons.toml`
+///
+/// ```toml
+/// [[weapons]]
+/// name = "Pistol"
+/// kind = "hitscan"
+/// range = 80.0
+/// damage = 25.0
+/// fire_rate = 4.0
+/// spread = 1.0
+/// recoil = 2.0
+/// magazine = 12
+/// reload_seconds = 1.2
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WeaponDefinition {
+    name: String,
+    #[serde(flatten)]
+    fire: FireKind,
+    /// Shots per second
+    fire_rate: f32,
+    /// How far a shot can stray from where the head looks, in degrees
+    #[serde(default)]
+    spread: f32,
+    /// How far a shot kicks the head up, in degrees
+    #[serde(default)]
+    recoil: f32,
+    /// Shots before reloading
+    magazine: u32,
+    reload_seconds: f32,
+    /// Whether holding the trigger keeps firing
+    #[serde(default)]
+    automatic: bool,
+    /// How many hits or projectiles a shot is made of, ie for a shotgun
+    #[serde(default = "default_pellets")]
+    pellets: u32,
+    #[serde(default = "default_damage_type")]
+    damage_type: DamageType,
+}
+
+impl WeaponDefinition {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn fire(&self) -> &FireKind {
+        &self.fire
+    }
+
+    /// The time between shots
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs_f32(1f32 / self.fire_rate.max(f32::EPSILON))
+    }
+
+    /// The spread, in radians
+    pub fn spread(&self) -> f32 {
+        self.spread.to_radians()
+    }
+
+    /// The recoil, in radians
+    pub fn recoil(&self) -> f32 {
+        self.recoil.to_radians()
+    }
+
+    pub fn magazine(&self) -> u32 {
+        self.magazine
+    }
+
+    pub fn reload_time(&self) -> Duration {
+        Duration::from_secs_f32(self.reload_seconds.max(0f32))
+    }
+
+    pub fn automatic(&self) -> bool {
+        self.automatic
+    }
+
+    pub fn pellets(&self) -> u32 {
+        self.pellets.max(1)
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+}
+
+/// This resource holds the weapons players are armed with, in slot order. By default, they're
+/// the ones in `assets/weapons.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WeaponDefinitions {
+    #[serde(default)]
+    weapons: Vec<WeaponDefinition>,
+}
+
+impl Default for WeaponDefinitions {
+    fn default() -> Self {
+        WeaponDefinitions::from_toml(WEAPONS)
+            .unwrap_or_else(|parse_err| panic!("Could not parse the weapons: {}", parse_err))
+    }
+}
+
+impl WeaponDefinitions {
+    pub fn from_toml(toml_str: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WeaponDefinition> {
+        self.weapons.iter()
+    }
+}
+
+/// A weapon someone is carrying, with its ammunition and how long until it can fire again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    definition: WeaponDefinition,
+    rounds: u32,
+    cooldown: Duration,
+    /// How long is left until the weapon is reloaded, while it's reloading
+    reloading: Option<Duration>,
+}
+
+impl Weapon {
+    /// A weapon with a full magazine
+    pub fn new(definition: WeaponDefinition) -> Self {
+        Weapon {
+            rounds: definition.magazine,
+            definition,
+            cooldown: Duration::ZERO,
+            reloading: None,
+        }
+    }
+
+    pub fn definition(&self) -> &WeaponDefinition {
+        &self.definition
+    }
+
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reloading.is_some()
+    }
+
+    /// Let `delta` pass, cooling down and finishing a reload
+    pub fn tick(&mut self, delta: Duration) {
+        self.cooldown = self.cooldown.saturating_sub(delta);
+        if let Some(remaining) = self.reloading {
+            let remaining = remaining.saturating_sub(delta);
+            if remaining.is_zero() {
+                self.rounds = self.definition.magazine;
+                self.reloading = None;
+            } else {
+                self.reloading = Some(remaining);
+            }
+        }
+    }
+
+    /// Fire if the trigger asks to and the weapon is ready. An automatic weapon fires while the
+    /// trigger is `held`, others only when it's `pressed`. Returns whether it fired. An empty
+    /// weapon reloads instead.
+    pub fn try_fire(&mut self, held: bool, pressed: bool) -> bool {
+        let trigger = if self.definition.automatic {
+            held
+        } else {
+            pressed
+        };
+        if !trigger || self.is_reloading() || !self.cooldown.is_zero() {
+            return false;
+        }
+        if self.rounds == 0 {
+            self.start_reload();
+            return false;
+        }
+        self.rounds -= 1;
+        self.cooldown = self.definition.cooldown();
+        if self.rounds == 0 {
+            self.start_reload();
+        }
+        true
+    }
+
+    /// Start reloading, unless the weapon is already reloading or full. Returns whether it started.
+    pub fn start_reload(&mut self) -> bool {
+        if self.is_reloading() || self.rounds == self.definition.magazine {
+            return false;
+        }
+        self.reloading = Some(self.definition.reload_time());
+        true
+    }
+
+    /// Stop reloading, ie when the weapon is put away, keeping the rounds it had
+    pub fn cancel_reload(&mut self) {
+        self.reloading = None;
+    }
+}
+
+/// This component arms a [`FirstPersonHead`](crate::components::FirstPersonHead) with weapons. Its
+/// subject fires the selected one following its [`WeaponIntents`](crate::components::WeaponIntents).
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Armory {
+    weapons: Vec<Weapon>,
+    selected: usize,
+}
+
+impl Armory {
+    pub fn new(weapons: impl IntoIterator<Item = Weapon>) -> Self {
+        Armory {
+            weapons: weapons.into_iter().collect(),
+            selected: 0,
+        }
+    }
+
+    /// An armory with every weapon in `definitions`, fully loaded
+    pub fn from_definitions(definitions: &WeaponDefinitions) -> Self {
+        Armory::new(definitions.iter().cloned().map(Weapon::new))
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// The weapon in hand, if there are any
+    pub fn selected(&self) -> Option<&Weapon> {
+        self.weapons.get(self.selected)
+    }
+
+    pub fn selected_mut(&mut self) -> Option<&mut Weapon> {
+        self.weapons.get_mut(self.selected)
+    }
+
+    /// Let `delta` pass for the weapon in hand
+    pub fn tick(&mut self, delta: Duration) {
+        if let Some(weapon) = self.selected_mut() {
+            weapon.tick(delta);
+        }
+    }
+
+    /// Switch weapons, cancelling the reload of the one put away. Returns whether the weapon in
+    /// hand changed. Next and previous wrap around, and a slot with no weapon is ignored.
+    pub fn switch(&mut self, switch: WeaponSwitch) -> bool {
+        let count = self.weapons.len();
+        if count == 0 {
+            return false;
+        }
+        let selected = match switch {
+            WeaponSwitch::Next => (self.selected + 1) % count,
+            WeaponSwitch::Previous => (self.selected + count - 1) % count,
+            WeaponSwitch::Slot(slot) if slot < count => slot,
+            WeaponSwitch::Slot(_) => self.selected,
+        };
+        if selected == self.selected {
+            return false;
+        }
+        self.weapons[self.selected].cancel_reload();
+        self.selected = selected;
+        true
+    }
+}
+
+/// The direction of a shot from a head turned by `rotation`, strayed at random by up to `spread`
+/// radians
+pub fn spread_direction(rotation: Quat, spread: f32, rng: &mut GameRng) -> Vec3 {
+    // The square root spreads shots evenly over the cone's base, not bunched in its middle
+    let angle = spread * rng.next_f32().sqrt();
+    let around = std::f32::consts::TAU * rng.next_f32();
+    rotation * Quat::from_rotation_z(around) * Quat::from_rotation_x(angle) * -Vec3::Z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(automatic: bool) -> WeaponDefinition {
+        WeaponDefinition {
+            name: String::from("Test"),
+            fire: FireKind::Hitscan {
+                range: 10f32,
+                damage: 10f32,
+            },
+            fire_rate: 2f32,
+            spread: 0f32,
+            recoil: 0f32,
+            magazine: 3,
+            reload_seconds: 1f32,
+            automatic,
+            pellets: 1,
+            damage_type: DamageType::Ballistic,
+        }
+    }
+
+    #[test]
+    fn test_fire_rate_and_trigger() {
+        let mut weapon = Weapon::new(definition(false));
+        assert!(weapon.try_fire(true, true));
+        // Cooling down
+        assert!(!weapon.try_fire(true, true));
+        weapon.tick(Duration::from_millis(500));
+        // A semi-automatic weapon needs the trigger pressed again
+        assert!(!weapon.try_fire(true, false));
+        assert!(weapon.try_fire(true, true));
+
+        let mut weapon = Weapon::new(definition(true));
+        assert!(weapon.try_fire(true, true));
+        weapon.tick(Duration::from_millis(500));
+        assert!(weapon.try_fire(true, false));
+    }
+
+    #[test]
+    fn test_reloads_when_empty() {
+        let mut weapon = Weapon::new(definition(true));
+        for _ in 0..3 {
+            assert!(weapon.try_fire(true, false));
+            weapon.tick(Duration::from_millis(500));
+        }
+        assert_eq!(weapon.rounds(), 0);
+        assert!(weapon.is_reloading());
+        assert!(!weapon.try_fire(true, false));
+        weapon.tick(Duration::from_millis(500));
+        assert_eq!(weapon.rounds(), 3);
+        assert!(!weapon.is_reloading());
+
+        // A full weapon doesn't reload
+        assert!(!weapon.start_reload());
+        weapon.try_fire(true, false);
+        assert!(weapon.start_reload());
+    }
+
+    #[test]
+    fn test_switching_cancels_reload() {
+        let mut armory = Armory::new([
+            Weapon::new(definition(false)),
+            Weapon::new(definition(true)),
+        ]);
+        armory.selected_mut().unwrap().try_fire(true, true);
+        armory.selected_mut().unwrap().start_reload();
+        assert!(armory.switch(WeaponSwitch::Next));
+        assert_eq!(armory.selected_index(), 1);
+        assert!(armory.switch(WeaponSwitch::Next));
+        assert_eq!(armory.selected_index(), 0);
+        assert!(!armory.selected().unwrap().is_reloading());
+        assert_eq!(armory.selected().unwrap().rounds(), 2);
+        assert!(armory.switch(WeaponSwitch::Previous));
+        assert_eq!(armory.selected_index(), 1);
+        assert!(!armory.switch(WeaponSwitch::Slot(1)));
+        assert!(!armory.switch(WeaponSwitch::Slot(5)));
+    }
+
+    #[test]
+    fn test_spread_stays_in_cone() {
+        let mut rng = GameRng::from_seed(11);
+        let spread = 5f32.to_radians();
+        for _ in 0..100 {
+            let direction = spread_direction(Quat::IDENTITY, spread, &mut rng);
+            assert!(direction.angle_between(-Vec3::Z) <= spread + 1e-3);
+        }
+        assert!(spread_direction(Quat::IDENTITY, 0f32, &mut rng).abs_diff_eq(-Vec3::Z, 1e-6));
+    }
+
+    #[test]
+    fn test_parse_weapons() {
+        let definitions = WeaponDefinitions::default();
+        assert!(definitions.iter().count() >= 2);
+        assert!(definitions
+            .iter()
+            .any(|definition| matches!(definition.fire(), FireKind::Hitscan { .. })));
+        assert!(definitions
+            .iter()
+            .any(|definition| matches!(definition.fire(), FireKind::Projectile { .. })));
+    }
+}
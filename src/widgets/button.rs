@@ -0,0 +1,96 @@
+use crate::resources::UiTheme;
+use crate::widgets::{MenuFocus, UiContext};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+/// A clickable button with a text label.
+pub struct Button {
+    label: String,
+    width: f32,
+}
+
+impl Button {
+    pub fn new(label: impl Into<String>) -> Self {
+        Button {
+            label: label.into(),
+            width: 200f32,
+        }
+    }
+
+    /// Make the button wider than the default, ie for longer labels
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Spawn the button, returning its entity so the caller can insert a marker component
+    /// to react to it being clicked.
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut button = parent.spawn();
+        button
+            .insert(ui.marker())
+            .insert(ui.next_focusable())
+            .insert_bundle(button_bundle(ui.theme(), self.width))
+            .with_children(|button| {
+                button
+                    .spawn()
+                    .insert(ui.marker())
+                    .insert_bundle(ui.text_bundle(
+                        self.label,
+                        ui.theme().text_font_size(),
+                        ui.theme().text_color(),
+                    ));
+            });
+        button
+    }
+}
+
+/// The bundle shared by every widget that the player can click
+pub(crate) fn button_bundle(theme: &UiTheme, width: f32) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(width), Val::Px(65.0)),
+            margin: Rect {
+                top: Val::Px(20.0),
+                bottom: Val::Px(20.0),
+                ..Default::default()
+            },
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: theme.button_color().into(),
+        ..Default::default()
+    }
+}
+
+/// Color every clickable widget based on how the player is interacting with it,
+/// and highlight the one with the keyboard / gamepad focus
+pub fn style_buttons_on_interaction(
+    theme: Res<UiTheme>,
+    menu_focus: Res<MenuFocus>,
+    mut interaction_query: Query<
+        (
+            Entity,
+            &Interaction,
+            ChangeTrackers<Interaction>,
+            &mut UiColor,
+        ),
+        With<bevy::ui::widget::Button>,
+    >,
+) {
+    for (entity, interaction, interaction_tracker, mut color) in interaction_query.iter_mut() {
+        // Every button has to be restyled when the focus moves, since one lost it
+        if !interaction_tracker.is_changed() && !menu_focus.is_changed() {
+            continue;
+        }
+        let focused = menu_focus.focused() == Some(entity);
+        *color = theme.button_color_with_focus(interaction, focused).into();
+    }
+}
@@ -0,0 +1,188 @@
+use crate::widgets::{button_bundle, UiContext, WidgetActivated};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+/// This component holds the options of a dropdown and which one is selected. Systems can
+/// query for `Changed<DropdownValue>` to react to the player picking an option.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct DropdownValue {
+    options: Vec<String>,
+    selected: usize,
+    open: bool,
+    text: Entity,
+    list: Entity,
+}
+
+impl DropdownValue {
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_option(&self) -> &String {
+        &self.options[self.selected]
+    }
+}
+
+/// This component is added to the buttons of a dropdown. `option` is `None` for the
+/// button that opens the list, and the index of the option otherwise.
+#[derive(Component)]
+pub struct DropdownButton {
+    dropdown: Entity,
+    option: Option<usize>,
+}
+
+/// A button that opens a list of options for the player to pick from.
+pub struct Dropdown {
+    options: Vec<String>,
+    selected: usize,
+}
+
+impl Dropdown {
+    pub fn new(options: Vec<String>, selected: usize) -> Self {
+        assert!(
+            selected < options.len(),
+            "Dropdown selection is out of bounds!"
+        );
+        Dropdown { options, selected }
+    }
+
+    /// Spawn the dropdown, returning the entity with its [`DropdownValue`](crate::widgets::DropdownValue)
+    /// so the caller can insert a marker component to find it later.
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let theme = ui.theme();
+        let mut text = None;
+        let mut list = None;
+        let mut container = parent.spawn();
+        let dropdown = container.id();
+        container
+            .insert(ui.marker())
+            .insert_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .with_children(|container| {
+                // The button showing the selected option
+                container
+                    .spawn()
+                    .insert(ui.marker())
+                    .insert(ui.next_focusable())
+                    .insert(DropdownButton {
+                        dropdown,
+                        option: None,
+                    })
+                    .insert_bundle(button_bundle(theme, 350f32))
+                    .with_children(|button| {
+                        text = Some(
+                            button
+                                .spawn()
+                                .insert(ui.marker())
+                                .insert_bundle(ui.text_bundle(
+                                    self.options[self.selected].clone(),
+                                    theme.text_font_size(),
+                                    theme.text_color(),
+                                ))
+                                .id(),
+                        );
+                    });
+                // The list of options, hidden until the dropdown is opened
+                list = Some(
+                    container
+                        .spawn()
+                        .insert(ui.marker())
+                        .insert_bundle(NodeBundle {
+                            style: Style {
+                                display: Display::None,
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            color: theme.panel_color().into(),
+                            ..Default::default()
+                        })
+                        .with_children(|list| {
+                            for (index, option) in self.options.iter().enumerate() {
+                                list.spawn()
+                                    .insert(ui.marker())
+                                    .insert(ui.next_focusable())
+                                    .insert(DropdownButton {
+                                        dropdown,
+                                        option: Some(index),
+                                    })
+                                    .insert_bundle(button_bundle(theme, 350f32))
+                                    .with_children(|button| {
+                                        button.spawn().insert(ui.marker()).insert_bundle(
+                                            ui.text_bundle(
+                                                option.clone(),
+                                                theme.small_font_size(),
+                                                theme.text_color(),
+                                            ),
+                                        );
+                                    });
+                            }
+                        })
+                        .id(),
+                );
+            });
+        container.insert(DropdownValue {
+            options: self.options,
+            selected: self.selected,
+            open: false,
+            text: text.expect("Dropdown text wasn't spawned!"),
+            list: list.expect("Dropdown list wasn't spawned!"),
+        });
+        container
+    }
+}
+
+/// Open a dropdown when the player activates it, and pick an option when they activate one
+pub fn select_dropdown_option_on_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    button_query: Query<&DropdownButton>,
+    mut dropdown_query: Query<&mut DropdownValue>,
+) {
+    for WidgetActivated(entity) in activated_events.iter() {
+        let button = match button_query.get(*entity) {
+            Ok(button) => button,
+            Err(_) => continue,
+        };
+        if let Ok(mut dropdown_value) = dropdown_query.get_mut(button.dropdown) {
+            match button.option {
+                None => dropdown_value.open = !dropdown_value.open,
+                Some(index) if index < dropdown_value.options.len() => {
+                    dropdown_value.selected = index;
+                    dropdown_value.open = false;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Show or hide a dropdown's list and redraw its text when its value changes
+pub fn update_dropdown_display(
+    dropdown_query: Query<&DropdownValue, Changed<DropdownValue>>,
+    mut style_query: Query<&mut Style>,
+    mut text_query: Query<&mut Text>,
+) {
+    for dropdown_value in dropdown_query.iter() {
+        if let Ok(mut list_style) = style_query.get_mut(dropdown_value.list) {
+            list_style.display = if dropdown_value.open {
+                Display::Flex
+            } else {
+                Display::None
+            };
+        }
+        if let Ok(mut text) = text_query.get_mut(dropdown_value.text) {
+            text.sections[0].value = dropdown_value.selected_option().clone();
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use crate::widgets::SliderValue;
+use bevy::prelude::*;
+
+/// How far the left stick must be pushed before it moves the focus
+const STICK_NAVIGATION_THRESHOLD: f32 = 0.5;
+
+/// This component marks a widget that can be focused with the keyboard or a gamepad.
+///
+/// Widgets are focused in `order`. Only widgets on the highest `layer` on screen can be focused,
+/// so a modal dialog keeps the focus away from the screen behind it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Focusable {
+    order: u32,
+    layer: u32,
+}
+
+impl Focusable {
+    pub fn new(order: u32, layer: u32) -> Self {
+        Focusable { order, layer }
+    }
+}
+
+/// The widget that currently has the keyboard / gamepad focus, if any.
+#[derive(Debug, Default)]
+pub struct MenuFocus {
+    focused: Option<Entity>,
+}
+
+impl MenuFocus {
+    pub fn focused(&self) -> Option<Entity> {
+        self.focused
+    }
+}
+
+/// This event is sent when the player activates a widget, either by clicking it
+/// or by pressing Enter / gamepad South while it has the focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetActivated(pub Entity);
+
+/// This event is sent when the player presses Escape or gamepad East in a menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MenuBack;
+
+/// A direction to move the focus in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusMove {
+    Next,
+    Previous,
+}
+
+/// Pick the widget that should be focused next. `candidates` must be sorted in focus order.
+/// If nothing in `candidates` is focused, the first candidate is picked.
+pub fn next_focus(
+    candidates: &[Entity],
+    focused: Option<Entity>,
+    focus_move: FocusMove,
+) -> Option<Entity> {
+    if candidates.is_empty() {
+        return None;
+    }
+    let current_index = focused.and_then(|focused| candidates.iter().position(|e| *e == focused));
+    let next_index = match (current_index, focus_move) {
+        (None, _) => 0,
+        (Some(index), FocusMove::Next) => (index + 1) % candidates.len(),
+        (Some(index), FocusMove::Previous) => (index + candidates.len() - 1) % candidates.len(),
+    };
+    Some(candidates[next_index])
+}
+
+/// The widgets that can currently be focused, in focus order. Widgets hidden with
+/// `Display::None` are laid out with no size, so they're skipped.
+fn focus_candidates(focusable_query: &Query<(Entity, &Focusable, &Node)>) -> Vec<Entity> {
+    let mut visible: Vec<(Entity, Focusable)> = focusable_query
+        .iter()
+        .filter(|(_, _, node)| node.size.x > 0f32 && node.size.y > 0f32)
+        .map(|(entity, focusable, _)| (entity, *focusable))
+        .collect();
+    let top_layer = match visible.iter().map(|(_, focusable)| focusable.layer).max() {
+        Some(top_layer) => top_layer,
+        None => return Vec::new(),
+    };
+    visible.retain(|(_, focusable)| focusable.layer == top_layer);
+    visible.sort_by_key(|(_, focusable)| focusable.order);
+    visible.into_iter().map(|(entity, _)| entity).collect()
+}
+
+/// Move the focus with the arrow keys, Tab / Shift+Tab, the D-pad or the left stick
+pub fn navigate_menu_focus(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    focusable_query: Query<(Entity, &Focusable, &Node)>,
+    mut menu_focus: ResMut<MenuFocus>,
+    mut stick_was_pushed: Local<bool>,
+) {
+    let candidates = focus_candidates(&focusable_query);
+    // Drop the focus if the focused widget was despawned, hidden or covered by a dialog
+    if let Some(focused) = menu_focus.focused {
+        if !candidates.contains(&focused) {
+            menu_focus.focused = None;
+        }
+    }
+    if candidates.is_empty() {
+        return;
+    }
+
+    let shift_pressed =
+        keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+    let mut focus_move = None;
+    if keyboard_input.just_pressed(KeyCode::Up)
+        || (keyboard_input.just_pressed(KeyCode::Tab) && shift_pressed)
+    {
+        focus_move = Some(FocusMove::Previous);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down)
+        || (keyboard_input.just_pressed(KeyCode::Tab) && !shift_pressed)
+    {
+        focus_move = Some(FocusMove::Next);
+    }
+
+    let mut stick_is_pushed = false;
+    for gamepad in gamepads.iter().cloned() {
+        if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::DPadUp)) {
+            focus_move = Some(FocusMove::Previous);
+        }
+        if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::DPadDown)) {
+            focus_move = Some(FocusMove::Next);
+        }
+        if let Some(magnitude) = axes.get(GamepadAxis(gamepad, GamepadAxisType::LeftStickY)) {
+            if magnitude.abs() > STICK_NAVIGATION_THRESHOLD {
+                stick_is_pushed = true;
+                // Only move once per push of the stick
+                if !*stick_was_pushed {
+                    focus_move = Some(if magnitude > 0f32 {
+                        FocusMove::Previous
+                    } else {
+                        FocusMove::Next
+                    });
+                }
+            }
+        }
+    }
+    *stick_was_pushed = stick_is_pushed;
+
+    if let Some(focus_move) = focus_move {
+        menu_focus.focused = next_focus(&candidates, menu_focus.focused, focus_move);
+    }
+}
+
+/// Move the focused slider with left / right on the keyboard or the D-pad
+pub fn adjust_focused_slider(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    menu_focus: Res<MenuFocus>,
+    track_query: Query<&Parent, With<Focusable>>,
+    mut slider_query: Query<&mut SliderValue>,
+) {
+    let focused = match menu_focus.focused {
+        Some(focused) => focused,
+        None => return,
+    };
+    let mut steps = 0;
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        steps -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        steps += 1;
+    }
+    for gamepad in gamepads.iter().cloned() {
+        if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::DPadLeft)) {
+            steps -= 1;
+        }
+        if gamepad_buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::DPadRight)) {
+            steps += 1;
+        }
+    }
+    if steps == 0 {
+        return;
+    }
+    // A slider's focusable track is a child of the entity holding its value
+    if let Ok(parent) = track_query.get(focused) {
+        if let Ok(mut slider_value) = slider_query.get_mut(parent.0) {
+            slider_value.step_by(steps);
+        }
+    }
+}
+
+/// Send [`WidgetActivated`](crate::widgets::WidgetActivated) when a widget is clicked,
+/// or when Enter / gamepad South is pressed while it has the focus.
+///
+/// The key or button is consumed, so a level entered because of it doesn't see it as well
+/// (ie the player doesn't jump when resuming the game with gamepad South).
+pub fn activate_widgets(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    menu_focus: Res<MenuFocus>,
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<Focusable>)>,
+    mut activated_events: EventWriter<WidgetActivated>,
+) {
+    for (entity, interaction) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            activated_events.send(WidgetActivated(entity));
+        }
+    }
+
+    let focused = match menu_focus.focused {
+        Some(focused) => focused,
+        None => return,
+    };
+    let mut activate_focused = false;
+    for key in [KeyCode::Return, KeyCode::NumpadEnter] {
+        if keyboard_input.just_pressed(key) {
+            activate_focused = true;
+            keyboard_input.reset(key);
+        }
+    }
+    for gamepad in gamepads.iter().cloned() {
+        let south = GamepadButton(gamepad, GamepadButtonType::South);
+        if gamepad_buttons.just_pressed(south) {
+            activate_focused = true;
+            gamepad_buttons.reset(south);
+        }
+    }
+    if activate_focused {
+        activated_events.send(WidgetActivated(focused));
+    }
+}
+
+/// Send [`MenuBack`](crate::widgets::MenuBack) when Escape or gamepad East is pressed while
+/// a menu is on screen. Like [`activate_widgets`](crate::widgets::activate_widgets), the key or
+/// button is consumed, so Escape doesn't pause the game again right after it resumed.
+pub fn send_menu_back(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    focusable_query: Query<Entity, With<Focusable>>,
+    mut back_events: EventWriter<MenuBack>,
+) {
+    // Outside of the menus, Escape and East belong to the level
+    if focusable_query.iter().next().is_none() {
+        return;
+    }
+    let mut back = false;
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        back = true;
+        keyboard_input.reset(KeyCode::Escape);
+    }
+    for gamepad in gamepads.iter().cloned() {
+        let east = GamepadButton(gamepad, GamepadButtonType::East);
+        if gamepad_buttons.just_pressed(east) {
+            back = true;
+            gamepad_buttons.reset(east);
+        }
+    }
+    if back {
+        back_events.send(MenuBack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(count: u32) -> Vec<Entity> {
+        (0..count).map(Entity::from_raw).collect()
+    }
+
+    #[test]
+    fn test_next_focus_no_candidates() {
+        assert_eq!(next_focus(&[], None, FocusMove::Next), None);
+    }
+
+    #[test]
+    fn test_next_focus_starts_at_first() {
+        let candidates = entities(3);
+        assert_eq!(
+            next_focus(&candidates, None, FocusMove::Next),
+            Some(candidates[0])
+        );
+        assert_eq!(
+            next_focus(&candidates, None, FocusMove::Previous),
+            Some(candidates[0])
+        );
+        // A focused entity that isn't a candidate is treated like no focus
+        assert_eq!(
+            next_focus(&candidates, Some(Entity::from_raw(10)), FocusMove::Next),
+            Some(candidates[0])
+        );
+    }
+
+    #[test]
+    fn test_next_focus_wraps() {
+        let candidates = entities(3);
+        assert_eq!(
+            next_focus(&candidates, Some(candidates[1]), FocusMove::Next),
+            Some(candidates[2])
+        );
+        assert_eq!(
+            next_focus(&candidates, Some(candidates[2]), FocusMove::Next),
+            Some(candidates[0])
+        );
+        assert_eq!(
+            next_focus(&candidates, Some(candidates[0]), FocusMove::Previous),
+            Some(candidates[2])
+        );
+    }
+
+    #[test]
+    fn test_navigate_menu_focus_skips_lower_layers_and_hidden_widgets() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+
+        let visible_node = Node {
+            size: Vec2::new(100f32, 50f32),
+        };
+        let hidden_node = Node { size: Vec2::ZERO };
+        // A screen behind a dialog
+        world
+            .spawn()
+            .insert(Focusable::new(0, 0))
+            .insert(visible_node.clone());
+        // The dialog's buttons, one of which is hidden
+        let dialog_hidden = world
+            .spawn()
+            .insert(Focusable::new(0, 1))
+            .insert(hidden_node)
+            .id();
+        let dialog_yes = world
+            .spawn()
+            .insert(Focusable::new(1, 1))
+            .insert(visible_node.clone())
+            .id();
+        let dialog_no = world
+            .spawn()
+            .insert(Focusable::new(2, 1))
+            .insert(visible_node)
+            .id();
+
+        let mut keyboard_input: Input<KeyCode> = Input::default();
+        keyboard_input.press(KeyCode::Down);
+        world.insert_resource(keyboard_input);
+        world.insert_resource(Gamepads::default());
+        world.insert_resource(Input::<GamepadButton>::default());
+        world.insert_resource(Axis::<GamepadAxis>::default());
+        world.insert_resource(MenuFocus::default());
+        schedule.add_system_to_stage("update", navigate_menu_focus);
+        schedule.run_once(&mut world);
+
+        let focused = world.get_resource::<MenuFocus>().unwrap().focused();
+        assert_eq!(focused, Some(dialog_yes));
+        assert_ne!(focused, Some(dialog_hidden));
+
+        // Pressing down again moves to the next button in the dialog
+        let mut keyboard_input = world.get_resource_mut::<Input<KeyCode>>().unwrap();
+        keyboard_input.clear();
+        keyboard_input.release(KeyCode::Down);
+        keyboard_input.press(KeyCode::Down);
+        schedule.run_once(&mut world);
+        let focused = world.get_resource::<MenuFocus>().unwrap().focused();
+        assert_eq!(focused, Some(dialog_no));
+    }
+}
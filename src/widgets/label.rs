@@ -0,0 +1,82 @@
+use crate::widgets::UiContext;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy)]
+enum LabelSize {
+    Title,
+    Text,
+    Small,
+}
+
+/// A piece of non-interactive text.
+pub struct Label {
+    text: String,
+    size: LabelSize,
+    color: Option<Color>,
+}
+
+impl Label {
+    /// Large text, ie for the name of a screen
+    pub fn title(text: impl Into<String>) -> Self {
+        Label {
+            text: text.into(),
+            size: LabelSize::Title,
+            color: None,
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Label {
+            text: text.into(),
+            size: LabelSize::Text,
+            color: None,
+        }
+    }
+
+    /// Small text, ie for notices and warnings
+    pub fn small(text: impl Into<String>) -> Self {
+        Label {
+            text: text.into(),
+            size: LabelSize::Small,
+            color: None,
+        }
+    }
+
+    /// Use a color other than the theme's default for this label
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Spawn the label, returning its entity so the caller can insert a marker
+    /// component to update its text later.
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let theme = ui.theme();
+        let (font_size, default_color) = match self.size {
+            LabelSize::Title => (theme.title_font_size(), theme.title_color()),
+            LabelSize::Text => (theme.text_font_size(), theme.text_color()),
+            LabelSize::Small => (theme.small_font_size(), theme.text_color()),
+        };
+        let mut text_bundle =
+            ui.text_bundle(self.text, font_size, self.color.unwrap_or(default_color));
+        if let LabelSize::Title = self.size {
+            text_bundle.style = Style {
+                margin: Rect {
+                    top: Val::Px(20.0),
+                    bottom: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+        }
+
+        let mut label = parent.spawn();
+        label.insert(ui.marker()).insert_bundle(text_bundle);
+        label
+    }
+}
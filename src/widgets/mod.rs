@@ -0,0 +1,109 @@
+//! Builders for the widgets that make up the menu levels.
+//!
+//! A screen is built by creating a [`UiContext`](crate::widgets::UiContext) and spawning a
+//! [`Panel`](crate::widgets::Panel) whose children are other widgets, ie
+//!
+//! ```ignore
+//! let ui = UiContext::new(&theme, &asset_server, LevelObject);
+//! widgets::Panel::screen().spawn_root(&mut commands, &ui, |screen| {
+//!     widgets::Label::title("Paused").spawn(screen, &ui);
+//!     widgets::Button::new("Resume").spawn(screen, &ui).insert(ResumeButton);
+//! });
+//! ```
+//!
+//! Every widget's look comes from the [`UiTheme`](crate::resources::UiTheme) resource, and the systems
+//! that make them interactive are added by the [`WidgetPlugin`](crate::plugins::WidgetPlugin).
+//!
+//! Widgets the player can activate are [`Focusable`](crate::widgets::Focusable) in the order they're
+//! spawned, so menus can be used with a keyboard or gamepad. Levels react to a
+//! [`WidgetActivated`](crate::widgets::WidgetActivated) event rather than to mouse clicks.
+use crate::resources::UiTheme;
+use bevy::prelude::{AssetServer, Color, Component, Font, Handle, Text, TextBundle, TextStyle};
+use std::cell::Cell;
+
+mod button;
+mod dropdown;
+mod focus;
+mod label;
+mod panel;
+mod slider;
+mod toggle;
+
+pub use self::button::*;
+pub use self::dropdown::*;
+pub use self::focus::*;
+pub use self::label::*;
+pub use self::panel::*;
+pub use self::slider::*;
+pub use self::toggle::*;
+
+/// Everything a widget needs to spawn itself. `marker` is inserted on every entity a widget
+/// spawns, so the whole screen can be torn down by querying for it.
+///
+/// `layer` is given to every [`Focusable`](crate::widgets::Focusable) spawned with this context,
+/// and `next_focus_order` counts them so they're focused in the order they were spawned.
+pub struct UiContext<M: Component + Clone> {
+    theme: UiTheme,
+    font: Handle<Font>,
+    marker: M,
+    layer: u32,
+    next_focus_order: Cell<u32>,
+}
+
+impl<M: Component + Clone> UiContext<M> {
+    pub fn new(theme: &UiTheme, asset_server: &AssetServer, marker: M) -> Self {
+        UiContext {
+            theme: theme.clone(),
+            font: asset_server.load(theme.font_path().as_str()),
+            marker,
+            layer: 0,
+            next_focus_order: Cell::new(0),
+        }
+    }
+
+    /// A context for widgets drawn on top of this one, ie a dialog. While they're on screen,
+    /// only they can be focused.
+    pub fn modal(&self) -> Self {
+        UiContext {
+            theme: self.theme.clone(),
+            font: self.font.clone(),
+            marker: self.marker(),
+            layer: self.layer + 1,
+            next_focus_order: Cell::new(0),
+        }
+    }
+
+    pub fn theme(&self) -> &UiTheme {
+        &self.theme
+    }
+
+    pub fn marker(&self) -> M {
+        self.marker.clone()
+    }
+
+    /// The [`Focusable`](crate::widgets::Focusable) for the next widget the player can activate
+    fn next_focusable(&self) -> Focusable {
+        let order = self.next_focus_order.get();
+        self.next_focus_order.set(order + 1);
+        Focusable::new(order, self.layer)
+    }
+
+    fn text_style(&self, font_size: f32, color: Color) -> TextStyle {
+        TextStyle {
+            font: self.font.clone(),
+            font_size,
+            color,
+        }
+    }
+
+    fn text_bundle(&self, text: impl Into<String>, font_size: f32, color: Color) -> TextBundle {
+        TextBundle {
+            text: Text::with_section(
+                text.into(),
+                self.text_style(font_size, color),
+                Default::default(),
+            ),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::widgets::UiContext;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+/// A container that lays out other widgets.
+pub struct Panel {
+    style: Style,
+    color: Color,
+}
+
+impl Panel {
+    /// Fill the whole window and center its children horizontally
+    pub fn screen() -> Self {
+        Panel {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE,
+        }
+    }
+
+    /// Fill the whole window on top of everything else, centering its children.
+    /// This is meant for modal dialogs.
+    pub fn overlay() -> Self {
+        Panel {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE,
+        }
+    }
+
+    /// Stack children from top to bottom in the center two thirds of the window
+    pub fn column() -> Self {
+        Panel {
+            style: Style {
+                size: Size::new(Val::Percent(66.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                // horizontally center child text
+                flex_direction: FlexDirection::ColumnReverse,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE,
+        }
+    }
+
+    /// Stack children from top to bottom, only taking up as much space as they need
+    pub fn boxed() -> Self {
+        Panel {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                padding: Rect::all(Val::Px(20.0)),
+                ..Default::default()
+            },
+            color: Color::NONE,
+        }
+    }
+
+    /// Lay children out from left to right
+    pub fn row() -> Self {
+        Panel {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Start the panel hidden. Set its `Style::display` to `Display::Flex` to show it.
+    pub fn hidden(mut self) -> Self {
+        self.style.display = Display::None;
+        self
+    }
+
+    fn node_bundle(self) -> NodeBundle {
+        NodeBundle {
+            style: self.style,
+            color: self.color.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Spawn the panel at the root of the UI
+    pub fn spawn_root<'w, 's, 'a, M: Component + Clone>(
+        self,
+        commands: &'a mut Commands<'w, 's>,
+        ui: &UiContext<M>,
+        children: impl FnOnce(&mut ChildBuilder),
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut panel = commands.spawn();
+        panel
+            .insert(ui.marker())
+            .insert_bundle(self.node_bundle())
+            .with_children(children);
+        panel
+    }
+
+    /// Spawn the panel inside of another widget
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+        children: impl FnOnce(&mut ChildBuilder),
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut panel = parent.spawn();
+        panel
+            .insert(ui.marker())
+            .insert_bundle(self.node_bundle())
+            .with_children(children);
+        panel
+    }
+}
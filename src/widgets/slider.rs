@@ -0,0 +1,256 @@
+use crate::widgets::UiContext;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+/// This component holds the current value of a slider. Systems can query for
+/// `Changed<SliderValue>` to react to the player moving the slider.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct SliderValue {
+    min: f32,
+    max: f32,
+    step: f32,
+    value: f32,
+}
+
+impl SliderValue {
+    pub fn new(min: f32, max: f32, step: f32, value: f32) -> Self {
+        let mut slider_value = SliderValue {
+            min,
+            max,
+            step,
+            value: min,
+        };
+        slider_value.set_value(value);
+        slider_value
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Set the value, clamping it to the slider's range and snapping it to the nearest step
+    pub fn set_value(&mut self, value: f32) {
+        let clamped = value.clamp(self.min, self.max);
+        self.value = if self.step > 0f32 {
+            (self.min + ((clamped - self.min) / self.step).round() * self.step).min(self.max)
+        } else {
+            clamped
+        };
+    }
+
+    /// Move the value by a number of steps, ie when the player uses the keyboard
+    pub fn step_by(&mut self, steps: i32) {
+        self.set_value(self.value + self.step * steps as f32);
+    }
+
+    /// The value as a fraction of the slider's range, from `0.0` to `1.0`
+    pub fn fraction(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0f32
+        }
+    }
+
+    pub fn set_fraction(&mut self, fraction: f32) {
+        self.set_value(self.min + fraction.clamp(0f32, 1f32) * (self.max - self.min));
+    }
+}
+
+/// This component keeps track of the entities that draw a slider's value.
+#[derive(Component)]
+pub struct SliderParts {
+    fill: Entity,
+    value_text: Entity,
+}
+
+/// A horizontal bar the player can drag to pick a number from a range.
+pub struct Slider {
+    label: String,
+    value: SliderValue,
+}
+
+impl Slider {
+    pub fn new(label: impl Into<String>, min: f32, max: f32, value: f32) -> Self {
+        Slider {
+            label: label.into(),
+            value: SliderValue::new(min, max, 1f32, value),
+        }
+    }
+
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.value = SliderValue::new(self.value.min, self.value.max, step, self.value.value);
+        self
+    }
+
+    /// Spawn the slider, returning the entity with its [`SliderValue`](crate::widgets::SliderValue)
+    /// so the caller can insert a marker component to find it later.
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let theme = ui.theme();
+        let fraction = self.value.fraction();
+        let value_string = format_slider_value(&self.value);
+        let mut fill = None;
+        let mut value_text = None;
+
+        let mut row = parent.spawn();
+        row.insert(ui.marker())
+            .insert_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    margin: Rect {
+                        top: Val::Px(10.0),
+                        bottom: Val::Px(10.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                row.spawn()
+                    .insert(ui.marker())
+                    .insert_bundle(ui.text_bundle(
+                        self.label,
+                        theme.small_font_size(),
+                        theme.text_color(),
+                    ));
+                // The track is what the player focuses to move the slider with the keyboard
+                row.spawn()
+                    .insert(ui.marker())
+                    .insert(ui.next_focusable())
+                    .insert_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.0), Val::Px(20.0)),
+                            margin: Rect::all(Val::Px(10.0)),
+                            ..Default::default()
+                        },
+                        color: theme.button_color().into(),
+                        ..Default::default()
+                    })
+                    .with_children(|track| {
+                        fill = Some(
+                            track
+                                .spawn()
+                                .insert(ui.marker())
+                                .insert_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(
+                                            Val::Percent(fraction * 100f32),
+                                            Val::Percent(100.0),
+                                        ),
+                                        ..Default::default()
+                                    },
+                                    color: theme.slider_fill_color().into(),
+                                    ..Default::default()
+                                })
+                                .id(),
+                        );
+                    });
+                value_text = Some(
+                    row.spawn()
+                        .insert(ui.marker())
+                        .insert_bundle(ui.text_bundle(
+                            value_string,
+                            theme.small_font_size(),
+                            theme.text_color(),
+                        ))
+                        .id(),
+                );
+            });
+        row.insert(self.value).insert(SliderParts {
+            fill: fill.expect("Slider fill wasn't spawned!"),
+            value_text: value_text.expect("Slider value text wasn't spawned!"),
+        });
+        row
+    }
+}
+
+fn format_slider_value(slider_value: &SliderValue) -> String {
+    if slider_value.step.fract() == 0f32 {
+        format!("{:.0}", slider_value.value())
+    } else {
+        format!("{:.2}", slider_value.value())
+    }
+}
+
+/// Move a slider to wherever the player clicks or drags on its track
+pub fn drag_slider(
+    windows: Res<Windows>,
+    track_query: Query<(&Interaction, &Node, &GlobalTransform, &Parent)>,
+    mut slider_query: Query<&mut SliderValue>,
+) {
+    let cursor_position = match windows.get_primary().and_then(|w| w.cursor_position()) {
+        Some(cursor_position) => cursor_position,
+        None => return,
+    };
+    for (interaction, node, transform, parent) in track_query.iter() {
+        if *interaction != Interaction::Clicked || node.size.x <= 0f32 {
+            continue;
+        }
+        if let Ok(mut slider_value) = slider_query.get_mut(parent.0) {
+            let track_left = transform.translation.x - node.size.x / 2f32;
+            let fraction = (cursor_position.x - track_left) / node.size.x;
+            let mut new_value = slider_value.clone();
+            new_value.set_fraction(fraction);
+            // Only trigger change detection if the value actually changed
+            if new_value != *slider_value {
+                *slider_value = new_value;
+            }
+        }
+    }
+}
+
+/// Redraw a slider's fill and value text when its value changes
+pub fn update_slider_display(
+    slider_query: Query<(&SliderValue, &SliderParts), Changed<SliderValue>>,
+    mut fill_query: Query<&mut Style>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (slider_value, parts) in slider_query.iter() {
+        if let Ok(mut fill_style) = fill_query.get_mut(parts.fill) {
+            fill_style.size.width = Val::Percent(slider_value.fraction() * 100f32);
+        }
+        if let Ok(mut value_text) = text_query.get_mut(parts.value_text) {
+            value_text.sections[0].value = format_slider_value(slider_value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slider_value_clamps() {
+        let mut slider_value = SliderValue::new(1f32, 10f32, 1f32, 20f32);
+        assert_eq!(slider_value.value(), 10f32);
+        slider_value.set_value(-3f32);
+        assert_eq!(slider_value.value(), 1f32);
+    }
+
+    #[test]
+    fn test_slider_value_snaps_to_step() {
+        let mut slider_value = SliderValue::new(0f32, 1f32, 0.25, 0.3);
+        assert_eq!(slider_value.value(), 0.25);
+        slider_value.step_by(2);
+        assert_eq!(slider_value.value(), 0.75);
+        slider_value.step_by(5);
+        assert_eq!(slider_value.value(), 1f32);
+    }
+
+    #[test]
+    fn test_slider_value_fraction() {
+        let mut slider_value = SliderValue::new(1f32, 11f32, 1f32, 6f32);
+        assert_eq!(slider_value.fraction(), 0.5);
+        slider_value.set_fraction(0.82);
+        assert_eq!(slider_value.value(), 9f32);
+        slider_value.set_fraction(1.5);
+        assert_eq!(slider_value.value(), 11f32);
+    }
+}
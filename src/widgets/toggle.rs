@@ -0,0 +1,100 @@
+use crate::widgets::{button_bundle, UiContext, WidgetActivated};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+/// This component holds the current state of a toggle. Systems can query for
+/// `Changed<ToggleValue>` to react to the player flipping the toggle.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct ToggleValue {
+    label: String,
+    on: bool,
+    text: Entity,
+}
+
+impl ToggleValue {
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn flip(&mut self) {
+        self.on = !self.on;
+    }
+
+    fn display_text(&self) -> String {
+        format!("{}: {}", self.label, if self.on { "On" } else { "Off" })
+    }
+}
+
+/// A button that switches a setting on and off.
+pub struct Toggle {
+    label: String,
+    on: bool,
+}
+
+impl Toggle {
+    pub fn new(label: impl Into<String>, on: bool) -> Self {
+        Toggle {
+            label: label.into(),
+            on,
+        }
+    }
+
+    /// Spawn the toggle, returning the entity with its [`ToggleValue`](crate::widgets::ToggleValue)
+    /// so the caller can insert a marker component to find it later.
+    pub fn spawn<'w, 's, 'a, M: Component + Clone>(
+        self,
+        parent: &'a mut ChildBuilder<'w, 's, '_>,
+        ui: &UiContext<M>,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut text = None;
+        let mut toggle = parent.spawn();
+        toggle
+            .insert(ui.marker())
+            .insert(ui.next_focusable())
+            .insert_bundle(button_bundle(ui.theme(), 350f32))
+            .with_children(|button| {
+                text = Some(
+                    button
+                        .spawn()
+                        .insert(ui.marker())
+                        .insert_bundle(ui.text_bundle(
+                            "",
+                            ui.theme().text_font_size(),
+                            ui.theme().text_color(),
+                        ))
+                        .id(),
+                );
+            });
+        // The text is filled in by update_toggle_display once the value is added
+        toggle.insert(ToggleValue {
+            label: self.label,
+            on: self.on,
+            text: text.expect("Toggle text wasn't spawned!"),
+        });
+        toggle
+    }
+}
+
+/// Flip a toggle when the player activates it
+pub fn flip_toggle_on_activated(
+    mut activated_events: EventReader<WidgetActivated>,
+    mut toggle_query: Query<&mut ToggleValue>,
+) {
+    for WidgetActivated(entity) in activated_events.iter() {
+        if let Ok(mut toggle_value) = toggle_query.get_mut(*entity) {
+            toggle_value.flip();
+        }
+    }
+}
+
+/// Redraw a toggle's text when its value changes
+pub fn update_toggle_display(
+    toggle_query: Query<&ToggleValue, Changed<ToggleValue>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for toggle_value in toggle_query.iter() {
+        if let Ok(mut text) = text_query.get_mut(toggle_value.text) {
+            text.sections[0].value = toggle_value.display_text();
+        }
+    }
+}
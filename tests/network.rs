@@ -0,0 +1,236 @@
+//! A dedicated server and two clients playing over loopback, each simulating physics like the
+//! game does.
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use bevy_fp_template::components::{
+    FirstPersonSubject, LevelObject, Lookaround, Movement, MovementDirection,
+};
+use bevy_fp_template::network::{
+    spawn_server_level, NetworkClient, NetworkId, NetworkServer, PredictedPlayer, RemoteEntity,
+    ServerPlayer, RECONCILE_DISTANCE,
+};
+use bevy_fp_template::plugins::{NetworkClientPlugin, NetworkServerPlugin};
+use bevy_fp_template::resources::{GameConfig, GameSettings};
+use bevy_fp_template::save::SaveId;
+use bevy_fp_template::systems::player::{
+    move_player_body, player_collider, player_rigid_body, rotate_player_body,
+};
+use bevy_fp_template::systems::SubjectDiagnostic;
+use bevy_rapier3d::physics::TimestepMode;
+use bevy_rapier3d::prelude::*;
+
+/// An app with the physics and resources the first person systems need. Every update is one
+/// physics step, however long it took.
+fn physics_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .insert_resource(RapierConfiguration {
+            gravity: Vector::y() * -9.81,
+            scale: 1.0,
+            physics_pipeline_active: true,
+            query_pipeline_active: true,
+            timestep_mode: TimestepMode::FixedTimestep,
+        })
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .insert_resource(GameConfig::default())
+        .insert_resource(GameSettings::default())
+        .add_event::<SubjectDiagnostic>();
+    app
+}
+
+fn server_app() -> App {
+    let server = NetworkServer::bind("127.0.0.1:0").expect("Could not bind the test server!");
+    let mut app = physics_app();
+    app.insert_resource(server)
+        .add_plugin(NetworkServerPlugin)
+        .add_startup_system(spawn_server_level);
+    app
+}
+
+/// A client standing on its own ground, moved by the same systems as the main level's player. Its
+/// level has the server's ball too, but somewhere else.
+fn client_app(server: &App) -> (App, Entity) {
+    let address = server
+        .world
+        .get_resource::<NetworkServer>()
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let client = NetworkClient::connect(address)
+        .expect("Could not start the test client!")
+        // Remote entities are drawn at their latest snapshot, so they can be checked straight away
+        .with_interpolation_delay(0.0);
+    let mut app = physics_app();
+    app.insert_resource(client)
+        .add_plugin(NetworkClientPlugin)
+        .add_system(rotate_player_body.label("player-body"))
+        .add_system(move_player_body.label("player-body"));
+    app.world.spawn().insert_bundle(ColliderBundle {
+        shape: ColliderShape::cuboid(100.0, 0.1, 100.0).into(),
+        ..Default::default()
+    });
+    app.world
+        .spawn()
+        .insert_bundle(RigidBodyBundle {
+            position: Vec3::new(20.0, 2.0, 20.0).into(),
+            ..Default::default()
+        })
+        .insert_bundle(ColliderBundle {
+            shape: ColliderShape::ball(0.5).into(),
+            ..Default::default()
+        })
+        .insert(LevelObject)
+        .insert(SaveId::new("ball"))
+        .insert(Transform::default())
+        .insert(GlobalTransform::default())
+        .insert(RigidBodyPositionSync::Discrete);
+    let player_config = GameConfig::default().player().clone();
+    let subject = app
+        .world
+        .spawn()
+        .insert(FirstPersonSubject)
+        .insert(Movement::default())
+        .insert(Lookaround::default())
+        .insert(Transform::default())
+        .insert(GlobalTransform::default())
+        .insert(RigidBodyPositionSync::Discrete)
+        .insert_bundle(player_collider(&player_config))
+        .insert_bundle(player_rigid_body(Vec3::new(
+            0f32,
+            0.1 + player_config.capsule_height() / 2f32,
+            0f32,
+        )))
+        .id();
+    (app, subject)
+}
+
+/// Update every app once a round until `done` or `max_rounds` have passed, and whether it's done
+fn pump_until(
+    apps: &mut [&mut App],
+    max_rounds: usize,
+    mut done: impl FnMut(&mut [&mut App]) -> bool,
+) -> bool {
+    for _ in 0..max_rounds {
+        for app in apps.iter_mut() {
+            app.update();
+        }
+        if done(apps) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Where the server has the player of the client whose player is `network_id`, and how fast it's going
+fn server_player(server: &mut App, network_id: u32) -> Option<(Vec3, f32)> {
+    server
+        .world
+        .query::<(
+            &ServerPlayer,
+            &NetworkId,
+            &Transform,
+            &RigidBodyVelocityComponent,
+        )>()
+        .iter(&server.world)
+        .find(|(_, id, _, _)| id.0 == network_id)
+        .map(|(_, _, transform, velocity)| (transform.translation, velocity.linvel.magnitude()))
+}
+
+fn predicted_player(client: &mut App) -> Option<Vec3> {
+    client
+        .world
+        .query_filtered::<&Transform, With<PredictedPlayer>>()
+        .iter(&client.world)
+        .next()
+        .map(|transform| transform.translation)
+}
+
+/// Where the ball is in an app's level
+fn ball(app: &mut App) -> Vec3 {
+    app.world
+        .query::<(&SaveId, &Transform)>()
+        .iter(&app.world)
+        .find(|(save_id, _)| save_id.as_str() == "ball")
+        .map(|(_, transform)| transform.translation)
+        .expect("There's no ball!")
+}
+
+fn remote_entity(client: &mut App, network_id: u32) -> Option<Vec3> {
+    client
+        .world
+        .query::<(&NetworkId, &Transform, &RemoteEntity)>()
+        .iter(&client.world)
+        .find(|(id, _, _)| id.0 == network_id)
+        .map(|(_, transform, _)| transform.translation)
+}
+
+#[test]
+fn test_loopback_server_and_two_clients() {
+    let mut server = server_app();
+    let (mut client_a, subject_a) = client_app(&server);
+    let (mut client_b, _) = client_app(&server);
+    let mut apps = [&mut server, &mut client_a, &mut client_b];
+
+    // Join, and let the players land
+    let joined = pump_until(&mut apps, 500, |apps| {
+        let client_count = apps[0]
+            .world
+            .get_resource::<NetworkServer>()
+            .unwrap()
+            .client_count();
+        client_count == 2 && predicted_player(apps[1]).is_some()
+    });
+    assert!(joined, "The clients never joined!");
+    pump_until(&mut apps, 60, |_| false);
+    let network_id_a = apps[1]
+        .world
+        .get_resource::<NetworkClient>()
+        .unwrap()
+        .network_id()
+        .unwrap();
+    let (spawn_a, _) =
+        server_player(apps[0], network_id_a).expect("The server has no player for client A!");
+
+    // Walk client A forward for two seconds and stop
+    *apps[1].world.get_mut::<Movement>(subject_a).unwrap() = Movement::from_components(
+        MovementDirection::Right(0f32),
+        MovementDirection::Forward(1f32),
+    );
+    pump_until(&mut apps, 120, |_| false);
+    *apps[1].world.get_mut::<Movement>(subject_a).unwrap() = Movement::default();
+
+    // Once A's player stops, its prediction and what B sees agree with the server
+    let settled = pump_until(&mut apps, 600, |apps| {
+        let (server_a, speed) = match server_player(apps[0], network_id_a) {
+            Some(server_a) => server_a,
+            None => return false,
+        };
+        let predicted_a = predicted_player(apps[1]);
+        let remote_a = remote_entity(apps[2], network_id_a);
+        speed < 0.01
+            && predicted_a.map_or(false, |predicted_a| {
+                (predicted_a - server_a).length() <= RECONCILE_DISTANCE + 0.01
+            })
+            && remote_a.map_or(false, |remote_a| (remote_a - server_a).length() < 0.01)
+    });
+    assert!(settled, "Client A's player never settled on every side!");
+
+    // The server's physics walked A forward (-Z), on the ground
+    let (server_a, _) = server_player(apps[0], network_id_a).unwrap();
+    assert!(server_a.z < spawn_a.z - 2f32);
+    assert!((server_a.y - spawn_a.y).abs() < 0.1);
+
+    // The clients' balls are where the server's is, and the only other thing they draw is the
+    // other player
+    let server_ball = ball(apps[0]);
+    for client in apps[1..].iter_mut() {
+        assert!((ball(client) - server_ball).length() < 0.01);
+    }
+    let remote_players = apps[2]
+        .world
+        .query_filtered::<&RemoteEntity, Without<LevelObject>>()
+        .iter(&apps[2].world)
+        .count();
+    assert_eq!(remote_players, 1);
+}